@@ -0,0 +1,283 @@
+use crate::{fib_u64, thousands_separator_with, Grouping};
+use dashu_float::DBig;
+use std::fmt;
+
+/// The number of digits calculated on top of the requested precision, which absorbs the
+/// rounding errors of the square root, the division and every squaring of the power.
+const GUARD_DIGITS: usize = 10;
+
+/// Approximates the nth Fibonacci number with Binet's formula in floating point.
+///
+/// Binet's formula `F(n) = (φ^n − ψ^n) / √5` reduces to `φ^n / √5` rounded to the nearest
+/// integer, as `|ψ^n / √5|` is always smaller than one half. With the 53-bit mantissa of
+/// an `f64` the result is **not exact** beyond `F(70)` and overflows to infinity beyond
+/// `F(1474)`, so it is only suitable for display purposes. Use [`fib_approx_big`] for
+/// larger indices.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to approximate.
+///
+/// # Returns
+/// An `f64` approximation of the nth Fibonacci number.
+pub fn fib_approx(n: u64) -> f64 {
+    let sqrt5 = 5f64.sqrt();
+    let phi = (1.0 + sqrt5) / 2.0;
+    phi.powf(n as f64) / sqrt5
+}
+
+/// An approximation of a Fibonacci number in scientific notation.
+///
+/// The value is `significand × 10^exponent`, where the significand has a single digit
+/// before the decimal point. Only `exact` approximations are guaranteed to equal the
+/// Fibonacci number, all others are correct up to the last significant digit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Approximation {
+    /// The significant digits, without a decimal point.
+    pub digits: String,
+    /// The decimal exponent of the first significant digit.
+    pub exponent: u64,
+    /// Whether every digit of the Fibonacci number is included in `digits`.
+    pub exact: bool,
+}
+
+impl Approximation {
+    /// Formats the approximation in scientific notation with the exponent grouped in the
+    /// given style.
+    ///
+    /// The `Display` implementation uses `Grouping::Comma`.
+    pub fn render(&self, grouping: Grouping) -> String {
+        let (integer_string, decimal_string) = self.digits.split_at(1);
+        let exponent = thousands_separator_with(self.exponent, grouping);
+        if decimal_string.is_empty() {
+            format!("{}e+{}", integer_string, exponent)
+        } else {
+            format!("{}.{}e+{}", integer_string, decimal_string, exponent)
+        }
+    }
+}
+
+impl fmt::Display for Approximation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(Grouping::Comma))
+    }
+}
+
+/// Approximates the nth Fibonacci number with Binet's formula in arbitrary precision.
+///
+/// `(φ^n − ψ^n) / √5` is calculated with decimal floating point numbers of `precision`
+/// significant digits plus guard digits, so the approximation works for any index and takes
+/// time proportional to `log(n)` instead of the size of the Fibonacci number. The result is
+/// **not exact** unless all digits of the Fibonacci number fit into `precision`.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to approximate.
+/// * `precision` - The number of significant digits of the approximation (at least 1).
+///
+/// # Returns
+/// An `Approximation` of the nth Fibonacci number, rounded half up to `precision` digits.
+pub fn fib_approx_big(n: u64, precision: usize) -> Approximation {
+    let precision = precision.max(1);
+
+    // Zero has no significant digit to normalize
+    if n == 0 {
+        return Approximation {
+            digits: "0".repeat(precision),
+            exponent: 0,
+            exact: true,
+        };
+    }
+
+    // Every squaring of the power can lose a digit, so the guard grows with log(n)
+    let working_precision = precision + GUARD_DIGITS + n.ilog2() as usize;
+
+    let sqrt5 = DBig::from(5u8)
+        .with_precision(working_precision)
+        .value()
+        .sqrt();
+    let one = DBig::ONE.with_precision(working_precision).value();
+    let phi = (&one + &sqrt5) / DBig::from(2u8);
+    let psi = (&one - &sqrt5) / DBig::from(2u8);
+
+    // ψ^n only matters for small indices, where it makes the rounded result exact
+    let value = (phi.powi(n.into()) - psi.powi(n.into())) / sqrt5;
+
+    // Round to the requested precision and normalize to one digit before the decimal point
+    let rounded = value.with_precision(precision).value();
+    let repr = rounded.repr();
+    let significand = repr.significand().to_string();
+    let exponent = repr.exponent() + significand.len() as isize - 1;
+
+    let mut digits = significand;
+    digits.truncate(precision);
+    while digits.len() < precision {
+        digits.push('0');
+    }
+
+    Approximation {
+        digits,
+        exponent: exponent as u64,
+        exact: exponent < precision as isize,
+    }
+}
+
+/// The number of significant digits of [`fib_approx_log`].
+pub const LOG_APPROX_DIGITS: usize = 15;
+
+/// Approximates the nth Fibonacci number from its decimal logarithm.
+///
+/// `log10(F(n))` is `n · log10(φ) − log10(√5)` up to a correction below `10^-38` for the
+/// indices beyond `F(93)`, so the exponent and the significand `10^fract` follow from a
+/// single logarithm in decimal floating point. This takes microseconds even for indices
+/// like `1e12`, as neither the Fibonacci number nor a power of `φ` is ever calculated.
+///
+/// The exponent, and with it the digit count `exponent + 1`, is always exact. When the
+/// logarithm is too close to an integer to tell which side it is on, it is calculated
+/// again with more digits. Only the significant digits are approximate: they are rounded
+/// to two more digits and then truncated, so the last one may be off by one. Indices up to
+/// 93 are calculated exactly, as their results fit into a `u64`.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to approximate.
+///
+/// # Returns
+/// An `Approximation` with [`LOG_APPROX_DIGITS`] significant digits of the nth Fibonacci
+/// number and its exact exponent.
+pub fn fib_approx_log(n: u64) -> Approximation {
+    if let Some(value) = fib_u64(n) {
+        let mut digits = value.to_string();
+        let exponent = digits.len() as u64 - 1;
+        digits.truncate(LOG_APPROX_DIGITS);
+        while digits.len() < LOG_APPROX_DIGITS {
+            digits.push('0');
+        }
+        return Approximation {
+            digits,
+            exponent,
+            exact: exponent < LOG_APPROX_DIGITS as u64,
+        };
+    }
+
+    let mut guard = GUARD_DIGITS;
+    loop {
+        // The integer part of the logarithm takes up as many digits as the index has
+        let working_precision = LOG_APPROX_DIGITS + guard + n.ilog10() as usize + 1;
+
+        let five = DBig::from(5u8).with_precision(working_precision).value();
+        let sqrt5 = five.sqrt();
+        let phi = (DBig::ONE + &sqrt5) / DBig::from(2u8);
+        let ln10 = DBig::from(10u8)
+            .with_precision(working_precision)
+            .value()
+            .ln();
+        let log = (DBig::from(n) * phi.ln() - sqrt5.ln()) / &ln10;
+
+        let exponent = log.floor();
+        let fraction = &log - &exponent;
+
+        // The error of the logarithm is far below this margin, so it cannot cross an integer
+        let margin = DBig::from_parts(1.into(), -(LOG_APPROX_DIGITS as isize + guard as isize / 2));
+        if fraction < margin || DBig::ONE - &fraction < margin {
+            guard *= 2;
+            continue;
+        }
+
+        let significand = (fraction * ln10)
+            .exp()
+            .with_precision(LOG_APPROX_DIGITS + 2)
+            .value();
+        let repr = significand.repr();
+        let mut digits = repr.significand().to_string();
+        digits.truncate(LOG_APPROX_DIGITS);
+        while digits.len() < LOG_APPROX_DIGITS {
+            digits.push('0');
+        }
+
+        return Approximation {
+            digits,
+            exponent: exponent.to_int().value().try_into().unwrap_or(u64::MAX),
+            exact: false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculate_fibonacci, SplitMix64};
+
+    /// The digits of `F(n)` rounded half up to `precision` digits, with their exponent.
+    fn rounded_digits(n: u64, precision: usize) -> (String, u64) {
+        let digits = calculate_fibonacci(n).unwrap().to_string();
+        let exponent = digits.len() as u64 - 1;
+        if digits.len() <= precision {
+            return (format!("{:0<width$}", digits, width = precision), exponent);
+        }
+
+        let mut prefix: u128 = digits[..precision].parse().unwrap();
+        prefix += u128::from(digits.as_bytes()[precision] >= b'5');
+        let rounded = prefix.to_string();
+        if rounded.len() > precision {
+            (rounded[..precision].to_string(), exponent + 1)
+        } else {
+            (rounded, exponent)
+        }
+    }
+
+    #[test]
+    fn floating_point_is_exact_up_to_70() {
+        let (mut current, mut next) = (0u64, 1u64);
+        for n in 0..=70 {
+            assert_eq!(fib_approx(n).round() as u64, current, "F({})", n);
+            (current, next) = (next, current + next);
+        }
+        assert!(fib_approx(1474).is_finite());
+        assert!(fib_approx(1475).is_infinite());
+    }
+
+    #[test]
+    fn arbitrary_precision_rounds_the_exact_digits() {
+        let mut random = SplitMix64::new(51);
+        for _ in 0..200 {
+            let n = random.next_u64() % 20_000;
+            let precision = 1 + (random.next_u64() % 30) as usize;
+            let approximation = fib_approx_big(n, precision);
+            let (digits, exponent) = rounded_digits(n, precision);
+            assert_eq!(
+                (approximation.digits.as_str(), approximation.exponent),
+                (digits.as_str(), exponent),
+                "F({}) to {} digits",
+                n,
+                precision
+            );
+            assert_eq!(approximation.exact, exponent < precision as u64);
+        }
+        assert_eq!(fib_approx_big(1000, 5).to_string(), "4.3467e+208");
+        assert_eq!(fib_approx_big(0, 0).to_string(), "0e+0");
+    }
+
+    #[test]
+    fn logarithms_give_the_exact_exponent() {
+        let mut random = SplitMix64::new(52);
+        for n in (0..200).chain((0..100).map(|_| random.next_u64() % 200_000)) {
+            let approximation = fib_approx_log(n);
+            let digits = calculate_fibonacci(n).unwrap().to_string();
+            assert_eq!(approximation.exponent, digits.len() as u64 - 1, "F({})", n);
+
+            // The last digit may be off by one
+            let exact: u64 = format!("{:0<15.15}", digits).parse().unwrap();
+            let approximate: u64 = approximation.digits.parse().unwrap();
+            assert!(
+                exact.abs_diff(approximate) <= 1,
+                "F({}): {} vs {}",
+                n,
+                approximate,
+                exact
+            );
+            assert_eq!(
+                approximation.exact,
+                n <= 93 && digits.len() <= LOG_APPROX_DIGITS
+            );
+        }
+        assert_eq!(fib_approx_log(1_000_000_000_000).exponent, 208_987_640_249);
+    }
+}
@@ -0,0 +1,446 @@
+use crate::{
+    calculate_fibonacci_deadline, calculate_fibonacci_timed, calculate_fibonacci_timed_with,
+    calculate_fibonacci_with, digit_count, random::SplitMix64, to_decimal_string, Algorithm,
+    FibError,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+/// The indices calculated by [`run_profile`], one per order of magnitude.
+pub const PROFILE_LADDER: [u64; 4] = [1_000, 10_000, 100_000, 1_000_000];
+
+/// The measurements of a single benchmarked Fibonacci calculation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BenchSample {
+    pub index: u64,
+    pub bits: u64,
+    pub compute: Duration,
+    pub conversion: Duration,
+}
+
+/// A calculation of [`largest_within`] that finished within the time budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BudgetStep {
+    pub index: u64,
+    pub digits: u64,
+    pub duration: Duration,
+}
+
+/// Summary statistics of repeated measurements of the same calculation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchStats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+impl BenchStats {
+    /// Calculates the statistics of a set of measurements.
+    ///
+    /// The median of an even number of measurements is the mean of the two middle ones.
+    ///
+    /// # Arguments
+    /// * `durations` - The measured durations, in any order.
+    ///
+    /// # Returns
+    /// An `Option<BenchStats>` with the statistics, or `None` if there are no measurements.
+    pub fn from_durations(durations: &[Duration]) -> Option<BenchStats> {
+        let mut sorted = durations.to_vec();
+        sorted.sort_unstable();
+        if sorted.is_empty() {
+            return None;
+        }
+
+        let runs = sorted.len();
+        let middle = runs / 2;
+        let median = if runs.is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2
+        } else {
+            sorted[middle]
+        };
+        let total: Duration = sorted.iter().sum();
+
+        Some(BenchStats {
+            runs,
+            min: sorted[0],
+            median,
+            mean: total / runs as u32,
+            max: sorted[runs - 1],
+        })
+    }
+}
+
+/// Calculates the same Fibonacci number repeatedly and summarizes the durations.
+///
+/// Every result is discarded after reading its lowest digit, which keeps the compiler from
+/// optimizing the calculation away.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to benchmark.
+/// * `runs` - The number of calculations, which must be at least 1.
+/// * `algorithm` - The algorithm used for the calculations.
+///
+/// # Returns
+/// A `Result<BenchStats, FibError>` with the statistics of the calculation durations.
+pub fn bench_runs(index: u64, runs: usize, algorithm: Algorithm) -> Result<BenchStats, FibError> {
+    let mut durations = Vec::with_capacity(runs);
+
+    for _ in 0..runs.max(1) {
+        let (value, duration) = calculate_fibonacci_timed_with(index, algorithm)?;
+        std::hint::black_box(value.iter_u32_digits().next());
+        durations.push(duration);
+    }
+
+    Ok(BenchStats::from_durations(&durations).expect("at least one run was measured"))
+}
+
+/// Benchmarks every algorithm on the same Fibonacci number after checking that they agree.
+///
+/// Each algorithm calculates the number once before any timing, and the results must be
+/// equal. Only then is every algorithm timed with [`bench_runs`], one after the other in the
+/// order of [`Algorithm::CONCRETE`].
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to benchmark.
+/// * `runs` - The number of timed calculations per algorithm, which must be at least 1.
+///
+/// # Returns
+/// A `Result<Vec<(Algorithm, BenchStats)>, FibError>` with one entry per algorithm, or
+/// `FibError::VerificationFailed` if the algorithms disagree.
+pub fn compare_algorithms(
+    index: u64,
+    runs: usize,
+) -> Result<Vec<(Algorithm, BenchStats)>, FibError> {
+    let mut expected = None;
+    for algorithm in Algorithm::CONCRETE {
+        let value = calculate_fibonacci_with(index, algorithm)?;
+        match &expected {
+            None => expected = Some(value),
+            Some(expected) if *expected != value => {
+                return Err(FibError::VerificationFailed(index))
+            }
+            Some(_) => {}
+        }
+    }
+
+    Algorithm::CONCRETE
+        .into_iter()
+        .map(|algorithm| Ok((algorithm, bench_runs(index, runs, algorithm)?)))
+        .collect()
+}
+
+/// Finds the largest Fibonacci number that can be calculated within a time budget.
+///
+/// The index starts at 1 and doubles after every calculation that finished within the
+/// budget. Every calculation gets the whole budget on its own and runs with
+/// [`calculate_fibonacci_deadline`], which gives up as soon as it cannot finish in time, so
+/// the first index that is too large costs at most the budget. As the duration more than
+/// doubles with the index, the whole search takes about twice the budget.
+///
+/// # Arguments
+/// * `budget` - The time a single calculation may take.
+/// * `max_index` - The largest index that may be calculated.
+///
+/// # Returns
+/// A `Vec<BudgetStep>` with every calculation that finished within the budget, where the
+/// last one has the largest index, or an empty `Vec` if not even `F(1)` finished in time.
+pub fn largest_within(budget: Duration, max_index: u64) -> Vec<BudgetStep> {
+    let mut steps = Vec::new();
+    let mut index = 1;
+
+    while index <= max_index {
+        let start_time = Instant::now();
+        let Ok(value) = calculate_fibonacci_deadline(index, start_time + budget) else {
+            break;
+        };
+        let duration = start_time.elapsed();
+        if duration > budget {
+            break;
+        }
+        steps.push(BudgetStep {
+            index,
+            digits: digit_count(&value),
+            duration,
+        });
+
+        let Some(next) = index.checked_mul(2) else {
+            break;
+        };
+        index = next;
+    }
+    steps
+}
+
+/// A performance snapshot of the indices of [`PROFILE_LADDER`], for comparing builds.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Profile {
+    /// The version of the crate that was measured.
+    pub version: &'static str,
+    /// The name of the algorithm used for the calculations.
+    pub algorithm: &'static str,
+    /// The number of calculations of every index.
+    pub runs: usize,
+    /// The median calculation duration in nanoseconds of every index.
+    pub durations_ns: BTreeMap<u64, u64>,
+}
+
+/// Calculates every index of [`PROFILE_LADDER`] and records the median durations.
+///
+/// The ladder is fixed, so reports of different builds or machines can be compared index by
+/// index.
+///
+/// # Arguments
+/// * `runs` - The number of calculations of every index, at least 1.
+/// * `algorithm` - The algorithm used for the calculations.
+///
+/// # Returns
+/// A `Result<Profile, FibError>` with the durations of every index of the ladder.
+pub fn run_profile(runs: usize, algorithm: Algorithm) -> Result<Profile, FibError> {
+    let mut durations_ns = BTreeMap::new();
+    for index in PROFILE_LADDER {
+        let stats = bench_runs(index, runs, algorithm)?;
+        durations_ns.insert(
+            index,
+            u64::try_from(stats.median.as_nanos()).unwrap_or(u64::MAX),
+        );
+    }
+
+    Ok(Profile {
+        version: env!("CARGO_PKG_VERSION"),
+        algorithm: algorithm.name(),
+        runs: runs.max(1),
+        durations_ns,
+    })
+}
+
+/// Formats a profile as a pretty-printed JSON object, where the durations are keyed by
+/// index.
+pub fn profile_to_json(profile: &Profile) -> String {
+    let mut json =
+        serde_json::to_string_pretty(profile).expect("a profile can always be serialized");
+    json.push('\n');
+    json
+}
+
+/// Picks reproducible pseudo-random indices from a range.
+///
+/// The indices are distributed log-uniformly, so every order of magnitude of the range gets
+/// a similar number of samples, which suits plotting the durations on a log-log scale. The
+/// same seed always produces the same indices, in ascending order.
+///
+/// # Arguments
+/// * `min` - The smallest index that may be picked.
+/// * `max` - The largest index that may be picked.
+/// * `samples` - The number of indices to pick.
+/// * `seed` - The seed of the pseudo-random number generator.
+///
+/// # Returns
+/// A `Vec<u64>` with `samples` indices between `min` and `max`.
+pub fn sample_indices(min: u64, max: u64, samples: usize, seed: u64) -> Vec<u64> {
+    let mut rng = SplitMix64::new(seed);
+    let (low, high) = ((min.max(1) as f64).ln(), (max.max(1) as f64).ln());
+
+    let mut indices: Vec<u64> = (0..samples)
+        .map(|_| {
+            let index = (low + rng.next_f64() * (high - low)).exp().round() as u64;
+            index.clamp(min, max)
+        })
+        .collect();
+    indices.sort_unstable();
+    indices
+}
+
+/// Calculates a Fibonacci number and measures the calculation and the decimal conversion.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to benchmark.
+///
+/// # Returns
+/// A `Result<BenchSample, FibError>` with the measurements of the calculation.
+pub fn bench_index(index: u64) -> Result<BenchSample, FibError> {
+    let (value, compute) = calculate_fibonacci_timed(index)?;
+
+    let conversion_start_time = Instant::now();
+    let digits = to_decimal_string(&value);
+    let conversion = conversion_start_time.elapsed();
+
+    // Keep the conversion from being optimized away
+    std::hint::black_box(digits);
+
+    Ok(BenchSample {
+        index,
+        bits: value.bits(),
+        compute,
+        conversion,
+    })
+}
+
+/// Formats benchmark samples as CSV with a header row.
+pub fn samples_to_csv(samples: &[BenchSample]) -> String {
+    let mut csv = String::from("index,bits,compute_ns,conversion_ns\n");
+    for sample in samples {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            sample.index,
+            sample.bits,
+            sample.compute.as_nanos(),
+            sample.conversion.as_nanos()
+        ));
+    }
+    csv
+}
+
+/// Formats benchmark samples as a JSON array of objects.
+pub fn samples_to_json(samples: &[BenchSample]) -> String {
+    let rows: Vec<String> = samples
+        .iter()
+        .map(|sample| {
+            format!(
+                "  {{\"index\": {}, \"bits\": {}, \"compute_ns\": {}, \"conversion_ns\": {}}}",
+                sample.index,
+                sample.bits,
+                sample.compute.as_nanos(),
+                sample.conversion.as_nanos()
+            )
+        })
+        .collect();
+
+    if rows.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_log_uniform_and_sorted() {
+        let indices = sample_indices(10, 10_000_000, 600, 7);
+        assert_eq!(indices, sample_indices(10, 10_000_000, 600, 7));
+        assert!(indices.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(indices
+            .iter()
+            .all(|index| (10..=10_000_000).contains(index)));
+
+        // Each of the six orders of magnitude gets about a sixth of the samples
+        for magnitude in 1..7 {
+            let range = 10u64.pow(magnitude)..10u64.pow(magnitude + 1);
+            let count = indices.iter().filter(|index| range.contains(index)).count();
+            assert!((60..140).contains(&count), "{} in {:?}", count, range);
+        }
+        assert_eq!(sample_indices(5, 5, 3, 1), [5, 5, 5]);
+    }
+
+    #[test]
+    fn samples_are_written_as_csv_and_json() {
+        let samples = [
+            BenchSample {
+                index: 1000,
+                bits: 694,
+                compute: Duration::from_nanos(1500),
+                conversion: Duration::from_nanos(300),
+            },
+            bench_index(10).unwrap(),
+        ];
+        assert_eq!(samples[1].bits, 6);
+
+        let csv = samples_to_csv(&samples);
+        assert!(csv.starts_with("index,bits,compute_ns,conversion_ns\n1000,694,1500,300\n10,6,"));
+        assert_eq!(csv.lines().count(), 3);
+
+        let json = samples_to_json(&samples);
+        assert!(json.starts_with(
+            "[\n  {\"index\": 1000, \"bits\": 694, \"compute_ns\": 1500, \"conversion_ns\": 300},\n  \
+             {\"index\": 10, \"bits\": 6, "
+        ));
+        assert!(json.ends_with("}\n]\n"));
+        assert_eq!(samples_to_json(&[]), "[]\n");
+    }
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values
+            .iter()
+            .map(|&value| Duration::from_millis(value))
+            .collect()
+    }
+
+    #[test]
+    fn statistics_of_odd_and_even_counts() {
+        assert_eq!(BenchStats::from_durations(&[]), None);
+
+        let odd = BenchStats::from_durations(&millis(&[9, 1, 5])).unwrap();
+        assert_eq!(
+            odd,
+            BenchStats {
+                runs: 3,
+                min: Duration::from_millis(1),
+                median: Duration::from_millis(5),
+                mean: Duration::from_millis(5),
+                max: Duration::from_millis(9),
+            }
+        );
+
+        let even = BenchStats::from_durations(&millis(&[4, 1, 2, 10])).unwrap();
+        assert_eq!(even.median, Duration::from_micros(3000));
+        assert_eq!(even.mean, Duration::from_millis(17) / 4);
+    }
+
+    #[test]
+    fn repeated_runs_are_summarized_in_order() {
+        for algorithm in Algorithm::ALL {
+            let stats = bench_runs(20_000, 5, algorithm).unwrap();
+            assert_eq!(stats.runs, 5);
+            assert!(stats.min <= stats.median && stats.median <= stats.max);
+            assert!(stats.min <= stats.mean && stats.mean <= stats.max);
+        }
+        assert_eq!(bench_runs(10, 0, Algorithm::Matrix).unwrap().runs, 1);
+    }
+
+    #[test]
+    fn profiles_have_a_duration_for_every_index_of_the_ladder() {
+        let profile = run_profile(1, Algorithm::FastDoubling).unwrap();
+        assert_eq!(profile.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(profile.algorithm, Algorithm::FastDoubling.name());
+        assert_eq!(profile.runs, 1);
+        assert!(profile.durations_ns.keys().eq(PROFILE_LADDER.iter()));
+        assert!(profile.durations_ns.values().all(|&duration| duration > 0));
+
+        let json = profile_to_json(&profile);
+        for index in PROFILE_LADDER {
+            assert!(json.contains(&format!("\"{}\": ", index)), "{}", json);
+        }
+        assert_eq!(run_profile(0, Algorithm::Matrix).unwrap().runs, 1);
+    }
+
+    #[test]
+    fn budgets_double_the_index_until_they_run_out() {
+        let steps = largest_within(Duration::from_secs(60), 100);
+        let indices: Vec<u64> = steps.iter().map(|step| step.index).collect();
+        assert_eq!(indices, [1, 2, 4, 8, 16, 32, 64]);
+        assert_eq!(steps[6].digits, 14);
+        assert!(largest_within(Duration::ZERO, 1 << 40).len() <= 1);
+    }
+
+    #[test]
+    fn every_algorithm_gets_a_row_after_they_agree() {
+        for index in [0, 1, 94, 5000] {
+            // Rows are only timed once every algorithm calculated the same number
+            let rows = compare_algorithms(index, 3).unwrap();
+            let algorithms: Vec<Algorithm> = rows.iter().map(|(algorithm, _)| *algorithm).collect();
+            assert_eq!(algorithms, Algorithm::CONCRETE, "F({})", index);
+            assert!(
+                rows.iter().all(|(_, stats)| stats.runs == 3),
+                "F({})",
+                index
+            );
+        }
+    }
+}
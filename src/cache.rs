@@ -0,0 +1,219 @@
+use crate::{
+    calculate_fibonacci_with,
+    fibonacci::{fib_pair_before, fib_pair_cancellable, fib_pair_from},
+    Algorithm, FibError, PrecomputedPair, Speculation,
+};
+use num_bigint::BigUint;
+use std::time::{Duration, Instant};
+
+/// Remembers the most recent Fibonacci pair of an interactive session.
+///
+/// Exploring the neighborhood of a huge index, such as `F(n+1)`, `F(n-1)` or `F(2n)`, is
+/// much cheaper when starting from the known pair `(F(n), F(n+1))` than calculating the
+/// new number from scratch, so the cache steps from the last pair with the addition
+/// formulas whenever fast doubling or [`Algorithm::Auto`] is selected. Pairs calculated
+/// ahead of time by a [`Speculation`] are returned without any calculation.
+#[derive(Clone, Debug, Default)]
+pub struct SessionCache {
+    /// The last index, its Fibonacci number and the following one if it is known.
+    last: Option<(u64, BigUint, Option<BigUint>)>,
+    /// The pairs calculated ahead of time for the likely next indices.
+    precomputed: Vec<PrecomputedPair>,
+}
+
+impl SessionCache {
+    /// Creates an empty cache.
+    pub fn new() -> SessionCache {
+        SessionCache::default()
+    }
+
+    /// Returns the most recently calculated index and Fibonacci number.
+    pub fn last(&self) -> Option<(u64, &BigUint)> {
+        self.last.as_ref().map(|(index, value, _)| (*index, value))
+    }
+
+    /// Returns the most recently calculated index.
+    pub fn last_index(&self) -> Option<u64> {
+        self.last.as_ref().map(|(index, _, _)| *index)
+    }
+
+    /// Returns the most recent pair `(index, F(index), F(index+1))`, if the following
+    /// number is known, which it is after every calculation with fast doubling.
+    pub fn last_pair(&self) -> Option<(u64, &BigUint, &BigUint)> {
+        match &self.last {
+            Some((index, value, Some(next))) => Some((*index, value, next)),
+            _ => None,
+        }
+    }
+
+    /// Adds pairs calculated ahead of time, such as the ones of [`Speculation::finish`].
+    ///
+    /// Only the pairs of the [`Speculation::targets`] of the most recent index are kept,
+    /// so the precomputed pairs never pile up over a session.
+    pub fn add_precomputed(&mut self, pairs: Vec<PrecomputedPair>) {
+        self.precomputed.extend(pairs);
+        let targets = self
+            .last_index()
+            .map(Speculation::targets)
+            .unwrap_or_default();
+        let mut kept: Vec<u64> = Vec::new();
+        self.precomputed.retain(|(index, _, _)| {
+            let keep = targets.contains(index) && !kept.contains(index);
+            kept.push(*index);
+            keep
+        });
+    }
+
+    /// Starts a [`Speculation`] for the most recent pair, skipping the indices that are
+    /// precomputed already.
+    ///
+    /// # Arguments
+    /// * `budget` - The most bytes the speculated pairs may take together.
+    ///
+    /// # Returns
+    /// The running `Speculation`, or `None` if the following number of the most recent
+    /// index is not known, as after every algorithm but fast doubling.
+    pub fn speculate(&self, budget: u64) -> Option<Speculation> {
+        let (k, current, next) = self.last_pair()?;
+        let known: Vec<u64> = self
+            .precomputed
+            .iter()
+            .map(|(index, _, _)| *index)
+            .collect();
+        Some(Speculation::start(k, current, next, budget, &known))
+    }
+
+    /// Checks whether [`SessionCache::calculate`] returns `F(n)` without calculating it.
+    pub fn is_precomputed(&self, n: u64, algorithm: Algorithm) -> bool {
+        matches!(algorithm, Algorithm::Auto | Algorithm::FastDoubling)
+            && self.precomputed.iter().any(|(index, _, _)| *index == n)
+    }
+
+    /// Calculates a Fibonacci number and remembers it, measuring how long the calculation took.
+    ///
+    /// With fast doubling and `Algorithm::Auto` the number is derived from the cached pair,
+    /// every other algorithm calculates it from scratch so the algorithms can still be
+    /// compared.
+    ///
+    /// # Arguments
+    /// * `n` - The index of the Fibonacci number to calculate.
+    /// * `algorithm` - The algorithm used for the calculation.
+    ///
+    /// # Returns
+    /// A `Result<(&BigUint, Duration), FibError>` with the nth Fibonacci number and the
+    /// duration of the calculation, or a `FibError` if the calculation fails, like
+    /// `FibError::Cancelled` after [`request_cancel`](crate::request_cancel).
+    pub fn calculate(
+        &mut self,
+        n: u64,
+        algorithm: Algorithm,
+    ) -> Result<(&BigUint, Duration), FibError> {
+        let start_time = Instant::now();
+
+        let precomputed = self
+            .precomputed
+            .iter()
+            .position(|(index, _, _)| *index == n);
+
+        let (value, next) = match (algorithm, precomputed) {
+            (Algorithm::Auto | Algorithm::FastDoubling, Some(position)) => {
+                let (_, value, next) = self.precomputed.swap_remove(position);
+                (value, Some(next))
+            }
+            (Algorithm::Auto | Algorithm::FastDoubling, None) => {
+                // A cancelled calculation puts the previous pair back
+                let stepped = match self.last.take() {
+                    Some((k, value, Some(next))) => {
+                        let pair = (value, next);
+                        let stepped = if n >= k {
+                            fib_pair_from(k, &pair, n)
+                        } else {
+                            fib_pair_before(k, &pair, n)
+                        };
+                        if stepped.is_err() {
+                            self.last = Some((k, pair.0, Some(pair.1)));
+                        }
+                        stepped
+                    }
+                    last => {
+                        let stepped = fib_pair_cancellable(n);
+                        if stepped.is_err() {
+                            self.last = last;
+                        }
+                        stepped
+                    }
+                };
+                let (value, next) = stepped?;
+                (value, Some(next))
+            }
+            (algorithm, _) => (calculate_fibonacci_with(n, algorithm)?, None),
+        };
+        let duration = start_time.elapsed();
+
+        let (_, value, _) = self.last.insert((n, value, next));
+        Ok((value, duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+
+    fn fib(n: u64) -> BigUint {
+        calculate_fibonacci(n).unwrap()
+    }
+
+    #[test]
+    fn steps_from_the_last_pair_in_both_directions() {
+        let mut cache = SessionCache::new();
+        assert!(cache.last().is_none());
+        for n in [1000, 1001, 999, 2000, 10, 0, 50_000, 49_999] {
+            let (value, _) = cache.calculate(n, Algorithm::Auto).unwrap();
+            assert_eq!(value, &fib(n), "F({})", n);
+            let (index, value, next) = cache.last_pair().unwrap();
+            assert_eq!((index, value, next), (n, &fib(n), &fib(n + 1)));
+        }
+    }
+
+    #[test]
+    fn other_algorithms_leave_no_pair() {
+        let mut cache = SessionCache::new();
+        cache.calculate(300, Algorithm::FastDoubling).unwrap();
+        cache.calculate(301, Algorithm::Matrix).unwrap();
+        assert_eq!(cache.last(), Some((301, &fib(301))));
+        assert!(cache.last_pair().is_none());
+        assert!(cache.speculate(u64::MAX).is_none());
+
+        // Without a pair the next number is calculated from scratch
+        let (value, _) = cache.calculate(302, Algorithm::Auto).unwrap();
+        assert_eq!(value, &fib(302));
+    }
+
+    #[test]
+    fn only_the_targets_of_the_last_index_are_precomputed() {
+        let mut cache = SessionCache::new();
+        cache.calculate(100, Algorithm::Auto).unwrap();
+
+        // A wrong value shows that a precomputed pair is returned as it is
+        let marked = BigUint::from(42u32);
+        cache.add_precomputed(vec![
+            (101, marked.clone(), fib(102)),
+            (101, fib(101), fib(102)),
+            (200, fib(200), fib(201)),
+            (7, fib(7), fib(8)),
+        ]);
+        assert!(cache.is_precomputed(101, Algorithm::Auto));
+        assert!(cache.is_precomputed(200, Algorithm::FastDoubling));
+        assert!(!cache.is_precomputed(200, Algorithm::Matrix));
+        assert!(!cache.is_precomputed(7, Algorithm::Auto));
+
+        assert_eq!(cache.calculate(101, Algorithm::Auto).unwrap().0, &marked);
+        assert!(!cache.is_precomputed(101, Algorithm::Auto));
+
+        // The pairs of 100 are dropped once new ones for 101 arrive
+        cache.add_precomputed(vec![(102, fib(102), fib(103))]);
+        assert!(cache.is_precomputed(102, Algorithm::Auto));
+        assert!(!cache.is_precomputed(200, Algorithm::Auto));
+    }
+}
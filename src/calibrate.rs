@@ -0,0 +1,353 @@
+use crate::{
+    calculate_fibonacci, decimal::divide_and_conquer_string, fib_core::MAX_U64_INDEX, fib_u64,
+    multiply::parallel_multiply_from, BufferPool, SplitMix64, Thresholds,
+};
+use num_bigint::BigUint;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// The bit lengths the conversion methods are compared at, from 2^14 to 2^23 bits.
+const CONVERSION_SIZES: [u64; 10] = [
+    1 << 14,
+    1 << 15,
+    1 << 16,
+    1 << 17,
+    1 << 18,
+    1 << 19,
+    1 << 20,
+    1 << 21,
+    1 << 22,
+    1 << 23,
+];
+
+/// The bit lengths the squarings are compared at, from 2^17 to 2^23 bits.
+const SQUARE_SIZES: [u64; 7] = [
+    1 << 17,
+    1 << 18,
+    1 << 19,
+    1 << 20,
+    1 << 21,
+    1 << 22,
+    1 << 23,
+];
+
+/// The indices machine words are compared with fast doubling at.
+const WORD_INDICES: [u64; 6] = [16, 32, 48, 64, 80, MAX_U64_INDEX];
+
+/// Runs shorter than this are repeated until they take this long together.
+const MIN_MEASUREMENT: Duration = Duration::from_millis(20);
+
+/// What a [`Timer`] is asked to measure during [`calibrate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Measurement {
+    /// Converting a number of `bits` bits to decimal, by divide and conquer or with
+    /// `BigUint::to_string`.
+    Conversion { bits: u64, divide_and_conquer: bool },
+    /// Squaring a number of `bits` bits, split over the threads or as a single product.
+    Square { bits: u64, parallel: bool },
+    /// Calculating `F(index)`, in machine words or with fast doubling.
+    Word { index: u64, machine_word: bool },
+}
+
+/// Measures how long the runs of a calibration take.
+///
+/// [`WallClock`] runs them and measures the time, while a timer that returns durations for
+/// the [`Measurement`] alone makes the outcome of [`calibrate`] predictable.
+pub trait Timer {
+    /// Returns the duration of a single `run` of `measurement`.
+    fn time(&mut self, measurement: Measurement, run: &mut dyn FnMut()) -> Duration;
+}
+
+/// A [`Timer`] that runs every measurement and measures it with `Instant`.
+///
+/// Runs that are shorter than 20 ms are repeated until they take that long together, and
+/// the average of them is returned.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WallClock;
+
+impl Timer for WallClock {
+    fn time(&mut self, _measurement: Measurement, run: &mut dyn FnMut()) -> Duration {
+        let start_time = Instant::now();
+        let mut runs = 0;
+        loop {
+            run();
+            runs += 1;
+            let elapsed = start_time.elapsed();
+            if elapsed >= MIN_MEASUREMENT {
+                return elapsed / runs;
+            }
+        }
+    }
+}
+
+/// Measures the thresholds of [`Thresholds`] on the current machine.
+///
+/// Every threshold is found by timing both methods at growing sizes: the faster method for
+/// large numbers takes over from the first size where it wins twice in a row, or at the last
+/// size if it only wins there. If it never wins, the threshold is placed above the largest
+/// measured size. With `WallClock` the whole calibration takes a few seconds, most of them
+/// converting the largest numbers with `BigUint::to_string`.
+///
+/// * `conversion_bits` compares the divide and conquer conversion with `BigUint::to_string`
+///   at 2^14 to 2^23 bits.
+/// * `parallel_multiply_bits` compares squarings split over `threads` threads with single
+///   products at 2^17 to 2^23 bits. With a single thread nothing is split, so the current
+///   threshold is kept.
+/// * `word_index` compares machine words with fast doubling up to `F(93)`, and ends before
+///   the first index where machine words lose.
+///
+/// # Arguments
+/// * `threads` - The number of threads the squarings are split over, usually the number of
+///   threads of rayon.
+/// * `timer` - Measures the runs, [`WallClock`] for real measurements.
+///
+/// # Returns
+/// The measured `Thresholds`, which are not applied yet.
+pub fn calibrate(threads: usize, timer: &mut impl Timer) -> Thresholds {
+    let mut random = SplitMix64::new(0x5eed);
+    let mut pool = BufferPool::new(0);
+
+    let conversion_bits = crossover(&CONVERSION_SIZES, |bits| {
+        let value = random_number(bits, &mut random);
+        let divide_and_conquer = timer.time(
+            Measurement::Conversion {
+                bits,
+                divide_and_conquer: true,
+            },
+            &mut || {
+                black_box(divide_and_conquer_string(&value, &mut pool));
+            },
+        );
+        let to_string = timer.time(
+            Measurement::Conversion {
+                bits,
+                divide_and_conquer: false,
+            },
+            &mut || {
+                black_box(value.to_string());
+            },
+        );
+        divide_and_conquer < to_string
+    });
+
+    let parallel_multiply_bits = if threads < 2 {
+        Thresholds::current().parallel_multiply_bits
+    } else {
+        crossover(&SQUARE_SIZES, |bits| {
+            let value = random_number(bits, &mut random);
+            let parallel = timer.time(
+                Measurement::Square {
+                    bits,
+                    parallel: true,
+                },
+                &mut || {
+                    black_box(parallel_multiply_from(&value, &value, threads, 0));
+                },
+            );
+            let single = timer.time(
+                Measurement::Square {
+                    bits,
+                    parallel: false,
+                },
+                &mut || {
+                    black_box(&value * &value);
+                },
+            );
+            parallel < single
+        })
+    };
+
+    let mut word_index = MAX_U64_INDEX;
+    let mut previous = 0;
+    for index in WORD_INDICES {
+        let machine_word = timer.time(
+            Measurement::Word {
+                index,
+                machine_word: true,
+            },
+            &mut || {
+                black_box(fib_u64(black_box(index)));
+            },
+        );
+        let fast_doubling = timer.time(
+            Measurement::Word {
+                index,
+                machine_word: false,
+            },
+            &mut || {
+                let _ = black_box(calculate_fibonacci(black_box(index)));
+            },
+        );
+        if machine_word >= fast_doubling {
+            word_index = previous;
+            break;
+        }
+        previous = index;
+    }
+
+    Thresholds {
+        parallel_multiply_bits,
+        word_index,
+        conversion_bits,
+    }
+}
+
+/// Returns the size from which `wins` reports the method for large numbers as faster.
+///
+/// The sizes are measured in ascending order until that method wins at two sizes in a row,
+/// so a single noisy win does not decide and the largest sizes are often skipped.
+fn crossover(sizes: &[u64], mut wins: impl FnMut(u64) -> bool) -> u64 {
+    let mut first_win = None;
+    for &size in sizes {
+        if !wins(size) {
+            first_win = None;
+        } else if let Some(first_win) = first_win {
+            return first_win;
+        } else {
+            first_win = Some(size);
+        }
+    }
+    let largest = sizes.last().copied().unwrap_or(0);
+    first_win.unwrap_or(largest.saturating_mul(2))
+}
+
+/// Returns a pseudo-random number with exactly `bits` bits.
+fn random_number(bits: u64, random: &mut SplitMix64) -> BigUint {
+    let mut digits: Vec<u32> = (0..bits.div_ceil(32))
+        .map(|_| random.next_u64() as u32)
+        .collect();
+    if let Some(top) = digits.last_mut().filter(|_| !bits.is_multiple_of(32)) {
+        *top &= (1 << (bits % 32)) - 1;
+    }
+    let mut value = BigUint::new(digits);
+    value.set_bit(bits - 1, true);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A [`Timer`] that never runs anything and reports the durations of a table instead,
+    /// recording what it was asked to measure.
+    struct FakeTimer<F> {
+        duration: F,
+        measured: Vec<Measurement>,
+    }
+
+    impl<F: FnMut(Measurement) -> u64> FakeTimer<F> {
+        fn new(duration: F) -> FakeTimer<F> {
+            FakeTimer {
+                duration,
+                measured: Vec::new(),
+            }
+        }
+    }
+
+    impl<F: FnMut(Measurement) -> u64> Timer for FakeTimer<F> {
+        fn time(&mut self, measurement: Measurement, _run: &mut dyn FnMut()) -> Duration {
+            self.measured.push(measurement);
+            Duration::from_micros((self.duration)(measurement))
+        }
+    }
+
+    /// Durations where divide and conquer, split squarings and fast doubling win from the
+    /// given sizes on.
+    fn crossovers(
+        conversion_bits: u64,
+        parallel_bits: u64,
+        word_index: u64,
+    ) -> impl FnMut(Measurement) -> u64 {
+        move |measurement| match measurement {
+            Measurement::Conversion {
+                bits,
+                divide_and_conquer,
+            } => u64::from(divide_and_conquer == (bits < conversion_bits)) + 10,
+            Measurement::Square { bits, parallel } => {
+                u64::from(parallel == (bits < parallel_bits)) + 10
+            }
+            Measurement::Word {
+                index,
+                machine_word,
+            } => u64::from(machine_word == (index >= word_index)) + 10,
+        }
+    }
+
+    #[test]
+    fn thresholds_are_placed_at_the_crossovers() {
+        let mut timer = FakeTimer::new(crossovers(1 << 18, 1 << 20, 64));
+        let thresholds = calibrate(4, &mut timer);
+        assert_eq!(
+            thresholds,
+            Thresholds {
+                parallel_multiply_bits: 1 << 20,
+                word_index: 48,
+                conversion_bits: 1 << 18,
+            }
+        );
+    }
+
+    #[test]
+    fn methods_that_never_win_are_placed_above_the_largest_size() {
+        let mut timer = FakeTimer::new(crossovers(u64::MAX, u64::MAX, u64::MAX));
+        let thresholds = calibrate(8, &mut timer);
+        assert_eq!(thresholds.conversion_bits, 1 << 24);
+        assert_eq!(thresholds.parallel_multiply_bits, 1 << 24);
+        assert_eq!(thresholds.word_index, MAX_U64_INDEX);
+        assert_eq!(
+            timer.measured.len(),
+            2 * (CONVERSION_SIZES.len() + SQUARE_SIZES.len() + WORD_INDICES.len())
+        );
+    }
+
+    #[test]
+    fn fast_doubling_winning_right_away_leaves_no_machine_words() {
+        let mut timer = FakeTimer::new(crossovers(0, 0, 0));
+        let thresholds = calibrate(2, &mut timer);
+        assert_eq!(thresholds.word_index, 0);
+        assert_eq!(thresholds.conversion_bits, CONVERSION_SIZES[0]);
+        assert_eq!(thresholds.parallel_multiply_bits, SQUARE_SIZES[0]);
+        // Two wins in a row decide, so the larger sizes are skipped
+        assert_eq!(timer.measured.len(), 2 * (2 + 2 + 1));
+    }
+
+    #[test]
+    fn single_thread_keeps_the_parallel_threshold_without_squaring() {
+        let mut timer = FakeTimer::new(crossovers(1 << 16, 1 << 17, 80));
+        let thresholds = calibrate(1, &mut timer);
+        assert_eq!(
+            thresholds.parallel_multiply_bits,
+            Thresholds::current().parallel_multiply_bits
+        );
+        assert_eq!(thresholds.word_index, 64);
+        assert!(!timer
+            .measured
+            .iter()
+            .any(|measurement| matches!(measurement, Measurement::Square { .. })));
+    }
+
+    #[test]
+    fn a_single_win_does_not_decide_the_crossover() {
+        let sizes = [1, 2, 4, 8, 16, 32];
+        let wins = |pattern: [bool; 6]| {
+            crossover(&sizes, |size| {
+                pattern[sizes.iter().position(|&s| s == size).unwrap()]
+            })
+        };
+        assert_eq!(wins([false, true, false, true, true, true]), 8);
+        assert_eq!(wins([true, false, true, false, true, false]), 64);
+        assert_eq!(wins([false, false, false, false, false, true]), 32);
+        assert_eq!(wins([true, true, false, false, false, false]), 1);
+        assert_eq!(crossover(&[], |_| true), 0);
+    }
+
+    #[test]
+    fn random_numbers_have_exactly_the_requested_bits() {
+        let mut random = SplitMix64::new(100);
+        for bits in [1, 31, 32, 33, 64, 1000, 1 << 14] {
+            assert_eq!(random_number(bits, &mut random).bits(), bits);
+        }
+    }
+}
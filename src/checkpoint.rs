@@ -0,0 +1,425 @@
+use crate::{DoublingState, FibError};
+use num_bigint::BigUint;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The magic bytes at the start of a range checkpoint file.
+const RANGE_MAGIC: &[u8; 8] = b"FIBRANGE";
+
+/// The version of the range checkpoint format written by this program.
+const RANGE_VERSION: u32 = 1;
+
+/// The magic bytes at the start of a computation checkpoint file.
+const COMPUTATION_MAGIC: &[u8; 8] = b"FIBCKPT\0";
+
+/// The version of the computation checkpoint format written by this program.
+const COMPUTATION_VERSION: u32 = 1;
+
+/// Serializes an in-progress fast doubling computation into checkpoint bytes.
+///
+/// The bytes start with a magic header and a format version, followed by `n`, the number
+/// of remaining bits and both operands, and end with an integrity hash of everything
+/// before it.
+pub fn computation_checkpoint_bytes(state: &DoublingState) -> Vec<u8> {
+    let (a, b) = state.pair();
+    let mut encoder = Encoder::new(COMPUTATION_MAGIC, COMPUTATION_VERSION);
+    encoder.u64(state.n());
+    encoder.u64(state.remaining_bits() as u64);
+    encoder.biguint(a);
+    encoder.biguint(b);
+    encoder.finish()
+}
+
+/// Deserializes an in-progress fast doubling computation from checkpoint bytes.
+///
+/// # Returns
+/// A `Result<DoublingState, FibError>` with the restored state, or a
+/// `FibError::CorruptCheckpoint` if the header, version or integrity hash is wrong.
+pub fn computation_from_checkpoint_bytes(bytes: &[u8]) -> Result<DoublingState, FibError> {
+    let mut decoder = Decoder::new(bytes, COMPUTATION_MAGIC, COMPUTATION_VERSION)?;
+    let n = decoder.u64()?;
+    let remaining_bits = decoder.u64()?;
+    let a = decoder.biguint()?;
+    let b = decoder.biguint()?;
+    decoder.finish()?;
+
+    u32::try_from(remaining_bits)
+        .ok()
+        .and_then(|remaining_bits| DoublingState::from_parts(n, remaining_bits, a, b))
+        .ok_or_else(|| FibError::CorruptCheckpoint("invalid bit position".into()))
+}
+
+/// Calculates the nth Fibonacci number while periodically writing a checkpoint.
+///
+/// Whenever at least `interval` has passed since the last checkpoint, the state of the
+/// computation is written atomically to `path`. The checkpoint file is removed once the
+/// calculation has finished.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `path` - The path of the checkpoint file.
+/// * `interval` - The minimum time between two checkpoints.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` with the nth Fibonacci number, or a `FibError` if a
+/// checkpoint could not be written.
+pub fn calculate_fibonacci_checkpointed(
+    n: u64,
+    path: &Path,
+    interval: Duration,
+) -> Result<BigUint, FibError> {
+    run_checkpointed(DoublingState::new(n), path, interval)
+}
+
+/// Continues a Fibonacci calculation from a checkpoint file.
+///
+/// The calculation keeps writing checkpoints to the same file and removes it once the
+/// calculation has finished.
+///
+/// # Arguments
+/// * `path` - The path of the checkpoint file.
+/// * `interval` - The minimum time between two checkpoints.
+///
+/// # Returns
+/// A `Result<(u64, BigUint), FibError>` with the index and the calculated Fibonacci number,
+/// or a `FibError` if the checkpoint is corrupt or could not be read or written.
+pub fn resume_fibonacci(path: &Path, interval: Duration) -> Result<(u64, BigUint), FibError> {
+    let state = computation_from_checkpoint_bytes(&fs::read(path)?)?;
+    let n = state.n();
+    Ok((n, run_checkpointed(state, path, interval)?))
+}
+
+fn run_checkpointed(
+    mut state: DoublingState,
+    path: &Path,
+    interval: Duration,
+) -> Result<BigUint, FibError> {
+    let mut last_checkpoint = Instant::now();
+
+    while !state.is_finished() {
+        state.step();
+
+        if !state.is_finished() && last_checkpoint.elapsed() >= interval {
+            write_atomically(path, &computation_checkpoint_bytes(&state))?;
+            last_checkpoint = Instant::now();
+        }
+    }
+
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(state.into_value())
+}
+
+/// The progress of a range computation that is written to an output file.
+///
+/// The checkpoint records the last index that was completely written, the length of the
+/// output file at that point and the Fibonacci pair needed to continue the sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeCheckpoint {
+    pub start: u64,
+    pub end: u64,
+    pub last_index: u64,
+    pub output_len: u64,
+    pub current: BigUint,
+    pub next: BigUint,
+}
+
+impl RangeCheckpoint {
+    /// Serializes the checkpoint into bytes, followed by an integrity hash.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(RANGE_MAGIC, RANGE_VERSION);
+        encoder.u64(self.start);
+        encoder.u64(self.end);
+        encoder.u64(self.last_index);
+        encoder.u64(self.output_len);
+        encoder.biguint(&self.current);
+        encoder.biguint(&self.next);
+        encoder.finish()
+    }
+
+    /// Deserializes a checkpoint, verifying its header and integrity hash.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes of a checkpoint file.
+    ///
+    /// # Returns
+    /// A `Result<RangeCheckpoint, FibError>` with the checkpoint, or a
+    /// `FibError::CorruptCheckpoint` if the bytes are damaged.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RangeCheckpoint, FibError> {
+        let mut decoder = Decoder::new(bytes, RANGE_MAGIC, RANGE_VERSION)?;
+        let checkpoint = RangeCheckpoint {
+            start: decoder.u64()?,
+            end: decoder.u64()?,
+            last_index: decoder.u64()?,
+            output_len: decoder.u64()?,
+            current: decoder.biguint()?,
+            next: decoder.biguint()?,
+        };
+        decoder.finish()?;
+        Ok(checkpoint)
+    }
+
+    /// Reads and verifies a checkpoint file.
+    pub fn read(path: &Path) -> Result<RangeCheckpoint, FibError> {
+        RangeCheckpoint::from_bytes(&fs::read(path)?)
+    }
+
+    /// Writes the checkpoint file atomically.
+    pub fn write(&self, path: &Path) -> Result<(), FibError> {
+        write_atomically(path, &self.to_bytes())
+    }
+}
+
+/// Returns the path of the checkpoint file that belongs to an output file.
+pub fn checkpoint_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".ckpt");
+    PathBuf::from(path)
+}
+
+/// Writes a file atomically by writing a temporary file and renaming it.
+///
+/// The temporary file is synced to disk before the rename, so after a crash the target
+/// path holds either the previous or the new contents, never a partial write.
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), FibError> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let mut file = File::create(&temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Calculates the 64-bit FNV-1a hash of the given bytes.
+pub(crate) fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Builds a checkpoint file consisting of a header, little-endian fields and a hash.
+pub(crate) struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn new(magic: &[u8; 8], version: u32) -> Encoder {
+        let mut bytes = magic.to_vec();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        Encoder { bytes }
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn biguint(&mut self, value: &BigUint) {
+        let value_bytes = value.to_bytes_le();
+        self.u64(value_bytes.len() as u64);
+        self.bytes.extend_from_slice(&value_bytes);
+    }
+
+    /// Appends the integrity hash of everything written so far and returns the bytes.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let hash = fnv1a_hash(&self.bytes);
+        self.u64(hash);
+        self.bytes
+    }
+}
+
+/// Reads the fields of a checkpoint file written by an `Encoder`.
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Verifies the header and integrity hash of the checkpoint bytes.
+    pub(crate) fn new(
+        bytes: &'a [u8],
+        magic: &[u8; 8],
+        version: u32,
+    ) -> Result<Decoder<'a>, FibError> {
+        if bytes.len() < magic.len() + 4 + 8 || &bytes[..magic.len()] != magic {
+            return Err(FibError::CorruptCheckpoint(
+                "unrecognized file header".into(),
+            ));
+        }
+
+        let (content, hash) = bytes.split_at(bytes.len() - 8);
+        if fnv1a_hash(content) != u64::from_le_bytes(hash.try_into().unwrap()) {
+            return Err(FibError::CorruptCheckpoint(
+                "integrity hash does not match".into(),
+            ));
+        }
+
+        let mut decoder = Decoder {
+            bytes: content,
+            position: magic.len(),
+        };
+        let found_version = decoder.u32()?;
+        if found_version != version {
+            return Err(FibError::CorruptCheckpoint(format!(
+                "unsupported version {} (expected {})",
+                found_version, version
+            )));
+        }
+
+        Ok(decoder)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], FibError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| FibError::CorruptCheckpoint("unexpected end of file".into()))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, FibError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, FibError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn biguint(&mut self) -> Result<BigUint, FibError> {
+        let len = usize::try_from(self.u64()?)
+            .map_err(|_| FibError::CorruptCheckpoint("value is too large".into()))?;
+        Ok(BigUint::from_bytes_le(self.take(len)?))
+    }
+
+    /// Checks that every field has been read.
+    pub(crate) fn finish(self) -> Result<(), FibError> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(FibError::CorruptCheckpoint(
+                "unexpected trailing data".into(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+
+    const N: u64 = 123_457;
+
+    /// Returns the path of a checkpoint file of its own for every test, as they run in
+    /// parallel.
+    fn checkpoint_file(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "fibonacci-checkpoint-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+        directory.join("fib.ckpt")
+    }
+
+    /// Returns the state of a calculation of `F(N)` that was stopped after `steps` steps.
+    fn stopped_after(steps: usize) -> DoublingState {
+        let mut state = DoublingState::new(N);
+        for _ in 0..steps {
+            state.step();
+        }
+        state
+    }
+
+    #[test]
+    fn states_round_trip_through_their_bytes() {
+        for steps in [0, 1, 10, 17] {
+            let state = stopped_after(steps);
+            let bytes = computation_checkpoint_bytes(&state);
+            assert_eq!(computation_from_checkpoint_bytes(&bytes).unwrap(), state);
+        }
+    }
+
+    #[test]
+    fn resuming_at_every_step_calculates_the_same_number() {
+        let expected = calculate_fibonacci(N).unwrap();
+        let path = checkpoint_file("resume");
+        for steps in 0..=DoublingState::new(N).remaining_bits() as usize {
+            fs::write(&path, computation_checkpoint_bytes(&stopped_after(steps))).unwrap();
+            let (n, value) = resume_fibonacci(&path, Duration::MAX).unwrap();
+            assert_eq!((n, &value), (N, &expected), "resumed after {} steps", steps);
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn checkpointed_calculation_resumes_from_its_own_checkpoint() {
+        let path = checkpoint_file("own");
+        assert_eq!(
+            calculate_fibonacci_checkpointed(N, &path, Duration::ZERO).unwrap(),
+            calculate_fibonacci(N).unwrap()
+        );
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn damaged_checkpoints_are_rejected() {
+        let bytes = computation_checkpoint_bytes(&stopped_after(12));
+        for len in [0, 8, 12, 20, bytes.len() / 2, bytes.len() - 1] {
+            assert!(
+                matches!(
+                    computation_from_checkpoint_bytes(&bytes[..len]),
+                    Err(FibError::CorruptCheckpoint(_))
+                ),
+                "checkpoint truncated to {} bytes",
+                len
+            );
+        }
+
+        let mut flipped = bytes.clone();
+        flipped[30] ^= 1;
+        assert!(matches!(
+            computation_from_checkpoint_bytes(&flipped),
+            Err(FibError::CorruptCheckpoint(_))
+        ));
+
+        let range = RangeCheckpoint {
+            start: 0,
+            end: 10,
+            last_index: 5,
+            output_len: 20,
+            current: BigUint::from(5u32),
+            next: BigUint::from(8u32),
+        };
+        assert!(computation_from_checkpoint_bytes(&range.to_bytes()).is_err());
+        assert_eq!(
+            RangeCheckpoint::from_bytes(&range.to_bytes()).unwrap(),
+            range
+        );
+    }
+
+    #[test]
+    fn invalid_bit_position_is_rejected() {
+        let mut encoder = Encoder::new(COMPUTATION_MAGIC, COMPUTATION_VERSION);
+        encoder.u64(5);
+        encoder.u64(4);
+        encoder.biguint(&BigUint::ZERO);
+        encoder.biguint(&BigUint::from(1u32));
+        assert!(matches!(
+            computation_from_checkpoint_bytes(&encoder.finish()),
+            Err(FibError::CorruptCheckpoint(_))
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::OutputOptions;
+use fibonacci_sequence::OutputFormat;
+use num_bigint::BigUint;
+
+/// Copies a calculated Fibonacci number to the system clipboard and confirms it.
+///
+/// The value is copied the way it is printed: in scientific notation above the threshold
+/// and shortened to its first and last digits above the digit limit, unless `full` is set,
+/// which copies every digit. The confirmation is left out for quiet output, templates and
+/// JSON, so it never mixes into results that are processed further.
+///
+/// # Arguments
+/// * `value` - The Fibonacci number to copy.
+/// * `full` - Whether every digit is copied, regardless of the threshold and the digit limit.
+/// * `output` - The options that control how the value is rendered.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if there is no clipboard to copy to.
+pub fn copy_value(value: &BigUint, full: bool, output: &OutputOptions) -> Result<(), String> {
+    let value_format = &output.settings.value_format;
+    let text = if full {
+        value_format.render(value)
+    } else if value_format.use_scientific(value) {
+        value_format.scientific(value)
+    } else {
+        value_format.render_limited(value)
+    };
+
+    set_clipboard(&text)?;
+
+    if !output.quiet && output.template.is_none() && output.settings.format == OutputFormat::Text {
+        let characters = text.chars().count() as u64;
+        println!(
+            "Copied {} {} to the clipboard",
+            value_format.count(characters),
+            if characters == 1 {
+                "character"
+            } else {
+                "characters"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Places the text on the system clipboard.
+#[cfg(feature = "clipboard")]
+fn set_clipboard(text: &str) -> Result<(), String> {
+    // Headless systems have no clipboard at all, which is reported instead of panicking
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|error| format!("Error: no clipboard is available ({})", error))?;
+    clipboard
+        .set_text(text)
+        .map_err(|error| format!("Error: could not copy to the clipboard ({})", error))
+}
+
+/// Fails, as the clipboard was not compiled in.
+#[cfg(not(feature = "clipboard"))]
+fn set_clipboard(_text: &str) -> Result<(), String> {
+    Err("Error: copying to the clipboard needs the clipboard feature".to_string())
+}
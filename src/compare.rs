@@ -0,0 +1,124 @@
+use crate::{
+    digit_count,
+    fib_core::fib_divides,
+    fibonacci::{fib_pair_cancellable, fib_pair_from},
+    format::{ratio_scientific_notation_with, Grouping},
+    FibError,
+};
+use num_bigint::BigUint;
+
+/// The result of comparing the Fibonacci numbers at two indices.
+///
+/// The indices are ordered on construction, so `smaller_index <= larger_index` always
+/// holds regardless of the order they were given in.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub smaller_index: u64,
+    pub larger_index: u64,
+    pub smaller: BigUint,
+    pub larger: BigUint,
+    pub smaller_digits: u64,
+    pub larger_digits: u64,
+}
+
+impl Comparison {
+    /// Returns how many more decimal digits the larger Fibonacci number has.
+    pub fn digit_difference(&self) -> u64 {
+        self.larger_digits - self.smaller_digits
+    }
+
+    /// Formats the ratio `F(larger) / F(smaller)` in scientific notation.
+    ///
+    /// # Arguments
+    /// * `significant_digits` - The number of significant digits of the mantissa.
+    /// * `grouping` - The style of the separators in the exponent.
+    ///
+    /// # Returns
+    /// An `Option<String>` with the ratio, or `None` when `F(smaller)` is zero.
+    pub fn ratio(&self, significant_digits: usize, grouping: Grouping) -> Option<String> {
+        ratio_scientific_notation_with(&self.larger, &self.smaller, significant_digits, grouping)
+    }
+
+    /// Checks whether `F(smaller)` divides `F(larger)`.
+    ///
+    /// This uses the divisibility property of the Fibonacci sequence instead of a big-number
+    /// division, see [`fib_divides`].
+    pub fn divides(&self) -> bool {
+        fib_divides(self.smaller_index, self.larger_index)
+    }
+}
+
+/// Calculates and compares the Fibonacci numbers at two indices.
+///
+/// The Fibonacci pair of the smaller index is calculated first and then extended to the
+/// larger index, so work is shared between both calculations where possible.
+///
+/// # Arguments
+/// * `first_index` - One of the indices to compare.
+/// * `second_index` - The other index to compare.
+///
+/// # Returns
+/// A `Result<Comparison, FibError>` holding both Fibonacci numbers and their digit counts,
+/// or `FibError::Cancelled` if [`request_cancel`](crate::request_cancel) was called before
+/// they were calculated.
+pub fn compare_fibonacci(first_index: u64, second_index: u64) -> Result<Comparison, FibError> {
+    let smaller_index = first_index.min(second_index);
+    let larger_index = first_index.max(second_index);
+
+    let smaller_pair = fib_pair_cancellable(smaller_index)?;
+    let (larger, _) = fib_pair_from(smaller_index, &smaller_pair, larger_index)?;
+    let (smaller, _) = smaller_pair;
+
+    Ok(Comparison {
+        smaller_index,
+        larger_index,
+        smaller_digits: digit_count(&smaller),
+        larger_digits: digit_count(&larger),
+        smaller,
+        larger,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+
+    #[test]
+    fn indices_are_ordered_and_both_numbers_calculated() {
+        for (first, second) in [(0, 0), (10, 20), (20, 10), (1000, 30_000), (7, 7)] {
+            let comparison = compare_fibonacci(first, second).unwrap();
+            let (smaller, larger) = (first.min(second), first.max(second));
+            assert_eq!(comparison.smaller_index, smaller);
+            assert_eq!(comparison.larger_index, larger);
+            assert_eq!(comparison.smaller, calculate_fibonacci(smaller).unwrap());
+            assert_eq!(comparison.larger, calculate_fibonacci(larger).unwrap());
+            assert_eq!(
+                comparison.digit_difference(),
+                comparison.larger.to_string().len() as u64
+                    - comparison.smaller.to_string().len() as u64
+            );
+            assert_eq!(
+                comparison.divides(),
+                comparison.smaller != BigUint::ZERO
+                    && (&comparison.larger % &comparison.smaller) == BigUint::ZERO
+                    || comparison.larger == BigUint::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn the_ratio_needs_a_nonzero_divisor() {
+        let comparison = compare_fibonacci(20, 10).unwrap();
+        assert_eq!(
+            comparison.ratio(5, Grouping::Comma).as_deref(),
+            Some("1.2300e+2")
+        );
+        assert!(comparison.divides());
+        assert!(!compare_fibonacci(10, 15).unwrap().divides());
+        assert_eq!(
+            compare_fibonacci(0, 5).unwrap().ratio(5, Grouping::Comma),
+            None
+        );
+    }
+}
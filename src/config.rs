@@ -0,0 +1,209 @@
+use crate::{FibError, Grouping, SciThreshold};
+use serde::Deserialize;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the configuration file.
+pub const CONFIG_FILE: &str = "fib.toml";
+
+/// Default options read from a `fib.toml` configuration file.
+///
+/// Every option may be left out, and command-line flags take precedence over the options
+/// of the file. A file looks like this:
+///
+/// ```toml
+/// base = 16
+/// separator = "indian"
+/// threshold = "1e50"
+/// max-index = 100_000_000
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The base full results are printed in (2 to 36).
+    pub base: Option<u32>,
+    /// The style of the separators in indices, counts, exponents and grouped results.
+    #[serde(with = "grouping")]
+    pub separator: Option<Grouping>,
+    /// The decimal results that are shown in scientific notation, written like `"1e50"`,
+    /// `"none"` or `"always"`, or as the exponent alone.
+    #[serde(with = "threshold")]
+    pub threshold: Option<SciThreshold>,
+    /// The largest index that is calculated, larger ones are rejected.
+    pub max_index: Option<u64>,
+}
+
+impl Config {
+    /// Parses the contents of a configuration file.
+    ///
+    /// # Arguments
+    /// * `text` - The TOML text of the configuration.
+    ///
+    /// # Returns
+    /// A `Result<Config, FibError>` with the configuration, or a `FibError::InvalidConfig`
+    /// for malformed TOML, unknown options and invalid values.
+    pub fn parse(text: &str) -> Result<Config, FibError> {
+        let config: Config =
+            toml::from_str(text).map_err(|error| FibError::InvalidConfig(error.to_string()))?;
+
+        if let Some(base) = config.base.filter(|base| !(2..=36).contains(base)) {
+            return Err(FibError::InvalidConfig(format!(
+                "base {} is not from 2 to 36",
+                base
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Reads the configuration file at `path`.
+    ///
+    /// # Returns
+    /// A `Result<Config, FibError>` with the configuration, a `FibError::Io` if the file
+    /// could not be read, or a `FibError::InvalidConfig` naming the file if it is invalid.
+    pub fn load(path: &Path) -> Result<Config, FibError> {
+        let text = fs::read_to_string(path)?;
+        Config::parse(&text).map_err(|error| match error {
+            FibError::InvalidConfig(reason) => {
+                FibError::InvalidConfig(format!("{}: {}", path.display(), reason))
+            }
+            error => error,
+        })
+    }
+
+    /// Reads the first configuration file that exists.
+    ///
+    /// `fib.toml` is looked up in the current directory first and then in
+    /// `$XDG_CONFIG_HOME`, which defaults to `~/.config`.
+    ///
+    /// # Returns
+    /// A `Result<Config, FibError>` with the configuration, or the built-in defaults when
+    /// there is no configuration file, or the error of a file that could not be loaded.
+    pub fn discover() -> Result<Config, FibError> {
+        let candidates = [Some(PathBuf::new()), config_dir()];
+
+        for directory in candidates.into_iter().flatten() {
+            match Config::load(&directory.join(CONFIG_FILE)) {
+                Err(FibError::Io(error)) if error.kind() == io::ErrorKind::NotFound => continue,
+                loaded => return loaded,
+            }
+        }
+        Ok(Config::default())
+    }
+}
+
+/// Returns the configuration directory, `$XDG_CONFIG_HOME` or else `~/.config`.
+///
+/// # Returns
+/// An `Option<PathBuf>` with the directory, or `None` if neither `XDG_CONFIG_HOME` nor
+/// `HOME` is set.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+}
+
+/// Deserializes a `SciThreshold` from a string like `"1e50"` or an exponent like `50`.
+mod threshold {
+    use crate::SciThreshold;
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Written {
+        Exponent(u32),
+        Text(String),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SciThreshold>, D::Error> {
+        match Written::deserialize(deserializer)? {
+            Written::Exponent(exponent) => Ok(Some(SciThreshold::Digits(exponent))),
+            Written::Text(text) => text.parse().map(Some).map_err(D::Error::custom),
+        }
+    }
+}
+
+/// Deserializes a `Grouping` from its name, like `"thin-space"`.
+mod grouping {
+    use crate::Grouping;
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Grouping>, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map(Some).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(text: &str) -> String {
+        match Config::parse(text) {
+            Err(FibError::InvalidConfig(reason)) => reason,
+            other => panic!("{:?} for {}", other, text),
+        }
+    }
+
+    #[test]
+    fn every_option_is_read() {
+        let config = Config::parse(
+            "base = 16\nseparator = \"indian\"\nthreshold = \"1e50\"\nmax-index = 100_000_000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                base: Some(16),
+                separator: Some(Grouping::Indian),
+                threshold: Some(SciThreshold::Digits(50)),
+                max_index: Some(100_000_000),
+            }
+        );
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert_eq!(
+            Config::parse("threshold = 20").unwrap().threshold,
+            Some(SciThreshold::Digits(20))
+        );
+        assert_eq!(
+            Config::parse("threshold = \"none\"").unwrap().threshold,
+            Some(SciThreshold::Never)
+        );
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        assert_eq!(invalid("base = 37"), "base 37 is not from 2 to 36");
+        assert!(invalid("colour = true").contains("unknown field `colour`"));
+        assert!(invalid("separator = \"dots\"").contains("dots"));
+        assert!(invalid("threshold = \"soon\"").contains("soon"));
+        assert!(invalid("max-index = -1").contains("max-index"));
+        assert!(invalid("base = ").contains("base"));
+    }
+
+    #[test]
+    fn loaded_files_are_named_in_errors() {
+        let path =
+            std::env::temp_dir().join(format!("fibonacci-config-{}.toml", std::process::id()));
+        fs::write(&path, "base = 8\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().base, Some(8));
+
+        fs::write(&path, "base = 1\n").unwrap();
+        let Err(FibError::InvalidConfig(reason)) = Config::load(&path) else {
+            panic!("base 1 is invalid");
+        };
+        assert_eq!(
+            reason,
+            format!("{}: base 1 is not from 2 to 36", path.display())
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(Config::load(&path), Err(FibError::Io(_))));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{digit_count, FibError, ValueFormat};
+use num_bigint::BigUint;
+use std::{borrow::Cow, io::Write};
+
+/// The header row of the CSV output.
+pub const CSV_HEADER: &str = "index,value,scientific,digit_count";
+
+/// Quotes a CSV field according to RFC 4180.
+///
+/// Fields containing a comma, a double quote or a line break are enclosed in double quotes,
+/// with double quotes inside the field doubled. Other fields are returned unchanged.
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Formats a single CSV row for a Fibonacci number.
+///
+/// Values that would be shown in scientific notation leave the `value` column empty and
+/// fill the `scientific` column instead, so a row never holds a megabyte-long cell. Smaller
+/// values fill the `value` column and leave `scientific` empty. The row ends with CRLF as
+/// required by RFC 4180.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number.
+/// * `value` - The Fibonacci number.
+/// * `value_format` - The format of the `value` column.
+///
+/// # Returns
+/// A `String` with the CSV row.
+pub fn csv_row(index: u64, value: &BigUint, value_format: &ValueFormat) -> String {
+    let (full, scientific) = if value_format.use_scientific(value) {
+        (String::new(), value_format.scientific(value))
+    } else {
+        (value_format.render(value), String::new())
+    };
+
+    format!(
+        "{},{},{},{}\r\n",
+        index,
+        csv_field(&full),
+        csv_field(&scientific),
+        digit_count(value)
+    )
+}
+
+/// Writes a sequence of Fibonacci numbers as CSV, starting with the header row.
+///
+/// # Arguments
+/// * `sequence` - The `(index, value)` pairs to write.
+/// * `value_format` - The format of the `value` column.
+/// * `writer` - The writer the CSV is written to.
+///
+/// # Returns
+/// A `Result<(), FibError>` which is an error if writing failed.
+pub fn write_csv<W: Write>(
+    sequence: impl Iterator<Item = (u64, BigUint)>,
+    value_format: &ValueFormat,
+    writer: &mut W,
+) -> Result<(), FibError> {
+    write!(writer, "{}\r\n", CSV_HEADER)?;
+    for (index, value) in sequence {
+        writer.write_all(csv_row(index, &value, value_format).as_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FibIter;
+
+    #[test]
+    fn fields_are_quoted_only_when_needed() {
+        assert!(matches!(csv_field("12345"), Cow::Borrowed("12345")));
+        assert_eq!(csv_field("1,234"), "\"1,234\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn large_values_fill_the_scientific_column() {
+        let value_format = ValueFormat {
+            group: true,
+            ..ValueFormat::default()
+        };
+        let mut csv = Vec::new();
+        write_csv(FibIter::new(169).take(2), &value_format, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "index,value,scientific,digit_count\r\n\
+             169,\"93,202,207,781,383,214,849,429,075,266,681,969\",,35\r\n\
+             170,,1.5080e+35,36\r\n"
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use crate::{threads::join, tuning::conversion_bits, BufferPool};
+use dashu_int::{
+    ops::{BitTest, DivRem},
+    UBig,
+};
+use num_bigint::BigUint;
+use std::{
+    io::{self, BufWriter, Write},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+/// Numbers with fewer bits are converted with `BigUint::to_string`, which is faster for
+/// them than splitting (about 2.5 million digits).
+///
+/// This is the default of [`Thresholds::conversion_bits`], which `calibrate` measures on
+/// the current machine.
+pub const DIVIDE_AND_CONQUER_BITS: u64 = 1 << 23;
+
+/// Parts with fewer bits are converted directly instead of being split further.
+const LEAF_BITS: usize = 8192;
+
+/// The smallest power of ten the numbers are split at is `10^(2^FIRST_SPLIT)`, which has
+/// fewer bits than [`LEAF_BITS`].
+const FIRST_SPLIT: usize = 11;
+
+/// The most bits the powers of [`POWERS_OF_TEN`] take together (32 MiB).
+const MAX_CACHED_BITS: usize = 1 << 28;
+
+/// The powers of ten shared by the decimal conversion and the formatting of results.
+pub(crate) static POWERS_OF_TEN: PowerOfTenCache = PowerOfTenCache::new(MAX_CACHED_BITS);
+
+/// Parts with more bits are split with their two halves converted in parallel, while smaller
+/// ones are not worth handing to another thread.
+const PARALLEL_BITS: usize = 1 << 20;
+
+/// The zeros that the padding of streamed parts is written from.
+const ZEROS: &[u8] = &[b'0'; 256];
+
+/// Converts a `BigUint` number to its decimal digits in subquadratic time.
+///
+/// The conversion of `BigUint::to_string` repeatedly divides the whole number by a single
+/// limb, so its duration grows with the square of the length and it takes far longer than
+/// calculating a Fibonacci number of tens of millions of digits. Larger numbers are
+/// converted by divide and conquer instead: the powers `10^(2^k)` are taken from
+/// [`POWERS_OF_TEN`], which calculates them by repeated squaring only once. The number is
+/// split with a single division at the largest power below its square root, and both halves
+/// are converted recursively, the lower one padded with leading zeros to the exact number
+/// of digits of the power. The divisions use the divide-and-conquer division of `dashu`,
+/// which makes the whole conversion subquadratic.
+///
+/// The halves are independent, so large ones are converted in parallel with `rayon::join`
+/// when the `parallel` feature is enabled.
+/// Every part is written straight into its own range of a single buffer, which is sized
+/// for the largest number of digits the bit length allows, so only the few leading zeros
+/// of that estimate are removed afterwards.
+///
+/// # Arguments
+/// * `value` - The number to convert.
+///
+/// # Returns
+/// A `String` with the decimal digits of `value`, the same as `value.to_string()`.
+pub fn to_decimal_string(value: &BigUint) -> String {
+    to_decimal_string_in(value, &mut BufferPool::new(0))
+}
+
+/// Converts a `BigUint` number to its decimal digits like [`to_decimal_string`], taking the
+/// large buffers of the conversion from a pool.
+///
+/// The copy of the number for the division is handed back to the pool afterwards, while
+/// the buffer of the digits is the returned `String`, which can be handed back with
+/// `pool.recycle(digits.into_bytes())` once it is no longer needed.
+///
+/// # Arguments
+/// * `value` - The number to convert.
+/// * `pool` - The pool the buffers are taken from.
+///
+/// # Returns
+/// A `String` with the decimal digits of `value`, the same as `value.to_string()`.
+pub fn to_decimal_string_in(value: &BigUint, pool: &mut BufferPool) -> String {
+    if value.bits() < conversion_bits() {
+        return value.to_string();
+    }
+    divide_and_conquer_string(value, pool)
+}
+
+/// Converts a `BigUint` number to its decimal digits by divide and conquer regardless of its
+/// size, which the calibration compares with `BigUint::to_string`.
+pub(crate) fn divide_and_conquer_string(value: &BigUint, pool: &mut BufferPool) -> String {
+    let mut bytes = pool.take(value.bits().div_ceil(64) as usize * 8);
+    for digit in value.iter_u64_digits() {
+        bytes.extend_from_slice(&digit.to_le_bytes());
+    }
+    let value = UBig::from_le_bytes(&bytes);
+    pool.recycle(bytes);
+    let powers = splitting_powers(&value);
+
+    // One more digit than the estimate guards against its rounding
+    let width = (value.bit_len() as f64 * std::f64::consts::LOG10_2) as usize + 2;
+    let mut digits = pool.take(width);
+    digits.resize(width, b'0');
+    write_part(value, &powers, &mut digits);
+
+    // Zero keeps its single digit
+    let leading_zeros = digits.iter().take_while(|digit| **digit == b'0').count();
+    digits.drain(..leading_zeros.min(digits.len() - 1));
+    String::from_utf8(digits).expect("the conversion only writes decimal digits")
+}
+
+/// Writes the decimal digits of a `BigUint` number to a writer while they are converted.
+///
+/// This is [`to_decimal_string`] without the string: the parts are converted one after the
+/// other from the most significant one on, and every finished leaf of the divide and
+/// conquer is written right away, so the digits are never held in memory all at once and
+/// the memory stays close to the size of the number itself. As the digits have to arrive
+/// in order, the halves are not converted in parallel.
+///
+/// The digits are buffered in chunks of 64 KiB, so `writer` does not need to be buffered.
+/// Writing stops at the first error, which includes a closed pipe.
+///
+/// # Arguments
+/// * `value` - The number to write.
+/// * `writer` - The writer the digits are written to, without a trailing newline.
+///
+/// # Returns
+/// An `io::Result<u64>` with the number of digits written, or the error of the writer.
+pub fn write_decimal(value: &BigUint, writer: &mut (impl Write + ?Sized)) -> io::Result<u64> {
+    if value.bits() < conversion_bits() {
+        let digits = value.to_string();
+        writer.write_all(digits.as_bytes())?;
+        return Ok(digits.len() as u64);
+    }
+    stream_decimal(value, writer)
+}
+
+/// Writes the decimal digits of a `BigUint` number to a writer by divide and conquer
+/// regardless of its size, like [`write_decimal`] does for large numbers.
+fn stream_decimal(value: &BigUint, writer: &mut (impl Write + ?Sized)) -> io::Result<u64> {
+    let value = UBig::from_le_bytes(&value.to_bytes_le());
+    let powers = splitting_powers(&value);
+    let mut writer = BufWriter::with_capacity(1 << 16, writer);
+    let mut written = 0;
+    stream_part(value, &powers, None, &mut writer, &mut written)?;
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Returns the powers `10^(2^k)` from `k = FIRST_SPLIT` up to the first one above the
+/// square root of `value`.
+fn splitting_powers(value: &UBig) -> Vec<Arc<UBig>> {
+    let digits = (value.bit_len() as f64 * std::f64::consts::LOG10_2) as usize + 1;
+    let count = (digits / 2).max(1).ilog2() as usize + 2;
+    let mut powers = POWERS_OF_TEN.squares(count.max(FIRST_SPLIT + 1));
+    powers.drain(..FIRST_SPLIT);
+    powers
+}
+
+/// A thread-safe cache of the powers `10^(2^k)`, capped in memory.
+///
+/// Every other power of ten is a product of these powers, one for every set bit of its
+/// exponent, so they are the only ones worth keeping. They are calculated by repeated
+/// squaring on first use and kept as long as they fit into the cap together, larger ones
+/// are calculated again whenever they are needed.
+pub(crate) struct PowerOfTenCache {
+    powers: RwLock<Vec<Arc<UBig>>>,
+    max_bits: usize,
+}
+
+impl PowerOfTenCache {
+    /// Creates an empty cache that keeps at most `max_bits` bits of powers.
+    pub(crate) const fn new(max_bits: usize) -> PowerOfTenCache {
+        PowerOfTenCache {
+            powers: RwLock::new(Vec::new()),
+            max_bits,
+        }
+    }
+
+    /// Returns the powers `10^(2^k)` for `k` from 0 to `count - 1`.
+    pub(crate) fn squares(&self, count: usize) -> Vec<Arc<UBig>> {
+        // The powers are only ever appended, so a poisoned lock still holds valid ones
+        let cached = self.powers.read().unwrap_or_else(PoisonError::into_inner);
+        if cached.len() >= count {
+            return cached[..count].to_vec();
+        }
+        drop(cached);
+
+        let mut cached = self.powers.write().unwrap_or_else(PoisonError::into_inner);
+        if cached.is_empty() {
+            cached.push(Arc::new(UBig::from(10u32)));
+        }
+        let mut cached_bits: usize = cached.iter().map(|power| power.bit_len()).sum();
+        let mut powers = cached.clone();
+        while powers.len() < count {
+            let power = Arc::new(powers[powers.len() - 1].sqr());
+            if cached.len() == powers.len() && cached_bits + power.bit_len() <= self.max_bits {
+                cached_bits += power.bit_len();
+                cached.push(power.clone());
+            }
+            powers.push(power);
+        }
+        powers.truncate(count);
+        powers
+    }
+
+    /// Calculates `10^exponent` as the product of the powers `10^(2^k)` of its set bits.
+    pub(crate) fn power(&self, exponent: u64) -> BigUint {
+        if let Some(power) = u32::try_from(exponent)
+            .ok()
+            .and_then(|exponent| 10u64.checked_pow(exponent))
+        {
+            return BigUint::from(power);
+        }
+        let squares = self.squares((u64::BITS - exponent.leading_zeros()) as usize);
+        let power = squares
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| (exponent >> k) & 1 == 1)
+            .fold(UBig::ONE, |power, (_, square)| power * square.as_ref());
+        BigUint::from_bytes_le(&power.to_le_bytes())
+    }
+}
+
+/// Writes the digits of `value` to `writer` in order, padded with zeros to `width` if given.
+///
+/// # Arguments
+/// * `value` - The part to write, which has at most `width` digits.
+/// * `powers` - The powers `10^(2^k)` that are still available for splitting.
+/// * `width` - The exact number of digits of the part, or `None` for the leading part,
+///   which is not padded.
+/// * `writer` - The writer the digits are written to.
+/// * `written` - The number of digits written so far, which is increased by the part.
+fn stream_part(
+    value: UBig,
+    powers: &[Arc<UBig>],
+    width: Option<usize>,
+    writer: &mut impl Write,
+    written: &mut u64,
+) -> io::Result<()> {
+    // Powers above the part would only split off zeros
+    let usable = match width {
+        Some(width) => (0..powers.len())
+            .take_while(|i| 1 << (FIRST_SPLIT + i) < width)
+            .count(),
+        None => powers
+            .iter()
+            .take_while(|power| power.bit_len() <= value.bit_len())
+            .count(),
+    };
+    let Some((power, smaller)) = powers[..usable]
+        .split_last()
+        .filter(|_| value.bit_len() >= LEAF_BITS)
+    else {
+        let leaf = value.to_string();
+        let padding = width.map_or(0, |width| width - leaf.len());
+        for _ in 0..padding / ZEROS.len() {
+            writer.write_all(ZEROS)?;
+        }
+        writer.write_all(&ZEROS[..padding % ZEROS.len()])?;
+        writer.write_all(leaf.as_bytes())?;
+        *written += (padding + leaf.len()) as u64;
+        return Ok(());
+    };
+
+    let lower_width = 1 << (FIRST_SPLIT + smaller.len());
+    let (upper, lower) = (&value).div_rem(power.as_ref());
+    drop(value);
+
+    // A leading part below the power has no upper half, which would add leading zeros
+    if width.is_none() && upper == UBig::ZERO {
+        return stream_part(lower, smaller, None, writer, written);
+    }
+    stream_part(
+        upper,
+        smaller,
+        width.map(|width| width - lower_width),
+        writer,
+        written,
+    )?;
+    stream_part(lower, smaller, Some(lower_width), writer, written)
+}
+
+/// Writes the digits of `value` into `digits`, padded with leading zeros to its length.
+///
+/// # Arguments
+/// * `value` - The part to convert, which has at most `digits.len()` digits.
+/// * `powers` - The powers `10^(2^k)` that are still available for splitting.
+/// * `digits` - The range of the buffer the part is written to, filled with zeros.
+fn write_part(value: UBig, powers: &[Arc<UBig>], digits: &mut [u8]) {
+    // Powers with at least as many digits as the part would only split off zeros
+    let usable = (0..powers.len())
+        .take_while(|i| 1 << (FIRST_SPLIT + i) < digits.len())
+        .count();
+    let Some((power, smaller)) = powers[..usable]
+        .split_last()
+        .filter(|_| value.bit_len() >= LEAF_BITS)
+    else {
+        let leaf = value.to_string();
+        let start = digits.len() - leaf.len();
+        digits[start..].copy_from_slice(leaf.as_bytes());
+        return;
+    };
+
+    let lower_width = 1 << (FIRST_SPLIT + smaller.len());
+    let parallel = value.bit_len() >= PARALLEL_BITS;
+    let (upper, lower) = (&value).div_rem(power.as_ref());
+    drop(value);
+
+    let (upper_digits, lower_digits) = digits.split_at_mut(digits.len() - lower_width);
+    if parallel {
+        join(
+            || write_part(upper, smaller, upper_digits),
+            || write_part(lower, smaller, lower_digits),
+        );
+    } else {
+        write_part(upper, smaller, upper_digits);
+        write_part(lower, smaller, lower_digits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplitMix64;
+
+    /// Returns a pseudo-random number with exactly `bits` bits.
+    fn random_number(random: &mut SplitMix64, bits: u64) -> BigUint {
+        let mut value = BigUint::new(
+            (0..bits.div_ceil(32))
+                .map(|_| random.next_u64() as u32)
+                .collect(),
+        );
+        value >>= value.bits().saturating_sub(bits);
+        value.set_bit(bits - 1, true);
+        value
+    }
+
+    /// Returns numbers whose digits have long runs of zeros inside, which are written by the
+    /// padding of the lower halves.
+    fn numbers_with_zero_runs() -> Vec<BigUint> {
+        let ten = BigUint::from(10u32);
+        vec![
+            ten.pow(2048),
+            ten.pow(2048) + 1u32,
+            ten.pow(4096) - 1u32,
+            ten.pow(20_000) + ten.pow(10_000) + 7u32,
+            ten.pow(30_000) * 123u32 + 45u32,
+            (BigUint::from(1u32) << 100_000u32) + 1u32,
+            ten.pow(40_000) + (BigUint::from(1u32) << 30_000u32),
+        ]
+    }
+
+    #[test]
+    fn divide_and_conquer_matches_to_string() {
+        let mut random = SplitMix64::new(87);
+        let mut pool = BufferPool::new(0);
+        let mut values = numbers_with_zero_runs();
+        values.extend([0, 1, 8191, 8192, 8193, 50_000, 123_457].map(|bits| {
+            if bits == 0 {
+                BigUint::ZERO
+            } else {
+                random_number(&mut random, bits)
+            }
+        }));
+        for value in values {
+            assert_eq!(
+                divide_and_conquer_string(&value, &mut pool),
+                value.to_string(),
+                "conversion of a number of {} bits",
+                value.bits()
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_halves_match_to_string() {
+        // Just above the size whose halves are converted in parallel, with runs of zeros
+        let ten = BigUint::from(10u32);
+        let value = ten.pow(316_000) + ten.pow(158_000) * 987u32 + 1u32;
+        assert!(value.bits() >= PARALLEL_BITS as u64);
+        let expected = value.to_string();
+
+        // The second conversion takes the buffers of the first one from the pool again
+        let mut pool = BufferPool::new(u64::MAX);
+        for _ in 0..2 {
+            let digits = divide_and_conquer_string(&value, &mut pool);
+            assert!(digits == expected, "conversion of {} bits", value.bits());
+            pool.recycle(digits.into_bytes());
+        }
+    }
+
+    #[test]
+    fn streamed_digits_match_to_string() {
+        let mut random = SplitMix64::new(89);
+        let mut values = numbers_with_zero_runs();
+        values.extend([BigUint::ZERO, random_number(&mut random, 8192)]);
+        values.push(random_number(&mut random, 200_000));
+        for value in values {
+            let mut digits = Vec::new();
+            let written = stream_decimal(&value, &mut digits).unwrap();
+            let expected = value.to_string();
+            assert!(digits == expected.as_bytes(), "{} bits", value.bits());
+            assert_eq!(written, expected.len() as u64);
+        }
+    }
+
+    #[test]
+    fn streaming_stops_at_the_first_error() {
+        /// Accepts a fixed number of bytes and fails afterwards.
+        struct Full(usize);
+
+        impl Write for Full {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+                let accepted = buf.len().min(self.0);
+                self.0 -= accepted;
+                Ok(accepted)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value = BigUint::from(10u32).pow(200_000) + 1u32;
+        let error = stream_decimal(&value, &mut Full(100_000)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
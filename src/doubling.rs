@@ -0,0 +1,49 @@
+//! The fast doubling step on `BigUint` numbers, without `std`.
+//!
+//! Only num-bigint is needed, so fast doubling on the calling thread is available without
+//! the default `std` feature, like for [`fib_string`](crate::fib_string) on WebAssembly.
+
+use num_bigint::BigUint;
+
+/// Calculates the Fibonacci pair `(F(n), F(n+1))` with fast doubling, with every product
+/// calculated on the calling thread.
+///
+/// The bits of `n` are processed from the most significant one with a loop instead of
+/// recursion, and nothing is handed to rayon, so this works where no threads can be spawned.
+pub(crate) fn fib_pair_sequential(n: u64) -> (BigUint, BigUint) {
+    let mut pair = (BigUint::ZERO, BigUint::from(1u32));
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        let (a, b) = pair;
+        pair = combine_squares(&a * &a, &b * &b, n >> bit);
+    }
+    pair
+}
+
+/// Finishes a fast doubling step from the squares `F(k)²` and `F(k+1)²`.
+///
+/// `F(2k+1) = F(k)² + F(k+1)²` and `F(2k) = 2F(k+1)² − 3F(k)² − 2(−1)^k`, which follows
+/// from Cassini's identity, so a step needs no other product than the two squares.
+///
+/// # Returns
+/// The Fibonacci pair `(F(index), F(index+1))` for `k = index / 2`.
+pub(crate) fn combine_squares(
+    a_squared: BigUint,
+    b_squared: BigUint,
+    index: u64,
+) -> (BigUint, BigUint) {
+    // 2F(k+1)² − 3F(k)² is F(2k) + 2 for even k and F(2k) − 2 for odd k
+    let twice_difference = (&b_squared - &a_squared) << 1u32;
+    let c = if (index >> 1) & 1 == 0 {
+        twice_difference - &a_squared - 2u32
+    } else {
+        twice_difference + 2u32 - &a_squared
+    };
+    let d = a_squared + b_squared;
+
+    if index & 1 == 1 {
+        let sum = &c + &d;
+        (d, sum)
+    } else {
+        (c, d)
+    }
+}
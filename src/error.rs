@@ -0,0 +1,65 @@
+use std::{fmt, io};
+
+/// The error type for Fibonacci calculations and the operations built on top of them.
+#[derive(Debug)]
+pub enum FibError {
+    /// The index of a Fibonacci number could not be parsed.
+    InvalidIndex(String),
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A checkpoint file is damaged or was not written by this program.
+    CorruptCheckpoint(String),
+    /// A checkpoint file is valid but does not belong to the requested computation.
+    CheckpointMismatch(String),
+    /// A binary result is damaged or was not written by this program.
+    CorruptResult(String),
+    /// A calculation did not finish before its deadline.
+    Timeout,
+    /// A configuration file is malformed or has invalid options.
+    InvalidConfig(String),
+    /// A calculation was stopped by a cancellation request.
+    Cancelled,
+    /// A Fibonacci code is malformed.
+    InvalidCode(String),
+    /// Two algorithms calculated different values for the Fibonacci number with this index.
+    VerificationFailed(u64),
+    /// Writing a file failed after this many bytes had been written.
+    WriteFailed { written: u64, error: io::Error },
+}
+
+impl fmt::Display for FibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FibError::InvalidIndex(reason) => write!(f, "invalid index: {}", reason),
+            FibError::Io(error) => write!(f, "{}", error),
+            FibError::CorruptCheckpoint(reason) => write!(f, "corrupt checkpoint: {}", reason),
+            FibError::CheckpointMismatch(reason) => write!(f, "checkpoint mismatch: {}", reason),
+            FibError::CorruptResult(reason) => write!(f, "corrupt result: {}", reason),
+            FibError::Timeout => write!(f, "the calculation did not finish before its deadline"),
+            FibError::InvalidConfig(reason) => write!(f, "invalid config: {}", reason),
+            FibError::Cancelled => write!(f, "the calculation was cancelled"),
+            FibError::InvalidCode(reason) => write!(f, "invalid Fibonacci code: {}", reason),
+            FibError::VerificationFailed(n) => {
+                write!(f, "the algorithms calculated different values for F({})", n)
+            }
+            FibError::WriteFailed { written, error } => {
+                write!(f, "writing failed after {} bytes: {}", written, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FibError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FibError::Io(error) | FibError::WriteFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FibError {
+    fn from(error: io::Error) -> Self {
+        FibError::Io(error)
+    }
+}
@@ -0,0 +1,229 @@
+use crate::format_duration;
+use std::{fmt, time::Duration};
+
+/// The number of bits of `F(n)` per unit of `n`, which is `log2(φ)`.
+pub(crate) const BITS_PER_INDEX: f64 = 0.694_241_913_630_617_3;
+
+/// How many numbers of the size of the result are alive at once during fast doubling.
+const PEAK_MEMORY_FACTOR: f64 = 6.0;
+
+/// The predicted cost of calculating a Fibonacci number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    /// The predicted duration of the calculation.
+    pub duration: Duration,
+    /// The predicted peak memory of the calculation in bytes.
+    pub memory: u64,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "about {} and {} of memory",
+            format_duration(self.duration),
+            format_bytes(self.memory)
+        )
+    }
+}
+
+/// Predicts the cost of calculating a Fibonacci number before it is started.
+///
+/// The interactive prompt asks for confirmation when the prediction exceeds the limits,
+/// and other predictions can be plugged in to trigger that deterministically.
+pub trait Estimator {
+    /// Predicts the cost of calculating `F(n)`.
+    fn estimate(&self, n: u64) -> Estimate;
+}
+
+/// A model that predicts the duration of a calculation with a power law.
+///
+/// The duration of `F(n)` is `reference_duration · (n / reference_index)^exponent`, which
+/// matches fast doubling with Karatsuba multiplication for large indices. The memory is
+/// derived from the size of the result, which is `n · log2(φ)` bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostModel {
+    /// The index the reference duration was measured at.
+    pub reference_index: u64,
+    /// The duration of calculating `F(reference_index)`.
+    pub reference_duration: Duration,
+    /// The exponent of the growth of the duration.
+    pub exponent: f64,
+}
+
+impl Default for CostModel {
+    /// A model of an optimized build on a typical desktop CPU.
+    fn default() -> Self {
+        CostModel {
+            reference_index: 10_000_000,
+            reference_duration: Duration::from_millis(550),
+            exponent: 1.58,
+        }
+    }
+}
+
+impl Estimator for CostModel {
+    fn estimate(&self, n: u64) -> Estimate {
+        let scale = n as f64 / self.reference_index.max(1) as f64;
+        let seconds = self.reference_duration.as_secs_f64() * scale.powf(self.exponent);
+        let result_bytes = n as f64 * BITS_PER_INDEX / 8.0;
+
+        Estimate {
+            duration: Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX),
+            memory: (result_bytes * PEAK_MEMORY_FACTOR) as u64,
+        }
+    }
+}
+
+impl CostModel {
+    /// Estimates which fraction of the work of a fast doubling calculation is done.
+    ///
+    /// Fast doubling takes one step per bit of `n`, but the steps are far from equal: the
+    /// step that reaches the index `k` multiplies numbers of about `k · log2(φ)` bits, so
+    /// its cost grows with that size to the power of the exponent. The fraction compares
+    /// the predicted cost of the steps done so far with the cost of all steps, which only
+    /// grows as steps are done.
+    ///
+    /// # Arguments
+    /// * `n` - The index of the Fibonacci number that is calculated.
+    /// * `remaining_bits` - The number of bits of `n` that still have to be processed, as
+    ///   returned by `DoublingState::remaining_bits`.
+    ///
+    /// # Returns
+    /// The estimated fraction of the work that is done, from 0.0 to 1.0.
+    pub fn fraction_done(&self, n: u64, remaining_bits: u32) -> f64 {
+        let total_bits = u64::BITS - n.leading_zeros();
+        let step_cost = |bits: u32| {
+            let index = n >> (total_bits - bits);
+            (index as f64 * BITS_PER_INDEX).powf(self.exponent)
+        };
+
+        let cost_of = |steps: u32| (1..=steps).map(step_cost).fold(0.0, |sum, cost| sum + cost);
+
+        let total = cost_of(total_bits);
+        if total == 0.0 {
+            return 1.0;
+        }
+        cost_of(total_bits.saturating_sub(remaining_bits)) / total
+    }
+}
+
+/// The estimated cost above which a calculation has to be confirmed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostLimits {
+    /// The longest duration that is calculated without confirmation.
+    pub duration: Duration,
+    /// The most memory in bytes that is used without confirmation.
+    pub memory: u64,
+}
+
+impl Default for CostLimits {
+    fn default() -> Self {
+        CostLimits {
+            duration: Duration::from_secs(30),
+            memory: 2 << 30,
+        }
+    }
+}
+
+impl CostLimits {
+    /// Checks whether an estimate is above either limit.
+    pub fn exceeded_by(&self, estimate: &Estimate) -> bool {
+        estimate.duration > self.duration || estimate.memory > self.memory
+    }
+}
+
+/// Formats a number of bytes with a binary unit, like `1.5 GiB`.
+///
+/// # Arguments
+/// * `bytes` - The number of bytes to format.
+///
+/// # Returns
+/// A `String` with the number of bytes in the largest unit that keeps it at least 1.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_grow_with_the_power_law() {
+        let model = CostModel::default();
+        let reference = model.estimate(10_000_000);
+        assert_eq!(reference.duration, Duration::from_millis(550));
+        assert_eq!(reference.memory, 5_206_814);
+
+        let doubled = model.estimate(20_000_000);
+        let growth = doubled.duration.as_secs_f64() / reference.duration.as_secs_f64();
+        assert!((growth - 2f64.powf(1.58)).abs() < 1e-9, "{}", growth);
+        let slow = CostModel {
+            reference_index: 1,
+            reference_duration: Duration::MAX,
+            exponent: 2.0,
+        };
+        assert_eq!(slow.estimate(10).duration, Duration::MAX);
+        assert_eq!(
+            model.estimate(0),
+            Estimate {
+                duration: Duration::ZERO,
+                memory: 0
+            }
+        );
+    }
+
+    #[test]
+    fn limits_are_exceeded_by_either_cost() {
+        let limits = CostLimits::default();
+        let within = Estimate {
+            duration: limits.duration,
+            memory: limits.memory,
+        };
+        assert!(!limits.exceeded_by(&within));
+        assert!(limits.exceeded_by(&Estimate {
+            duration: limits.duration + Duration::from_nanos(1),
+            ..within
+        }));
+        assert!(limits.exceeded_by(&Estimate {
+            memory: limits.memory + 1,
+            ..within
+        }));
+    }
+
+    #[test]
+    fn the_fraction_done_grows_to_one() {
+        let model = CostModel::default();
+        let n = 1_000_000u64;
+        let bits = u64::BITS - n.leading_zeros();
+        let fractions: Vec<f64> = (0..=bits)
+            .rev()
+            .map(|remaining| model.fraction_done(n, remaining))
+            .collect();
+        assert_eq!((fractions[0], fractions[bits as usize]), (0.0, 1.0));
+        assert!(fractions.windows(2).all(|pair| pair[0] < pair[1]));
+        // The last step costs about as much as all steps before it together
+        assert!((0.3..0.7).contains(&fractions[bits as usize - 1]));
+        assert_eq!(model.fraction_done(0, 0), 1.0);
+    }
+
+    #[test]
+    fn bytes_use_the_largest_fitting_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(3 << 29), "1.5 GiB");
+        assert_eq!(format_bytes(u64::MAX), "16.0 EiB");
+    }
+}
@@ -0,0 +1,511 @@
+use crate::FibError;
+use num_bigint::{BigInt, BigUint, Sign};
+use std::fmt;
+
+/// The largest number of bits the result of `^` may have, about 40 million decimal digits.
+pub const MAX_POWER_BITS: u64 = 1 << 27;
+
+/// The functions that can be called in an expression.
+pub const FUNCTIONS: [&str; 1] = ["fib"];
+
+/// An error in an expression, pointing at the offending token.
+#[derive(Debug)]
+pub struct ExprError {
+    /// The character position of the offending token in the expression.
+    pub position: usize,
+    /// The description of the error.
+    pub message: String,
+}
+
+impl ExprError {
+    fn new(position: usize, message: impl Into<String>) -> ExprError {
+        ExprError {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// Returns a line with a `^` under the offending token, to be printed below the expression.
+    pub fn caret(&self) -> String {
+        format!("{}^", " ".repeat(self.position))
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// A binary operator of an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+/// A parsed expression over Fibonacci values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    /// An integer literal.
+    Number(BigInt),
+    /// A negated expression.
+    Negate(Box<Expr>),
+    /// A binary operation, with the position of the operator.
+    Binary {
+        op: BinaryOp,
+        position: usize,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// A function call like `fib(20)`, with the position of the function name.
+    Call {
+        name: String,
+        position: usize,
+        argument: Box<Expr>,
+    },
+}
+
+/// A token of an expression, with its character position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(BigInt),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    End,
+}
+
+/// Splits an expression into tokens.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < chars.len() {
+        let start = position;
+        let c = chars[position];
+
+        if c.is_whitespace() {
+            position += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            // Digits with underscores, optionally followed by a decimal exponent like 1e6
+            while position < chars.len()
+                && (chars[position].is_ascii_digit() || chars[position] == '_')
+            {
+                position += 1;
+            }
+            let digits: String = chars[start..position]
+                .iter()
+                .filter(|c| **c != '_')
+                .collect();
+            let mut value: BigInt = digits.parse().expect("the token only holds digits");
+
+            if position + 1 < chars.len()
+                && matches!(chars[position], 'e' | 'E')
+                && chars[position + 1].is_ascii_digit()
+            {
+                let exponent_start = position + 1;
+                position = exponent_start;
+                while position < chars.len() && chars[position].is_ascii_digit() {
+                    position += 1;
+                }
+                let exponent: String = chars[exponent_start..position].iter().collect();
+                let exponent = exponent
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|exponent| (*exponent as u64) * 4 <= MAX_POWER_BITS)
+                    .ok_or_else(|| ExprError::new(start, "number is too large"))?;
+                value *= BigInt::from(10u32).pow(exponent);
+            }
+
+            tokens.push((Token::Number(value), start));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            while position < chars.len()
+                && (chars[position].is_alphanumeric() || chars[position] == '_')
+            {
+                position += 1;
+            }
+            tokens.push((Token::Ident(chars[start..position].iter().collect()), start));
+            continue;
+        }
+
+        let token = match c {
+            '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ => {
+                return Err(ExprError::new(
+                    start,
+                    format!("unexpected character '{}'", c),
+                ))
+            }
+        };
+        tokens.push((token, start));
+        position += 1;
+    }
+
+    tokens.push((Token::End, chars.len()));
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of an expression.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &(Token, usize) {
+        &self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.next].clone();
+        if token.0 != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.term()?;
+        while let (Token::Op(op @ ('+' | '-')), position) = *self.peek() {
+            self.advance();
+            let right = self.term()?;
+            let op = if op == '+' {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Subtract
+            };
+            left = binary(op, position, left, right);
+        }
+        Ok(left)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.unary()?;
+        while let (Token::Op(op @ ('*' | '/' | '%')), position) = *self.peek() {
+            self.advance();
+            let right = self.unary()?;
+            let op = match op {
+                '*' => BinaryOp::Multiply,
+                '/' => BinaryOp::Divide,
+                _ => BinaryOp::Remainder,
+            };
+            left = binary(op, position, left, right);
+        }
+        Ok(left)
+    }
+
+    /// unary := '-' unary | power
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        if let (Token::Op('-'), _) = self.peek() {
+            self.advance();
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// power := primary ('^' unary)?, which makes `^` right-associative
+    fn power(&mut self) -> Result<Expr, ExprError> {
+        let base = self.primary()?;
+        if let (Token::Op('^'), position) = *self.peek() {
+            self.advance();
+            let exponent = self.unary()?;
+            return Ok(binary(BinaryOp::Power, position, base, exponent));
+        }
+        Ok(base)
+    }
+
+    /// primary := number | identifier '(' expression ')' | '(' expression ')'
+    fn primary(&mut self) -> Result<Expr, ExprError> {
+        match self.advance() {
+            (Token::Number(value), _) => Ok(Expr::Number(value)),
+            (Token::Ident(name), position) => {
+                if !FUNCTIONS.contains(&name.as_str()) {
+                    return Err(ExprError::new(
+                        position,
+                        format!(
+                            "unknown function '{}', available functions: {}",
+                            name,
+                            FUNCTIONS.join(", ")
+                        ),
+                    ));
+                }
+                self.expect_open(&name)?;
+                let argument = self.expression()?;
+                self.expect_close()?;
+                Ok(Expr::Call {
+                    name,
+                    position,
+                    argument: Box::new(argument),
+                })
+            }
+            (Token::Open, _) => {
+                let inner = self.expression()?;
+                self.expect_close()?;
+                Ok(inner)
+            }
+            (Token::End, position) => Err(ExprError::new(position, "unexpected end of expression")),
+            (_, position) => Err(ExprError::new(
+                position,
+                "expected a number, function or '('",
+            )),
+        }
+    }
+
+    fn expect_open(&mut self, name: &str) -> Result<(), ExprError> {
+        match self.advance() {
+            (Token::Open, _) => Ok(()),
+            (_, position) => Err(ExprError::new(
+                position,
+                format!("expected '(' after '{}'", name),
+            )),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), ExprError> {
+        match self.advance() {
+            (Token::Close, _) => Ok(()),
+            (_, position) => Err(ExprError::new(position, "expected ')'")),
+        }
+    }
+}
+
+fn binary(op: BinaryOp, position: usize, left: Expr, right: Expr) -> Expr {
+    Expr::Binary {
+        op,
+        position,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+/// Parses an expression over Fibonacci values.
+///
+/// Expressions consist of integer literals (`42`, `1_000`, `1e6`), calls of the functions
+/// in [`FUNCTIONS`], parentheses, unary minus and the operators `+ - * / % ^` with the
+/// usual precedence. `^` binds tightest and is right-associative.
+///
+/// # Arguments
+/// * `input` - The expression to parse.
+///
+/// # Returns
+/// A `Result<Expr, ExprError>` with the parsed expression, or an `ExprError` pointing at
+/// the offending token.
+pub fn parse_expr(input: &str) -> Result<Expr, ExprError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        next: 0,
+    };
+
+    let expr = parser.expression()?;
+    match parser.peek() {
+        (Token::End, _) => Ok(expr),
+        (Token::Close, position) => Err(ExprError::new(*position, "unmatched ')'")),
+        (_, position) => Err(ExprError::new(*position, "expected an operator")),
+    }
+}
+
+/// The value of an evaluated expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Evaluation {
+    /// The exact value of the expression.
+    pub value: BigInt,
+    /// Whether a division or remainder had to truncate its result.
+    pub truncated: bool,
+}
+
+/// Evaluates a parsed expression with exact big-integer arithmetic.
+///
+/// Division truncates toward zero and the remainder takes the sign of the dividend, like
+/// Rust's integer operators. Every `fib(n)` call is answered by `fib`, so the caller can
+/// serve repeated calls from a cache.
+///
+/// # Arguments
+/// * `expr` - The expression to evaluate.
+/// * `fib` - Calculates the Fibonacci number at an index.
+///
+/// # Returns
+/// A `Result<Evaluation, ExprError>` with the value of the expression, or an `ExprError`
+/// pointing at the operator or call that failed.
+pub fn evaluate(
+    expr: &Expr,
+    fib: &mut dyn FnMut(u64) -> Result<BigUint, FibError>,
+) -> Result<Evaluation, ExprError> {
+    let mut truncated = false;
+    let value = evaluate_inner(expr, fib, &mut truncated)?;
+    Ok(Evaluation { value, truncated })
+}
+
+fn evaluate_inner(
+    expr: &Expr,
+    fib: &mut dyn FnMut(u64) -> Result<BigUint, FibError>,
+    truncated: &mut bool,
+) -> Result<BigInt, ExprError> {
+    match expr {
+        Expr::Number(value) => Ok(value.clone()),
+        Expr::Negate(inner) => Ok(-evaluate_inner(inner, fib, truncated)?),
+        Expr::Call {
+            position, argument, ..
+        } => {
+            let argument = evaluate_inner(argument, fib, truncated)?;
+            let index = u64::try_from(&argument).map_err(|_| {
+                ExprError::new(
+                    *position,
+                    format!(
+                        "fib() needs an index from 0 to {}, got {}",
+                        u64::MAX,
+                        argument
+                    ),
+                )
+            })?;
+            let value = fib(index).map_err(|error| ExprError::new(*position, error.to_string()))?;
+            Ok(BigInt::from_biguint(Sign::Plus, value))
+        }
+        Expr::Binary {
+            op,
+            position,
+            left,
+            right,
+        } => {
+            let left = evaluate_inner(left, fib, truncated)?;
+            let right = evaluate_inner(right, fib, truncated)?;
+
+            match op {
+                BinaryOp::Add => Ok(left + right),
+                BinaryOp::Subtract => Ok(left - right),
+                BinaryOp::Multiply => Ok(left * right),
+                BinaryOp::Divide | BinaryOp::Remainder => {
+                    if right == BigInt::ZERO {
+                        return Err(ExprError::new(*position, "division by zero"));
+                    }
+                    if *op == BinaryOp::Remainder {
+                        return Ok(left % right);
+                    }
+                    if &left % &right != BigInt::ZERO {
+                        *truncated = true;
+                    }
+                    Ok(left / right)
+                }
+                BinaryOp::Power => {
+                    let exponent = u32::try_from(&right).map_err(|_| {
+                        ExprError::new(
+                            *position,
+                            format!("exponent must be from 0 to {}, got {}", u32::MAX, right),
+                        )
+                    })?;
+                    // 0, 1 and -1 stay small for any exponent
+                    if left.magnitude() > &BigUint::from(1u32)
+                        && left.bits().saturating_mul(exponent as u64) > MAX_POWER_BITS
+                    {
+                        return Err(ExprError::new(
+                            *position,
+                            format!("result of '^' would exceed {} bits", MAX_POWER_BITS),
+                        ));
+                    }
+                    Ok(left.pow(exponent))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates an expression whose `fib` calls are answered by iteration.
+    fn value_of(input: &str) -> Result<Evaluation, ExprError> {
+        evaluate(&parse_expr(input)?, &mut |n| {
+            let (mut current, mut next) = (BigUint::ZERO, BigUint::from(1u32));
+            for _ in 0..n {
+                let sum = &current + &next;
+                current = std::mem::replace(&mut next, sum);
+            }
+            Ok(current)
+        })
+    }
+
+    fn number(input: &str) -> String {
+        value_of(input).unwrap().value.to_string()
+    }
+
+    #[test]
+    fn operators_have_the_usual_precedence() {
+        assert_eq!(number("1 + 2 * 3"), "7");
+        assert_eq!(number("(1 + 2) * 3"), "9");
+        assert_eq!(number("2 ^ 3 ^ 2"), "512");
+        assert_eq!(number("-2 ^ 2"), "-4");
+        assert_eq!(number("10 - 4 - 3"), "3");
+        assert_eq!(number("fib(10) * fib(12) - fib(11)^2"), "-1");
+        assert_eq!(number("fib(2 * 50) % 1_000"), "75");
+        assert_eq!(number("1e3 + fib(fib(5))"), "1005");
+    }
+
+    #[test]
+    fn division_truncates_toward_zero() {
+        let quotient = value_of("-7 / 2").unwrap();
+        assert_eq!(
+            (quotient.value.to_string(), quotient.truncated),
+            ("-3".into(), true)
+        );
+        assert_eq!(number("-7 % 2"), "-1");
+        assert!(!value_of("fib(20) / fib(10)").unwrap().truncated);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        for (input, position, message) in [
+            ("1 +", 3, "unexpected end of expression"),
+            ("(1 + 2", 6, "expected ')'"),
+            ("1 + 2)", 5, "unmatched ')'"),
+            ("2 3", 2, "expected an operator"),
+            ("1 $ 2", 2, "unexpected character '$'"),
+            (
+                "fob(3)",
+                0,
+                "unknown function 'fob', available functions: fib",
+            ),
+            ("fib 3", 4, "expected '(' after 'fib'"),
+            ("5 / (3 - 3)", 2, "division by zero"),
+            (
+                "fib(-1)",
+                0,
+                "fib() needs an index from 0 to 18446744073709551615, got -1",
+            ),
+            ("2 ^ -1", 2, "exponent must be from 0 to 4294967295, got -1"),
+            (
+                "3 ^ 100000000",
+                2,
+                "result of '^' would exceed 134217728 bits",
+            ),
+        ] {
+            let error = value_of(input).unwrap_err();
+            assert_eq!(
+                (error.position, error.message.as_str()),
+                (position, message),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(value_of("1 + x").unwrap_err().caret(), "    ^");
+        assert_eq!(number("(-1) ^ 4000000000"), "1");
+    }
+}
@@ -0,0 +1,220 @@
+//! Fibonacci numbers that fit into machine integers, without `std`.
+//!
+//! Everything in this module only uses `core` and never allocates, so it is available
+//! without the default `std` feature and can be used on embedded targets.
+
+/// The largest index whose Fibonacci number fits into a `u64`.
+pub const MAX_U64_INDEX: u64 = 93;
+
+/// Calculates the nth Fibonacci number as a `u64` using fast doubling.
+///
+/// The pair `(F(k), F(k+1))` is doubled once per bit of `n` in `u128` arithmetic, which
+/// leaves enough headroom for the products of every step up to `F(93)`.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// An `Option<u64>` with the nth Fibonacci number, or `None` if it does not fit into a
+/// `u64`, which is the case for every index above [`MAX_U64_INDEX`].
+pub fn fib_u64(n: u64) -> Option<u64> {
+    if n > MAX_U64_INDEX {
+        return None;
+    }
+
+    let (mut a, mut b) = (0u128, 1u128);
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        // F(2k) = F(k)·(2F(k+1) − F(k)) and F(2k+1) = F(k)² + F(k+1)²
+        let c = a * (2 * b - a);
+        let d = a * a + b * b;
+        (a, b) = if (n >> bit) & 1 == 1 {
+            (d, c + d)
+        } else {
+            (c, d)
+        };
+    }
+
+    u64::try_from(a).ok()
+}
+
+/// Calculates the nth Fibonacci number modulo `modulus` using fast doubling.
+///
+/// Every intermediate value is reduced modulo `modulus` and multiplied in `u128`, so this
+/// works for any index and any 64-bit modulus in `O(log n)` steps.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `modulus` - The modulus of the result.
+///
+/// # Returns
+/// The nth Fibonacci number modulo `modulus`.
+///
+/// # Panics
+/// Panics if `modulus` is zero.
+pub fn fib_mod(n: u64, modulus: u64) -> u64 {
+    assert!(modulus != 0, "the modulus must not be zero");
+
+    let m = modulus as u128;
+    let (mut a, mut b) = (0u128, 1 % m);
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        // The same identities as fib_u64, with 2F(k+1) − F(k) kept non-negative
+        let c = a * ((2 * b + m - a) % m) % m;
+        let d = (a * a % m + b * b % m) % m;
+        (a, b) = if (n >> bit) & 1 == 1 {
+            (d, (c + d) % m)
+        } else {
+            (c, d)
+        };
+    }
+
+    a as u64
+}
+
+/// An iterator over the Fibonacci numbers modulo `m`, starting at any index.
+///
+/// The iterator yields `(index, F(index) mod m)` tuples. Only the first pair is calculated
+/// with [`fib_mod`], every following number is a single modular addition. The sequence
+/// repeats with the Pisano period of `m`, and every period starts with the pair `(0, 1)`,
+/// which [`FibModIter::at_period_start`] detects.
+#[derive(Clone, Debug)]
+pub struct FibModIter {
+    index: u64,
+    current: u64,
+    next: u64,
+    modulus: u64,
+}
+
+impl FibModIter {
+    /// Creates an iterator starting at `F(start) mod modulus`.
+    ///
+    /// # Panics
+    /// Panics if `modulus` is zero.
+    pub fn new(start: u64, modulus: u64) -> FibModIter {
+        let current = fib_mod(start, modulus);
+        let next = match start.checked_add(1) {
+            Some(following) => fib_mod(following, modulus),
+            None => 0,
+        };
+        FibModIter {
+            index: start,
+            current,
+            next,
+            modulus,
+        }
+    }
+
+    /// Returns the index of the Fibonacci number that will be yielded next.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Checks whether the number yielded next starts a new Pisano period, which is the
+    /// case when it and its successor are `(0, 1)` modulo `m`.
+    pub fn at_period_start(&self) -> bool {
+        self.current == 0 && self.next == 1 % self.modulus
+    }
+}
+
+impl Iterator for FibModIter {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        self.index = index.checked_add(1)?;
+
+        // The sum of two residues can exceed a u64 for moduli above 2^63
+        let following = ((self.current as u128 + self.next as u128) % self.modulus as u128) as u64;
+        let value = self.current;
+        (self.current, self.next) = (self.next, following);
+
+        Some((index, value))
+    }
+}
+
+/// Checks whether `F(m)` divides `F(n)` without calculating either Fibonacci number.
+///
+/// The answer follows from the indices alone, because `gcd(F(m), F(n)) = F(gcd(m, n))`:
+/// for `m` of at least 3, `F(m)` divides `F(n)` exactly when `m` divides `n`. The small
+/// indices are special, `F(1) = F(2) = 1` divide every Fibonacci number while `F(0) = 0`
+/// only divides `F(0)` itself.
+///
+/// # Arguments
+/// * `m` - The index of the divisor.
+/// * `n` - The index of the dividend.
+///
+/// # Returns
+/// `true` if `F(m)` divides `F(n)`.
+pub fn fib_divides(m: u64, n: u64) -> bool {
+    match m {
+        0 => n == 0,
+        1 | 2 => true,
+        m => n.is_multiple_of(m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn machine_numbers_match_the_recurrence() {
+        let (mut current, mut next) = (0u64, 1u64);
+        for n in 0..=MAX_U64_INDEX {
+            assert_eq!(fib_u64(n), Some(current), "F({})", n);
+            for modulus in [1, 2, 10, 1_000_000_007, u64::MAX] {
+                assert_eq!(
+                    fib_mod(n, modulus),
+                    current % modulus,
+                    "F({}) mod {}",
+                    n,
+                    modulus
+                );
+            }
+            (current, next) = (next, current.wrapping_add(next));
+        }
+        assert_eq!(fib_u64(MAX_U64_INDEX), Some(12_200_160_415_121_876_738));
+        assert_eq!(fib_u64(MAX_U64_INDEX + 1), None);
+    }
+
+    #[test]
+    fn residues_repeat_with_the_pisano_period() {
+        // The Pisano periods of 2, 10 and 1000
+        for (modulus, period) in [(2, 3), (10, 60), (1000, 1500)] {
+            let mut iter = FibModIter::new(0, modulus);
+            assert!(iter.at_period_start());
+            let mut length = 0;
+            while {
+                iter.next();
+                length += 1;
+                !iter.at_period_start()
+            } {}
+            assert_eq!(length, period, "mod {}", modulus);
+            assert_eq!(iter.index(), period);
+        }
+
+        // A far away start agrees with fib_mod at every index
+        let start = u64::MAX - 20;
+        for (index, value) in FibModIter::new(start, 97) {
+            assert_eq!(value, fib_mod(index, 97), "F({}) mod 97", index);
+        }
+        assert_eq!(
+            fib_mod(u64::MAX, 1 << 63),
+            fib_mod(u64::MAX % (3 << 62), 1 << 63)
+        );
+    }
+
+    #[test]
+    fn divisibility_follows_from_the_indices() {
+        for m in 0..=MAX_U64_INDEX {
+            for n in 0..=MAX_U64_INDEX {
+                let (divisor, dividend) = (fib_u64(m).unwrap(), fib_u64(n).unwrap());
+                let divides = if divisor == 0 {
+                    dividend == 0
+                } else {
+                    dividend % divisor == 0
+                };
+                assert_eq!(fib_divides(m, n), divides, "F({}) | F({})", m, n);
+            }
+        }
+    }
+}
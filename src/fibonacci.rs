@@ -0,0 +1,1300 @@
+use crate::{
+    doubling::combine_squares,
+    estimate::BITS_PER_INDEX,
+    fib_u64,
+    multiply::parallel_multiply,
+    threads::{current_num_threads, join},
+    tuning::{parallel_multiply_bits, word_index},
+    FibError,
+};
+use num_bigint::{BigInt, BigUint};
+use std::{
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Whether the running fast doubling calculations were asked to stop.
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Asks every running fast doubling calculation to stop at its next doubling level.
+///
+/// The request is a single atomic flag, so it can be made from a signal handler and is seen
+/// by every thread, including the workers of rayon. [`calculate_fibonacci`] and the
+/// calculations of the session cache then fail with `FibError::Cancelled`. The request
+/// stays in place until [`clear_cancel`] is called.
+pub fn request_cancel() {
+    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Withdraws a request to cancel, so the following calculations run to the end.
+pub fn clear_cancel() {
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+/// Checks whether the running calculations were asked to stop with [`request_cancel`].
+pub fn cancel_requested() -> bool {
+    CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Fails with `FibError::Cancelled` if the running calculations were asked to stop.
+fn check_cancel() -> Result<(), FibError> {
+    if cancel_requested() {
+        Err(FibError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// The algorithms available for calculating a single Fibonacci number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The fastest way for the index and the number of threads, see [`choose_algorithm`].
+    #[default]
+    Auto,
+    /// Fast doubling with parallel products, as used by [`calculate_fibonacci`].
+    FastDoubling,
+    /// Exponentiation of the matrix `[[1, 1], [1, 0]]` by repeated squaring.
+    Matrix,
+    /// Adding consecutive Fibonacci numbers one index at a time.
+    Iterative,
+}
+
+impl Algorithm {
+    /// Every algorithm, in the order they are listed in help texts.
+    pub const ALL: [Algorithm; 4] = [
+        Algorithm::Auto,
+        Algorithm::FastDoubling,
+        Algorithm::Matrix,
+        Algorithm::Iterative,
+    ];
+
+    /// Every algorithm except [`Algorithm::Auto`], which always calculates like one of them.
+    pub const CONCRETE: [Algorithm; 3] = [
+        Algorithm::FastDoubling,
+        Algorithm::Matrix,
+        Algorithm::Iterative,
+    ];
+
+    /// Returns the name of the algorithm as accepted by `from_str`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Auto => "auto",
+            Algorithm::FastDoubling => "fast-doubling",
+            Algorithm::Matrix => "matrix",
+            Algorithm::Iterative => "iterative",
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The way [`Algorithm::Auto`] calculates a Fibonacci number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Fast doubling with `u64` arithmetic, for the numbers that fit into one.
+    MachineWord,
+    /// Fast doubling with every product calculated by num-bigint on its own.
+    FastDoubling,
+    /// Fast doubling with the largest products split over the threads.
+    SplitFastDoubling,
+}
+
+impl Strategy {
+    /// Returns the name of the strategy.
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::MachineWord => "machine-word",
+            Strategy::FastDoubling => "fast-doubling",
+            Strategy::SplitFastDoubling => "split-fast-doubling",
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The decision of [`choose_algorithm`] and the reason for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlgorithmChoice {
+    pub strategy: Strategy,
+    pub reason: String,
+}
+
+/// Chooses how [`Algorithm::Auto`] calculates the Fibonacci number at `n`.
+///
+/// Numbers up to `F(93)` fit into a `u64` and are calculated without any allocation, up to
+/// the index of [`Thresholds::word_index`](crate::Thresholds::word_index). Every larger
+/// number is calculated with fast doubling, which needs far fewer and smaller products than
+/// matrix exponentiation and far fewer additions than the iterative algorithm for every
+/// index, so those two are never chosen. The largest products of fast doubling square
+/// numbers of about `F(n/2)`, which are only split over the threads from the size of
+/// [`Thresholds::parallel_multiply_bits`](crate::Thresholds::parallel_multiply_bits) on and
+/// with at least three threads, as each of the two squarings of a step gets half of them.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `threads` - The number of threads the calculation runs on.
+///
+/// # Returns
+/// The `AlgorithmChoice` with the strategy and a sentence explaining it.
+pub fn choose_algorithm(n: u64, threads: usize) -> AlgorithmChoice {
+    if n <= word_index() {
+        return AlgorithmChoice {
+            strategy: Strategy::MachineWord,
+            reason: format!("F({}) fits into 64 bits", n),
+        };
+    }
+
+    let product_bits = (n / 2) as f64 * BITS_PER_INDEX;
+    let parallel_bits = parallel_multiply_bits();
+    let (strategy, reason) = if threads < 3 {
+        (
+            Strategy::FastDoubling,
+            format!(
+                "with {} thread{} each squaring of a step runs on a single thread",
+                threads,
+                if threads == 1 { "" } else { "s" }
+            ),
+        )
+    } else if product_bits < parallel_bits as f64 {
+        (
+            Strategy::FastDoubling,
+            format!(
+                "the largest squarings have about {:.0} bits, below the {} bits from which \
+                 splitting them pays off",
+                product_bits, parallel_bits
+            ),
+        )
+    } else {
+        (
+            Strategy::SplitFastDoubling,
+            format!(
+                "the largest squarings have about {:.0} bits, at least the {} bits from which \
+                 splitting them over {} threads pays off",
+                product_bits, parallel_bits, threads
+            ),
+        )
+    };
+    AlgorithmChoice { strategy, reason }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Algorithm::ALL.iter().map(|a| a.name()).collect();
+                format!(
+                    "unknown algorithm '{}', expected one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Calculates the nth Fibonacci number using a parallel computation approach.
+///
+/// This function takes a `u64` value `n` as input and returns the nth Fibonacci number
+/// as a `BigUint` result. It uses the recursive helper function `fib_pair` to perform
+/// the Fibonacci calculation in a parallel manner for large numbers.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` where the `BigUint` represents the nth Fibonacci number,
+/// or `FibError::Cancelled` if [`request_cancel`] was called before it finished.
+pub fn calculate_fibonacci(n: u64) -> Result<BigUint, FibError> {
+    if n == 0 {
+        return Ok(BigUint::ZERO);
+    }
+
+    let (result, _) = fib_pair_cancellable(n)?;
+    Ok(result)
+}
+
+/// Calculates the nth Fibonacci number and measures how long the calculation took.
+///
+/// The number is calculated with [`Algorithm::Auto`], like the command-line program does by
+/// default, so the duration is the one of the fastest strategy for `n`.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// A `Result<(BigUint, Duration), FibError>` with the nth Fibonacci number and the duration
+/// of the calculation, or a `FibError` if the calculation fails.
+pub fn calculate_fibonacci_timed(n: u64) -> Result<(BigUint, Duration), FibError> {
+    calculate_fibonacci_timed_with(n, Algorithm::Auto)
+}
+
+/// Calculates the nth Fibonacci number with the given algorithm.
+///
+/// All algorithms return the same value, they only differ in speed. Fast doubling is by
+/// far the fastest for large indices, the others are mainly useful for comparison.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `algorithm` - The algorithm used for the calculation.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` where the `BigUint` represents the nth Fibonacci number,
+/// or a `FibError` if the calculation fails.
+pub fn calculate_fibonacci_with(n: u64, algorithm: Algorithm) -> Result<BigUint, FibError> {
+    match algorithm {
+        Algorithm::Auto => match choose_algorithm(n, current_num_threads()).strategy {
+            Strategy::MachineWord => Ok(BigUint::from(
+                fib_u64(n).expect("the machine word strategy is only chosen when F(n) fits"),
+            )),
+            Strategy::FastDoubling => fib_pair_unsplit(n).map(|(value, _)| value),
+            Strategy::SplitFastDoubling => calculate_fibonacci(n),
+        },
+        Algorithm::FastDoubling => calculate_fibonacci(n),
+        Algorithm::Matrix => Ok(fib_matrix(n)),
+        Algorithm::Iterative => Ok(fib_iterative(n)),
+    }
+}
+
+/// Calculates the nth Fibonacci number with the given algorithm and measures how long the
+/// calculation took.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `algorithm` - The algorithm used for the calculation.
+///
+/// # Returns
+/// A `Result<(BigUint, Duration), FibError>` with the nth Fibonacci number and the duration
+/// of the calculation, or a `FibError` if the calculation fails.
+pub fn calculate_fibonacci_timed_with(
+    n: u64,
+    algorithm: Algorithm,
+) -> Result<(BigUint, Duration), FibError> {
+    let start_time = Instant::now();
+    let result = calculate_fibonacci_with(n, algorithm)?;
+    Ok((result, start_time.elapsed()))
+}
+
+/// Calculates the nth Fibonacci number with two independent algorithms and checks that
+/// they agree.
+///
+/// Fast doubling and matrix exponentiation are run in parallel, so the calculation costs
+/// about twice as much as fast doubling alone. It is a self-test for new hardware or
+/// builds, not a replacement for [`calculate_fibonacci`].
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` with the nth Fibonacci number, or
+/// `FibError::VerificationFailed` if the two algorithms calculated different values.
+pub fn calculate_fibonacci_verified(n: u64) -> Result<BigUint, FibError> {
+    let (doubling, matrix) = join(|| calculate_fibonacci(n), || fib_matrix(n));
+    let doubling = doubling?;
+
+    if doubling == matrix {
+        Ok(doubling)
+    } else {
+        Err(FibError::VerificationFailed(n))
+    }
+}
+
+/// Calculates the nth Fibonacci number, giving up once a deadline has passed.
+///
+/// The fast doubling steps are run one at a time and the deadline is checked before every
+/// step. As every step works on numbers twice as large as the previous one, it takes at
+/// least twice as long, so the calculation also gives up early when the next step is
+/// estimated to end after the deadline instead of allocating and multiplying numbers that
+/// would be discarded anyway.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `deadline` - The point in time the calculation has to finish by.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` where the `BigUint` represents the nth Fibonacci number,
+/// or `FibError::Timeout` if it could not be calculated before the deadline.
+pub fn calculate_fibonacci_deadline(n: u64, deadline: Instant) -> Result<BigUint, FibError> {
+    let mut state = DoublingState::new(n);
+    let mut last_step = Duration::ZERO;
+
+    while !state.is_finished() {
+        let step_start_time = Instant::now();
+        if step_start_time + last_step * 2 > deadline {
+            return Err(FibError::Timeout);
+        }
+
+        state.step();
+        last_step = step_start_time.elapsed();
+    }
+
+    Ok(state.into_value())
+}
+
+/// Calculates the nth Lucas number.
+///
+/// The Lucas numbers `2, 1, 3, 4, 7, 11, ...` follow the same recurrence as the Fibonacci
+/// numbers, and `L(n) = F(n−1) + F(n+1) = 2F(n+1) − F(n)` is derived from the pair of `n`,
+/// so it costs about as much as `F(n)`.
+///
+/// # Arguments
+/// * `n` - The index of the Lucas number to calculate.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` with the nth Lucas number, or `FibError::Cancelled` if
+/// [`request_cancel`] was called before it finished.
+pub fn calculate_lucas(n: u64) -> Result<BigUint, FibError> {
+    let (a, b) = fib_pair_cancellable(n)?;
+    Ok((b << 1u32) - a)
+}
+
+/// Calculates the Fibonacci number at a possibly negative index.
+///
+/// Running the recurrence backwards gives `F(−n) = (−1)^(n+1)·F(n)`, so the numbers at
+/// negative indices are the ones at positive indices with every even one negated.
+///
+/// # Arguments
+/// * `n` - The signed index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// A `Result<BigInt, FibError>` with the Fibonacci number, or `FibError::Cancelled` if
+/// [`request_cancel`] was called before it finished.
+pub fn calculate_fibonacci_signed(n: i64) -> Result<BigInt, FibError> {
+    let value = BigInt::from(calculate_fibonacci(n.unsigned_abs())?);
+    Ok(if n < 0 && n % 2 == 0 { -value } else { value })
+}
+
+/// Calculates the sum of the squares of the first `n + 1` Fibonacci numbers.
+///
+/// This uses the identity `F(0)² + F(1)² + ... + F(n)² = F(n)·F(n+1)`, so instead of
+/// summing `n + 1` squares the result is a single product of the pair returned by
+/// `fib_pair`.
+///
+/// # Arguments
+/// * `n` - The index of the last Fibonacci number included in the sum.
+///
+/// # Returns
+/// A `BigUint` equal to `F(0)² + F(1)² + ... + F(n)²`.
+pub fn fib_square_sum(n: u64) -> BigUint {
+    let (a, b) = fib_pair(n);
+    a * b
+}
+
+/// Calculates the Fibonacci number `F(2n)` directly from the pair of `n`.
+///
+/// This uses the doubling identity `F(2n) = F(n)·(2F(n+1) − F(n))` on the pair returned by
+/// `fib_pair`, so it costs a single product more than `F(n)`. As the index `2n` is never
+/// formed, this also works for `n` above `u64::MAX / 2`.
+///
+/// # Arguments
+/// * `n` - Half of the index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// The Fibonacci number `F(2n)`.
+pub fn fib_double(n: u64) -> BigUint {
+    let (a, b) = fib_pair(n);
+    let twice_b: BigUint = &b << 1;
+    &a * (twice_b - &a)
+}
+
+/// Calculates the consecutive Fibonacci numbers `F(n)` and `F(n+1)` in one pass.
+///
+/// Fast doubling produces both numbers of the pair anyway, so callers that continue the
+/// recurrence or need both neighbours for an identity get `F(n+1)` without a second
+/// calculation.
+///
+/// # Arguments
+/// * `n` - The index of the first Fibonacci number of the pair.
+///
+/// # Returns
+/// A tuple `(F(n), F(n+1))`, which is `(0, 1)` for `n = 0`.
+pub fn fib_consecutive(n: u64) -> (BigUint, BigUint) {
+    fib_pair(n)
+}
+
+/// Calculates the nth convergent of the continued fraction of the golden ratio.
+///
+/// The continued fraction of `φ` is `[1; 1, 1, 1, ...]` and its convergents are the ratios
+/// of consecutive Fibonacci numbers `F(n+1) / F(n)`: `1/1`, `2/1`, `3/2`, `5/3`, `8/5`, ...
+/// They alternate around `φ`, the odd convergents are below it and the even ones above.
+/// For `n = 0` this is the degenerate convergent `1/0` that starts every continued fraction.
+///
+/// # Arguments
+/// * `n` - The index of the convergent.
+///
+/// # Returns
+/// A tuple `(numerator, denominator)` equal to `(F(n+1), F(n))`.
+pub fn golden_convergent(n: u64) -> (BigUint, BigUint) {
+    let (a, b) = fib_pair(n);
+    (b, a)
+}
+
+/// Finds the Fibonacci number closest to an arbitrary value.
+///
+/// The index is estimated with `n ≈ log_φ(value·√5)`, which follows from Binet's formula,
+/// and corrected until `F(n) ≤ value < F(n+1)`. Of the two bracketing Fibonacci numbers the
+/// closer one is returned, and when both are equally far away the larger one. As `1` is both
+/// `F(1)` and `F(2)`, it is returned with the smaller index `1`.
+///
+/// # Arguments
+/// * `value` - The value to find the closest Fibonacci number for.
+///
+/// # Returns
+/// A tuple `(index, F(index))` with the Fibonacci number closest to `value`.
+pub fn nearest_fib(value: &BigUint) -> (u64, BigUint) {
+    if value <= &BigUint::from(1u32) {
+        return (u64::from(value.bit(0)), value.clone());
+    }
+
+    // Only the 64 most significant bits are needed for the logarithm
+    let shift = value.bits().saturating_sub(64);
+    let top = (value >> shift).iter_u64_digits().next().unwrap_or(0);
+    let ln_value = (top as f64).ln() + shift as f64 * std::f64::consts::LN_2;
+    let ln_phi = ((1.0 + 5f64.sqrt()) / 2.0).ln();
+    let estimate = ((ln_value + 5f64.sqrt().ln()) / ln_phi).round() as u64;
+
+    // The estimate is off by a few indices at most, which single steps correct
+    let mut n = estimate;
+    let (mut current, mut next) = fib_pair(n);
+    while &current > value {
+        let previous = &next - &current;
+        next = std::mem::replace(&mut current, previous);
+        n -= 1;
+    }
+    while &next <= value {
+        let following = &current + &next;
+        current = std::mem::replace(&mut next, following);
+        n += 1;
+    }
+
+    if value - &current < &next - value {
+        (n, current)
+    } else {
+        (n + 1, next)
+    }
+}
+
+/// A lazy iterator over consecutive Fibonacci numbers.
+///
+/// The iterator yields `(index, F(index))` tuples. Only the first pair is calculated with
+/// fast doubling, every following number is a single addition of the previous two.
+#[derive(Clone, Debug)]
+pub struct FibIter {
+    index: u64,
+    current: BigUint,
+    next: BigUint,
+}
+
+impl FibIter {
+    /// Creates an iterator starting at the Fibonacci number `F(start)`.
+    pub fn new(start: u64) -> FibIter {
+        let (current, next) = fib_pair(start);
+        FibIter {
+            index: start,
+            current,
+            next,
+        }
+    }
+
+    /// Creates an iterator from a known Fibonacci pair `(F(index), F(index+1))`.
+    ///
+    /// The pair is not verified, so the caller is responsible for passing a valid pair.
+    pub fn from_pair(index: u64, current: BigUint, next: BigUint) -> FibIter {
+        FibIter {
+            index,
+            current,
+            next,
+        }
+    }
+
+    /// Returns the index of the Fibonacci number that will be yielded next.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Returns the pair `(F(index), F(index+1))` the iterator will continue from.
+    pub fn pair(&self) -> (&BigUint, &BigUint) {
+        (&self.current, &self.next)
+    }
+}
+
+impl Iterator for FibIter {
+    type Item = (u64, BigUint);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        self.index = index.checked_add(1)?;
+
+        let following = &self.current + &self.next;
+        let current = std::mem::replace(&mut self.next, following);
+        let value = std::mem::replace(&mut self.current, current);
+
+        Some((index, value))
+    }
+}
+
+/// Calculates the even-indexed Fibonacci number `F(2k)` with the recurrence of the bisection.
+///
+/// Every other Fibonacci number satisfies `a(k) = 3·a(k-1) − a(k-2)` on its own, which
+/// follows from `F(n+2) = 3F(n) − F(n-2)`. Starting from `F(0) = 0` and `F(2) = 1`, the
+/// recurrence is applied `k` times, so this suits the small indices of a table more than
+/// huge ones, which [`calculate_fibonacci`] reaches in a logarithmic number of steps.
+///
+/// # Arguments
+/// * `k` - Half of the index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// The Fibonacci number `F(2k)`.
+pub fn fib_even(k: u64) -> BigUint {
+    every_other(k, BigUint::ZERO, BigUint::from(1u32))
+}
+
+/// Calculates the odd-indexed Fibonacci number `F(2k+1)` with the recurrence of the bisection.
+///
+/// This is [`fib_even`] for the odd indices, starting from `F(1) = 1` and `F(3) = 2`.
+///
+/// # Arguments
+/// * `k` - The index of the Fibonacci number to calculate, halved and rounded down.
+///
+/// # Returns
+/// The Fibonacci number `F(2k+1)`.
+pub fn fib_odd(k: u64) -> BigUint {
+    every_other(k, BigUint::from(1u32), BigUint::from(2u32))
+}
+
+/// Applies the recurrence `a(k) = 3·a(k-1) − a(k-2)` `k` times to the pair `(a(0), a(1))`.
+fn every_other(k: u64, mut current: BigUint, mut next: BigUint) -> BigUint {
+    for _ in 0..k {
+        let following = &next * 3u32 - &current;
+        current = std::mem::replace(&mut next, following);
+    }
+    current
+}
+
+/// Which indices a [`FibParityIter`] yields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    /// Returns whether `index` has this parity.
+    pub fn matches(self, index: u64) -> bool {
+        (index & 1 == 1) == (self == Parity::Odd)
+    }
+}
+
+/// A lazy iterator over every other Fibonacci number, all with even or all with odd indices.
+///
+/// Like [`FibIter`] only the first pair is calculated with fast doubling, every following
+/// number is derived from the two before it with `F(n+2) = 3F(n) − F(n-2)`, so the numbers
+/// in between are never calculated.
+#[derive(Clone, Debug)]
+pub struct FibParityIter {
+    index: u64,
+    current: BigUint,
+    next: BigUint,
+}
+
+impl FibParityIter {
+    /// Creates an iterator starting at the first index from `start` on with the given parity.
+    pub fn new(start: u64, parity: Parity) -> FibParityIter {
+        let index = if parity.matches(start) {
+            start
+        } else {
+            start.saturating_add(1)
+        };
+        let (current, following) = fib_pair(index);
+        let next = &current + &following;
+        FibParityIter {
+            index,
+            current,
+            next,
+        }
+    }
+}
+
+impl Iterator for FibParityIter {
+    type Item = (u64, BigUint);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        self.index = index.checked_add(2)?;
+
+        let following = &self.next * 3u32 - &self.current;
+        let current = std::mem::replace(&mut self.next, following);
+        let value = std::mem::replace(&mut self.current, current);
+
+        Some((index, value))
+    }
+}
+
+/// Calculates the Fibonacci pair `(F(n), F(n+1))` using fast doubling.
+///
+/// The pair for `n` is derived from the pair for `n / 2` with the identities
+/// `F(2k) = 2F(k+1)² − 3F(k)² − 2(−1)^k` and `F(2k+1) = F(k)² + F(k+1)²`. Both squares
+/// are computed in parallel.
+///
+/// # Arguments
+/// * `n` - The index of the first Fibonacci number of the pair.
+///
+/// # Returns
+/// A tuple `(F(n), F(n+1))`.
+pub(crate) fn fib_pair(n: u64) -> (BigUint, BigUint) {
+    if n == 0 {
+        return (BigUint::ZERO, BigUint::from(1u32));
+    }
+
+    let (a, b) = fib_pair(n >> 1);
+    doubling_step(&a, &b, n)
+}
+
+/// Calculates the Fibonacci pair `(F(n), F(n+1))` like `fib_pair_cancellable`, with every
+/// square calculated by num-bigint in one piece.
+///
+/// The two squares of a step still run side by side, but they are never split over more
+/// threads, which is how [`Strategy::FastDoubling`] calculates.
+///
+/// # Returns
+/// A `Result<(BigUint, BigUint), FibError>` with the pair, or `FibError::Cancelled` if
+/// [`request_cancel`] was called before it finished.
+fn fib_pair_unsplit(n: u64) -> Result<(BigUint, BigUint), FibError> {
+    let mut pair = (BigUint::ZERO, BigUint::from(1u32));
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        check_cancel()?;
+        let (a, b) = pair;
+        let (a_squared, b_squared) = join(|| &a * &a, || &b * &b);
+        pair = combine_squares(a_squared, b_squared, n >> bit);
+    }
+    Ok(pair)
+}
+
+/// Calculates the Fibonacci pair `(F(n), F(n+1))` like `fib_pair`, checking for a
+/// cancellation request before every doubling level.
+///
+/// # Returns
+/// A `Result<(BigUint, BigUint), FibError>` with the pair, or `FibError::Cancelled` if
+/// [`request_cancel`] was called before it finished.
+pub(crate) fn fib_pair_cancellable(n: u64) -> Result<(BigUint, BigUint), FibError> {
+    if n == 0 {
+        return Ok((BigUint::ZERO, BigUint::from(1u32)));
+    }
+
+    let (a, b) = fib_pair_cancellable(n >> 1)?;
+    check_cancel()?;
+    Ok(doubling_step(&a, &b, n))
+}
+
+/// The in-progress state of an iterative fast doubling computation of `F(n)`.
+///
+/// The bits of `n` are processed from the most significant to the least significant one.
+/// After every step the state holds the pair `(F(k), F(k+1))` where `k` is the prefix of
+/// `n` processed so far, so the state can be stored and continued at any point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DoublingState {
+    n: u64,
+    remaining_bits: u32,
+    a: BigUint,
+    b: BigUint,
+}
+
+impl DoublingState {
+    /// Creates the initial state for calculating `F(n)`.
+    pub fn new(n: u64) -> DoublingState {
+        DoublingState {
+            n,
+            remaining_bits: u64::BITS - n.leading_zeros(),
+            a: BigUint::ZERO,
+            b: BigUint::from(1u32),
+        }
+    }
+
+    /// Restores a state from its parts, as stored in a checkpoint.
+    ///
+    /// # Returns
+    /// `None` if `remaining_bits` is not valid for `n`.
+    pub fn from_parts(
+        n: u64,
+        remaining_bits: u32,
+        a: BigUint,
+        b: BigUint,
+    ) -> Option<DoublingState> {
+        (remaining_bits <= u64::BITS - n.leading_zeros()).then_some(DoublingState {
+            n,
+            remaining_bits,
+            a,
+            b,
+        })
+    }
+
+    /// Returns the target index `n`.
+    pub fn n(&self) -> u64 {
+        self.n
+    }
+
+    /// Returns the number of bits of `n` that still have to be processed.
+    pub fn remaining_bits(&self) -> u32 {
+        self.remaining_bits
+    }
+
+    /// Returns the index `k` of the current pair `(F(k), F(k+1))`.
+    pub fn index(&self) -> u64 {
+        self.n.checked_shr(self.remaining_bits).unwrap_or(0)
+    }
+
+    /// Returns the current pair `(F(k), F(k+1))`.
+    pub fn pair(&self) -> (&BigUint, &BigUint) {
+        (&self.a, &self.b)
+    }
+
+    /// Checks whether every bit of `n` has been processed.
+    pub fn is_finished(&self) -> bool {
+        self.remaining_bits == 0
+    }
+
+    /// Processes the next bit of `n` with a single fast doubling step.
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        self.remaining_bits -= 1;
+        (self.a, self.b) = doubling_step(&self.a, &self.b, self.index());
+    }
+
+    /// Consumes the state and returns `F(k)`, which is `F(n)` once the state is finished.
+    pub fn into_value(self) -> BigUint {
+        self.a
+    }
+}
+
+/// A single step of a traced fast doubling computation, as returned by
+/// [`explain_fibonacci`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// The index `k` of the pair `(F(k), F(k+1))` calculated in this step.
+    pub index: u64,
+    /// The index of the pair the step started from, which is `index / 2`.
+    pub half: u64,
+    /// Whether `index` is odd, so it is `2·half + 1` instead of `2·half`.
+    pub odd: bool,
+    /// The duration of the step.
+    pub duration: Duration,
+}
+
+/// Calculates the nth Fibonacci number with fast doubling and records every step.
+///
+/// Fast doubling halves the index until it reaches 0 and then doubles the pair
+/// `(F(k), F(k+1))` back up, once per bit of `n`. The trace holds one [`Step`] per bit in
+/// the order the steps are performed, so for `n = 10` it visits the indices
+/// `1, 2, 5, 10`, the halving chain `10 → 5 → 2 → 1 → 0` read backwards. This is a separate
+/// function, so the normal calculation does not pay for the trace.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// A tuple with the nth Fibonacci number and the steps of the calculation.
+pub fn explain_fibonacci(n: u64) -> (BigUint, Vec<Step>) {
+    let mut state = DoublingState::new(n);
+    let mut trace = Vec::with_capacity(state.remaining_bits() as usize);
+
+    while !state.is_finished() {
+        let half = state.index();
+        let start_time = Instant::now();
+        state.step();
+        let index = state.index();
+        trace.push(Step {
+            index,
+            half,
+            odd: index & 1 == 1,
+            duration: start_time.elapsed(),
+        });
+    }
+
+    (state.into_value(), trace)
+}
+
+/// Performs a single fast doubling step.
+///
+/// Given the pair `(F(k), F(k+1))` with `k = index / 2` this returns `(F(2k), F(2k+1))`,
+/// or `(F(2k+1), F(2k+2))` when `index` is odd.
+///
+/// A step takes only the two squares `F(k)²` and `F(k+1)²`, which are computed in
+/// parallel, instead of the product `F(k)·(2F(k+1) − F(k))` and both squares:
+/// `F(2k+1) = F(k)² + F(k+1)²` as before, and `F(2k) = 2F(k+1)² − 3F(k)² − 2(−1)^k`, which
+/// follows from Cassini's identity. The sign only needs the parity of `k`, which is why the
+/// step takes the index instead of the pair alone.
+///
+/// # Arguments
+/// * `a` - The Fibonacci number `F(k)`.
+/// * `b` - The Fibonacci number `F(k+1)`.
+/// * `index` - The index of the first Fibonacci number of the returned pair.
+///
+/// # Returns
+/// The Fibonacci pair `(F(index), F(index+1))`.
+pub(crate) fn doubling_step(a: &BigUint, b: &BigUint, index: u64) -> (BigUint, BigUint) {
+    // Both squares share the threads
+    let threads = current_num_threads().div_ceil(2);
+    let (a_squared, b_squared) = join(
+        || parallel_multiply(a, a, threads),
+        || parallel_multiply(b, b, threads),
+    );
+    combine_squares(a_squared, b_squared, index)
+}
+
+/// Calculates the Fibonacci pair for a larger index from the pair of a smaller one.
+///
+/// When the binary representation of `k` is a prefix of `m`, the remaining fast doubling
+/// steps are applied directly to the known pair. Otherwise the pair for `m - k` is
+/// calculated and combined with the known pair using the addition formulas
+/// `F(a+b) = F(a)·F(b+1) + F(a+1)·F(b) − F(a)·F(b)` and
+/// `F(a+b+1) = F(a+1)·F(b+1) + F(a)·F(b)`.
+///
+/// # Arguments
+/// * `k` - The index of the known pair.
+/// * `pair` - The known Fibonacci pair `(F(k), F(k+1))`.
+/// * `m` - The target index, which must be at least `k`.
+///
+/// # Returns
+/// A `Result<(BigUint, BigUint), FibError>` with the Fibonacci pair `(F(m), F(m+1))`, or
+/// `FibError::Cancelled` if [`request_cancel`] was called before it finished.
+pub(crate) fn fib_pair_from(
+    k: u64,
+    pair: &(BigUint, BigUint),
+    m: u64,
+) -> Result<(BigUint, BigUint), FibError> {
+    debug_assert!(k <= m);
+
+    if k == m {
+        return Ok(pair.clone());
+    }
+
+    // Continue doubling when k is a binary prefix of m
+    if k > 0 {
+        let remaining_bits = k.leading_zeros() - m.leading_zeros();
+        if m >> remaining_bits == k {
+            let (mut a, mut b) = pair.clone();
+            for bit in (0..remaining_bits).rev() {
+                check_cancel()?;
+                (a, b) = doubling_step(&a, &b, m >> bit);
+            }
+            return Ok((a, b));
+        }
+    }
+
+    let (fa, fa1) = pair;
+    let (fb, fb1) = fib_pair_cancellable(m - k)?;
+    let (ab1, (a1b, ab)) = join(|| fa * &fb1, || join(|| fa1 * &fb, || fa * &fb));
+    let a1b1 = fa1 * &fb1;
+
+    Ok((ab1 + a1b - &ab, a1b1 + ab))
+}
+
+/// Calculates `F(n)` by raising the matrix `[[1, 1], [1, 0]]` to the nth power.
+///
+/// Every power of the matrix has the form `[[F(k+1), F(k)], [F(k), F(k-1)]]`, so a matrix
+/// is stored as the triple `(F(k+1), F(k), F(k-1))` and the power is calculated by
+/// repeated squaring.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// The Fibonacci number `F(n)`.
+pub(crate) fn fib_matrix(n: u64) -> BigUint {
+    // Powers of the same matrix commute, so every product stays symmetric
+    fn multiply(
+        (a1, b1, c1): &(BigUint, BigUint, BigUint),
+        (a2, b2, c2): &(BigUint, BigUint, BigUint),
+    ) -> (BigUint, BigUint, BigUint) {
+        let b1b2 = b1 * b2;
+        (a1 * a2 + &b1b2, a1 * b2 + b1 * c2, b1b2 + c1 * c2)
+    }
+
+    let mut result = (BigUint::from(1u32), BigUint::ZERO, BigUint::from(1u32));
+    let mut power = (BigUint::from(1u32), BigUint::from(1u32), BigUint::ZERO);
+    let mut remaining = n;
+
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result = multiply(&result, &power);
+        }
+        remaining >>= 1;
+        if remaining > 0 {
+            power = multiply(&power, &power);
+        }
+    }
+
+    result.1
+}
+
+/// Calculates `F(n)` by adding consecutive Fibonacci numbers `n` times.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+///
+/// # Returns
+/// The Fibonacci number `F(n)`.
+fn fib_iterative(n: u64) -> BigUint {
+    let mut a = BigUint::ZERO;
+    let mut b = BigUint::from(1u32);
+
+    for _ in 0..n {
+        let sum = &a + &b;
+        a = std::mem::replace(&mut b, sum);
+    }
+
+    a
+}
+
+/// Calculates the Fibonacci pair for a smaller index from the pair of a larger one.
+///
+/// With `d = k - m` this uses d'Ocagne's identity `F(k)·F(d+1) − F(k+1)·F(d) =
+/// (−1)^d·F(k−d)`, so only the small pair for `d` has to be calculated. The sign is
+/// handled by taking the absolute difference, as Fibonacci numbers with a non-negative
+/// index are never negative.
+///
+/// # Arguments
+/// * `k` - The index of the known pair.
+/// * `pair` - The known Fibonacci pair `(F(k), F(k+1))`.
+/// * `m` - The target index, which must be at most `k`.
+///
+/// # Returns
+/// A `Result<(BigUint, BigUint), FibError>` with the Fibonacci pair `(F(m), F(m+1))`, or
+/// `FibError::Cancelled` if [`request_cancel`] was called before it finished.
+pub(crate) fn fib_pair_before(
+    k: u64,
+    pair: &(BigUint, BigUint),
+    m: u64,
+) -> Result<(BigUint, BigUint), FibError> {
+    debug_assert!(m <= k);
+
+    if k == m {
+        return Ok(pair.clone());
+    }
+
+    let absolute_difference = |x: BigUint, y: BigUint| if x > y { x - y } else { y - x };
+
+    let (fk, fk1) = pair;
+    let (fd_1, fd) = fib_pair_cancellable(k - m - 1)?;
+    let fd1 = &fd_1 + &fd;
+
+    let ((k_d1, k1_d), (k_d, k1_d_1)) = join(
+        || join(|| fk * &fd1, || fk1 * &fd),
+        || join(|| fk * &fd, || fk1 * &fd_1),
+    );
+
+    Ok((
+        absolute_difference(k_d1, k1_d),
+        absolute_difference(k_d, k1_d_1),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{doubling::fib_pair_sequential, SplitMix64};
+
+    #[test]
+    fn doubling_agrees_with_iteration_up_to_2000() {
+        let (mut current, mut next) = (BigUint::ZERO, BigUint::from(1u32));
+        let mut pairs = Vec::new();
+        for n in 0..2000u64 {
+            let pair = (current.clone(), next.clone());
+            assert_eq!(fib_pair(n), pair, "fib_pair({})", n);
+            assert_eq!(fib_pair_sequential(n), pair, "fib_pair_sequential({})", n);
+            assert_eq!(
+                fib_pair_unsplit(n).unwrap(),
+                pair,
+                "fib_pair_unsplit({})",
+                n
+            );
+            if n > 0 {
+                let (a, b): &(BigUint, BigUint) = &pairs[(n / 2) as usize];
+                assert_eq!(doubling_step(a, b, n), pair, "doubling step to {}", n);
+                assert_eq!(
+                    combine_squares(a * a, b * b, n),
+                    pair,
+                    "squares combined to {}",
+                    n
+                );
+            }
+            pairs.push(pair);
+            let sum = &current + &next;
+            current = std::mem::replace(&mut next, sum);
+        }
+        assert_eq!(fib_iterative(1999), pairs[1999].0);
+    }
+
+    #[test]
+    fn doubling_agrees_with_the_matrix_at_random_large_indices() {
+        let mut random = SplitMix64::new(83);
+        for _ in 0..20 {
+            let n = 2000 + random.next_u64() % 300_000;
+            let expected = fib_matrix(n);
+            assert_eq!(fib_pair(n).0, expected, "fib_pair({})", n);
+            assert_eq!(
+                fib_pair_sequential(n).0,
+                expected,
+                "fib_pair_sequential({})",
+                n
+            );
+        }
+    }
+
+    /// The Fibonacci numbers up to `F(last)`, added up one after the other.
+    fn iterated(last: usize) -> Vec<BigUint> {
+        let mut numbers = vec![BigUint::ZERO, BigUint::from(1u32)];
+        while numbers.len() <= last {
+            let sum = &numbers[numbers.len() - 2] + &numbers[numbers.len() - 1];
+            numbers.push(sum);
+        }
+        numbers.truncate(last + 1);
+        numbers
+    }
+
+    #[test]
+    fn square_sums_match_the_squares_added_up() {
+        let numbers = iterated(40);
+        let mut sum = BigUint::ZERO;
+        for (n, number) in numbers.iter().enumerate() {
+            sum += number * number;
+            assert_eq!(fib_square_sum(n as u64), sum, "n = {}", n);
+        }
+        assert_eq!(
+            fib_square_sum(40),
+            BigUint::from(165_580_141u64 * 102_334_155)
+        );
+    }
+
+    #[test]
+    fn derived_numbers_match_iteration() {
+        let numbers = iterated(401);
+        for n in 0..200u64 {
+            let i = n as usize;
+            assert_eq!(fib_double(n), numbers[2 * i], "F(2·{})", n);
+            assert_eq!(fib_even(n), numbers[2 * i], "F(2·{})", n);
+            assert_eq!(fib_odd(n), numbers[2 * i + 1], "F(2·{}+1)", n);
+            assert_eq!(
+                fib_consecutive(n),
+                (numbers[i].clone(), numbers[i + 1].clone())
+            );
+            assert_eq!(
+                golden_convergent(n),
+                (numbers[i + 1].clone(), numbers[i].clone())
+            );
+
+            // L(n) = F(n-1) + F(n+1), with F(-1) = 1
+            let before = if n == 0 {
+                BigUint::from(1u32)
+            } else {
+                numbers[i - 1].clone()
+            };
+            assert_eq!(
+                calculate_lucas(n).unwrap(),
+                before + &numbers[i + 1],
+                "L({})",
+                n
+            );
+
+            let negative = calculate_fibonacci_signed(-(n as i64)).unwrap();
+            assert_eq!(negative.magnitude(), &numbers[i], "F(-{})", n);
+            assert_eq!(
+                negative.sign() == num_bigint::Sign::Minus,
+                n % 2 == 0 && n > 0
+            );
+        }
+    }
+
+    #[test]
+    fn convergents_alternate_around_the_golden_ratio() {
+        // A ratio p/q is above φ exactly when p² > pq + q², as φ is the root of x² = x + 1
+        let phi = (1.0 + 5f64.sqrt()) / 2.0;
+        for n in 1..300u64 {
+            let (p, q) = golden_convergent(n);
+            let above = &p * &p > &p * &q + &q * &q;
+            assert_eq!(above, n % 2 == 0, "convergent {}", n);
+            if n < 20 {
+                let ratio = u64::try_from(&p).unwrap() as f64 / u64::try_from(&q).unwrap() as f64;
+                assert_eq!(ratio > phi, above, "convergent {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn iterators_continue_where_they_start() {
+        let numbers = iterated(300);
+        for start in [0, 1, 2, 57, 200] {
+            let expected = (start..=300).map(|n| (n, numbers[n as usize].clone()));
+            assert!(
+                FibIter::new(start).take(50).eq(expected.take(50)),
+                "from {}",
+                start
+            );
+
+            for parity in [Parity::Even, Parity::Odd] {
+                let expected = (start..=300)
+                    .filter(|&n| parity.matches(n))
+                    .map(|n| (n, numbers[n as usize].clone()));
+                let iter = FibParityIter::new(start, parity);
+                assert!(
+                    iter.take(40).eq(expected.take(40)),
+                    "{:?} from {}",
+                    parity,
+                    start
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_numbers_are_the_closest_of_their_neighbours() {
+        let numbers = iterated(120);
+        let mut random = SplitMix64::new(51);
+        for _ in 0..500 {
+            let value = BigUint::from(random.next_u64()) * (random.next_u64() % 1000 + 1);
+            let (n, nearest) = nearest_fib(&value);
+            assert_eq!(nearest, numbers[n as usize]);
+
+            // No other Fibonacci number is strictly closer, and ties go to the larger one
+            let distance = |number: &BigUint| {
+                if number > &value {
+                    number - &value
+                } else {
+                    &value - number
+                }
+            };
+            for (other, number) in numbers.iter().enumerate().skip(1) {
+                assert!(
+                    distance(number) >= distance(&nearest),
+                    "{} vs F({})",
+                    value,
+                    other
+                );
+                if distance(number) == distance(&nearest) {
+                    assert!(number <= &nearest);
+                }
+            }
+        }
+        assert_eq!(nearest_fib(&BigUint::from(1u32)), (1, BigUint::from(1u32)));
+        assert_eq!(nearest_fib(&BigUint::from(4u32)), (5, BigUint::from(5u32)));
+    }
+
+    #[test]
+    fn doubling_state_steps_to_the_number() {
+        for n in [0, 1, 2, 3, 1000, 65_537, 123_456] {
+            let mut state = DoublingState::new(n);
+            while !state.is_finished() {
+                let remaining = state.remaining_bits();
+                state.step();
+                assert_eq!(state.remaining_bits(), remaining - 1);
+                assert_eq!(state.pair().0, &calculate_fibonacci(state.index()).unwrap());
+            }
+            assert_eq!(state.index(), n);
+            assert_eq!(state.into_value(), calculate_fibonacci(n).unwrap());
+        }
+    }
+
+    #[test]
+    fn timed_calculations_report_their_duration() {
+        let (value, duration) = calculate_fibonacci_timed(100_000).unwrap();
+        assert_eq!(value, calculate_fibonacci(100_000).unwrap());
+        assert!(duration > Duration::ZERO);
+    }
+
+    #[test]
+    fn verified_calculations_return_the_agreed_number() {
+        assert_eq!(
+            calculate_fibonacci_verified(1000).unwrap(),
+            iterated(1000)[1000]
+        );
+    }
+
+    #[test]
+    fn the_strategy_changes_at_every_threshold() {
+        let strategy = |n, threads| choose_algorithm(n, threads).strategy;
+        assert_eq!(strategy(word_index(), 8), Strategy::MachineWord);
+        assert_eq!(strategy(word_index() + 1, 8), Strategy::FastDoubling);
+
+        // Squarings of F(n/2) are split from parallel_multiply_bits() on, with three threads
+        let half = (parallel_multiply_bits() as f64 / BITS_PER_INDEX).ceil() as u64;
+        assert_eq!(strategy(2 * half - 1, 8), Strategy::FastDoubling);
+        assert_eq!(strategy(2 * half, 8), Strategy::SplitFastDoubling);
+        assert_eq!(strategy(2 * half, 3), Strategy::SplitFastDoubling);
+        assert_eq!(strategy(2 * half, 2), Strategy::FastDoubling);
+        assert_eq!(strategy(u64::MAX, 1), Strategy::FastDoubling);
+    }
+
+    #[test]
+    fn every_algorithm_calculates_the_same_numbers() {
+        for n in [0, 1, 2, 93, 94, 1000, 4096, 12_345] {
+            let expected = fib_matrix(n);
+            for algorithm in Algorithm::ALL {
+                assert_eq!(
+                    calculate_fibonacci_with(n, algorithm).unwrap(),
+                    expected,
+                    "{} at {}",
+                    algorithm,
+                    n
+                );
+            }
+            assert_eq!(calculate_fibonacci_timed(n).unwrap().0, expected);
+        }
+    }
+
+    #[test]
+    fn passed_deadlines_time_out() {
+        let past = Instant::now() - Duration::from_millis(1);
+        for n in [1, 1000, 100_000] {
+            assert!(matches!(
+                calculate_fibonacci_deadline(n, past),
+                Err(FibError::Timeout)
+            ));
+        }
+
+        // Nothing is left to calculate for 0, and a distant deadline is kept
+        assert_eq!(
+            calculate_fibonacci_deadline(0, past).unwrap(),
+            BigUint::ZERO
+        );
+        let distant = Instant::now() + Duration::from_secs(3600);
+        assert_eq!(
+            calculate_fibonacci_deadline(1000, distant).unwrap(),
+            calculate_fibonacci(1000).unwrap()
+        );
+    }
+
+    #[test]
+    fn explanations_trace_every_doubling_step() {
+        let (value, trace) = explain_fibonacci(13);
+        assert_eq!(value, BigUint::from(233u32));
+
+        // 13 is 1101 in binary, so the pair doubles up through 1, 3 and 6
+        let steps: Vec<(u64, u64, bool)> = trace
+            .iter()
+            .map(|step| (step.index, step.half, step.odd))
+            .collect();
+        assert_eq!(
+            steps,
+            [(1, 0, true), (3, 1, true), (6, 3, false), (13, 6, true)]
+        );
+
+        let (zero, no_steps) = explain_fibonacci(0);
+        assert_eq!(zero, BigUint::ZERO);
+        assert!(no_steps.is_empty());
+    }
+}
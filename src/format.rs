@@ -0,0 +1,1552 @@
+use crate::{
+    decimal::POWERS_OF_TEN, to_decimal_string_in, write_decimal, Algorithm, BufferPool, FibResult,
+};
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+/// The default number of significant digits shown in scientific notation.
+pub const DEFAULT_PRECISION: usize = 5;
+
+/// The default number of digits above which numbers are shown in scientific notation.
+pub const DEFAULT_SCI_THRESHOLD: u32 = 35;
+
+/// The default number of digits above which printed numbers are shortened.
+pub const DEFAULT_MAX_PRINT_DIGITS: usize = 5000;
+
+/// Converts a `BigUint` number to a string representation in scientific notation.
+///
+/// This function takes a `BigUint` number as input and returns a string representation
+/// of the number in scientific notation format. The function ensures that the output
+/// string has a fixed number of significant digits (5 by default) and adjusts the
+/// exponent accordingly.
+///
+/// # Arguments
+/// * `number` - The `BigUint` number to be converted to scientific notation.
+///
+/// # Returns
+/// A `String` representing the input `BigUint` number in scientific notation format.
+pub fn scientific_notation(number: &BigUint) -> String {
+    scientific_notation_with_precision(number, DEFAULT_PRECISION)
+}
+
+/// Converts a `BigUint` number to scientific notation with the given number of significant
+/// digits.
+///
+/// The exponent is the exact number of decimal digits minus one. Digits beyond the requested
+/// precision are truncated toward zero, so the mantissa never rounds up to the next power of
+/// ten. Numbers with fewer digits than requested are padded with zeros, like `1.2000e+1` for
+/// 12 with 5 significant digits, and zero is written as `0.0000e+0`.
+///
+/// # Arguments
+/// * `number` - The `BigUint` number to be converted to scientific notation.
+/// * `precision` - The number of significant digits of the mantissa (at least 1).
+///
+/// # Returns
+/// A `String` representing the input `BigUint` number in scientific notation format.
+pub fn scientific_notation_with_precision(number: &BigUint, precision: usize) -> String {
+    scientific_notation_grouped(
+        number,
+        precision,
+        Notation::Scientific,
+        Grouping::Comma,
+        false,
+    )
+}
+
+/// Converts a `BigUint` number to scientific or engineering notation with the exponent
+/// grouped in the given style, or written with superscript digits.
+fn scientific_notation_grouped(
+    number: &BigUint,
+    precision: usize,
+    notation: Notation,
+    grouping: Grouping,
+    superscript: bool,
+) -> String {
+    let significant_digits = precision.max(1);
+
+    let (leading_digits, exponent) = leading_digits(number, significant_digits);
+
+    // Zero has a single digit as well, so its mantissa has to be padded separately
+    let mantissa = format!(
+        "{:0>width$}",
+        leading_digits.to_string(),
+        width = significant_digits
+    );
+    format_scientific(&mantissa, exponent, notation, grouping, superscript)
+}
+
+/// Joins the significant digits and the exponent of a number in scientific notation.
+///
+/// The decimal point is placed after the first digit and omitted when there is only one.
+/// In engineering notation the exponent is lowered to a multiple of 3 and the decimal point
+/// moves right by the same amount, like `43.466e+207`, where missing integer digits are
+/// filled with zeros, like `40e+207` for a single significant digit. As the digits are
+/// truncated rather than rounded, the integer part never reaches 1000.
+///
+/// The exponent is written as `e+` with separators in the given style, like
+/// `4.3466e+208`, or with `superscript` as a power of ten with a multiplication sign and
+/// Unicode superscript digits, like `4.3466 × 10²⁰⁸`. Superscript exponents are never
+/// grouped, as there are no superscript separators that read well in every font.
+///
+/// # Arguments
+/// * `digits` - The significant digits, at least one.
+/// * `exponent` - The power of ten the first digit is multiplied with.
+/// * `notation` - Whether the exponent is lowered to a multiple of 3.
+/// * `grouping` - The style of the separators in an `e+` exponent.
+/// * `superscript` - Whether the exponent is written with superscript digits.
+///
+/// # Returns
+/// A `String` with the number in scientific or engineering notation.
+pub fn format_scientific(
+    digits: &str,
+    exponent: u64,
+    notation: Notation,
+    grouping: Grouping,
+    superscript: bool,
+) -> String {
+    let integer_digits = match notation {
+        Notation::Scientific => 1,
+        Notation::Engineering => (exponent % 3) as usize + 1,
+    };
+    let exponent = exponent - (integer_digits as u64 - 1);
+
+    let digits = format!("{:0<width$}", digits, width = integer_digits);
+    let (integer_string, decimal_string) = digits.split_at(integer_digits);
+    let mantissa = if decimal_string.is_empty() {
+        integer_string.to_string()
+    } else {
+        format!("{}.{}", integer_string, decimal_string)
+    };
+
+    if superscript {
+        format!("{} × 10{}", mantissa, superscript_digits(exponent))
+    } else {
+        format!(
+            "{}e+{}",
+            mantissa,
+            thousands_separator_with(exponent, grouping)
+        )
+    }
+}
+
+/// Writes a number with Unicode superscript digits, like `²⁰⁸` for 208.
+fn superscript_digits(number: u64) -> String {
+    const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+    number
+        .to_string()
+        .bytes()
+        .map(|digit| SUPERSCRIPTS[usize::from(digit - b'0')])
+        .collect()
+}
+
+/// Formats a duration as a human-readable string.
+///
+/// The unit is chosen by the magnitude of the duration: whole nanoseconds, microseconds and
+/// milliseconds with one decimal (`847.3µs`), seconds with three decimals, minutes with the
+/// seconds to a tenth (`3m 12.4s`) and hours with whole minutes and seconds (`1h 02m 05s`).
+/// The duration is rounded before the unit is chosen, so the number in front of a unit
+/// below seconds is always less than 1000: 999.97µs is shown as `1.0ms` rather than
+/// `1000.0µs`. Microseconds are written with the micro sign, `µs`; use
+/// [`format_duration_with`] for plain ASCII.
+///
+/// # Arguments
+/// * `duration` - The duration to be formatted.
+///
+/// # Returns
+/// A `String` representing the input duration in a human-readable format.
+pub fn format_duration(duration: Duration) -> String {
+    format_duration_with(duration, false)
+}
+
+/// Formats a duration as a human-readable string like [`format_duration`], optionally
+/// restricted to ASCII.
+///
+/// # Arguments
+/// * `duration` - The duration to be formatted.
+/// * `ascii` - Whether microseconds are written as `us` instead of `µs`.
+///
+/// # Returns
+/// A `String` representing the input duration in a human-readable format.
+pub fn format_duration_with(duration: Duration, ascii: bool) -> String {
+    let nanos = duration.as_nanos();
+    let rounded = |unit: u128| (nanos + unit / 2) / unit;
+
+    if nanos < 1_000 {
+        return format!("{}ns", nanos);
+    }
+    let micro_tenths = rounded(100);
+    if micro_tenths < 10_000 {
+        return format!(
+            "{}.{}{}",
+            micro_tenths / 10,
+            micro_tenths % 10,
+            if ascii { "us" } else { "\u{b5}s" }
+        );
+    }
+    let milli_tenths = rounded(100_000);
+    if milli_tenths < 10_000 {
+        return format!("{}.{}ms", milli_tenths / 10, milli_tenths % 10);
+    }
+    let millis = rounded(1_000_000);
+    if millis < 60_000 {
+        return format!("{}.{:03}s", millis / 1_000, millis % 1_000);
+    }
+    let tenths = rounded(100_000_000);
+    if tenths < 36_000 {
+        return format!(
+            "{}m {:02}.{}s",
+            tenths / 600,
+            tenths % 600 / 10,
+            tenths % 10
+        );
+    }
+    let seconds = rounded(1_000_000_000);
+    format!(
+        "{}h {:02}m {:02}s",
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Which numbers are shown in scientific notation instead of every digit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SciThreshold {
+    /// Numbers with more than this many digits, so `Digits(35)` written as `1e35` starts at
+    /// exactly 10^35, the smallest number with 36 digits.
+    Digits(u32),
+    /// No number, every digit is always shown.
+    Never,
+    /// Every number, even single digits.
+    Always,
+}
+
+impl Default for SciThreshold {
+    fn default() -> Self {
+        SciThreshold::Digits(DEFAULT_SCI_THRESHOLD)
+    }
+}
+
+impl SciThreshold {
+    /// Checks whether a number is at or above the threshold.
+    ///
+    /// `Digits(n)` is reached by every number with at least `n + 1` digits, as decided by
+    /// [`should_use_scientific`], so `10^n` itself is shown in scientific notation and
+    /// `10^n − 1` with every digit.
+    ///
+    /// # Arguments
+    /// * `value` - The number to check.
+    ///
+    /// # Returns
+    /// `true` if `value` is shown in scientific notation.
+    pub fn reached_by(self, value: &BigUint) -> bool {
+        match self {
+            SciThreshold::Digits(max_digits) => {
+                should_use_scientific(value, u64::from(max_digits) + 1)
+            }
+            SciThreshold::Never => false,
+            SciThreshold::Always => true,
+        }
+    }
+}
+
+/// Decides whether a number is shown in scientific notation by its number of digits.
+///
+/// The digit count is compared instead of the number itself, so the boundary is exactly
+/// "at least `threshold_digits` digits": with 36, `10^35` is the smallest number shown in
+/// scientific notation. The count is derived from the bit length unless the number is
+/// within a digit of the threshold, so no power of ten has to be built for most numbers.
+///
+/// # Arguments
+/// * `value` - The number to be shown.
+/// * `threshold_digits` - The smallest number of digits shown in scientific notation.
+///
+/// # Returns
+/// `true` if `value` has at least `threshold_digits` decimal digits.
+pub fn should_use_scientific(value: &BigUint, threshold_digits: u64) -> bool {
+    // 2^(bits-1) <= value < 2^bits bounds the digit count, widened by one for rounding
+    let bits = value.bits() as f64;
+    let at_least = ((bits - 1.0).max(0.0) * 2f64.log10()) as u64;
+    let at_most = (bits * 2f64.log10()) as u64 + 2;
+    if at_least >= threshold_digits {
+        true
+    } else if at_most < threshold_digits {
+        false
+    } else {
+        digit_count(value) >= threshold_digits
+    }
+}
+
+impl fmt::Display for SciThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SciThreshold::Digits(max_digits) => write!(f, "1e{}", max_digits),
+            SciThreshold::Never => f.write_str("none"),
+            SciThreshold::Always => f.write_str("always"),
+        }
+    }
+}
+
+impl FromStr for SciThreshold {
+    type Err = String;
+
+    /// Parses `none`, `always` or a power of ten written as `1e50`, `10^50` or just `50`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "none" => return Ok(SciThreshold::Never),
+            "always" => return Ok(SciThreshold::Always),
+            _ => {}
+        }
+        let exponent = input
+            .strip_prefix("1e")
+            .or_else(|| input.strip_prefix("1E"))
+            .or_else(|| input.strip_prefix("10^"))
+            .unwrap_or(input);
+
+        exponent.parse().map(SciThreshold::Digits).map_err(|_| {
+            format!(
+                "Invalid threshold '{}', expected a power of ten like 1e50, none or always",
+                input
+            )
+        })
+    }
+}
+
+/// How the exponent of numbers too large to show every digit is chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// A single digit before the decimal point, like `4.3466e+208`.
+    #[default]
+    Scientific,
+    /// An exponent that is a multiple of 3 with one to three digits before the decimal
+    /// point, like `43.466e+207`.
+    Engineering,
+}
+
+impl Notation {
+    /// Every notation.
+    pub const ALL: [Notation; 2] = [Notation::Scientific, Notation::Engineering];
+
+    /// Returns the name of the notation as accepted by `from_str`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Notation::Scientific => "scientific",
+            Notation::Engineering => "engineering",
+        }
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Notation::ALL
+            .into_iter()
+            .find(|notation| notation.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown notation '{}', expected scientific or engineering",
+                    name
+                )
+            })
+    }
+}
+
+/// The style of the separators that group the digits of long numbers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Grouping {
+    /// A comma every three digits, like `1,234,567`.
+    #[default]
+    Comma,
+    /// A period every three digits, like `1.234.567`.
+    Period,
+    /// A space every three digits, like `1 234 567`.
+    Space,
+    /// A thin space (U+2009) every three digits, like `1 234 567`.
+    ThinSpace,
+    /// An underscore every three digits, like `1_234_567`.
+    Underscore,
+    /// A comma before the last three digits and every two digits above them, like
+    /// `12,34,567` in the Indian numbering system.
+    Indian,
+    /// No separators at all, like `1234567`.
+    None,
+}
+
+impl Grouping {
+    /// Every grouping style.
+    pub const ALL: [Grouping; 7] = [
+        Grouping::Comma,
+        Grouping::Period,
+        Grouping::Space,
+        Grouping::ThinSpace,
+        Grouping::Underscore,
+        Grouping::Indian,
+        Grouping::None,
+    ];
+
+    /// Returns the name of the style as accepted by `from_str`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Grouping::Comma => "comma",
+            Grouping::Period => "period",
+            Grouping::Space => "space",
+            Grouping::ThinSpace => "thin-space",
+            Grouping::Underscore => "underscore",
+            Grouping::Indian => "indian",
+            Grouping::None => "none",
+        }
+    }
+
+    /// Returns the separator inserted between two groups of digits.
+    pub fn separator(self) -> &'static str {
+        match self {
+            Grouping::Comma | Grouping::Indian => ",",
+            Grouping::Period => ".",
+            Grouping::Space => " ",
+            Grouping::ThinSpace => "\u{2009}",
+            Grouping::Underscore => "_",
+            Grouping::None => "",
+        }
+    }
+
+    /// Checks whether a separator goes in front of a digit that is followed by
+    /// `digits_to_end - 1` more digits.
+    fn separates(self, digits_to_end: usize) -> bool {
+        match self {
+            Grouping::None => false,
+            Grouping::Indian => digits_to_end >= 3 && (digits_to_end - 3).is_multiple_of(2),
+            _ => digits_to_end.is_multiple_of(3),
+        }
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Grouping::ALL
+            .into_iter()
+            .find(|grouping| grouping.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown grouping '{}', expected comma, period, space, thin-space, \
+                     underscore, indian or none",
+                    name
+                )
+            })
+    }
+}
+
+/// Formats a number with a thousands separator.
+///
+/// This function takes any integer up to 64 bits, or an `i128`, and returns a `String`
+/// representation of the number with a thousands separator (`,`) inserted every three
+/// digits. Negative numbers keep their leading `-` in front of the grouped magnitude, like
+/// `-1,234,567`.
+///
+/// # Arguments
+/// * `number` - The number to be formatted with a thousands separator.
+///
+/// # Returns
+/// A `String` representing the input number with a thousands separator.
+pub fn thousands_separator(number: impl Into<i128>) -> String {
+    thousands_separator_with(number, Grouping::Comma)
+}
+
+/// Formats a number with separators in the given grouping style.
+///
+/// # Arguments
+/// * `number` - The number to be formatted.
+/// * `grouping` - The style of the separators.
+///
+/// # Returns
+/// A `String` representing the input number with separators, like `12,34,567` for
+/// `Grouping::Indian` or `-1,234,567` for a negative number.
+pub fn thousands_separator_with(number: impl Into<i128>, grouping: Grouping) -> String {
+    let number = number.into();
+    let magnitude = group_digits_with(&number.unsigned_abs().to_string(), grouping);
+    if number < 0 {
+        format!("-{}", magnitude)
+    } else {
+        magnitude
+    }
+}
+
+/// Inserts a thousands separator into a string of digits.
+///
+/// This function takes a string of digits of any length, such as the decimal representation
+/// of a `BigUint`, and inserts a `,` every three digits counted from the right.
+///
+/// # Arguments
+/// * `digits` - The digits to be grouped.
+///
+/// # Returns
+/// A `String` with the digits grouped by a thousands separator.
+pub fn group_digits(digits: &str) -> String {
+    group_digits_with(digits, Grouping::Comma)
+}
+
+/// Inserts separators in the given grouping style into a string of digits.
+///
+/// # Arguments
+/// * `digits` - The digits to be grouped.
+/// * `grouping` - The style of the separators.
+///
+/// # Returns
+/// A `String` with the grouped digits.
+pub fn group_digits_with(digits: &str, grouping: Grouping) -> String {
+    group_part(digits, digits.len(), grouping)
+}
+
+/// Groups a part of a longer string of digits.
+///
+/// Separators are placed by the position of a digit counted from the end of the whole
+/// number, so a part of it is grouped at the same positions as the whole.
+///
+/// # Arguments
+/// * `part` - The digits to be grouped.
+/// * `digits_to_end` - The number of digits from the start of `part` to the end of the
+///   whole number.
+/// * `grouping` - The style of the separators.
+fn group_part(part: &str, digits_to_end: usize, grouping: Grouping) -> String {
+    let separator = grouping.separator();
+    let mut result = String::with_capacity(part.len() + part.len() / 2 * separator.len());
+    for (position, digit) in part.chars().enumerate() {
+        if position > 0 && grouping.separates(digits_to_end - position) {
+            result.push_str(separator);
+        }
+        result.push(digit);
+    }
+    result
+}
+
+/// Shortens a long string of digits to its first and last digits.
+///
+/// Strings with more than `max_digits` digits keep their first and last `max_digits / 2`
+/// digits, with the exact number of omitted digits in between, like
+/// `12345… <90 digits omitted> …67890`. Shorter strings are returned whole. With `group`
+/// the digits are grouped in the `grouping` style, at the same positions as in the whole
+/// number. The number of omitted digits is always grouped.
+///
+/// # Arguments
+/// * `digits` - The digits to shorten, without separators.
+/// * `max_digits` - The maximum number of digits kept (at least 2).
+/// * `grouping` - The style of the separators.
+/// * `group` - Whether the digits themselves are grouped.
+///
+/// # Returns
+/// A `String` with the whole or shortened digits.
+pub fn elide_digits(digits: &str, max_digits: usize, grouping: Grouping, group: bool) -> String {
+    let digit_grouping = if group { grouping } else { Grouping::None };
+    let total = digits.len();
+    if total <= max_digits.max(2) {
+        return group_digits_with(digits, digit_grouping);
+    }
+
+    let kept = max_digits.max(2) / 2;
+    let omitted = total - 2 * kept;
+    let (head, tail) = (&digits[..kept], &digits[total - kept..]);
+
+    format!(
+        "{}… <{} {} omitted> …{}",
+        group_part(head, total, digit_grouping),
+        thousands_separator_with(omitted as u64, grouping),
+        if omitted == 1 { "digit" } else { "digits" },
+        group_part(tail, kept, digit_grouping)
+    )
+}
+
+/// The layout of digits printed as wrapped lines of fixed-size blocks.
+///
+/// This is the layout of classic mathematical tables, like
+/// `3542248481 7926191507 5` for `F(100)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockLayout {
+    /// The maximum length of a line, including the offset.
+    pub width: usize,
+    /// The number of digits of a block.
+    pub block_len: usize,
+    /// Whether every line starts with the number of digits before it.
+    pub offsets: bool,
+}
+
+impl Default for BlockLayout {
+    fn default() -> Self {
+        BlockLayout {
+            width: 80,
+            block_len: 10,
+            offsets: false,
+        }
+    }
+}
+
+impl BlockLayout {
+    /// Returns the number of digits on a full line of a number with `total_digits` digits.
+    ///
+    /// Every line holds at least one block, even if it is wider than `width`.
+    pub fn digits_per_line(&self, total_digits: usize) -> usize {
+        let block_len = self.block_len.max(1);
+        let offset_len = if self.offsets {
+            total_digits.to_string().len() + 2
+        } else {
+            0
+        };
+        let blocks = (self.width.saturating_sub(offset_len) + 1) / (block_len + 1);
+        blocks.max(1) * block_len
+    }
+
+    /// Returns the number of lines of a number with `total_digits` digits.
+    pub fn line_count(&self, total_digits: usize) -> usize {
+        total_digits
+            .div_ceil(self.digits_per_line(total_digits))
+            .max(1)
+    }
+}
+
+/// Writes digits as wrapped lines of blocks separated by spaces.
+///
+/// The digits are written straight from `digits` to `writer`, so no second copy of a
+/// huge number is built. With `offsets` every line starts with the number of digits on the
+/// lines before it, right-aligned.
+///
+/// # Arguments
+/// * `digits` - The digits to write, without separators.
+/// * `layout` - The width, block length and offsets of the lines.
+/// * `writer` - The writer the lines are written to, each ending with a newline.
+///
+/// # Returns
+/// An `io::Result<()>` with the error if writing failed.
+pub fn write_digit_blocks(
+    digits: &str,
+    layout: &BlockLayout,
+    writer: &mut (impl Write + ?Sized),
+) -> io::Result<()> {
+    let total = digits.len();
+    let per_line = layout.digits_per_line(total);
+    let offset_width = total.to_string().len();
+
+    for (line_number, line) in digits.as_bytes().chunks(per_line).enumerate() {
+        if layout.offsets {
+            write!(
+                writer,
+                "{:>width$}  ",
+                line_number * per_line,
+                width = offset_width
+            )?;
+        }
+        for (block_number, block) in line.chunks(layout.block_len.max(1)).enumerate() {
+            if block_number > 0 {
+                writer.write_all(b" ")?;
+            }
+            writer.write_all(block)?;
+        }
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Counts the digits of a `BigUint` number in any base from 2 to 36 exactly.
+///
+/// Decimal digits are counted with [`digit_count`]. In bases that are powers of two every
+/// digit stands for the same number of bits, so the count follows from the bit length, and
+/// other bases are counted by converting the number.
+///
+/// # Arguments
+/// * `number` - The `BigUint` number whose digits should be counted.
+/// * `base` - The base of the digits.
+///
+/// # Returns
+/// The number of digits of `number` in `base` (`1` for zero).
+pub fn digit_count_in(number: &BigUint, base: u32) -> u64 {
+    match base {
+        10 => digit_count(number),
+        _ if base.is_power_of_two() => number.bits().div_ceil(u64::from(base.ilog2())).max(1),
+        _ => number.to_radix_le(base).len() as u64,
+    }
+}
+
+/// Counts the decimal digits of a `BigUint` number exactly.
+///
+/// The digit count follows from the leading digit, which is derived from the top bits of
+/// the number and an approximation of a power of ten as described in [`leading_digits`],
+/// so the number is never converted to a string and no power of ten of its size is built.
+///
+/// # Arguments
+/// * `number` - The `BigUint` number whose digits should be counted.
+///
+/// # Returns
+/// The number of decimal digits of `number` (`1` for zero).
+pub fn digit_count(number: &BigUint) -> u64 {
+    leading_digits(number, 1).1 + 1
+}
+
+/// Finds the leading decimal digits of a number and its decimal exponent.
+///
+/// The leading digits are `number / 10^(digits - significant_digits)` truncated toward
+/// zero, with zeros appended to numbers that have fewer digits. Instead of dividing by that
+/// power of ten, which has about as many bits as the number itself and cannot even be built
+/// with `BigUint::pow` beyond `u32::MAX` digits, the top bits of the number are divided by
+/// the top bits of the power, calculated with [`power_of_ten_bounds`]. Both truncations are
+/// bounded, so the division yields a lower and an upper bound of the leading digits. When
+/// they agree the result is exact, otherwise more bits are kept, up to the whole number.
+///
+/// The digit count is estimated from the bit length and corrected when the leading digits
+/// come out one digit too short or too long.
+///
+/// # Returns
+/// A tuple with the `significant_digits` leading digits as a number, which is below
+/// `10^significant_digits`, and the exponent, which is the digit count minus one.
+fn leading_digits(number: &BigUint, significant_digits: usize) -> (BigUint, u64) {
+    let bits = number.bits();
+    if bits == 0 {
+        return (BigUint::ZERO, 0);
+    }
+
+    let significant_digits = significant_digits as u64;
+    let lowest = power_of_ten(significant_digits - 1);
+    let highest = &lowest * 10u32;
+
+    // 2^(bits-1) <= number < 2^bits, so the estimate is off by at most one
+    let mut digits = ((bits - 1) as f64 * 2f64.log10()).floor() as u64 + 1;
+    // The quotient has about 3.3 bits per digit, and the bounds lose a few bits per step of
+    // the power of ten, so this leaves a wide margin for both
+    let mut kept_bits = 128 + 4 * significant_digits;
+
+    loop {
+        let leading = match digits.checked_sub(significant_digits) {
+            None => number * power_of_ten(significant_digits - digits),
+            Some(shift) if kept_bits >= bits => number / power_of_ten(shift),
+            Some(shift) => {
+                // top · 2^dropped <= number < (top + 1) · 2^dropped
+                let dropped = bits - kept_bits;
+                let top = number >> dropped;
+                let ((low, low_exponent), (high, high_exponent)) =
+                    power_of_ten_bounds(shift, kept_bits);
+                let lower = shifted_quotient(&top, dropped as i64 - high_exponent, &high);
+                let upper = shifted_quotient(&(top + 1u32), dropped as i64 - low_exponent, &low);
+                if lower != upper {
+                    kept_bits *= 4;
+                    continue;
+                }
+                lower
+            }
+        };
+
+        if leading < lowest && digits > 1 {
+            digits -= 1;
+        } else if leading >= highest {
+            digits += 1;
+        } else {
+            return (leading, digits - 1);
+        }
+    }
+}
+
+/// Calculates `10^exponent` exactly from the powers of [`POWERS_OF_TEN`], also for
+/// exponents beyond `u32::MAX`.
+fn power_of_ten(exponent: u64) -> BigUint {
+    POWERS_OF_TEN.power(exponent)
+}
+
+/// Calculates a lower and an upper bound of `10^exponent` with `bits` significant bits.
+///
+/// The power is calculated by repeated squaring, truncating every intermediate result to
+/// its top `bits` bits, once rounded down for the lower bound and once rounded up for the
+/// upper bound.
+///
+/// # Returns
+/// A tuple with the lower and the upper bound, each as a mantissa `m` and a binary exponent
+/// `e` that stand for `m · 2^e`.
+fn power_of_ten_bounds(exponent: u64, bits: u64) -> ((BigUint, i64), (BigUint, i64)) {
+    let truncate = |(mantissa, shift): (BigUint, i64), round_up: bool| {
+        let excess = mantissa.bits().saturating_sub(bits);
+        if excess == 0 {
+            return (mantissa, shift);
+        }
+        let top = &mantissa >> excess;
+        let top = if round_up { top + 1u32 } else { top };
+        (top, shift + excess as i64)
+    };
+    let multiply =
+        |(a, a_shift): &(BigUint, i64), (b, b_shift): &(BigUint, i64)| (a * b, a_shift + b_shift);
+
+    let ten = (BigUint::from(10u32), 0);
+    let mut low = (BigUint::from(1u32), 0);
+    let mut high = low.clone();
+    for bit in (0..u64::BITS - exponent.leading_zeros()).rev() {
+        low = truncate(multiply(&low, &low), false);
+        high = truncate(multiply(&high, &high), true);
+        if (exponent >> bit) & 1 == 1 {
+            low = truncate(multiply(&low, &ten), false);
+            high = truncate(multiply(&high, &ten), true);
+        }
+    }
+    (low, high)
+}
+
+/// Divides `numerator · 2^shift` by `divisor`, truncated toward zero.
+fn shifted_quotient(numerator: &BigUint, shift: i64, divisor: &BigUint) -> BigUint {
+    if shift >= 0 {
+        (numerator << shift as u64) / divisor
+    } else {
+        numerator / (divisor << shift.unsigned_abs())
+    }
+}
+
+/// Writes the quotient of two `BigUint` numbers as a decimal number with a fixed number of
+/// places.
+///
+/// The quotient is calculated exactly with a single big-number division of the numerator
+/// scaled by `10^places`, which yields the same digits as long division, and the last place
+/// is rounded half up. A carry from the rounding propagates into the integer part, like
+/// `1.00` for 0.9999 with 2 places.
+///
+/// # Arguments
+/// * `numerator` - The dividend of the quotient.
+/// * `denominator` - The divisor of the quotient.
+/// * `places` - The number of digits after the decimal point, without a point for 0.
+///
+/// # Returns
+/// An `Option<String>` with the quotient, like `0.3333333333` for 1/3 with 10 places, or
+/// `None` when the denominator is zero.
+pub fn divide_decimal(numerator: &BigUint, denominator: &BigUint, places: usize) -> Option<String> {
+    if denominator == &BigUint::ZERO {
+        return None;
+    }
+
+    // ⌊(2·n·10^places + d) / 2d⌋ is n·10^places / d rounded half up
+    let scaled = numerator * power_of_ten(places as u64) * 2u32 + denominator;
+    let rounded = scaled / (denominator * 2u32);
+
+    let digits = format!("{:0>width$}", rounded.to_string(), width = places + 1);
+    let (integer_part, decimal_part) = digits.split_at(digits.len() - places);
+    Some(if places == 0 {
+        integer_part.to_string()
+    } else {
+        format!("{}.{}", integer_part, decimal_part)
+    })
+}
+
+/// Formats the ratio of two `BigUint` numbers in scientific notation.
+///
+/// The ratio is calculated with exact big-number division, so the mantissa is correct for
+/// any magnitude. The last significant digit is rounded half up, and a mantissa that
+/// rounds up to `10` is renormalized by increasing the exponent.
+///
+/// # Arguments
+/// * `numerator` - The dividend of the ratio.
+/// * `denominator` - The divisor of the ratio.
+/// * `significant_digits` - The number of significant digits of the mantissa (at least 1).
+///
+/// # Returns
+/// An `Option<String>` with the ratio in scientific notation, or `None` when the
+/// denominator is zero.
+pub fn ratio_scientific_notation(
+    numerator: &BigUint,
+    denominator: &BigUint,
+    significant_digits: usize,
+) -> Option<String> {
+    ratio_scientific_notation_with(numerator, denominator, significant_digits, Grouping::Comma)
+}
+
+/// Formats the ratio of two `BigUint` numbers in scientific notation, with the exponent
+/// grouped in the given style.
+///
+/// This is [`ratio_scientific_notation`] with a configurable [`Grouping`].
+pub fn ratio_scientific_notation_with(
+    numerator: &BigUint,
+    denominator: &BigUint,
+    significant_digits: usize,
+    grouping: Grouping,
+) -> Option<String> {
+    if denominator == &BigUint::ZERO {
+        return None;
+    }
+    if numerator == &BigUint::ZERO {
+        return Some("0.0e0".to_string());
+    }
+
+    let significant_digits = significant_digits.max(1);
+
+    // Find the exponent e with 10^e <= numerator / denominator < 10^(e+1)
+    let mut exponent = digit_count(numerator) as i64 - digit_count(denominator) as i64;
+    let below_exponent = if exponent >= 0 {
+        numerator < &(denominator * power_of_ten(exponent as u64))
+    } else {
+        &(numerator * power_of_ten(exponent.unsigned_abs())) < denominator
+    };
+    if below_exponent {
+        exponent -= 1;
+    }
+
+    // Scale the ratio so that the quotient holds exactly the significant digits
+    let shift = significant_digits as i64 - 1 - exponent;
+    let (scaled_numerator, scaled_denominator) = if shift >= 0 {
+        (numerator * power_of_ten(shift as u64), denominator.clone())
+    } else {
+        (
+            numerator.clone(),
+            denominator * power_of_ten(shift.unsigned_abs()),
+        )
+    };
+
+    // Round the last significant digit half up
+    let mut mantissa = &scaled_numerator / &scaled_denominator;
+    let remainder = scaled_numerator - &mantissa * &scaled_denominator;
+    if remainder * 2u32 >= scaled_denominator {
+        mantissa += 1u32;
+    }
+    if mantissa == power_of_ten(significant_digits as u64) {
+        mantissa /= 10u32;
+        exponent += 1;
+    }
+
+    let mantissa_str = mantissa.to_string();
+    let (integer_string, decimal_string) = mantissa_str.split_at(1);
+    let sign = if exponent < 0 { "-" } else { "+" };
+
+    Some(if decimal_string.is_empty() {
+        format!(
+            "{}e{}{}",
+            integer_string,
+            sign,
+            thousands_separator_with(exponent.unsigned_abs(), grouping)
+        )
+    } else {
+        format!(
+            "{}.{}e{}{}",
+            integer_string,
+            decimal_string,
+            sign,
+            thousands_separator_with(exponent.unsigned_abs(), grouping)
+        )
+    })
+}
+
+/// A one-line summary of the size of a Fibonacci number, like
+/// `F(1,000,000) has 208,988 digits`.
+///
+/// For huge indices the digit count tells more than the digits themselves. The index and
+/// the digit count are grouped in the given style, and an estimated digit count is marked
+/// with `about`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DigitSummary {
+    /// The index of the Fibonacci number.
+    pub index: u64,
+    /// The number of decimal digits of the Fibonacci number.
+    pub digits: u64,
+    /// Whether the digit count is an estimate rather than counted.
+    pub estimated: bool,
+    /// The style of the separators in the index and the digit count.
+    pub grouping: Grouping,
+}
+
+impl fmt::Display for DigitSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "F({}) has {}{} {}",
+            thousands_separator_with(self.index, self.grouping),
+            if self.estimated { "about " } else { "" },
+            thousands_separator_with(self.digits, self.grouping),
+            if self.digits == 1 { "digit" } else { "digits" }
+        )
+    }
+}
+
+/// Options for rendering a Fibonacci number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueFormat {
+    /// The base the number is rendered in (2 to 36).
+    pub base: u32,
+    /// Whether the digits of full numbers are grouped.
+    pub group: bool,
+    /// The style of the separators in grouped numbers, exponents and counts.
+    pub grouping: Grouping,
+    /// The number of significant digits shown in scientific notation.
+    pub precision: usize,
+    /// The decimal numbers that are shown in scientific notation.
+    pub sci_threshold: SciThreshold,
+    /// Printed numbers with more digits are shortened to their first and last digits, or
+    /// `None` to always print every digit.
+    pub max_print_digits: Option<usize>,
+    /// Whether the exponent of large numbers is any power of ten or a multiple of 3.
+    pub notation: Notation,
+    /// Whether scientific notation is written like `4.3466 × 10²⁰⁸` instead of `4.3466e+208`.
+    pub superscript: bool,
+    /// Whether durations are written with ASCII units only, like `us` instead of `µs`.
+    pub ascii: bool,
+    /// Whether numbers in base 2, 8 and 16 start with `0b`, `0o` and `0x`.
+    pub prefix: bool,
+}
+
+impl Default for ValueFormat {
+    fn default() -> Self {
+        ValueFormat {
+            base: 10,
+            group: false,
+            grouping: Grouping::default(),
+            precision: DEFAULT_PRECISION,
+            sci_threshold: SciThreshold::default(),
+            max_print_digits: Some(DEFAULT_MAX_PRINT_DIGITS),
+            notation: Notation::default(),
+            superscript: false,
+            ascii: false,
+            prefix: true,
+        }
+    }
+}
+
+impl ValueFormat {
+    /// Returns all digits of a number in the configured base, without grouping.
+    ///
+    /// Decimal digits are converted with [`to_decimal_string`](crate::to_decimal_string), which is much faster than
+    /// `to_str_radix` for numbers with millions of digits. Bases that are powers of two need
+    /// no conversion at all, `to_str_radix` slices their digits straight from the bits.
+    pub fn digits(&self, value: &BigUint) -> String {
+        self.digits_in(value, &mut BufferPool::new(0))
+    }
+
+    /// Returns all digits of a number like [`ValueFormat::digits`], taking the buffers of a
+    /// decimal conversion from `pool`, see [`to_decimal_string_in`].
+    pub fn digits_in(&self, value: &BigUint, pool: &mut BufferPool) -> String {
+        if self.base == 10 {
+            to_decimal_string_in(value, pool)
+        } else {
+            value.to_str_radix(self.base)
+        }
+    }
+
+    /// Renders all digits of a `BigUint` number according to the format.
+    ///
+    /// # Arguments
+    /// * `value` - The number to render.
+    ///
+    /// # Returns
+    /// A `String` with the digits of `value` in the configured base, grouped if requested
+    /// and after the [`ValueFormat::prefix`] of the base.
+    pub fn render(&self, value: &BigUint) -> String {
+        self.render_in(value, &mut BufferPool::new(0))
+    }
+
+    /// Renders all digits of a number like [`ValueFormat::render`], taking the buffers of
+    /// the conversion from `pool` and handing back the ones that are not returned.
+    pub fn render_in(&self, value: &BigUint, pool: &mut BufferPool) -> String {
+        let digits = self.digits_in(value, pool);
+        self.with_prefix(if self.group {
+            let grouped = group_digits_with(&digits, self.grouping);
+            pool.recycle(digits.into_bytes());
+            grouped
+        } else {
+            digits
+        })
+    }
+
+    /// Writes every digit of a number to a writer, like [`ValueFormat::render`].
+    ///
+    /// Plain decimal digits are streamed with [`write_decimal`], so no string of the whole
+    /// number is built. Other bases and grouped digits are rendered first.
+    ///
+    /// # Arguments
+    /// * `value` - The number to write.
+    /// * `writer` - The writer the digits are written to, without a trailing newline.
+    ///
+    /// # Returns
+    /// An `io::Result<()>` with the error if writing failed.
+    pub fn write_to(&self, value: &BigUint, writer: &mut (impl Write + ?Sized)) -> io::Result<()> {
+        if self.base == 10 && !self.group {
+            write_decimal(value, writer).map(|_| ())
+        } else {
+            writer.write_all(self.render(value).as_bytes())
+        }
+    }
+
+    /// Returns the prefix of numbers in the configured base, like `0x` for base 16.
+    ///
+    /// Only bases 2, 8 and 16 have a prefix, and only if `prefix` is set.
+    pub fn prefix(&self) -> &'static str {
+        match self.base {
+            _ if !self.prefix => "",
+            2 => "0b",
+            8 => "0o",
+            16 => "0x",
+            _ => "",
+        }
+    }
+
+    /// Puts the prefix of the configured base in front of rendered digits.
+    fn with_prefix(&self, digits: String) -> String {
+        match self.prefix() {
+            "" => digits,
+            prefix => format!("{}{}", prefix, digits),
+        }
+    }
+
+    /// Renders a number for printing, shortened if it has more than `max_print_digits`.
+    ///
+    /// Use [`ValueFormat::render`] for files and other output that needs every digit.
+    ///
+    /// # Arguments
+    /// * `value` - The number to render.
+    ///
+    /// # Returns
+    /// A `String` with the digits of `value` in the configured base, grouped if requested,
+    /// shortened as described in [`elide_digits`] and after the prefix of the base.
+    pub fn render_limited(&self, value: &BigUint) -> String {
+        self.render_limited_in(value, &mut BufferPool::new(0))
+    }
+
+    /// Renders a number for printing like [`ValueFormat::render_limited`], taking the
+    /// buffers of the conversion from `pool` and handing back the ones that are not returned.
+    pub fn render_limited_in(&self, value: &BigUint, pool: &mut BufferPool) -> String {
+        let digits = self.digits_in(value, pool);
+        let rendered = match self.max_print_digits {
+            Some(max_digits) => elide_digits(&digits, max_digits, self.grouping, self.group),
+            None if self.group => group_digits_with(&digits, self.grouping),
+            None => return self.with_prefix(digits),
+        };
+        pool.recycle(digits.into_bytes());
+        self.with_prefix(rendered)
+    }
+
+    /// Decides whether a number is shown in scientific notation instead of all digits.
+    ///
+    /// Only decimal numbers that reach `sci_threshold` are shown in scientific notation,
+    /// numbers in other bases always show all digits.
+    ///
+    /// # Arguments
+    /// * `value` - The number to be shown.
+    ///
+    /// # Returns
+    /// `true` if `value` should be shown in scientific notation.
+    pub fn use_scientific(&self, value: &BigUint) -> bool {
+        self.base == 10 && self.sci_threshold.reached_by(value)
+    }
+
+    /// Renders a number in scientific or engineering notation with the configured
+    /// precision, with the exponent grouped in the configured style or written with
+    /// superscript digits.
+    pub fn scientific(&self, value: &BigUint) -> String {
+        scientific_notation_grouped(
+            value,
+            self.precision,
+            self.notation,
+            self.grouping,
+            self.superscript,
+        )
+    }
+
+    /// Formats an index or a count with separators in the configured style.
+    pub fn count(&self, number: impl Into<i128>) -> String {
+        thousands_separator_with(number, self.grouping)
+    }
+
+    /// Formats a duration with [`format_duration_with`], in ASCII if configured.
+    pub fn duration(&self, duration: Duration) -> String {
+        format_duration_with(duration, self.ascii)
+    }
+}
+
+/// Decides whether a Fibonacci number is shown in scientific notation.
+///
+/// Numbers of at least 10^35 are shown in scientific notation, smaller ones with all digits.
+/// Use [`ValueFormat::use_scientific`] for a configurable threshold.
+///
+/// # Arguments
+/// * `value` - The number to be shown.
+///
+/// # Returns
+/// `true` if `value` should be shown in scientific notation.
+pub fn use_scientific_notation(value: &BigUint) -> bool {
+    SciThreshold::default().reached_by(value)
+}
+
+/// Formats a calculated Fibonacci number as a single-line JSON object.
+///
+/// The object is the serialized `FibResult` with its `index`, the full decimal `value` and
+/// the calculation time as `duration_ns`, extended by the exact `digits` count, the value
+/// in `scientific` notation and the name of the `algorithm`. The `scientific` field is
+/// always written like `4.3466e+208` with a comma every three exponent digits, or in
+/// engineering notation like `43.466e+207`.
+///
+/// # Arguments
+/// * `result` - The calculated Fibonacci number.
+/// * `algorithm` - The algorithm used for the calculation.
+/// * `precision` - The number of significant digits of the scientific notation.
+/// * `notation` - Whether the exponent of the scientific notation is a multiple of 3.
+///
+/// # Returns
+/// A `String` with the JSON object, without a trailing newline.
+pub fn result_json(
+    result: &FibResult,
+    algorithm: Algorithm,
+    precision: usize,
+    notation: Notation,
+) -> String {
+    #[derive(Serialize)]
+    struct ResultJson<'a> {
+        #[serde(flatten)]
+        result: &'a FibResult,
+        digits: u64,
+        scientific: String,
+        algorithm: &'static str,
+    }
+
+    let json = ResultJson {
+        result,
+        digits: digit_count(&result.value),
+        scientific: scientific_notation_grouped(
+            &result.value,
+            precision,
+            notation,
+            Grouping::Comma,
+            false,
+        ),
+        algorithm: algorithm.name(),
+    };
+    serde_json::to_string(&json).expect("a result can always be serialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplitMix64;
+
+    /// Returns a pseudo-random number of up to `max_bits` bits, with a random bit length so
+    /// that small and large numbers are mixed.
+    fn random_number(random: &mut SplitMix64, max_bits: u64) -> BigUint {
+        let bits = random.next_u64() % max_bits + 1;
+        let words = (0..bits.div_ceil(64)).map(|_| random.next_u64());
+        let digits: Vec<u32> = words
+            .flat_map(|word| [word as u32, (word >> 32) as u32])
+            .collect();
+        BigUint::new(digits) >> (bits.div_ceil(64) * 64 - bits)
+    }
+
+    #[test]
+    fn durations_switch_units_at_every_boundary() {
+        let cases = [
+            (0, "0ns"),
+            (999, "999ns"),
+            (1_000, "1.0\u{b5}s"),
+            (999_949, "999.9\u{b5}s"),
+            (999_950, "1.0ms"),
+            (999_970, "1.0ms"),
+            (999_949_999, "999.9ms"),
+            (999_950_000, "1.000s"),
+            (999_960_000, "1.000s"),
+            (59_999_499_999, "59.999s"),
+            (59_999_500_000, "1m 00.0s"),
+            (192_400_000_000, "3m 12.4s"),
+            (3_599_949_999_999, "59m 59.9s"),
+            (3_599_950_000_000, "1h 00m 00s"),
+            (3_725_000_000_000, "1h 02m 05s"),
+        ];
+        for (nanos, expected) in cases {
+            assert_eq!(
+                format_duration(Duration::from_nanos(nanos)),
+                expected,
+                "{} ns",
+                nanos
+            );
+        }
+    }
+
+    #[test]
+    fn ascii_durations_write_microseconds_as_us() {
+        assert_eq!(
+            format_duration_with(Duration::from_nanos(847_300), true),
+            "847.3us"
+        );
+        assert_eq!(
+            format_duration_with(Duration::from_nanos(999_950), true),
+            "1.0ms"
+        );
+    }
+
+    #[test]
+    fn leading_digits_match_the_decimal_string() {
+        let mut random = SplitMix64::new(84);
+        for _ in 0..500 {
+            let number = random_number(&mut random, 20_000);
+            let decimal = number.to_string();
+            for precision in [1, 5, 17, 40] {
+                let (digits, exponent) = leading_digits(&number, precision);
+                assert_eq!(
+                    format!("{:0>width$}", digits.to_string(), width = precision),
+                    format!("{:0<width$.width$}", decimal, width = precision)
+                );
+                assert_eq!(exponent, decimal.len() as u64 - 1, "exponent of {}", number);
+            }
+        }
+    }
+
+    #[test]
+    fn leading_digits_are_exact_around_powers_of_ten() {
+        for k in 1..300u32 {
+            let power = BigUint::from(10u32).pow(k);
+            for number in [&power - 1u32, power.clone(), &power + 1u32] {
+                let decimal = number.to_string();
+                let (digits, exponent) = leading_digits(&number, 5);
+                assert_eq!(exponent, decimal.len() as u64 - 1);
+                assert_eq!(
+                    format!("{:0>5}", digits.to_string()),
+                    format!("{:0<5.5}", decimal)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scientific_notation_splits_the_decimal_string() {
+        let mut random = SplitMix64::new(0x84);
+        for _ in 0..500 {
+            let number = random_number(&mut random, 5_000);
+            let decimal = number.to_string();
+            let exponent = decimal.len() - 1;
+            let mantissa = format!("{:0<5.5}", decimal);
+            let expected = format!(
+                "{}.{}e+{}",
+                &mantissa[..1],
+                &mantissa[1..],
+                thousands_separator_with(exponent as u64, Grouping::Comma)
+            );
+            assert_eq!(scientific_notation(&number), expected);
+
+            let integer_digits = exponent % 3 + 1;
+            let engineering = format_scientific(
+                &mantissa,
+                exponent as u64,
+                Notation::Engineering,
+                Grouping::Comma,
+                false,
+            );
+            let padded = format!("{:0<width$}", mantissa, width = integer_digits);
+            let (integer, fraction) = padded.split_at(integer_digits);
+            let integer_mantissa = if fraction.is_empty() {
+                integer.to_string()
+            } else {
+                format!("{}.{}", integer, fraction)
+            };
+            assert_eq!(
+                engineering,
+                format!(
+                    "{}e+{}",
+                    integer_mantissa,
+                    thousands_separator_with(
+                        (exponent + 1 - integer_digits) as u64,
+                        Grouping::Comma
+                    )
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn digit_count_matches_the_decimal_string_at_powers_of_ten() {
+        assert_eq!(digit_count(&BigUint::ZERO), 1);
+        for k in 1..2000u32 {
+            let power = BigUint::from(10u32).pow(k);
+            for number in [&power - 1u32, power.clone(), &power + 1u32] {
+                assert_eq!(digit_count(&number), number.to_string().len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn digit_count_matches_the_decimal_string_of_random_numbers() {
+        let mut random = SplitMix64::new(86);
+        for _ in 0..300 {
+            let number = random_number(&mut random, 20_000);
+            assert_eq!(digit_count(&number), number.to_string().len() as u64);
+            for base in [2, 3, 7, 16, 32, 36] {
+                assert_eq!(
+                    digit_count_in(&number, base),
+                    number.to_str_radix(base).len() as u64,
+                    "digits in base {}",
+                    base
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scientific_notation_starts_at_the_threshold_digits() {
+        let ten = BigUint::from(10u32);
+        assert!(should_use_scientific(&ten.pow(35), 36));
+        assert!(!should_use_scientific(&(ten.pow(35) - 1u32), 36));
+
+        for k in 1..400u32 {
+            // 10^k - 1 has k digits and 10^k has k + 1
+            let below = ten.pow(k) - 1u32;
+            let above = ten.pow(k);
+            let digits = u64::from(k);
+            assert!(should_use_scientific(&below, digits), "10^{} - 1", k);
+            assert!(!should_use_scientific(&below, digits + 1), "10^{} - 1", k);
+            assert!(should_use_scientific(&above, digits + 1), "10^{}", k);
+            assert!(!should_use_scientific(&above, digits + 2), "10^{}", k);
+        }
+        assert!(should_use_scientific(&BigUint::ZERO, 1));
+        assert!(!should_use_scientific(&BigUint::ZERO, 2));
+    }
+
+    #[test]
+    fn quotients_are_rounded_half_up_to_the_places() {
+        let decimal = |numerator: u32, denominator: u32, places| {
+            divide_decimal(&numerator.into(), &denominator.into(), places)
+        };
+        assert_eq!(decimal(1, 3, 10).as_deref(), Some("0.3333333333"));
+        assert_eq!(decimal(2, 3, 3).as_deref(), Some("0.667"));
+        assert_eq!(decimal(7, 2, 0).as_deref(), Some("4"));
+        assert_eq!(decimal(1, 0, 3), None);
+
+        // The carry of the rounding reaches the integer part and can add a digit to it
+        assert_eq!(decimal(9995, 10_000, 3).as_deref(), Some("1.000"));
+        assert_eq!(decimal(99_995, 10_000, 3).as_deref(), Some("10.000"));
+        assert_eq!(decimal(99_994, 10_000, 3).as_deref(), Some("9.999"));
+    }
+
+    #[test]
+    fn digits_are_only_elided_above_the_limit() {
+        for max_digits in [10, 11, 1000] {
+            assert_eq!(
+                elide_digits("1234567890", max_digits, Grouping::Comma, false),
+                "1234567890"
+            );
+            assert_eq!(
+                elide_digits("1234567890", max_digits, Grouping::Comma, true),
+                "1,234,567,890"
+            );
+        }
+
+        // An odd limit keeps the same number of digits on both sides
+        assert_eq!(
+            elide_digits("1234567890", 9, Grouping::Comma, false),
+            "1234… <2 digits omitted> …7890"
+        );
+        assert_eq!(
+            elide_digits("1234567890", 5, Grouping::Comma, true),
+            "1,2… <6 digits omitted> …90"
+        );
+        assert_eq!(
+            elide_digits("123456789", 8, Grouping::Comma, false),
+            "1234… <1 digit omitted> …6789"
+        );
+        assert_eq!(
+            elide_digits("1234567890", 9, Grouping::Indian, true),
+            "1,23,4… <2 digits omitted> …7,890"
+        );
+    }
+
+    /// Writes `digits` with `layout` to a string.
+    fn blocks(digits: &str, layout: BlockLayout) -> String {
+        let mut output = Vec::new();
+        write_digit_blocks(digits, &layout, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn digit_blocks_are_wrapped_to_the_width() {
+        let layout = BlockLayout {
+            width: 11,
+            block_len: 3,
+            offsets: false,
+        };
+        assert_eq!(blocks("1234567890123", layout), "123 456 789\n012 3\n");
+        assert_eq!(blocks("1", layout), "1\n");
+
+        // The offsets take their width and two spaces from every line
+        let with_offsets = BlockLayout {
+            offsets: true,
+            ..layout
+        };
+        assert_eq!(
+            blocks("1234567890123", with_offsets),
+            " 0  123 456\n 6  789 012\n12  3\n"
+        );
+
+        // A block wider than the line still gets a line of its own
+        let narrow = BlockLayout { width: 2, ..layout };
+        assert_eq!(blocks("1234567", narrow), "123\n456\n7\n");
+    }
+
+    #[test]
+    fn negative_numbers_keep_their_sign_in_front_of_the_groups() {
+        assert_eq!(thousands_separator(-1_234_567), "-1,234,567");
+        assert_eq!(
+            thousands_separator_with(-1_234_567, Grouping::Comma),
+            "-1,234,567"
+        );
+        assert_eq!(
+            thousands_separator_with(-1_234_567, Grouping::Indian),
+            "-12,34,567"
+        );
+        assert_eq!(thousands_separator_with(-123, Grouping::Comma), "-123");
+        assert_eq!(thousands_separator(i64::MIN), "-9,223,372,036,854,775,808");
+        assert_eq!(thousands_separator(1_234_567u64), "1,234,567");
+    }
+
+    /// Scientific notation with `precision` significant digits, built from the decimal string.
+    fn expected_scientific(number: &BigUint, precision: usize) -> String {
+        let digits = number.to_string();
+        let mantissa = format!("{:0<width$.width$}", digits, width = precision);
+        format_scientific(
+            &mantissa,
+            digits.len() as u64 - 1,
+            Notation::Scientific,
+            Grouping::Comma,
+            false,
+        )
+    }
+
+    #[test]
+    fn significant_digits_are_truncated_or_padded() {
+        let twelve = BigUint::from(12u32);
+        assert_eq!(scientific_notation_with_precision(&twelve, 1), "1e+1");
+        assert_eq!(scientific_notation_with_precision(&twelve, 5), "1.2000e+1");
+        assert_eq!(
+            scientific_notation_with_precision(&twelve, 50),
+            format!("1.2{}e+1", "0".repeat(48))
+        );
+        assert_eq!(
+            scientific_notation_with_precision(&BigUint::from(98_765u32), 1),
+            "9e+4"
+        );
+
+        // Just below a power of ten the mantissa stays below 10 and keeps the exponent
+        for exponent in 1..200u32 {
+            let power = BigUint::from(10u32).pow(exponent);
+            for number in [&power - 1u32, power.clone(), &power + 1u32] {
+                for precision in [1, 2, 5, 17, 50] {
+                    assert_eq!(
+                        scientific_notation_with_precision(&number, precision),
+                        expected_scientific(&number, precision),
+                        "{} with {} digits",
+                        number,
+                        precision
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::digit_count;
+use num_bigint::BigUint;
+use std::time::{Duration, SystemTime};
+
+/// The default number of bytes of Fibonacci numbers a session history retains (256 MiB).
+pub const DEFAULT_HISTORY_BUDGET: u64 = 256 << 20;
+
+/// A numbered result of an interactive session.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// The sequence number of the result, starting at 1.
+    pub number: usize,
+    /// The index of the Fibonacci number.
+    pub index: u64,
+    /// The number of decimal digits of the Fibonacci number.
+    pub digits: u64,
+    /// When the calculation finished.
+    pub calculated_at: SystemTime,
+    /// How long the calculation took.
+    pub duration: Duration,
+    /// The Fibonacci number, or `None` once it was evicted to stay within the budget.
+    value: Option<BigUint>,
+}
+
+impl HistoryEntry {
+    /// Returns the stored Fibonacci number, or `None` if it was evicted.
+    pub fn value(&self) -> Option<&BigUint> {
+        self.value.as_ref()
+    }
+}
+
+/// The numbered results of an interactive session, which can be shown again without
+/// calculating them.
+///
+/// The Fibonacci numbers are retained up to a total budget of bytes. When a new result
+/// exceeds it, the values of the oldest results are evicted first, while their index,
+/// digit count and duration stay listed. A value larger than the whole budget is never
+/// retained.
+#[derive(Clone, Debug)]
+pub struct ResultHistory {
+    entries: Vec<HistoryEntry>,
+    /// The most bytes of Fibonacci numbers that are retained at once
+    budget: u64,
+    /// The bytes of the Fibonacci numbers that are currently retained
+    retained: u64,
+}
+
+impl Default for ResultHistory {
+    fn default() -> Self {
+        ResultHistory::new(DEFAULT_HISTORY_BUDGET)
+    }
+}
+
+impl ResultHistory {
+    /// Creates an empty history that retains up to `budget` bytes of Fibonacci numbers.
+    pub fn new(budget: u64) -> ResultHistory {
+        ResultHistory {
+            entries: Vec::new(),
+            budget,
+            retained: 0,
+        }
+    }
+
+    /// Returns every result in the order they were calculated.
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Returns the result with the sequence number `number`, if there is one.
+    pub fn get(&self, number: usize) -> Option<&HistoryEntry> {
+        self.entries.get(number.checked_sub(1)?)
+    }
+
+    /// Adds a calculated Fibonacci number to the history.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the Fibonacci number.
+    /// * `value` - The Fibonacci number, which is retained if it fits into the budget.
+    /// * `duration` - How long the calculation took.
+    ///
+    /// # Returns
+    /// The sequence number of the new result.
+    pub fn record(&mut self, index: u64, value: &BigUint, duration: Duration) -> usize {
+        let number = self.entries.len() + 1;
+        self.entries.push(HistoryEntry {
+            number,
+            index,
+            digits: digit_count(value),
+            calculated_at: SystemTime::now(),
+            duration,
+            value: None,
+        });
+        self.retain(number, value);
+        number
+    }
+
+    /// Retains the value of an evicted result again after it was recalculated.
+    ///
+    /// # Arguments
+    /// * `number` - The sequence number of the result.
+    /// * `value` - The recalculated Fibonacci number.
+    pub fn restore(&mut self, number: usize, value: &BigUint) {
+        if self.get(number).is_some_and(|entry| entry.value.is_none()) {
+            self.retain(number, value);
+        }
+    }
+
+    /// Stores the value of a result, evicting the oldest values until it fits.
+    fn retain(&mut self, number: usize, value: &BigUint) {
+        let size = value.bits().div_ceil(8);
+        if size > self.budget {
+            return;
+        }
+
+        let mut oldest = 0;
+        while self.retained + size > self.budget {
+            if let Some(evicted) = self.entries[oldest].value.take() {
+                self.retained -= evicted.bits().div_ceil(8);
+            }
+            oldest += 1;
+        }
+
+        self.entries[number - 1].value = Some(value.clone());
+        self.retained += size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A number that takes exactly `bytes` bytes.
+    fn number_of_bytes(bytes: u64) -> BigUint {
+        BigUint::from(1u32) << (8 * bytes - 1)
+    }
+
+    fn retained(history: &ResultHistory) -> Vec<usize> {
+        history
+            .entries()
+            .iter()
+            .filter(|entry| entry.value().is_some())
+            .map(|entry| entry.number)
+            .collect()
+    }
+
+    #[test]
+    fn the_oldest_values_are_evicted_first() {
+        let mut history = ResultHistory::new(100);
+        for (index, bytes) in [(1, 40), (2, 40), (3, 10)] {
+            let number = history.record(index, &number_of_bytes(bytes), Duration::ZERO);
+            assert_eq!(number, index as usize);
+        }
+        assert_eq!(retained(&history), [1, 2, 3]);
+
+        history.record(4, &number_of_bytes(30), Duration::ZERO);
+        assert_eq!(retained(&history), [2, 3, 4]);
+
+        // Too large for the whole budget, so only listed
+        history.record(5, &number_of_bytes(101), Duration::ZERO);
+        assert_eq!(retained(&history), [2, 3, 4]);
+        assert_eq!(history.get(5).unwrap().digits, 243);
+
+        history.record(6, &number_of_bytes(100), Duration::ZERO);
+        assert_eq!(retained(&history), [6]);
+        assert_eq!(history.entries().len(), 6);
+    }
+
+    #[test]
+    fn restored_values_are_retained_again() {
+        let mut history = ResultHistory::new(10);
+        let first = BigUint::from(12_345u32);
+        history.record(20, &first, Duration::from_millis(3));
+        history.record(40, &number_of_bytes(10), Duration::ZERO);
+        assert_eq!(history.get(1).unwrap().value(), None);
+
+        history.restore(1, &first);
+        assert_eq!(history.get(1).unwrap().value(), Some(&first));
+        assert_eq!(retained(&history), [1]);
+
+        // Retained values and unknown numbers stay as they are
+        history.restore(1, &BigUint::from(7u32));
+        history.restore(3, &first);
+        assert_eq!(history.get(1).unwrap().value(), Some(&first));
+        assert!(history.get(0).is_none() && history.get(3).is_none());
+        assert_eq!(history.get(1).unwrap().duration, Duration::from_millis(3));
+    }
+}
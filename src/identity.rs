@@ -0,0 +1,169 @@
+use crate::fibonacci::{fib_matrix, fib_pair};
+use num_bigint::{BigInt, BigUint};
+
+/// A well-known identity of the Fibonacci numbers that can be checked at an index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Identity {
+    /// `F(n-1)·F(n+1) − F(n)² = (−1)^n`, checked by [`cassini_holds`].
+    Cassini,
+    /// `F(n)² − F(n−r)·F(n+r) = (−1)^(n−r)·F(r)²`, checked by [`catalan_holds`].
+    Catalan { r: u64 },
+    /// `F(2n) = F(n)·(2F(n+1) − F(n))`, checked by [`doubling_holds`].
+    Doubling,
+}
+
+impl Identity {
+    /// Returns the name of the identity, like `Cassini's identity`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Identity::Cassini => "Cassini's identity",
+            Identity::Catalan { .. } => "Catalan's identity",
+            Identity::Doubling => "Doubling formula",
+        }
+    }
+
+    /// Returns the identity as a formula in `n`, and `r` for Catalan's identity.
+    pub fn statement(self) -> &'static str {
+        match self {
+            Identity::Cassini => "F(n−1)·F(n+1) − F(n)² = (−1)^n",
+            Identity::Catalan { .. } => "F(n)² − F(n−r)·F(n+r) = (−1)^(n−r)·F(r)²",
+            Identity::Doubling => "F(2n) = F(n)·(2F(n+1) − F(n))",
+        }
+    }
+
+    /// Checks whether the identity holds at `n` by calculating both of its sides.
+    pub fn holds(self, n: u64) -> bool {
+        match self {
+            Identity::Cassini => cassini_holds(n),
+            Identity::Catalan { r } => catalan_holds(n, r),
+            Identity::Doubling => doubling_holds(n),
+        }
+    }
+}
+
+/// Checks every [`Identity`] at an index.
+///
+/// Catalan's identity is checked with `r = n / 2`, so the three Fibonacci numbers on its
+/// right side are far apart.
+///
+/// # Arguments
+/// * `n` - The index the identities are checked at, at most `u64::MAX / 2`.
+///
+/// # Returns
+/// A `Vec` with every identity and whether it holds at `n`.
+pub fn check_identities(n: u64) -> Vec<(Identity, bool)> {
+    [
+        Identity::Cassini,
+        Identity::Catalan { r: n / 2 },
+        Identity::Doubling,
+    ]
+    .into_iter()
+    .map(|identity| (identity, identity.holds(n)))
+    .collect()
+}
+
+/// Checks Cassini's identity `F(n−1)·F(n+1) − F(n)² = (−1)^n` at an index.
+///
+/// `F(n−1)` is derived from the pair `(F(n), F(n+1))` as `F(n+1) − F(n)`, which is
+/// `F(−1) = 1` for `n = 0`, so the identity is checked at every index.
+///
+/// # Arguments
+/// * `n` - The index the identity is checked at.
+///
+/// # Returns
+/// `true` if both sides of the identity are equal.
+pub fn cassini_holds(n: u64) -> bool {
+    let (current, next) = fib_pair(n);
+    let previous = &next - &current;
+    let left = BigInt::from(previous * next) - BigInt::from(&current * &current);
+    left == sign(n)
+}
+
+/// Checks Catalan's identity `F(n)² − F(n−r)·F(n+r) = (−1)^(n−r)·F(r)²` at an index.
+///
+/// Cassini's identity is the special case `r = 1`. All four Fibonacci numbers are
+/// calculated on their own.
+///
+/// # Arguments
+/// * `n` - The index the identity is checked at.
+/// * `r` - The distance of the two outer Fibonacci numbers from `n`, at most `n`.
+///
+/// # Returns
+/// `true` if both sides of the identity are equal, `false` if `r` is larger than `n` or
+/// `n + r` is beyond `u64::MAX`.
+pub fn catalan_holds(n: u64, r: u64) -> bool {
+    let (Some(lower), Some(upper)) = (n.checked_sub(r), n.checked_add(r)) else {
+        return false;
+    };
+    let fib = |index: u64| fib_pair(index).0;
+    let (middle, lower, upper, distance) = (fib(n), fib(lower), fib(upper), fib(r));
+
+    let left = BigInt::from(&middle * &middle) - BigInt::from(lower * upper);
+    left == sign(n - r) * BigInt::from(&distance * &distance)
+}
+
+/// Checks the doubling formula `F(2n) = F(n)·(2F(n+1) − F(n))` at an index.
+///
+/// Both sides use `F(n)` and `F(n+1)` from fast doubling, so `F(2n)` is calculated by
+/// matrix exponentiation instead, which shares no formula with it.
+///
+/// # Arguments
+/// * `n` - The index the formula is checked at.
+///
+/// # Returns
+/// `true` if both sides of the formula are equal, `false` if `2n` is beyond `u64::MAX`.
+pub fn doubling_holds(n: u64) -> bool {
+    let Some(double) = n.checked_mul(2) else {
+        return false;
+    };
+    let (current, next) = fib_pair(n);
+    let twice_next: BigUint = &next << 1;
+    fib_matrix(double) == &current * (twice_next - &current)
+}
+
+/// Returns `(−1)^exponent`.
+fn sign(exponent: u64) -> BigInt {
+    if exponent.is_multiple_of(2) {
+        BigInt::from(1)
+    } else {
+        BigInt::from(-1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_identity_holds_at_small_and_large_indices() {
+        for n in (0..300).chain([4095, 4096, 65_537]) {
+            let checks = check_identities(n);
+            assert_eq!(checks.len(), 3);
+            assert!(
+                checks.iter().all(|(_, holds)| *holds),
+                "{:?} at {}",
+                checks,
+                n
+            );
+            for r in [0, 1.min(n), n / 3, n] {
+                assert!(catalan_holds(n, r), "Catalan at {} with r = {}", n, r);
+            }
+        }
+        assert_eq!(check_identities(10)[1].0, Identity::Catalan { r: 5 });
+    }
+
+    #[test]
+    fn indices_out_of_range_do_not_hold() {
+        assert!(!catalan_holds(5, 6));
+        assert!(!catalan_holds(u64::MAX, 1));
+        assert!(!doubling_holds(u64::MAX / 2 + 1));
+        assert!(!Identity::Catalan { r: 8 }.holds(7));
+    }
+
+    #[test]
+    fn the_sign_alternates_with_the_exponent() {
+        assert_eq!(sign(0), BigInt::from(1));
+        assert_eq!(sign(7), BigInt::from(-1));
+        assert_eq!(sign(u64::MAX - 1), BigInt::from(1));
+    }
+}
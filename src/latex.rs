@@ -0,0 +1,149 @@
+use crate::{Grouping, ValueFormat};
+use num_bigint::BigUint;
+
+/// The number of digits between two `\allowbreak` hints in full numbers.
+pub const LATEX_BREAK_DIGITS: usize = 5;
+
+/// Formats a number for LaTeX math mode.
+///
+/// Numbers that reach the scientific threshold of `value_format` are written like
+/// `4.3466 \times 10^{208}` with its precision and notation. Smaller ones are written with
+/// every digit and an `\allowbreak` every [`LATEX_BREAK_DIGITS`] digits, counted from the
+/// last digit, so long numbers can be broken across lines. Numbers in other bases than 10
+/// get the base as a subscript, like `37_{16}`.
+///
+/// # Arguments
+/// * `value` - The number to format.
+/// * `value_format` - The base, precision, notation and scientific threshold to use.
+///
+/// # Returns
+/// A `String` with the number, to be placed in math mode.
+pub fn latex_value(value: &BigUint, value_format: &ValueFormat) -> String {
+    if value_format.use_scientific(value) {
+        let plain = ValueFormat {
+            grouping: Grouping::None,
+            superscript: false,
+            ..*value_format
+        };
+        let scientific = plain.scientific(value);
+        let (mantissa, exponent) = scientific
+            .split_once("e+")
+            .expect("scientific notation without superscript has an e+ exponent");
+        return format!("{} \\times 10^{{{}}}", mantissa, exponent);
+    }
+
+    let digits = value_format.digits(value);
+    let mut latex = String::with_capacity(digits.len() / LATEX_BREAK_DIGITS * 13 + digits.len());
+    for (position, digit) in digits.chars().enumerate() {
+        if position > 0 && (digits.len() - position).is_multiple_of(LATEX_BREAK_DIGITS) {
+            latex.push_str("\\allowbreak ");
+        }
+        latex.push(digit);
+    }
+    if value_format.base != 10 {
+        latex.push_str(&format!("_{{{}}}", value_format.base));
+    }
+    latex
+}
+
+/// Formats a Fibonacci number as a LaTeX equation.
+///
+/// The equation reads like `F_{1000} \approx 4.3466 \times 10^{208}`, where the relation
+/// is `\approx` for numbers in scientific notation and `=` for full ones. It has no
+/// surrounding `$` or environment, so it fits into any math mode.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number.
+/// * `value` - The Fibonacci number.
+/// * `value_format` - The format of the number, see [`latex_value`].
+///
+/// # Returns
+/// A `String` with the equation.
+pub fn latex_equation(index: u64, value: &BigUint, value_format: &ValueFormat) -> String {
+    let relation = if value_format.use_scientific(value) {
+        "\\approx"
+    } else {
+        "="
+    };
+    format!(
+        "F_{{{}}} {} {}",
+        index,
+        relation,
+        latex_value(value, value_format)
+    )
+}
+
+/// Renders a sequence of Fibonacci numbers as an `align*` environment of LaTeX.
+///
+/// Every number is a line of the environment, aligned at its relation, in the format of
+/// [`latex_equation`]. All lines except the last end with `\\`.
+///
+/// # Arguments
+/// * `sequence` - The `(index, value)` pairs to render.
+/// * `value_format` - The format of the numbers, see [`latex_value`].
+///
+/// # Returns
+/// A `String` with the environment, ending in a line break.
+pub fn render_latex_align(
+    sequence: impl Iterator<Item = (u64, BigUint)>,
+    value_format: &ValueFormat,
+) -> String {
+    let lines: Vec<String> = sequence
+        .map(|(index, value)| latex_equation(index, &value, value_format).replacen(' ', " &", 1))
+        .collect();
+    format!(
+        "\\begin{{align*}}\n{}\n\\end{{align*}}\n",
+        lines.join(" \\\\\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FibIter, SciThreshold};
+
+    #[test]
+    fn full_numbers_get_a_break_every_five_digits() {
+        let value_format = ValueFormat {
+            sci_threshold: SciThreshold::Never,
+            ..ValueFormat::default()
+        };
+        let value: BigUint = "354224848179261915075".parse().unwrap();
+        assert_eq!(
+            latex_equation(100, &value, &value_format),
+            "F_{100} = 3\\allowbreak 54224\\allowbreak 84817\\allowbreak 92619\\allowbreak 15075"
+        );
+        assert_eq!(
+            latex_value(&BigUint::from(12_345u32), &value_format),
+            "12345"
+        );
+
+        let hex = ValueFormat {
+            base: 16,
+            ..value_format
+        };
+        assert_eq!(latex_value(&BigUint::from(55u32), &hex), "37_{16}");
+    }
+
+    #[test]
+    fn large_numbers_are_approximated() {
+        let value_format = ValueFormat {
+            superscript: true,
+            ..ValueFormat::default()
+        };
+        let value = crate::calculate_fibonacci(1000).unwrap();
+        assert_eq!(
+            latex_equation(1000, &value, &value_format),
+            "F_{1000} \\approx 4.3466 \\times 10^{208}"
+        );
+    }
+
+    #[test]
+    fn every_line_but_the_last_ends_with_a_break() {
+        let align = render_latex_align(FibIter::new(10).take(3), &ValueFormat::default());
+        assert_eq!(
+            align,
+            "\\begin{align*}\nF_{10} &= 55 \\\\\nF_{11} &= 89 \\\\\nF_{12} &= 144\n\\end{align*}\n"
+        );
+    }
+}
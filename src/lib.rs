@@ -0,0 +1,179 @@
+//! High-performance calculation of arbitrarily large Fibonacci numbers.
+//!
+//! The computation lives in [`fibonacci`] and [`compare`] and the helpers that turn results into
+//! human-readable text live in [`format`] and [`template`]. The most commonly used
+//! functions are re-exported at the crate root.
+//!
+//! Everything except [`fib_core`], [`search`] and [`wasm`] needs the default `std` feature.
+//! Without it the crate is `no_std` and only offers Fibonacci numbers that fit into machine
+//! integers, for embedded targets, and [`fib_string`], which only needs num-bigint and an
+//! allocator, for WebAssembly.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod approx;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod calibrate;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod csv;
+#[cfg(feature = "std")]
+pub mod decimal;
+mod doubling;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod estimate;
+#[cfg(feature = "std")]
+pub mod expr;
+pub mod fib_core;
+#[cfg(feature = "std")]
+pub mod fibonacci;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "std")]
+pub mod identity;
+#[cfg(feature = "std")]
+pub mod latex;
+#[cfg(feature = "std")]
+mod multiply;
+#[cfg(feature = "std")]
+pub mod parse;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod random;
+#[cfg(feature = "std")]
+pub mod range;
+#[cfg(feature = "std")]
+pub mod repl;
+#[cfg(feature = "std")]
+pub mod result;
+pub mod search;
+#[cfg(feature = "std")]
+pub mod settings;
+#[cfg(feature = "std")]
+pub mod speculate;
+#[cfg(feature = "std")]
+pub mod stress;
+#[cfg(feature = "std")]
+pub mod table;
+#[cfg(feature = "std")]
+pub mod template;
+#[cfg(feature = "std")]
+mod threads;
+#[cfg(feature = "std")]
+pub mod tuning;
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod word;
+#[cfg(feature = "std")]
+pub mod writer;
+#[cfg(feature = "std")]
+pub mod zeckendorf;
+
+#[cfg(feature = "std")]
+pub use approx::{fib_approx, fib_approx_big, fib_approx_log, Approximation, LOG_APPROX_DIGITS};
+#[cfg(feature = "std")]
+pub use bench::{
+    bench_index, bench_runs, compare_algorithms, largest_within, profile_to_json, run_profile,
+    sample_indices, BenchSample, BenchStats, BudgetStep, Profile,
+};
+#[cfg(feature = "std")]
+pub use cache::SessionCache;
+#[cfg(feature = "std")]
+pub use calibrate::{calibrate, Measurement, Timer, WallClock};
+#[cfg(feature = "std")]
+pub use checkpoint::{calculate_fibonacci_checkpointed, resume_fibonacci};
+#[cfg(feature = "std")]
+pub use compare::{compare_fibonacci, Comparison};
+#[cfg(feature = "std")]
+pub use config::Config;
+#[cfg(feature = "std")]
+pub use csv::write_csv;
+#[cfg(feature = "std")]
+pub use decimal::{to_decimal_string, to_decimal_string_in, write_decimal};
+#[cfg(feature = "std")]
+pub use error::FibError;
+#[cfg(feature = "std")]
+pub use estimate::{format_bytes, CostLimits, CostModel, Estimate, Estimator};
+#[cfg(feature = "std")]
+pub use expr::{evaluate, parse_expr, Evaluation, Expr, ExprError};
+pub use fib_core::{fib_divides, fib_mod, fib_u64, FibModIter};
+#[cfg(feature = "std")]
+pub use fibonacci::{
+    calculate_fibonacci, calculate_fibonacci_deadline, calculate_fibonacci_signed,
+    calculate_fibonacci_timed, calculate_fibonacci_timed_with, calculate_fibonacci_verified,
+    calculate_fibonacci_with, calculate_lucas, cancel_requested, choose_algorithm, clear_cancel,
+    explain_fibonacci, fib_consecutive, fib_double, fib_even, fib_odd, fib_square_sum,
+    golden_convergent, nearest_fib, request_cancel, Algorithm, AlgorithmChoice, DoublingState,
+    FibIter, FibParityIter, Parity, Step, Strategy,
+};
+#[cfg(feature = "std")]
+pub use format::{
+    digit_count, digit_count_in, divide_decimal, elide_digits, format_duration,
+    format_duration_with, format_scientific, group_digits, group_digits_with,
+    ratio_scientific_notation, ratio_scientific_notation_with, result_json, scientific_notation,
+    scientific_notation_with_precision, should_use_scientific, thousands_separator,
+    thousands_separator_with, use_scientific_notation, write_digit_blocks, BlockLayout,
+    DigitSummary, Grouping, Notation, SciThreshold, ValueFormat,
+};
+#[cfg(feature = "std")]
+pub use history::{HistoryEntry, ResultHistory, DEFAULT_HISTORY_BUDGET};
+#[cfg(feature = "std")]
+pub use identity::{cassini_holds, catalan_holds, check_identities, doubling_holds, Identity};
+#[cfg(feature = "std")]
+pub use latex::{latex_equation, latex_value, render_latex_align, LATEX_BREAK_DIGITS};
+#[cfg(feature = "std")]
+pub use parse::{parse_index, parse_index_list, parse_indices, FibRequest};
+#[cfg(feature = "std")]
+pub use pool::{BufferPool, DEFAULT_BUFFER_POOL_BUDGET};
+#[cfg(feature = "std")]
+pub use random::SplitMix64;
+#[cfg(feature = "std")]
+pub use range::{
+    append_range, fib_range, fib_range_par, stream_range, write_range, write_range_to_file,
+};
+#[cfg(feature = "std")]
+pub use repl::{
+    complete_input, parse_input, parse_repl_command, Input, RelativeIndex, ReplCommand, View,
+    REPL_COMMANDS,
+};
+#[cfg(feature = "std")]
+pub use result::{read_result, write_result, FibResult};
+pub use search::fibonacci_search;
+#[cfg(feature = "std")]
+pub use settings::{Notify, OutputFormat, Setting, Settings};
+#[cfg(feature = "std")]
+pub use speculate::{PrecomputedPair, Speculation, DEFAULT_SPECULATION_BUDGET};
+#[cfg(feature = "std")]
+pub use stress::{run_stress, stress_check, stress_indices, StressFailure, StressReport};
+#[cfg(feature = "std")]
+pub use table::{render_table, TableStyle};
+#[cfg(feature = "std")]
+pub use template::{Placeholder, Template, TemplateFields};
+#[cfg(feature = "std")]
+pub use threads::{current_num_threads, thread_pool, ThreadPool};
+#[cfg(feature = "std")]
+pub use tuning::{ThresholdOverrides, Thresholds, CALIBRATION_FILE, CALIBRATION_VERSION};
+pub use wasm::fib_string;
+#[cfg(feature = "std")]
+pub use word::{fibonacci_word, MAX_WORD_LENGTH};
+#[cfg(feature = "std")]
+pub use writer::{write_file_with_progress, ProgressWriter, CHUNK_SIZE};
+#[cfg(feature = "std")]
+pub use zeckendorf::{fib_decode, fib_encode, zeckendorf};
@@ -1,215 +1,2772 @@
+mod clipboard;
+mod memory;
+mod notify;
+mod pager;
+mod progress;
+mod prompt;
+mod session;
+mod style;
+mod transcript;
+mod tui;
+mod watch;
+
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum,
+};
+use clap_complete::{generate, Shell};
+use clap_mangen::Man;
+use clipboard::copy_value;
+use fibonacci_sequence::{
+    append_range,
+    bench::{samples_to_csv, samples_to_json},
+    bench_index, bench_runs, calculate_fibonacci_checkpointed, calculate_fibonacci_timed_with,
+    calculate_fibonacci_verified, calculate_fibonacci_with, calibrate, check_identities,
+    choose_algorithm, compare_algorithms, compare_fibonacci, current_num_threads, digit_count,
+    digit_count_in, divide_decimal, explain_fibonacci, fib_approx_big, fib_approx_log,
+    fib_core::MAX_U64_INDEX,
+    fib_divides,
+    format::DEFAULT_MAX_PRINT_DIGITS,
+    format_bytes, golden_convergent, largest_within, latex_equation, latex_value, parse_index,
+    parse_index_list, profile_to_json,
+    range::range_line,
+    render_latex_align, render_table, result_json, resume_fibonacci, run_profile, run_stress,
+    sample_indices,
+    settings::{
+        parse_max_print_digits, parse_threshold, DEFAULT_NOTIFY_AFTER, MAX_PRECISION,
+        SCI_THRESHOLD_VAR,
+    },
+    thread_pool, write_csv, write_digit_blocks, write_file_with_progress, write_range_to_file,
+    Algorithm, BenchStats, BlockLayout, BudgetStep, BufferPool, Config, CostLimits, CostModel,
+    DigitSummary, FibError, FibIter, FibModIter, FibParityIter, FibResult, Grouping, Identity,
+    Notation, Notify, OutputFormat, Parity, Placeholder, SciThreshold, Settings, StressReport,
+    TableStyle, Template, TemplateFields, ThreadPool, ThresholdOverrides, Thresholds, ValueFormat,
+    WallClock, DEFAULT_BUFFER_POOL_BUDGET, DEFAULT_HISTORY_BUDGET,
+};
+use notify::notify_finished;
 use num_bigint::BigUint;
+use pager::{print_paged, terminal_width, write_paged};
+use progress::{calculate_with_progress, WriteProgress};
+use prompt::LineReader;
+use session::{cancel_on_interrupt, Confirm, Flow, Session};
 use std::{
-    io::{self, Write},
-    time::Instant,
+    env, fmt,
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Write},
+    iter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
 };
+use style::{print_error, unicode_supported, ColorChoice, Style};
+use tui::run_tui;
+use watch::run_watch;
+
+/// A high-performance CLI application that calculates any Nth number of the Fibonacci sequence.
+#[derive(Parser)]
+#[command(version, about)]
+#[command(args_conflicts_with_subcommands = true)]
+// The lines of --range and --input share the options of their output
+#[command(group(ArgGroup::new("sequence").args(["range", "input"])))]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Index of the Fibonacci number to calculate, e.g. 1000, 1_000_000, 1e6 or 2^32. Starts the
+    /// interactive prompt when omitted
+    #[arg(value_parser = parse_index)]
+    index: Option<u64>,
+
+    /// Print each result as a single line built from a template, e.g. "{index}\t{digits}\t{duration_ms}"
+    ///
+    /// Available placeholders: {index}, {digits}, {value}, {sci}, {duration}, {duration_ns},
+    /// {duration_us}, {duration_ms}, {duration_s}, {conversion}, {conversion_ns},
+    /// {conversion_us}, {conversion_ms}, {conversion_s} and {algorithm}.
+    /// The escapes \t, \n, \\, \{ and \} are supported.
+    #[arg(long, global = true, value_name = "TEMPLATE", value_parser = Template::parse)]
+    format_template: Option<Template>,
+
+    /// Print the first K Fibonacci numbers, F(0) to F(K-1), as "index value" lines
+    #[arg(long, value_name = "K", value_parser = parse_index, conflicts_with_all = ["index", "range"])]
+    count: Option<u64>,
+
+    /// Print full results in the given base (default: 10, or `base` of fib.toml), base 2, 8
+    /// and 16 with the prefix 0b, 0o and 0x
+    #[arg(long, visible_alias = "radix", global = true, value_parser = clap::value_parser!(u32).range(2..=36))]
+    base: Option<u32>,
+
+    /// Print results in base 2, 8 and 16 without the prefix 0b, 0o and 0x
+    #[arg(long, global = true)]
+    no_prefix: bool,
+
+    /// Group the digits of full results with separators in the --grouping style
+    #[arg(long, global = true)]
+    group: bool,
+
+    /// Style of the separators in indices, digit counts, exponents and grouped results
+    /// (default: comma, or `separator` of fib.toml)
+    #[arg(
+        long,
+        global = true,
+        value_name = "STYLE",
+        value_parser = PossibleValuesParser::new(Grouping::ALL.map(Grouping::name))
+            .map(|name| name.parse::<Grouping>().unwrap())
+    )]
+    grouping: Option<Grouping>,
+
+    /// Number of significant digits of results shown in scientific notation, further digits
+    /// are truncated
+    #[arg(long, visible_alias = "sig-digits", global = true, value_name = "DIGITS", default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..=MAX_PRECISION as i64))]
+    precision: u16,
+
+    /// Show decimal results of at least 10^N in scientific notation, written as 1e50, 10^50 or
+    /// 50, or `none` for every digit of all results and `always` for scientific notation of
+    /// all results (default: FIB_SCI_THRESHOLD, or `threshold` of fib.toml, or 1e35)
+    #[arg(long, global = true, value_name = "1eN|none|always", value_parser = parse_threshold)]
+    sci_threshold: Option<SciThreshold>,
+
+    /// Shorten printed results with more than DIGITS digits to their first and last digits, or
+    /// `none` to print every digit
+    #[arg(long, global = true, value_name = "DIGITS", default_value_t = DigitLimit(Some(DEFAULT_MAX_PRINT_DIGITS)), value_parser = |input: &str| parse_max_print_digits(input).map(DigitLimit))]
+    max_print_digits: DigitLimit,
+
+    /// Print every digit of results, without scientific notation or shortening
+    #[arg(long, global = true)]
+    full: bool,
+
+    /// Print every digit of results as lines of 10-digit blocks, instead of a single line
+    #[arg(long, global = true)]
+    pretty: bool,
+
+    /// Maximum line width of --pretty results, including the offsets
+    #[arg(long, global = true, value_name = "COLUMNS", default_value_t = 80, requires = "pretty", value_parser = clap::value_parser!(u16).range(1..))]
+    pretty_width: u16,
+
+    /// Start every line of --pretty results with the number of digits before it
+    #[arg(long, global = true, requires = "pretty")]
+    offsets: bool,
+
+    /// Print every digit of results in lines of COLUMNS digits, or as wide as the terminal
+    /// with `auto`
+    #[arg(long, global = true, value_name = "COLUMNS", conflicts_with = "pretty", value_parser = parse_wrap)]
+    wrap: Option<WrapWidth>,
+
+    /// Print only how many digits the result has, like "F(1,000,000) has 208,988 digits",
+    /// instead of the result itself
+    #[arg(long, requires = "index", conflicts_with_all = ["full", "pretty", "wrap", "json", "format_template", "raw", "approx", "explain", "bench", "output", "copy", "copy_full"])]
+    summary: bool,
+
+    /// Write large results with any exponent, like 4.3466e+208, or with an exponent that is a
+    /// multiple of 3, like 43.466e+207
+    #[arg(
+        long,
+        global = true,
+        default_value = "scientific",
+        value_parser = PossibleValuesParser::new(Notation::ALL.map(Notation::name))
+            .map(|name| name.parse::<Notation>().unwrap())
+    )]
+    notation: Notation,
+
+    /// Write scientific notation with a multiplication sign and superscript exponents, like
+    /// 4.3466 × 10²⁰⁸, when the locale supports Unicode
+    #[arg(long, global = true)]
+    superscript: bool,
+
+    /// Never write superscript exponents, even with --superscript or :superscript, and write
+    /// microseconds as `us` instead of `µs`
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Never show results longer than a screen in a pager
+    #[arg(long, global = true)]
+    no_pager: bool,
+
+    /// Announce calculations that take longer than --notify-after with the terminal bell or a
+    /// desktop notification
+    #[arg(
+        long,
+        global = true,
+        value_name = "KIND",
+        default_value = "none",
+        value_parser = PossibleValuesParser::new(Notify::ALL.map(Notify::name))
+            .map(|name| name.parse::<Notify>().unwrap())
+    )]
+    notify: Notify,
+
+    /// Minimum duration of an announced calculation
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = DEFAULT_NOTIFY_AFTER.as_secs())]
+    notify_after: u64,
+
+    /// Ask before calculating indices at the prompt that are estimated to take longer
+    #[arg(long, value_name = "SECONDS", default_value_t = CostLimits::default().duration.as_secs())]
+    confirm_after: u64,
+
+    /// Ask before calculating indices at the prompt that are estimated to need more memory
+    #[arg(long, value_name = "MIB", default_value_t = CostLimits::default().memory >> 20)]
+    confirm_memory: u64,
+
+    /// Keep at most this many MiB of results at the prompt for :show and :export
+    #[arg(long, value_name = "MIB", default_value_t = DEFAULT_HISTORY_BUDGET >> 20)]
+    history_budget: u64,
+
+    /// Keep at most this many MiB of conversion buffers at the prompt for the next result
+    #[arg(long, value_name = "MIB", default_value_t = DEFAULT_BUFFER_POOL_BUDGET >> 20)]
+    buffer_budget: u64,
+
+    /// While the prompt waits after F(n), calculate F(n+1), F(n-1) and F(2n) in the background
+    #[arg(long, conflicts_with = "script")]
+    speculate: bool,
+
+    /// Refuse to calculate indices above N (default: `max-index` of fib.toml, or no limit)
+    #[arg(long, value_name = "N", value_parser = parse_index)]
+    max_index: Option<u64>,
+
+    /// Calculate indices above the --confirm-after and --confirm-memory limits without asking
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Algorithm used to calculate single Fibonacci numbers, where auto picks the fastest for
+    /// the index and the number of threads
+    #[arg(
+        long,
+        default_value = "auto",
+        value_parser = PossibleValuesParser::new(Algorithm::ALL.map(Algorithm::name))
+            .map(|name| name.parse::<Algorithm>().unwrap()),
+        conflicts_with_all = ["checkpoint", "progress", "verify"]
+    )]
+    algorithm: Algorithm,
+
+    /// Format results are printed in, where latex prints ranges as an align* environment
+    #[arg(
+        long,
+        default_value = "text",
+        value_parser = PossibleValuesParser::new(OutputFormat::ALL.map(OutputFormat::name))
+            .map(|name| name.parse::<OutputFormat>().unwrap())
+    )]
+    format: OutputFormat,
+
+    /// Print single results as JSON, the same as --format json
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+
+    /// Number of worker threads used for calculations (default: one per CPU core)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Split products over the threads from this many bits on (default: calibrated or 2097152)
+    #[arg(long, value_name = "BITS")]
+    parallel_multiply_bits: Option<u64>,
+
+    /// Calculate in machine words up to this index (default: calibrated or 93)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(..=MAX_U64_INDEX))]
+    word_index: Option<u64>,
+
+    /// Convert results by divide and conquer from this many bits on (default: calibrated or 8388608)
+    #[arg(long, value_name = "BITS")]
+    conversion_bits: Option<u64>,
+
+    /// Only print the results, without indices, durations or headings
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// When to color the output, `auto` colors it for terminals unless NO_COLOR is set
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
+
+    /// Calculate every Fibonacci number from START to END (inclusive) as "index value" lines
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["START", "END"],
+        value_parser = parse_index,
+        conflicts_with = "index"
+    )]
+    range: Option<Vec<u64>>,
+
+    /// Calculate every index of FILE, separated by whitespace or newlines, as "index value"
+    /// lines, reporting invalid indices without stopping
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["index", "count", "range", "script", "transcript", "stress", "profile"])]
+    input: Option<PathBuf>,
+
+    /// Write the range to a file instead of stdout, with checkpoints for resuming, the
+    /// results of --input, or every digit of a single result
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["count", "checkpoint", "bench", "approx"])]
+    output: Option<PathBuf>,
+
+    /// Only calculate the even or odd indices of the range, skipping the numbers in between
+    #[arg(
+        long,
+        value_name = "PARITY",
+        value_enum,
+        requires = "range",
+        conflicts_with = "output"
+    )]
+    parity: Option<IndexParity>,
+
+    /// Write the range as CSV with the columns index, value, scientific and digit_count
+    #[arg(long, requires = "sequence")]
+    csv: bool,
+
+    /// Print the range as a table with the columns index, value and digits
+    #[arg(long, value_name = "STYLE", value_enum, requires = "sequence", conflicts_with_all = ["csv", "output"])]
+    table: Option<TableFormat>,
+
+    /// Append the range to --output line by line as it is calculated, without checkpoints
+    #[arg(long, requires_all = ["sequence", "output"], conflicts_with_all = ["csv", "resume"])]
+    append: bool,
+
+    /// Continue an interrupted range from its checkpoint file
+    #[arg(long, requires = "output", conflicts_with_all = ["index", "input", "csv"])]
+    resume: bool,
+
+    /// Number of indices between two checkpoints of a range written to a file
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1000,
+        requires = "output",
+        requires = "range"
+    )]
+    checkpoint_every: u64,
+
+    /// Calculate the Fibonacci number RUNS times and print the min, median, mean and max durations
+    #[arg(long, value_name = "RUNS", requires = "index", conflicts_with = "checkpoint", value_parser = clap::value_parser!(u32).range(1..))]
+    bench: Option<u32>,
+
+    /// Calculate the Fibonacci number with every algorithm, check that they agree and print
+    /// their durations side by side, timing each --bench times (default: once)
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "approx", "explain", "raw", "verify", "progress", "stats"])]
+    compare_algos: bool,
+
+    /// Calculate the indices 10^3, 10^4, 10^5 and 10^6 and print their durations as a JSON
+    /// report, to compare the performance of builds
+    #[arg(long, conflicts_with_all = ["index", "count", "range", "script", "transcript"])]
+    profile: bool,
+
+    /// Number of calculations of every --profile index, the report holds the median
+    #[arg(long, value_name = "RUNS", default_value_t = 1, requires = "profile", value_parser = clap::value_parser!(u32).range(1..))]
+    profile_runs: u32,
+
+    /// Check COUNT pseudo-random indices picked from SEED: the digit count against the
+    /// decimal string and Binet's formula, and the value against its remainder
+    #[arg(long, num_args = 2, value_names = ["SEED", "COUNT"], conflicts_with_all = ["index", "count", "range", "script", "transcript", "profile"])]
+    stress: Option<Vec<u64>>,
+
+    /// Largest index picked by --stress
+    #[arg(long, value_name = "N", default_value_t = 1_000_000, requires = "stress", value_parser = parse_index)]
+    stress_max: u64,
+
+    /// Find the largest Fibonacci number that can be calculated within SECONDS by doubling
+    /// the index until a calculation takes longer
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds, conflicts_with_all = ["index", "count", "range", "input", "script", "transcript", "profile", "stress"])]
+    time_budget: Option<Duration>,
+
+    /// Approximate the Fibonacci number with Binet's formula instead of calculating it exactly.
+    /// Only the first --precision digits are shown, and they are not exact
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench"])]
+    approx: bool,
+
+    /// Show how fast doubling halves the index and which identities it applies at every step
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx"])]
+    explain: bool,
+
+    /// Check Cassini's and Catalan's identity and the doubling formula at the index and print
+    /// whether each of them holds
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx", "explain"])]
+    identity: bool,
+
+    /// Write the Fibonacci number to stdout as raw little-endian bytes, without any text, for
+    /// piping into other programs
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx", "output", "format_template", "json"])]
+    raw: bool,
+
+    /// Copy the result to the clipboard, shortened or in scientific notation like it is printed
+    #[arg(long, requires = "index", conflicts_with_all = ["bench", "approx", "explain", "raw"])]
+    copy: bool,
+
+    /// Copy every digit of the result to the clipboard, regardless of the threshold and the
+    /// digit limit
+    #[arg(long, requires = "index", conflicts_with_all = ["bench", "approx", "explain", "raw"])]
+    copy_full: bool,
+
+    /// Print the estimated percentage of the calculation that is done and the elapsed time to
+    /// stderr while a single Fibonacci number is calculated with fast doubling
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx", "explain", "raw"])]
+    progress: bool,
+
+    /// Print the duration and peak memory of the calculation and of the conversion of a single
+    /// Fibonacci number to stderr (peak memory needs the mem-stats feature)
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx", "explain", "raw", "verify"])]
+    stats: bool,
+
+    /// Print how the Fibonacci number would be calculated and why, without calculating it
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx", "explain", "raw", "verify", "compare_algos"])]
+    explain_choice: bool,
+
+    /// Calculate the Fibonacci number with both fast doubling and matrix exponentiation and
+    /// fail if they disagree, a self-test for new hardware or builds at twice the cost
+    #[arg(long, requires = "index", conflicts_with_all = ["checkpoint", "bench", "approx", "explain", "raw", "progress"])]
+    verify: bool,
+
+    /// Open a terminal interface with live progress, a history and a digit viewer
+    #[arg(long, conflicts_with_all = ["index", "count", "range", "script", "transcript"])]
+    tui: bool,
+
+    /// Run the lines of FILE as if they were typed at the interactive prompt, stopping at the
+    /// first error
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["index", "count", "range"])]
+    script: Option<PathBuf>,
+
+    /// Continue a script after a line failed, but still exit with a failure
+    #[arg(long, requires = "script")]
+    keep_going: bool,
+
+    /// Append every prompt input and a summary of its result to FILE, with timestamps
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["index", "count", "range"])]
+    transcript: Option<PathBuf>,
+
+    /// Log every digit of the results to the transcript, not only their summary
+    #[arg(long, requires = "transcript")]
+    transcript_values: bool,
+
+    /// Periodically save the progress of the calculation to FILE, to continue it with `resume`
+    #[arg(long, value_name = "FILE", requires = "index")]
+    checkpoint: Option<PathBuf>,
+
+    /// Minimum number of seconds between two checkpoints of a single calculation
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 60)]
+    checkpoint_interval: u64,
+}
+
+/// The options that control how results are calculated and printed.
+struct OutputOptions {
+    template: Option<Template>,
+    settings: Settings,
+    quiet: bool,
+    /// Whether every digit is printed, regardless of the threshold and the digit limit
+    full: bool,
+    /// The layout of results printed as lines of digit blocks, if they are
+    pretty: Option<BlockLayout>,
+    /// Whether only the digit count of single results is printed instead of the result
+    summary: bool,
+    /// Whether output is restricted to ASCII, by --ascii or a locale without Unicode
+    ascii: bool,
+    /// Whether the progress of single calculations is printed to stderr
+    progress: bool,
+    /// Whether the durations and peak memory of single calculations are printed to stderr
+    stats: bool,
+    color: ColorChoice,
+}
+
+/// The value of `--wrap`.
+#[derive(Clone, Copy)]
+enum WrapWidth {
+    /// The width of the terminal, if stdout is one
+    Auto,
+    Columns(u16),
+}
+
+/// Parses the value of `--wrap`, either `auto` or a positive number of columns.
+fn parse_wrap(input: &str) -> Result<WrapWidth, String> {
+    if input.eq_ignore_ascii_case("auto") {
+        return Ok(WrapWidth::Auto);
+    }
+    match input.parse::<u16>() {
+        Ok(columns) if columns > 0 => Ok(WrapWidth::Columns(columns)),
+        _ => Err(format!(
+            "'{}' is neither `auto` nor a number of columns from 1 to {}",
+            input,
+            u16::MAX
+        )),
+    }
+}
+
+/// Parses the value of `--time-budget`, a positive number of seconds like `1` or `0.25`.
+fn parse_seconds(input: &str) -> Result<Duration, String> {
+    match input.parse::<f64>().map(Duration::try_from_secs_f64) {
+        Ok(Ok(duration)) if !duration.is_zero() => Ok(duration),
+        _ => Err(format!("'{}' is not a positive number of seconds", input)),
+    }
+}
+
+/// The value of `--max-print-digits`, where `None` stands for no limit.
+#[derive(Clone, Copy)]
+struct DigitLimit(Option<usize>);
+
+impl fmt::Display for DigitLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(max_digits) => write!(f, "{}", max_digits),
+            None => f.write_str("none"),
+        }
+    }
+}
+
+impl OutputOptions {
+    /// Combines the flags with the defaults of the configuration file, where flags win.
+    fn from_cli(cli: &Cli, config: &Config) -> OutputOptions {
+        let ascii = cli.ascii || !unicode_supported();
+        // Wrapped lines are blocks as wide as the line, so the blocks add no spaces
+        let wrap = cli.wrap.and_then(|wrap| match wrap {
+            WrapWidth::Auto => terminal_width(),
+            WrapWidth::Columns(columns) => Some(usize::from(columns)),
+        });
+        OutputOptions {
+            template: cli.format_template.clone(),
+            settings: Settings {
+                algorithm: cli.algorithm,
+                threads: cli.threads.map(usize::from),
+                format: if cli.json {
+                    OutputFormat::Json
+                } else {
+                    cli.format
+                },
+                value_format: ValueFormat {
+                    base: cli.base.or(config.base).unwrap_or(10),
+                    group: cli.group,
+                    grouping: cli.grouping.or(config.separator).unwrap_or_default(),
+                    precision: cli.precision as usize,
+                    max_print_digits: cli.max_print_digits.0,
+                    sci_threshold: cli
+                        .sci_threshold
+                        .or_else(sci_threshold_from_env)
+                        .or(config.threshold)
+                        .unwrap_or_default(),
+                    notation: cli.notation,
+                    superscript: cli.superscript && !ascii,
+                    ascii,
+                    prefix: !cli.no_prefix,
+                },
+                pager: !cli.no_pager,
+                notify: cli.notify,
+                notify_after: Duration::from_secs(cli.notify_after),
+                limits: CostLimits {
+                    duration: Duration::from_secs(cli.confirm_after),
+                    memory: cli.confirm_memory.saturating_mul(1 << 20),
+                },
+                max_index: cli.max_index.or(config.max_index),
+            },
+            quiet: cli.quiet,
+            full: cli.full || cli.wrap.is_some(),
+            pretty: cli
+                .pretty
+                .then_some(BlockLayout {
+                    width: usize::from(cli.pretty_width),
+                    offsets: cli.offsets,
+                    ..BlockLayout::default()
+                })
+                .or(wrap.map(|width| BlockLayout {
+                    width,
+                    block_len: width,
+                    offsets: false,
+                })),
+            summary: cli.summary,
+            ascii,
+            progress: cli.progress,
+            stats: cli.stats,
+            color: cli.color,
+        }
+    }
+}
+
+/// Reads the default of `--sci-threshold` from the environment variable `FIB_SCI_THRESHOLD`.
+///
+/// An empty or missing variable is ignored, and so is an invalid one after a warning on
+/// stderr, so a typo in the shell profile never stops the program.
+///
+/// # Returns
+/// An `Option<u32>` with the exponent of the threshold, or `None` to use the configuration
+/// file or the built-in default.
+fn sci_threshold_from_env() -> Option<SciThreshold> {
+    let value = env::var_os(SCI_THRESHOLD_VAR).filter(|value| !value.is_empty())?;
+
+    match value.to_str().map(parse_threshold) {
+        Some(Ok(threshold)) => Some(threshold),
+        _ => {
+            eprintln!(
+                "Warning: ignoring {}={}, expected a power of ten like 1e50, none or always",
+                SCI_THRESHOLD_VAR,
+                value.to_string_lossy()
+            );
+            None
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare the Fibonacci numbers at two indices
+    #[command(visible_alias = "cmp")]
+    Compare {
+        /// Index of the first Fibonacci number
+        #[arg(value_parser = parse_index)]
+        first: u64,
+
+        /// Index of the second Fibonacci number
+        #[arg(value_parser = parse_index)]
+        second: u64,
+
+        /// Number of significant digits of the ratio
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..))]
+        digits: u16,
+
+        /// Also print both Fibonacci numbers
+        #[arg(long)]
+        values: bool,
+    },
+
+    /// Check whether F(m) divides F(n), from the indices alone
+    Divides {
+        /// Index of the divisor
+        #[arg(value_parser = parse_index)]
+        m: u64,
+
+        /// Index of the dividend
+        #[arg(value_parser = parse_index)]
+        n: u64,
+    },
+
+    /// Print the nth convergent F(n+1) / F(n) of the continued fraction of the golden ratio
+    Convergent {
+        /// Index of the convergent
+        #[arg(value_parser = parse_index)]
+        n: u64,
+    },
+
+    /// Instantly approximate F(n) and its exact digit count from a logarithm
+    Approx {
+        /// Index of the Fibonacci number to approximate
+        #[arg(value_parser = parse_index)]
+        n: u64,
+    },
+
+    /// Calculate F(start), F(start·factor), F(start·factor²), … and print how the durations grow
+    Watch {
+        /// First index
+        #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+        start: u64,
+
+        /// Factor between consecutive indices
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(2..))]
+        factor: u64,
+
+        /// Largest index to calculate (default: until Ctrl+C)
+        #[arg(long, value_parser = parse_index)]
+        max: Option<u64>,
+    },
+
+    /// Print the Fibonacci numbers modulo M, which repeat with the Pisano period of M
+    ModTable {
+        /// Modulus of the Fibonacci numbers
+        #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+        modulus: u64,
+
+        /// Number of Fibonacci numbers to print
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        terms: u64,
+
+        /// Index of the first Fibonacci number
+        #[arg(long, default_value_t = 0, value_parser = parse_index)]
+        start: u64,
+
+        /// Separate the periods with `|` and print the length of the period
+        #[arg(long)]
+        mark_period: bool,
+    },
+
+    /// Benchmark reproducible pseudo-random indices and print the measurements
+    BenchSample {
+        /// Smallest index to sample
+        #[arg(long, value_parser = parse_index)]
+        min: u64,
+
+        /// Largest index to sample
+        #[arg(long, value_parser = parse_index)]
+        max: u64,
+
+        /// Number of indices to sample
+        #[arg(long, default_value_t = 50)]
+        samples: usize,
+
+        /// Seed of the pseudo-random index selection
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+
+        /// Format of the measurements
+        #[arg(long, value_enum, default_value_t = SampleFormat::Csv)]
+        format: SampleFormat,
+    },
+
+    /// Measure the thresholds of this machine and save them for the following runs
+    Calibrate {
+        /// Only print the measured thresholds without saving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Continue a calculation from a checkpoint file written with --checkpoint
+    Resume {
+        /// The checkpoint file to continue from
+        checkpoint: PathBuf,
+    },
+
+    /// Print a shell completion script to stdout
+    Completions {
+        /// The shell to generate the completion script for
+        shell: Shell,
+    },
+
+    /// Print a roff man page to stdout
+    Manpage,
+}
+
+/// The layout of the `--table` output.
+#[derive(Clone, Copy, ValueEnum)]
+enum TableFormat {
+    Markdown,
+    Plain,
+}
+
+impl From<TableFormat> for TableStyle {
+    fn from(format: TableFormat) -> Self {
+        match format {
+            TableFormat::Markdown => TableStyle::Markdown,
+            TableFormat::Plain => TableStyle::Plain,
+        }
+    }
+}
+
+/// The indices of a range selected with `--parity`.
+#[derive(Clone, Copy, ValueEnum)]
+enum IndexParity {
+    Even,
+    Odd,
+}
+
+impl From<IndexParity> for Parity {
+    fn from(parity: IndexParity) -> Self {
+        match parity {
+            IndexParity::Even => Parity::Even,
+            IndexParity::Odd => Parity::Odd,
+        }
+    }
+}
+
+/// The output format of the `bench-sample` command.
+#[derive(Clone, Copy, ValueEnum)]
+enum SampleFormat {
+    Csv,
+    Json,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config = match Config::discover() {
+        Ok(config) => config,
+        Err(error) => {
+            print_error(cli.color, format_args!("Error: {}", error));
+            return ExitCode::FAILURE;
+        }
+    };
+    let output = OutputOptions::from_cli(&cli, &config);
+
+    // A broken calibration file must not keep `calibrate` from replacing it
+    if !matches!(cli.command, Some(Command::Calibrate { .. })) {
+        if let Err(error) = apply_thresholds(&cli) {
+            print_error(cli.color, format_args!("Error: {}", error));
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // Every mode runs on the threads of --threads, so the pool is built before any of them
+    let pool = match build_thread_pool(output.settings.threads) {
+        Ok(pool) => pool,
+        Err(error) => {
+            print_error(cli.color, format_args!("Error: {}", error));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let run = || run_mode(&cli, &output);
+    let exit_code = match &pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    };
+    exit_code.unwrap_or_else(|| run_prompt(&cli, output, pool))
+}
+
+/// Runs the subcommand or the mode selected with the options of the command line.
+///
+/// # Returns
+/// An `Option<ExitCode>` with the exit code of the mode, or `None` when no mode was
+/// selected and the prompt is started instead.
+fn run_mode(cli: &Cli, output: &OutputOptions) -> Option<ExitCode> {
+    if let Some(command) = &cli.command {
+        return Some(run_command(command, cli, output));
+    }
+
+    // Only the largest index of the command line has to be checked against the maximum
+    let largest_index = [
+        cli.index,
+        cli.range.as_ref().map(|range| range[0].max(range[1])),
+        cli.count.map(|count| count.saturating_sub(1)),
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+    if let Some(Err(error)) = largest_index.map(|index| output.settings.check_index(index)) {
+        print_error(cli.color, format_args!("Error: {}", error));
+        return Some(ExitCode::FAILURE);
+    }
+
+    let exit_code = if let Some(path) = &cli.input {
+        run_input(cli, output, path)
+    } else if let Some(count) = cli.count {
+        run_count(count, output)
+    } else if let Some(range) = &cli.range {
+        run_range(cli, output, range[0], range[1])
+    } else if let Some(index) = cli.index {
+        run_index(index, cli, output)
+    } else if cli.profile {
+        run_profile_runs(cli.profile_runs, output)
+    } else if let Some(stress) = &cli.stress {
+        run_stress_test(stress[0], stress[1] as usize, cli.stress_max, output)
+    } else if let Some(budget) = cli.time_budget {
+        run_time_budget(budget, output)
+    } else if cli.output.is_some() {
+        print_error(
+            cli.color,
+            "Error: --output requires an index, --range or --input",
+        );
+        ExitCode::FAILURE
+    } else if cli.tui {
+        match run_tui(output) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(message) => {
+                print_error(cli.color, message);
+                ExitCode::FAILURE
+            }
+        }
+    } else {
+        return None;
+    };
+    Some(exit_code)
+}
+
+/// Runs a subcommand.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the subcommand failed.
+fn run_command(command: &Command, cli: &Cli, output: &OutputOptions) -> ExitCode {
+    let exit_on = |result: Result<(), String>| match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            print_error(cli.color, message);
+            ExitCode::FAILURE
+        }
+    };
+
+    match command {
+        Command::Compare {
+            first,
+            second,
+            digits,
+            values,
+        } => exit_on(print_comparison(*first, *second, *digits, *values, output)),
+        Command::Divides { m, n } => {
+            print_divisibility(*m, *n, output);
+            ExitCode::SUCCESS
+        }
+        Command::Convergent { n } => {
+            print_convergent(*n, output);
+            ExitCode::SUCCESS
+        }
+        Command::Approx { n } => {
+            print_log_approximation(*n, output);
+            ExitCode::SUCCESS
+        }
+        Command::Watch { start, factor, max } => exit_on(run_watch(*start, *factor, *max, output)),
+        Command::ModTable {
+            modulus,
+            terms,
+            start,
+            mark_period,
+        } => {
+            print_mod_table(*modulus, *start, *terms, *mark_period, output);
+            ExitCode::SUCCESS
+        }
+        Command::BenchSample {
+            min,
+            max,
+            samples,
+            seed,
+            format,
+        } => exit_on(print_bench_samples(*min, *max, *samples, *seed, *format)),
+        Command::Completions { shell } => {
+            let mut command = Cli::command();
+            let bin_name = command.get_name().to_string();
+            generate(*shell, &mut command, bin_name, &mut io::stdout());
+            ExitCode::SUCCESS
+        }
+        Command::Manpage => exit_on(
+            Man::new(Cli::command())
+                .render(&mut io::stdout())
+                .map_err(|error| format!("Error: {}", error)),
+        ),
+        Command::Calibrate { dry_run } => run_calibration(*dry_run, output),
+        Command::Resume { checkpoint } => {
+            let interval = Duration::from_secs(cli.checkpoint_interval);
+            exit_on(print_resumed(checkpoint, interval, output))
+        }
+    }
+}
+
+/// Measures the calculation and conversion of randomly sampled indices for the
+/// `bench-sample` command and prints the measurements.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if the bounds are invalid or a
+/// calculation failed.
+fn print_bench_samples(
+    min: u64,
+    max: u64,
+    samples: usize,
+    seed: u64,
+    format: SampleFormat,
+) -> Result<(), String> {
+    if min > max {
+        return Err("Error: --min must not be larger than --max".to_string());
+    }
+
+    let mut measurements = Vec::with_capacity(samples);
+    for index in sample_indices(min, max, samples, seed) {
+        let sample = bench_index(index).map_err(|error| format!("Error: {}", error))?;
+        measurements.push(sample);
+    }
+
+    match format {
+        SampleFormat::Csv => print!("{}", samples_to_csv(&measurements)),
+        SampleFormat::Json => print!("{}", samples_to_json(&measurements)),
+    }
+    Ok(())
+}
+
+/// Continues a calculation from a checkpoint file for the `resume` command and prints it.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if the checkpoint could not be resumed.
+fn print_resumed(
+    checkpoint: &Path,
+    interval: Duration,
+    output: &OutputOptions,
+) -> Result<(), String> {
+    let start_time = Instant::now();
+    let (index, fibonacci_result) =
+        resume_fibonacci(checkpoint, interval).map_err(|error| format!("Error: {}", error))?;
+    print_result(index, &fibonacci_result, start_time.elapsed(), output);
+    notify_finished(
+        index,
+        &fibonacci_result,
+        start_time.elapsed(),
+        &output.settings,
+    );
+    Ok(())
+}
+
+/// Prints `F(0)` to `F(count - 1)` for `--count`, like a range.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the numbers could not be printed.
+fn run_count(count: u64, output: &OutputOptions) -> ExitCode {
+    let mut stdout = io::stdout().lock();
+    let sequence = FibIter::new(0).take(count as usize);
+    match print_sequence(sequence, output, &mut stdout) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            print_error(output.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the mode of the options given together with a single index, or calculates and
+/// prints the Fibonacci number at the index if there is none.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the mode failed.
+fn run_index(index: u64, cli: &Cli, output: &OutputOptions) -> ExitCode {
+    let exit_on = |result: Result<(), FibError>| match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            print_error(cli.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    };
+
+    if let Some(checkpoint) = &cli.checkpoint {
+        run_checkpointed(index, checkpoint, cli, output)
+    } else if cli.compare_algos {
+        let runs = cli.bench.unwrap_or(1) as usize;
+        exit_on(
+            compare_algorithms(index, runs)
+                .map(|timings| print_algorithm_timings(index, &timings, output)),
+        )
+    } else if let Some(runs) = cli.bench {
+        exit_on(
+            bench_runs(index, runs as usize, output.settings.algorithm)
+                .map(|stats| print_bench_stats(index, &stats, output)),
+        )
+    } else if cli.approx {
+        print_approximation(index, output);
+        ExitCode::SUCCESS
+    } else if cli.explain_choice {
+        print_choice(index, output);
+        ExitCode::SUCCESS
+    } else if cli.explain {
+        print_explanation(index, output);
+        ExitCode::SUCCESS
+    } else if cli.identity {
+        print_identities(index, output)
+    } else if cli.raw {
+        exit_on(write_raw(index, output))
+    } else if cli.verify {
+        exit_on(print_verified(index, output))
+    } else {
+        run_single(index, cli, output)
+    }
+}
+
+/// Calculates the Fibonacci number at `index` with checkpoints for `--checkpoint` and
+/// prints it.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the calculation or a checkpoint
+/// failed.
+fn run_checkpointed(index: u64, checkpoint: &Path, cli: &Cli, output: &OutputOptions) -> ExitCode {
+    let start_time = Instant::now();
+    let interval = Duration::from_secs(cli.checkpoint_interval);
+    match calculate_fibonacci_checkpointed(index, checkpoint, interval) {
+        Ok(fibonacci_result) => {
+            print_result(index, &fibonacci_result, start_time.elapsed(), output);
+            if let Err(message) = copy_requested(cli, &fibonacci_result, output) {
+                print_error(cli.color, message);
+                return ExitCode::FAILURE;
+            }
+            notify_finished(
+                index,
+                &fibonacci_result,
+                start_time.elapsed(),
+                &output.settings,
+            );
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            print_error(cli.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Calculates and prints the Fibonacci number at `index`, and writes or copies it when
+/// asked to.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the value could not be written
+/// or copied.
+fn run_single(index: u64, cli: &Cli, output: &OutputOptions) -> ExitCode {
+    let start_time = Instant::now();
+    let Some(fibonacci_result) = print_fibonacci(index, output) else {
+        return ExitCode::SUCCESS;
+    };
+    if let Some(path) = &cli.output {
+        if let Err(message) = write_value(&fibonacci_result, path, output) {
+            print_error(cli.color, message);
+            return ExitCode::FAILURE;
+        }
+    }
+    if let Err(message) = copy_requested(cli, &fibonacci_result, output) {
+        print_error(cli.color, message);
+        return ExitCode::FAILURE;
+    }
+    notify_finished(
+        index,
+        &fibonacci_result,
+        start_time.elapsed(),
+        &output.settings,
+    );
+    ExitCode::SUCCESS
+}
+
+/// Measures the calculation and conversion at a fixed set of indices for `--profile` and
+/// prints the profile as JSON.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if a calculation failed.
+fn run_profile_runs(runs: u32, output: &OutputOptions) -> ExitCode {
+    match run_profile(runs as usize, output.settings.algorithm) {
+        Ok(profile) => {
+            print!("{}", profile_to_json(&profile));
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            print_error(output.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Checks random indices against identities for `--stress` and prints the report.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if any index failed a check.
+fn run_stress_test(seed: u64, count: usize, max: u64, output: &OutputOptions) -> ExitCode {
+    let started = Instant::now();
+    match run_stress(seed, count, max) {
+        Ok(report) => {
+            print_stress_report(seed, &report, started.elapsed(), output, max);
+            if report.failures.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(error) => {
+            print_error(output.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Searches the largest index that is calculated within `budget` for `--time-budget` and
+/// prints the steps of the search.
+fn run_time_budget(budget: Duration, output: &OutputOptions) -> ExitCode {
+    let max_index = output.settings.max_index.unwrap_or(u64::MAX);
+    let steps = largest_within(budget, max_index);
+    print_budget_steps(budget, &steps, output);
+    ExitCode::SUCCESS
+}
+
+/// Runs the prompt, or the script of `--script` in its session.
+///
+/// The session keeps the thread pool, as the prompt reads its lines on the main thread and
+/// only runs the calculations on the pool, which `:threads` can replace.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the session could not be started
+/// or a line of the script failed.
+fn run_prompt(cli: &Cli, output: OutputOptions, pool: Option<ThreadPool>) -> ExitCode {
+    // Expensive indices are only confirmed by a user at a terminal, otherwise they fail
+    let confirm = if cli.yes {
+        Confirm::Skip
+    } else if io::stdin().is_terminal() {
+        Confirm::Ask
+    } else {
+        Confirm::Refuse
+    };
+    let mut session = Session::new(
+        output,
+        pool,
+        cli.transcript_values,
+        confirm,
+        Box::new(CostModel::default()),
+        cli.history_budget.saturating_mul(1 << 20),
+        cli.buffer_budget.saturating_mul(1 << 20),
+    );
+    if let Some(path) = &cli.transcript {
+        if let Err(message) = session.start_transcript(path) {
+            print_error(cli.color, message);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Err(message) = cancel_on_interrupt() {
+        print_error(cli.color, message);
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(script) = &cli.script {
+        return run_script(script, &mut session, cli.keep_going, cli.color);
+    }
+
+    if cli.speculate {
+        session.enable_speculation();
+    }
+    let mut reader = LineReader::new();
+
+    // Prompt the user for a Fibonacci number index
+    while let Some(line) = reader.read_line("Enter Fibonacci number index (or 'q' to quit): ") {
+        match session.run_line(&line) {
+            Ok(Flow::Continue) => {}
+            Ok(Flow::Quit) => break,
+            Err(message) => println!("{}", Style::stdout(cli.color).error(message)),
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Runs every line of a script file as if it was typed at the prompt.
+///
+/// Each line is echoed before its output, while blank lines and comments starting with `#`
+/// are skipped. The script stops at the first line that fails unless `keep_going` is set.
+///
+/// # Arguments
+/// * `path` - The script file to run.
+/// * `session` - The session the lines are run in.
+/// * `keep_going` - Whether to continue with the next line after a line failed.
+/// * `color` - When error messages are colored.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the script could not be read or
+/// any of its lines failed.
+fn run_script(
+    path: &Path,
+    session: &mut Session,
+    keep_going: bool,
+    color: ColorChoice,
+) -> ExitCode {
+    let script = match fs::read_to_string(path) {
+        Ok(script) => script,
+        Err(error) => {
+            print_error(
+                color,
+                format_args!("Error: could not read {}: {}", path.display(), error),
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = false;
+    for (number, line) in script.lines().enumerate() {
+        let command = line.trim();
+        if command.is_empty() || command.starts_with('#') {
+            continue;
+        }
+
+        println!("> {}", command);
+        match session.run_line(command) {
+            Ok(Flow::Continue) => {}
+            Ok(Flow::Quit) => break,
+            Err(message) => {
+                print_error(
+                    color,
+                    format_args!(
+                        "Error in {} line {}:\n{}",
+                        path.display(),
+                        number + 1,
+                        message
+                    ),
+                );
+                failed = true;
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Builds the thread pool calculations run on.
+///
+/// # Arguments
+/// * `threads` - The number of worker threads, or `None` to use the global thread pool.
+///
+/// # Returns
+/// A `Result<Option<ThreadPool>, String>` with the new thread pool, `None` for the global
+/// thread pool, or a `String` error message if the threads could not be started.
+fn build_thread_pool(threads: Option<usize>) -> Result<Option<ThreadPool>, String> {
+    match threads {
+        Some(threads) => thread_pool(threads).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Returns the number of threads calculations run on, which is always 1 without the
+/// `parallel` feature, as `--threads` has no threads to start then.
+fn thread_count(output: &OutputOptions) -> usize {
+    output
+        .settings
+        .threads
+        .filter(|_| cfg!(feature = "parallel"))
+        .unwrap_or_else(current_num_threads)
+}
+
+/// Applies the thresholds of the calibration file, with the explicit flags taking precedence.
+///
+/// # Returns
+/// A `Result<(), FibError>` with the error of a calibration file that could not be loaded.
+fn apply_thresholds(cli: &Cli) -> Result<(), FibError> {
+    let calibrated = Thresholds::discover()?.unwrap_or_default();
+    let overrides = ThresholdOverrides {
+        parallel_multiply_bits: cli.parallel_multiply_bits,
+        word_index: cli.word_index,
+        conversion_bits: cli.conversion_bits,
+    };
+    calibrated.merge(overrides).apply();
+    Ok(())
+}
+
+/// Measures the thresholds of this machine for the `calibrate` command and saves them to
+/// the calibration file of the configuration directory.
+///
+/// # Arguments
+/// * `dry_run` - Whether the thresholds are only printed without saving them.
+/// * `output` - The options with the number of threads and whether to print quietly.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the thresholds could not be saved.
+fn run_calibration(dry_run: bool, output: &OutputOptions) -> ExitCode {
+    let threads = thread_count(output);
+
+    if !output.quiet {
+        eprintln!(
+            "Calibrating on {} thread{}, this takes a few seconds",
+            threads,
+            if threads == 1 { "" } else { "s" }
+        );
+    }
+    let thresholds = calibrate(threads, &mut WallClock);
+
+    if output.quiet {
+        print!("{}", thresholds.to_toml());
+    } else {
+        println!("{}", thresholds);
+        if threads < 2 {
+            println!("Products are never split on a single thread, so that threshold was kept");
+        }
+    }
+
+    if dry_run {
+        if !output.quiet {
+            println!("Not saved, as --dry-run was given");
+        }
+        return ExitCode::SUCCESS;
+    }
+    let Some(path) = Thresholds::path() else {
+        print_error(
+            output.color,
+            "Error: the thresholds cannot be saved, as neither XDG_CONFIG_HOME nor HOME is set",
+        );
+        return ExitCode::FAILURE;
+    };
+    match thresholds.save(&path) {
+        Ok(()) => {
+            if !output.quiet {
+                println!("Saved to {}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            print_error(
+                output.color,
+                format_args!("Error: could not write {}: {}", path.display(), error),
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Calculates every Fibonacci number in a range and writes it to stdout or a file.
+///
+/// Files are written as "index value" lines so they can be resumed, while stdout follows
+/// the output options. With `--csv` the range is written as CSV to the file or stdout, and
+/// with `--table` it is printed as a table. With `--parity` only the even or odd indices
+/// are calculated.
+///
+/// # Arguments
+/// * `cli` - The parsed command line arguments.
+/// * `output` - The options that control how results are printed.
+/// * `start` - The index of the first Fibonacci number of the range.
+/// * `end` - The index of the last Fibonacci number of the range (inclusive).
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the range could not be written.
+fn run_range(cli: &Cli, output: &OutputOptions, start: u64, end: u64) -> ExitCode {
+    if start > end {
+        print_error(
+            cli.color,
+            "Error: the start of the range must not be larger than its end",
+        );
+        return ExitCode::FAILURE;
+    }
 
-fn main() {
-    loop {
-        // Prompt the user for a Fibonacci number index
-        print!("Enter Fibonacci number index (or 'q' to quit): ");
-        io::stdout().flush().unwrap();
+    let sequence: Box<dyn Iterator<Item = (u64, BigUint)>> = match cli.parity {
+        Some(parity) => Box::new(
+            FibParityIter::new(start, parity.into()).take_while(move |(index, _)| *index <= end),
+        ),
+        None => Box::new(FibIter::new(start).take((end - start).saturating_add(1) as usize)),
+    };
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+    if let Some(format) = cli.table {
+        print!(
+            "{}",
+            render_table(sequence, format.into(), &output.settings.value_format)
+        );
+        return ExitCode::SUCCESS;
+    }
 
-        let input = input.trim();
-        if input.eq_ignore_ascii_case("q") {
-            break;
+    let result = match (&cli.output, cli.csv) {
+        (Some(path), true) => File::create(path).map_err(FibError::from).and_then(|file| {
+            write_csv(
+                sequence,
+                &output.settings.value_format,
+                &mut BufWriter::new(file),
+            )
+        }),
+        (None, true) => write_csv(
+            sequence,
+            &output.settings.value_format,
+            &mut io::stdout().lock(),
+        ),
+        (Some(path), false) if cli.append => append_range(sequence, path).map(|_| ()),
+        (Some(path), false) => {
+            write_range_to_file(start, end, path, cli.checkpoint_every, cli.resume)
+        }
+        (None, false) => print_sequence(sequence, output, &mut io::stdout().lock()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            print_error(cli.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Calculates every index of an input file and writes the results like a range.
+///
+/// Invalid indices, and indices above the maximum index, are reported with their line and
+/// position before anything is calculated, and the valid ones are still calculated. The
+/// results go to stdout or, as "index value" lines, to `--output`, and `--csv` and
+/// `--table` work like for ranges.
+///
+/// # Arguments
+/// * `cli` - The parsed command line arguments.
+/// * `output` - The options that control how results are calculated and printed.
+/// * `path` - The path of the file with the indices.
+///
+/// # Returns
+/// The `ExitCode` of the program, which is a failure if the file could not be read or
+/// written, or if any of its indices was invalid.
+fn run_input(cli: &Cli, output: &OutputOptions, path: &Path) -> ExitCode {
+    let input = match fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(error) => {
+            print_error(
+                cli.color,
+                format_args!("Error: could not read {}: {}", path.display(), error),
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = false;
+    let mut indices = Vec::new();
+    for (line, position, result) in parse_index_list(&input) {
+        match result.and_then(|index| output.settings.check_index(index).map(|()| index)) {
+            Ok(index) => indices.push(index),
+            Err(error) => {
+                print_error(
+                    cli.color,
+                    format_args!(
+                        "Error: {}:{}, value {}: {}",
+                        path.display(),
+                        line,
+                        position,
+                        error
+                    ),
+                );
+                failed = true;
+            }
+        }
+    }
+
+    let algorithm = output.settings.algorithm;
+    let mut calculation_error = None;
+    let sequence =
+        indices
+            .into_iter()
+            .map_while(|index| match calculate_fibonacci_with(index, algorithm) {
+                Ok(value) => Some((index, value)),
+                Err(error) => {
+                    calculation_error = Some(error);
+                    None
+                }
+            });
+
+    let result = if let Some(format) = cli.table {
+        print!(
+            "{}",
+            render_table(sequence, format.into(), &output.settings.value_format)
+        );
+        Ok(())
+    } else {
+        match (&cli.output, cli.csv) {
+            (Some(path), true) => File::create(path).map_err(FibError::from).and_then(|file| {
+                write_csv(
+                    sequence,
+                    &output.settings.value_format,
+                    &mut BufWriter::new(file),
+                )
+            }),
+            (None, true) => write_csv(
+                sequence,
+                &output.settings.value_format,
+                &mut io::stdout().lock(),
+            ),
+            (Some(path), false) if cli.append => append_range(sequence, path).map(|_| ()),
+            (Some(path), false) => File::create(path).map_err(FibError::from).and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                for (index, value) in sequence {
+                    writer.write_all(range_line(index, &value).as_bytes())?;
+                }
+                writer.flush()?;
+                Ok(())
+            }),
+            (None, false) => print_sequence(sequence, output, &mut io::stdout().lock()),
+        }
+    };
+
+    match result.and(calculation_error.map_or(Ok(()), Err)) {
+        Ok(()) if failed => ExitCode::FAILURE,
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            print_error(cli.color, format_args!("Error: {}", error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints a sequence of Fibonacci numbers, one per line.
+///
+/// Each line holds the index and the value, or only the value when `quiet` is set. With
+/// a template every result is rendered from the template instead, and with the LaTeX
+/// format the sequence is printed as a single `align*` environment.
+///
+/// # Arguments
+/// * `sequence` - The `(index, value)` pairs to print.
+/// * `output` - The options that control how results are printed.
+/// * `writer` - The writer the lines are written to.
+///
+/// # Returns
+/// A `Result<(), FibError>` which is an error if writing failed.
+fn print_sequence(
+    sequence: impl Iterator<Item = (u64, BigUint)>,
+    output: &OutputOptions,
+    writer: &mut impl Write,
+) -> Result<(), FibError> {
+    if output.template.is_none() && output.settings.format == OutputFormat::Latex {
+        writer.write_all(render_latex_align(sequence, &latex_format(output)).as_bytes())?;
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let mut start_time = Instant::now();
+
+    for (index, value) in sequence {
+        if let Some(template) = &output.template {
+            print_template(template, index, &value, start_time.elapsed(), output);
+        } else if output.quiet {
+            writeln!(writer, "{}", output.settings.value_format.render(&value))?;
+        } else {
+            writeln!(
+                writer,
+                "{} {}",
+                index,
+                output.settings.value_format.render(&value)
+            )?;
         }
+        start_time = Instant::now();
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Calculates the Fibonacci number at `index` and prints it.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to calculate.
+/// * `output` - The options that control how the result is calculated and printed.
+///
+/// # Returns
+/// An `Option<BigUint>` with the calculated Fibonacci number, or `None` if the calculation failed.
+fn print_fibonacci(index: u64, output: &OutputOptions) -> Option<BigUint> {
+    // Calculate the Fibonacci number together with the duration of the calculation
+    let algorithm = output.settings.algorithm;
+    memory::start_phase();
+    let result = if output.progress {
+        Ok(calculate_with_progress(
+            index,
+            &output.settings.value_format,
+            output.color,
+        ))
+    } else {
+        calculate_fibonacci_timed_with(index, algorithm)
+    };
+    let calc_peak = memory::phase_peak();
 
-        let input_value = match input.parse::<u64>() {
-            Ok(num) => num,
-            Err(_) => {
-                println!("Please enter a valid number");
-                continue;
+    match result {
+        Ok((fibonacci_result, calc_duration)) => {
+            memory::start_phase();
+            let conversion_start_time = Instant::now();
+            print_result(index, &fibonacci_result, calc_duration, output);
+            if output.stats {
+                let conversion = (conversion_start_time.elapsed(), memory::phase_peak());
+                print_stats(index, (calc_duration, calc_peak), conversion, output);
             }
+            Some(fibonacci_result)
+        }
+        Err(error) => {
+            let style = Style::stdout(output.color);
+            println!("{}", style.error(format_args!("Error: {}", error)));
+            None
+        }
+    }
+}
+
+/// Prints the duration and peak memory of the calculation and the conversion of a result
+/// to stderr for `--stats`.
+///
+/// The conversion covers everything [`print_result`] does, from rendering the result to
+/// writing it. The peaks are the most bytes that were allocated at once during each phase,
+/// including what was allocated before it started, like the result for the conversion.
+///
+/// # Arguments
+/// * `index` - The index of the calculated Fibonacci number.
+/// * `calculation` - The duration and peak memory of the calculation.
+/// * `conversion` - The duration and peak memory of the conversion.
+/// * `output` - The options that control how the statistics are printed.
+fn print_stats(
+    index: u64,
+    calculation: (Duration, Option<u64>),
+    conversion: (Duration, Option<u64>),
+    output: &OutputOptions,
+) {
+    let style = Style::stderr(output.color);
+    let phase = |(duration, peak): (Duration, Option<u64>)| {
+        let peak = match peak {
+            Some(bytes) => style.number(format_bytes(bytes)),
+            None => "not measured (needs the mem-stats feature)".to_string(),
         };
+        format!(
+            "{}, peak memory {}",
+            style.duration(output.settings.value_format.duration(duration)),
+            peak
+        )
+    };
+    let (algorithm, reason) = algorithm_choice(index, output);
+    eprintln!("Algorithm:   {} ({})", style.number(algorithm), reason);
+    eprintln!("Calculation: {}", phase(calculation));
+    eprintln!("Conversion:  {}", phase(conversion));
+}
+
+/// Returns how a single Fibonacci number is calculated with the settings, and why.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number.
+/// * `output` - The options with the algorithm and the number of threads.
+///
+/// # Returns
+/// A tuple with the name of the algorithm or of the strategy `auto` chooses, and the reason.
+fn algorithm_choice(index: u64, output: &OutputOptions) -> (&'static str, String) {
+    let algorithm = output.settings.algorithm;
+    if output.progress {
+        return (
+            Algorithm::FastDoubling.name(),
+            "--progress follows the steps of fast doubling".to_string(),
+        );
+    }
+    if algorithm != Algorithm::Auto {
+        return (
+            algorithm.name(),
+            "it was selected with --algorithm".to_string(),
+        );
+    }
 
-        // Calculate the Fibonacci number and save the duration of the calculation
-        let start_time = Instant::now();
-        let calc_result = calculate_fibonacci(input_value);
-        let duration = format_duration(start_time.elapsed().as_secs_f64());
+    let threads = thread_count(output);
+    let choice = choose_algorithm(index, threads);
+    (choice.strategy.name(), choice.reason)
+}
 
-        match calc_result {
-            Ok(fibonacci_result) => {
-                println!(
-                    "\nCalculated the {}th Fibonacci number",
-                    thousands_separator(input_value)
-                );
-                println!("Fibonacci calculation duration: {}", duration);
+/// Prints how a single Fibonacci number would be calculated and why, for `--explain-choice`.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number.
+/// * `output` - The options with the algorithm and the number of threads.
+fn print_choice(index: u64, output: &OutputOptions) {
+    let (algorithm, reason) = algorithm_choice(index, output);
+    if output.quiet {
+        println!("{}", algorithm);
+        return;
+    }
 
-                // Start time of the conversion duration
-                let conversion_start_time = Instant::now();
+    let style = Style::stdout(output.color);
+    println!(
+        "F({}) would be calculated with {}, as {}",
+        style.number(output.settings.value_format.count(index)),
+        style.number(algorithm),
+        reason
+    );
+}
 
-                // Use scientific notation when the result is larger than 10^35
-                let use_scientific_notation = fibonacci_result > BigUint::from(10u32).pow(35);
+/// Calculates a Fibonacci number and writes its little-endian bytes to stdout.
+///
+/// The bytes are written without any header or text around them and without a decimal
+/// conversion, so even the largest results are written almost instantly. Zero is written
+/// as a single zero byte.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to calculate.
+/// * `output` - The options that control how the result is calculated.
+///
+/// # Returns
+/// A `Result<(), FibError>` with a `FibError` if the calculation or writing failed.
+fn write_raw(index: u64, output: &OutputOptions) -> Result<(), FibError> {
+    let fibonacci_result = calculate_fibonacci_with(index, output.settings.algorithm)?;
 
-                // Convert the result based on the use_scientific_notation boolean
-                let result = if use_scientific_notation {
-                    scientific_notation(&fibonacci_result)
-                } else {
-                    fibonacci_result.to_string()
-                };
-                // Save the duration of the conversion
-                let conversion_duration =
-                    format_duration(conversion_start_time.elapsed().as_secs_f64());
-
-                if use_scientific_notation {
-                    println!(
-                        "Result to Scientific notation duration: {}",
-                        conversion_duration
-                    );
-                } else {
-                    println!("Result to String duration: {}", conversion_duration);
-                }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&fibonacci_result.to_bytes_le())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Calculates a Fibonacci number with two algorithms, prints it and reports that they agree.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to calculate.
+/// * `output` - The options that control how the result is printed.
+///
+/// # Returns
+/// A `Result<(), FibError>` with `FibError::VerificationFailed` if the algorithms disagree.
+fn print_verified(index: u64, output: &OutputOptions) -> Result<(), FibError> {
+    let start_time = Instant::now();
+    let fibonacci_result = calculate_fibonacci_verified(index)?;
+    print_result(index, &fibonacci_result, start_time.elapsed(), output);
+
+    if !output.quiet && output.template.is_none() && output.settings.format == OutputFormat::Text {
+        let style = Style::stdout(output.color);
+        println!(
+            "Verified: fast doubling and matrix exponentiation agree on F({})",
+            style.number(output.settings.value_format.count(index))
+        );
+    }
+    Ok(())
+}
+
+/// Writes every digit of a calculated Fibonacci number to a file.
+///
+/// The digits are written in the configured base and grouping, or as lines of blocks with
+/// `--pretty`, but never in scientific notation or shortened.
+///
+/// # Arguments
+/// * `fibonacci_result` - The calculated Fibonacci number.
+/// * `path` - The path of the file, which is replaced if it exists.
+/// * `output` - The options that control how the value is rendered.
+///
+/// The file is written in chunks while its progress is reported on stderr, synced to disk
+/// at the end and removed again if writing fails.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if the file could not be written, which
+/// tells how many digits were written before the error.
+fn write_value(
+    fibonacci_result: &BigUint,
+    path: &Path,
+    output: &OutputOptions,
+) -> Result<(), String> {
+    let value_format = &output.settings.value_format;
+    let digits = digit_count_in(fibonacci_result, value_format.base);
+    let prefix_len = value_format.prefix().len() as u64;
+    // Plain digits fill the file exactly, separators and blocks only add to it
+    let plain = output.pretty.is_none() && !value_format.group;
+    let total = match &output.pretty {
+        Some(layout) => digits + digits / layout.block_len.max(1) as u64,
+        None if value_format.group => digits + digits / 3,
+        None => prefix_len + digits + 1,
+    };
 
-                println!("Result:\n{}", result);
+    let mut progress = WriteProgress::new(path, total, output.color);
+    let result = write_file_with_progress(
+        path,
+        |written| progress.update(written),
+        |writer| match &output.pretty {
+            Some(layout) => {
+                write_digit_blocks(&value_format.digits(fibonacci_result), layout, writer)
             }
-            Err(error) => {
-                println!("Error: {}", error);
+            None => {
+                value_format.write_to(fibonacci_result, writer)?;
+                writer.write_all(b"\n")
             }
+        },
+    );
+    progress.finish();
+
+    // Devices like /dev/full are not removed after a failed write
+    let removed = match path.exists() {
+        true => "",
+        false => ", the partial file was removed",
+    };
+    match result {
+        Err(FibError::WriteFailed { written, error }) if plain => {
+            return Err(format!(
+                "Error: writing {} failed after {} of {} digits{}: {}",
+                path.display(),
+                value_format.count(written.saturating_sub(prefix_len).min(digits)),
+                value_format.count(digits),
+                removed,
+                error
+            ));
+        }
+        Err(FibError::WriteFailed { written, error }) => {
+            return Err(format!(
+                "Error: writing {} failed after {} bytes{}: {}",
+                path.display(),
+                value_format.count(written),
+                removed,
+                error
+            ));
         }
-        println!("\n");
+        Err(error) => {
+            return Err(format!(
+                "Error: could not create {}: {}",
+                path.display(),
+                error
+            ));
+        }
+        Ok(_) => {}
+    }
+
+    if !output.quiet && output.template.is_none() && output.settings.format == OutputFormat::Text {
+        println!("Wrote every digit to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Copies a single result to the clipboard if `--copy` or `--copy-full` was passed.
+///
+/// Results that are printed with every digit, with `--full` or `--pretty`, are copied with
+/// every digit as well.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if the result could not be copied.
+fn copy_requested(
+    cli: &Cli,
+    fibonacci_result: &BigUint,
+    output: &OutputOptions,
+) -> Result<(), String> {
+    if !cli.copy && !cli.copy_full {
+        return Ok(());
+    }
+    let full = cli.copy_full || output.full || output.pretty.is_some();
+    copy_value(fibonacci_result, full, output)
+}
+
+/// Returns the format of LaTeX output, which shows every digit with `--full`.
+fn latex_format(output: &OutputOptions) -> ValueFormat {
+    ValueFormat {
+        sci_threshold: if output.full {
+            SciThreshold::Never
+        } else {
+            output.settings.value_format.sci_threshold
+        },
+        ..output.settings.value_format
     }
 }
 
-/// Calculates the nth Fibonacci number using a parallel computation approach.
+/// Prints a calculated Fibonacci number.
 ///
-/// This function takes a `u64` value `n` as input and returns the nth Fibonacci number
-/// as a `BigUint` result. It uses a recursive helper function `fib_pair` to perform
-/// the Fibonacci calculation in a parallel manner for large numbers.
+/// When a template is given the result is printed as a single line rendered from the
+/// template, with the JSON format it is printed as a JSON object, when `quiet` is set only
+/// the result is printed, otherwise the full summary with durations and the result is
+/// printed.
 ///
 /// # Arguments
-/// * `n` - The index of the Fibonacci number to calculate.
+/// * `index` - The index of the calculated Fibonacci number.
+/// * `fibonacci_result` - The calculated Fibonacci number.
+/// * `calc_duration` - The duration of the Fibonacci calculation.
+/// * `output` - The options that control how the result is printed.
+fn print_result(
+    index: u64,
+    fibonacci_result: &BigUint,
+    calc_duration: Duration,
+    output: &OutputOptions,
+) {
+    print_result_of(
+        index,
+        fibonacci_result,
+        calc_duration,
+        false,
+        &mut BufferPool::new(0),
+        output,
+    );
+}
+
+/// Prints a calculated Fibonacci number like [`print_result`], marking the calculation
+/// duration of the full summary with `(precomputed)` if the number was calculated ahead of
+/// time by `--speculate`.
 ///
-/// # Returns
-/// A `Result<BigUint, String>` where the `BigUint` represents the nth Fibonacci number,
-/// or a `String` error message if the calculation fails.
-fn calculate_fibonacci(n: u64) -> Result<BigUint, String> {
-    if n == 0 {
-        return Ok(BigUint::ZERO);
+/// The buffers of the decimal conversion are taken from `buffers` and handed back to it
+/// after printing, so the prompt reuses them for the next result.
+fn print_result_of(
+    index: u64,
+    fibonacci_result: &BigUint,
+    calc_duration: Duration,
+    precomputed: bool,
+    buffers: &mut BufferPool,
+    output: &OutputOptions,
+) {
+    if let Some(template) = &output.template {
+        print_template(template, index, fibonacci_result, calc_duration, output);
+        return;
+    }
+
+    let settings = &output.settings;
+    if settings.format == OutputFormat::Json {
+        let result = FibResult::new(index, fibonacci_result.clone(), calc_duration);
+        println!(
+            "{}",
+            result_json(
+                &result,
+                settings.algorithm,
+                settings.value_format.precision,
+                settings.value_format.notation
+            )
+        );
+        return;
+    }
+
+    if settings.format == OutputFormat::Latex {
+        let value_format = latex_format(output);
+        if output.quiet {
+            println!("{}", latex_value(fibonacci_result, &value_format));
+        } else {
+            println!("{}", latex_equation(index, fibonacci_result, &value_format));
+        }
+        return;
+    }
+
+    if output.summary {
+        print_summary(index, fibonacci_result, calc_duration, output);
+        return;
+    }
+
+    // Without the summary there is no conversion duration, so every digit is streamed
+    if output.quiet && output.full && output.pretty.is_none() {
+        let digits = digit_count_in(fibonacci_result, settings.value_format.base) as usize;
+        let lines = |width: usize| digits.div_ceil(width);
+        write_paged(lines, settings.pager, |writer| {
+            settings.value_format.write_to(fibonacci_result, writer)?;
+            writer.write_all(b"\n")
+        });
+        return;
+    }
+
+    // Start time of the conversion duration
+    let conversion_start_time = Instant::now();
+
+    // Use scientific notation when the decimal result is larger than the threshold
+    let use_scientific_notation = !output.full
+        && output.pretty.is_none()
+        && settings.value_format.use_scientific(fibonacci_result);
+
+    // Convert the result based on the use_scientific_notation boolean
+    let result = if use_scientific_notation {
+        settings.value_format.scientific(fibonacci_result)
+    } else if output.pretty.is_some() {
+        settings.value_format.digits_in(fibonacci_result, buffers)
+    } else if output.full {
+        settings.value_format.render_in(fibonacci_result, buffers)
+    } else {
+        settings
+            .value_format
+            .render_limited_in(fibonacci_result, buffers)
+    };
+    // Save the duration of the conversion
+    let conversion_duration = output
+        .settings
+        .value_format
+        .duration(conversion_start_time.elapsed());
+
+    if output.quiet {
+        print_value(&result, !use_scientific_notation, output);
+        buffers.recycle(result.into_bytes());
+        return;
+    }
+
+    let style = Style::stdout(output.color);
+    println!(
+        "\nCalculated the {}th Fibonacci number",
+        style.number(output.settings.value_format.count(index))
+    );
+    println!(
+        "Fibonacci calculation duration: {}{}",
+        style.duration(output.settings.value_format.duration(calc_duration)),
+        if precomputed { " (precomputed)" } else { "" }
+    );
+    // The digit count is exact and derived from the top bits, not from the decimal string
+    let base = settings.value_format.base;
+    let digits = settings
+        .value_format
+        .count(digit_count_in(fibonacci_result, base));
+    if base == 10 {
+        println!("Digits: {}", style.number(digits));
+    } else {
+        println!("Digits: {} in base {}", style.number(digits), base);
+    }
+
+    if use_scientific_notation {
+        println!(
+            "Result to Scientific notation duration: {}",
+            style.duration(conversion_duration)
+        );
+        println!("Result:\n{}", style.scientific(&result));
+    } else {
+        println!(
+            "Result to String duration: {}",
+            style.duration(conversion_duration)
+        );
+        println!("Result:");
+        print_value(&result, true, output);
     }
+    buffers.recycle(result.into_bytes());
+}
 
-    fn fib_pair(n: u64) -> (BigUint, BigUint) {
-        if n == 0 {
-            return (BigUint::ZERO, BigUint::from(1u32));
+/// Prints a rendered result, as lines of blocks with `--pretty`.
+///
+/// # Arguments
+/// * `result` - The rendered result.
+/// * `digits` - Whether `result` consists of digits only, which can be printed as blocks.
+/// * `output` - The options that control how the result is printed.
+fn print_value(result: &str, digits: bool, output: &OutputOptions) {
+    match output.pretty.filter(|_| digits) {
+        Some(layout) => {
+            let line_width = layout
+                .width
+                .min(result.len() + result.len() / layout.block_len);
+            let lines = |width: usize| layout.line_count(result.len()) * line_width.div_ceil(width);
+            write_paged(lines, output.settings.pager, |writer| {
+                write_digit_blocks(result, &layout, writer)
+            });
         }
+        None => print_paged(result, output.settings.pager),
+    }
+}
+
+/// Prints whether `F(m)` divides `F(n)` and the reason, without calculating either number.
+///
+/// # Arguments
+/// * `m` - The index of the divisor.
+/// * `n` - The index of the dividend.
+/// * `output` - The options that control how the answer is printed.
+fn print_divisibility(m: u64, n: u64, output: &OutputOptions) {
+    let divides = fib_divides(m, n);
+    if output.quiet {
+        println!("{}", if divides { "yes" } else { "no" });
+        return;
+    }
+
+    let style = Style::stdout(output.color);
+    let (m_text, n_text) = (
+        output.settings.value_format.count(m),
+        output.settings.value_format.count(n),
+    );
+    println!(
+        "F({}) {} F({})",
+        style.number(&m_text),
+        if divides {
+            "divides"
+        } else {
+            "does not divide"
+        },
+        style.number(&n_text)
+    );
+    let reason = match m {
+        0 => "F(0) = 0 only divides itself".to_string(),
+        1 | 2 => format!("F({}) = 1 divides every Fibonacci number", m),
+        _ if divides => format!("{} divides {}", m_text, n_text),
+        _ => format!("{} does not divide {}", m_text, n_text),
+    };
+    println!("Because {}", reason);
+}
+
+/// Calculates the Fibonacci number at `index` and prints every fast doubling step before it.
+///
+/// The halving chain of the index is printed first, followed by the identities applied on
+/// the way back up and the duration of every step.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to calculate.
+/// * `output` - The options that control how the steps and the result are printed.
+fn print_explanation(index: u64, output: &OutputOptions) {
+    let start_time = Instant::now();
+    let (fibonacci_result, trace) = explain_fibonacci(index);
+    let calc_duration = start_time.elapsed();
 
-        let (a, b) = fib_pair(n >> 1);
-        let two = BigUint::from(2u32);
+    let value_format = &output.settings.value_format;
+    let style = Style::stdout(output.color);
+    let f = |index: u64| format!("F({})", value_format.count(index));
 
-        // Execute the Fibonacci pair calculation in parallel
-        let (c, d) = rayon::join(|| &a * (&b * &two - &a), || &a * &a + &b * &b);
+    let chain: Vec<String> = trace
+        .iter()
+        .rev()
+        .map(|step| value_format.count(step.index))
+        .chain(iter::once("0".to_string()))
+        .collect();
+    println!("Fast doubling halves the index until it reaches 0:");
+    println!("{}", chain.join(" → "));
+    if trace.is_empty() {
+        println!("\nF(0) = 0 is where the doubling starts, so no step is needed");
+    } else {
+        println!("\nand doubles the pair (F(k), F(k+1)) back up from (F(0), F(1)) = (0, 1):");
+    }
 
-        // Determine the result based on if n is even or odd
-        let result = if n & 1 == 0 {
-            (c, d)
+    let width = value_format.count(index).len();
+    for step in &trace {
+        let (k, next) = (step.half, step.index + 1);
+        let identities = if step.odd {
+            format!(
+                "{} = {}² + {}²,  {} = {} + {}",
+                f(step.index),
+                f(k),
+                f(k + 1),
+                f(next),
+                f(2 * k),
+                f(step.index)
+            )
         } else {
-            let sum = &c + &d;
-            (d, sum)
+            // 2F(k+1)² − 3F(k)² overshoots F(2k) by 2(−1)^k
+            format!(
+                "{} = 2{}² − 3{}² {} 2,  {} = {}² + {}²",
+                f(step.index),
+                f(k + 1),
+                f(k),
+                if k % 2 == 0 { "−" } else { "+" },
+                f(next),
+                f(k),
+                f(k + 1)
+            )
         };
-
-        result
+        println!(
+            "{:>width$} {} → {}  ({})",
+            value_format.count(step.index),
+            if step.odd { "odd " } else { "even" },
+            identities,
+            style.duration(output.settings.value_format.duration(step.duration)),
+            width = width
+        );
     }
 
-    let (result, _) = fib_pair(n);
-    Ok(result)
+    print_result(index, &fibonacci_result, calc_duration, output);
 }
 
-/// Converts a `BigUint` number to a string representation in scientific notation.
+/// Prints the nth convergent of the continued fraction of the golden ratio.
 ///
-/// This function takes a `BigUint` number as input and returns a string representation
-/// of the number in scientific notation format. The function ensures that the output
-/// string has a fixed number of significant digits (5 by default) and adjusts the
-/// exponent accordingly.
+/// The convergent is printed as a fraction, as a decimal number rounded to `precision`
+/// decimal places and with the side of `φ` it lies on. The convergent `1/0` at 0 has no
+/// decimal value, so only its fraction is printed.
 ///
 /// # Arguments
-/// * `number` - The `BigUint` number to be converted to scientific notation.
+/// * `n` - The index of the convergent.
+/// * `output` - The options that control how the convergent is printed.
+fn print_convergent(n: u64, output: &OutputOptions) {
+    let (numerator, denominator) = golden_convergent(n);
+    let value_format = &output.settings.value_format;
+    let render = |value: &BigUint| {
+        if value_format.use_scientific(value) {
+            value_format.scientific(value)
+        } else {
+            value_format.render_limited(value)
+        }
+    };
+
+    let decimal = divide_decimal(&numerator, &denominator, value_format.precision);
+
+    if output.quiet {
+        println!("{} / {}", render(&numerator), render(&denominator));
+        return;
+    }
+
+    let style = Style::stdout(output.color);
+    println!(
+        "Convergent {} of φ = [1; 1, 1, 1, …] is F({}) / F({})",
+        style.number(value_format.count(n)),
+        value_format.count(n + 1),
+        value_format.count(n)
+    );
+    println!("{} / {}", render(&numerator), render(&denominator));
+    let Some(decimal) = decimal else {
+        println!("This degenerate convergent starts every continued fraction");
+        return;
+    };
+    println!(
+        "≈ {} ({} φ)",
+        decimal,
+        if n.is_multiple_of(2) {
+            "above"
+        } else {
+            "below"
+        }
+    );
+}
+
+/// Prints a row of Fibonacci numbers modulo `modulus`.
 ///
-/// # Returns
-/// A `String` representing the input `BigUint` number in scientific notation format.
-fn scientific_notation(number: &BigUint) -> String {
-    let first_digits_count = 5 as usize;
-    let extra_digits = first_digits_count * 2;
+/// With `mark_period` a `|` is printed in front of every number that starts a new Pisano
+/// period, and the length of the period is printed if the row holds two period starts.
+///
+/// # Arguments
+/// * `modulus` - The modulus of the Fibonacci numbers.
+/// * `start` - The index of the first Fibonacci number.
+/// * `terms` - The number of Fibonacci numbers to print.
+/// * `mark_period` - Whether the periods are marked.
+/// * `output` - The options that control how the row is printed.
+fn print_mod_table(
+    modulus: u64,
+    start: u64,
+    terms: u64,
+    mark_period: bool,
+    output: &OutputOptions,
+) {
+    let value_format = &output.settings.value_format;
+    let mut sequence = FibModIter::new(start, modulus);
+    let mut row = String::new();
+    let mut period_starts = Vec::new();
+    let mut last = start;
+
+    for _ in 0..terms {
+        let period_start = sequence.at_period_start();
+        let Some((index, value)) = sequence.next() else {
+            break;
+        };
+        if period_start {
+            period_starts.push(index);
+        }
+        if index > start {
+            row.push_str(if mark_period && period_start {
+                " | "
+            } else {
+                " "
+            });
+        }
+        row.push_str(&value.to_string());
+        last = index;
+    }
 
-    if number == &BigUint::new(vec![]) {
-        return "0.0e0".to_string();
+    if output.quiet {
+        println!("{}", row);
+        return;
     }
 
-    let base = BigUint::from(10u64);
-    let mut first_digits_power = base.pow(first_digits_count as u32);
+    let style = Style::stdout(output.color);
+    println!(
+        "F(n) mod {} for n = {} to {}",
+        style.number(value_format.count(modulus)),
+        style.number(value_format.count(start)),
+        style.number(value_format.count(last))
+    );
+    println!("{}", row);
+    if mark_period {
+        match period_starts[..] {
+            [first, second, ..] => println!(
+                "Pisano period: {}",
+                style.number(value_format.count(second - first))
+            ),
+            _ => println!("Pisano period: not within the printed terms"),
+        }
+    }
+}
 
-    // Approximate digit count
-    let bits = number.bits() as f64;
-    let mut total_digits = (bits * 2f64.log10()) as u64;
+/// Prints how many digits a calculated Fibonacci number has instead of its digits.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number.
+/// * `fibonacci_result` - The calculated Fibonacci number.
+/// * `calc_duration` - The duration of the calculation.
+/// * `output` - The options that control how the summary is printed.
+fn print_summary(
+    index: u64,
+    fibonacci_result: &BigUint,
+    calc_duration: Duration,
+    output: &OutputOptions,
+) {
+    let value_format = &output.settings.value_format;
+    let summary = DigitSummary {
+        index,
+        digits: digit_count(fibonacci_result),
+        estimated: false,
+        grouping: value_format.grouping,
+    };
+    if output.quiet {
+        println!("{}", summary);
+        return;
+    }
 
-    // Compute shift and divisor to get more digits than needed
-    let shift = total_digits.saturating_sub(extra_digits as u64);
-    let divisor = base.pow(shift as u32);
+    let style = Style::stdout(output.color);
+    println!(
+        "\nCalculated the {}th Fibonacci number",
+        style.number(value_format.count(index))
+    );
+    println!(
+        "Fibonacci calculation duration: {}",
+        style.duration(value_format.duration(calc_duration))
+    );
+    println!("{}", summary);
+}
 
-    // Get the first portion of digits
-    let first_digits = number / &divisor;
+/// Approximates the Fibonacci number at `index` with Binet's formula and prints it.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to approximate.
+/// * `output` - The options that control how the approximation is printed.
+fn print_approximation(index: u64, output: &OutputOptions) {
+    let start_time = Instant::now();
+    let approximation = fib_approx_big(index, output.settings.value_format.precision);
+    let calc_duration = start_time.elapsed();
 
-    // Correct the total digits when the integer part is zero
-    let mut integer_part = &first_digits / &first_digits_power;
+    let exactness = if approximation.exact { "" } else { "~" };
+    if output.quiet {
+        println!(
+            "{}{}",
+            exactness,
+            approximation.render(output.settings.value_format.grouping)
+        );
+        return;
+    }
 
-    while integer_part == BigUint::new(vec![]) {
-        total_digits -= 1;
-        first_digits_power *= &base;
-        integer_part = &first_digits / &first_digits_power;
+    let style = Style::stdout(output.color);
+    println!(
+        "\nApproximated the {}th Fibonacci number with Binet's formula",
+        style.number(output.settings.value_format.count(index))
+    );
+    println!(
+        "Approximation duration: {}",
+        style.duration(output.settings.value_format.duration(calc_duration))
+    );
+    if !approximation.exact {
+        println!(
+            "The result is not exact, it is rounded to {} significant digits",
+            approximation.digits.len()
+        );
     }
+    println!(
+        "Result:\n{}{}",
+        exactness,
+        style.scientific(&approximation.render(output.settings.value_format.grouping))
+    );
+}
 
-    // Get the integer part and the decimal part of the first digits
-    let first_digits_str = first_digits.to_string();
-    let (integer_string, decimal_string) = first_digits_str[..first_digits_count].split_at(1);
+/// Approximates the Fibonacci number at `index` from its logarithm and prints it along
+/// with its exact digit count.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to approximate.
+/// * `output` - The options that control how the approximation is printed.
+fn print_log_approximation(index: u64, output: &OutputOptions) {
+    let value_format = &output.settings.value_format;
+    let start_time = Instant::now();
+    let approximation = fib_approx_log(index);
+    let calc_duration = start_time.elapsed();
+
+    let exactness = if approximation.exact { "" } else { "~" };
+    if output.quiet {
+        println!(
+            "{}{}",
+            exactness,
+            approximation.render(value_format.grouping)
+        );
+        return;
+    }
 
-    format!(
-        "{}.{}e+{}",
-        integer_string,
-        decimal_string,
-        thousands_separator(total_digits)
-    )
+    let style = Style::stdout(output.color);
+    println!(
+        "\nApproximated the {}th Fibonacci number from its logarithm",
+        style.number(value_format.count(index))
+    );
+    println!(
+        "Approximation duration: {}",
+        style.duration(output.settings.value_format.duration(calc_duration))
+    );
+    println!(
+        "Digits: {} (exact)",
+        style.number(value_format.count(approximation.exponent + 1))
+    );
+    if !approximation.exact {
+        println!(
+            "The result is approximate, only the first {} significant digits are shown",
+            approximation.digits.len()
+        );
+    }
+    println!(
+        "Result:\n{}{}",
+        exactness,
+        style.scientific(&approximation.render(value_format.grouping))
+    );
 }
-/// Formats a duration value as a human-readable string.
+
+/// Checks the well-known identities at an index and prints whether each of them holds.
 ///
-/// This function takes a duration value in seconds and formats it as a string
-/// with the appropriate time unit (microseconds, milliseconds, or seconds).
-/// The function will choose the most appropriate unit based on the magnitude
-/// of the duration value.
+/// With `quiet` only `pass` or `fail` is printed for every identity.
 ///
 /// # Arguments
-/// * `duration` - The duration value in seconds to be formatted.
+/// * `index` - The index the identities are checked at.
+/// * `output` - The options that control how the checks are printed.
 ///
 /// # Returns
-/// A `String` representing the input duration value in a human-readable format.
-fn format_duration(duration: f64) -> String {
-    if duration < 1e-3 {
-        format!("{}μs", (duration * 1e6).round() as u16)
-    } else if duration < 1.0 {
-        format!("{}ms", (duration * 1e3).round() as u16)
+/// The `ExitCode` of the program, which is a failure if any identity does not hold.
+fn print_identities(index: u64, output: &OutputOptions) -> ExitCode {
+    let checks = check_identities(index);
+    let style = Style::stdout(output.color);
+    let value_format = &output.settings.value_format;
+
+    if !output.quiet {
+        println!(
+            "Identities at n = {}:",
+            style.number(value_format.count(index))
+        );
+    }
+    let width = checks
+        .iter()
+        .map(|(identity, _)| identity.statement().chars().count())
+        .max()
+        .unwrap_or(0);
+    for (identity, holds) in &checks {
+        let verdict = if *holds {
+            "pass".to_string()
+        } else {
+            style.error("fail")
+        };
+        if output.quiet {
+            println!("{}", verdict);
+            continue;
+        }
+        let parameter = match identity {
+            Identity::Catalan { r } => format!(" with r = {}", value_format.count(*r)),
+            _ => String::new(),
+        };
+        println!(
+            "  {:<18}  {:<width$}  {}{}",
+            identity.name(),
+            identity.statement(),
+            verdict,
+            parameter,
+            width = width
+        );
+    }
+
+    if checks.iter().all(|(_, holds)| *holds) {
+        ExitCode::SUCCESS
     } else {
-        format!("{:.3}s", duration)
+        ExitCode::FAILURE
+    }
+}
+
+/// Prints the statistics of a repeated Fibonacci calculation.
+///
+/// # Arguments
+/// * `index` - The index of the benchmarked Fibonacci number.
+/// * `stats` - The statistics of the calculation durations.
+/// * `output` - The options that control how the statistics are printed.
+fn print_bench_stats(index: u64, stats: &BenchStats, output: &OutputOptions) {
+    let style = Style::stdout(output.color);
+    println!(
+        "Calculated the {}th Fibonacci number {} times with {}",
+        style.number(output.settings.value_format.count(index)),
+        stats.runs,
+        output.settings.algorithm
+    );
+    let duration =
+        |duration: Duration| style.duration(output.settings.value_format.duration(duration));
+    println!("Min:    {}", duration(stats.min));
+    println!("Median: {}", duration(stats.median));
+    println!("Mean:   {}", duration(stats.mean));
+    println!("Max:    {}", duration(stats.max));
+}
+
+/// Prints the durations of every algorithm for the same Fibonacci number as a table.
+///
+/// The durations are the medians of the runs, and the speedup of each algorithm is
+/// relative to the slowest one.
+///
+/// # Arguments
+/// * `index` - The index of the compared Fibonacci number.
+/// * `timings` - The statistics of every algorithm, see [`compare_algorithms`].
+/// * `output` - The options that control how the table is printed.
+fn print_algorithm_timings(
+    index: u64,
+    timings: &[(Algorithm, BenchStats)],
+    output: &OutputOptions,
+) {
+    let style = Style::stdout(output.color);
+    let value_format = &output.settings.value_format;
+    let runs = timings.first().map_or(1, |(_, stats)| stats.runs);
+    let slowest = timings
+        .iter()
+        .map(|(_, stats)| stats.median)
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "All algorithms agree on the {}th Fibonacci number, {}",
+        style.number(value_format.count(index)),
+        if runs == 1 {
+            "calculated once each".to_string()
+        } else {
+            format!("median of {} runs each", runs)
+        }
+    );
+    let name_width = Algorithm::CONCRETE
+        .map(|algorithm| algorithm.name().len())
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+    let durations: Vec<String> = timings
+        .iter()
+        .map(|(_, stats)| value_format.duration(stats.median).to_string())
+        .collect();
+    let duration_width = durations
+        .iter()
+        .map(|duration| duration.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Duration".len());
+    println!(
+        "{:<name_width$}  {:>duration_width$}  Speedup",
+        "Algorithm", "Duration"
+    );
+    for ((algorithm, stats), duration) in timings.iter().zip(&durations) {
+        // A median of zero only happens for trivial indices, where no algorithm is faster
+        let speedup = if stats.median.is_zero() {
+            1.0
+        } else {
+            slowest.as_secs_f64() / stats.median.as_secs_f64()
+        };
+        println!(
+            "{:<name_width$}  {}  {}",
+            algorithm.name(),
+            style.duration(format!("{:>duration_width$}", duration)),
+            style.number(format!("{:.1}x", speedup))
+        );
+    }
+}
+
+/// Prints the failures of a stress run, followed by a summary unless `quiet` is set.
+///
+/// # Arguments
+/// * `seed` - The seed the indices were picked from.
+/// * `report` - The outcome of the stress run.
+/// * `duration` - The duration of the whole run.
+/// * `output` - The options that control how the report is printed.
+/// * `max_index` - The largest index that could be picked.
+fn print_stress_report(
+    seed: u64,
+    report: &StressReport,
+    duration: Duration,
+    output: &OutputOptions,
+    max_index: u64,
+) {
+    let style = Style::stdout(output.color);
+    let value_format = &output.settings.value_format;
+    for failure in &report.failures {
+        println!(
+            "F({}): {}",
+            style.number(value_format.count(failure.index)),
+            failure.reason
+        );
     }
+    if output.quiet {
+        return;
+    }
+
+    println!(
+        "Checked {} pseudo-random indices up to {} from seed {} in {}: {}",
+        style.number(value_format.count(report.checked as u64)),
+        style.number(value_format.count(max_index)),
+        seed,
+        style.duration(value_format.duration(duration)),
+        match report.failures.len() {
+            0 => "all checks passed".to_string(),
+            1 => "1 check failed".to_string(),
+            failed => format!("{} checks failed", failed),
+        }
+    );
 }
 
-/// Formats a number with a thousands separator.
+/// Prints the calculations of a `--time-budget` run and the largest index among them.
+///
+/// With `quiet` only the largest index is printed.
 ///
-/// This function takes a `u32` number and returns a `String` representation of the number with a thousands separator (`,`) inserted every three digits.
+/// # Arguments
+/// * `budget` - The time every calculation was allowed to take.
+/// * `steps` - The calculations that finished within the budget, the largest one last.
+/// * `output` - The options that control how the steps are printed.
+fn print_budget_steps(budget: Duration, steps: &[BudgetStep], output: &OutputOptions) {
+    let style = Style::stdout(output.color);
+    let value_format = &output.settings.value_format;
+    let Some(largest) = steps.last() else {
+        if !output.quiet {
+            println!(
+                "Not even F(1) could be calculated within {}",
+                style.duration(value_format.duration(budget))
+            );
+        }
+        return;
+    };
+    if output.quiet {
+        println!("{}", largest.index);
+        return;
+    }
+
+    let width = value_format.count(largest.index).len();
+    for step in steps {
+        println!(
+            "F({}) in {}",
+            style.number(format!(
+                "{:>width$}",
+                value_format.count(step.index),
+                width = width
+            )),
+            style.duration(value_format.duration(step.duration))
+        );
+    }
+    println!(
+        "\nThe largest Fibonacci number calculated within {} is F({}) with {} digits",
+        style.duration(value_format.duration(budget)),
+        style.number(value_format.count(largest.index)),
+        style.number(value_format.count(largest.digits))
+    );
+}
+
+/// Compares the Fibonacci numbers at two indices and prints the comparison.
 ///
 /// # Arguments
-/// * `number` - The number to be formatted with a thousands separator.
+/// * `first` - The index of the first Fibonacci number.
+/// * `second` - The index of the second Fibonacci number.
+/// * `digits` - The number of significant digits of the ratio.
+/// * `values` - Whether both Fibonacci numbers should be printed as well.
+/// * `output` - The options that control how the values are printed.
 ///
 /// # Returns
-/// A `String` representing the input number with a thousands separator.
-fn thousands_separator(number: u64) -> String {
-    number
-        .to_string()
-        .as_bytes()
-        .rchunks(3)
-        .rev()
-        .map(std::str::from_utf8)
-        .collect::<Result<Vec<&str>, _>>()
-        .unwrap()
-        .join(",")
+/// A `Result<(), String>` with the error message if the calculation was cancelled.
+fn print_comparison(
+    first: u64,
+    second: u64,
+    digits: u16,
+    values: bool,
+    output: &OutputOptions,
+) -> Result<(), String> {
+    let start_time = Instant::now();
+    let comparison =
+        compare_fibonacci(first, second).map_err(|error| format!("Error: {}", error))?;
+    let calc_duration = start_time.elapsed();
+
+    let smaller = output.settings.value_format.count(comparison.smaller_index);
+    let larger = output.settings.value_format.count(comparison.larger_index);
+
+    let style = Style::stdout(output.color);
+    println!(
+        "Compared F({}) and F({})",
+        style.number(&smaller),
+        style.number(&larger)
+    );
+    println!(
+        "Fibonacci calculation duration: {}",
+        style.duration(output.settings.value_format.duration(calc_duration))
+    );
+    println!(
+        "Digits: {} and {} (difference: {})",
+        style.number(
+            output
+                .settings
+                .value_format
+                .count(comparison.smaller_digits)
+        ),
+        style.number(output.settings.value_format.count(comparison.larger_digits)),
+        style.number(
+            output
+                .settings
+                .value_format
+                .count(comparison.digit_difference())
+        )
+    );
+    match comparison.ratio(digits as usize, output.settings.value_format.grouping) {
+        Some(ratio) => println!("Ratio F({}) / F({}): {}", larger, smaller, ratio),
+        None => println!(
+            "Ratio F({}) / F({}): undefined, F(0) is zero",
+            larger, smaller
+        ),
+    }
+    println!(
+        "F({}) divides F({}): {}",
+        smaller,
+        larger,
+        if comparison.divides() { "yes" } else { "no" }
+    );
+
+    if values {
+        print_result(
+            comparison.smaller_index,
+            &comparison.smaller,
+            calc_duration,
+            output,
+        );
+        print_result(
+            comparison.larger_index,
+            &comparison.larger,
+            calc_duration,
+            output,
+        );
+    }
+    Ok(())
+}
+
+/// Prints a Fibonacci result as a single line rendered from an output template.
+///
+/// Only the conversions the template refers to are performed, so a template without
+/// `{value}` never converts the full result to a string.
+///
+/// # Arguments
+/// * `template` - The output template to render.
+/// * `index` - The index of the calculated Fibonacci number.
+/// * `fibonacci_result` - The calculated Fibonacci number.
+/// * `calc_duration` - The duration of the Fibonacci calculation.
+/// * `output` - The options that control how `{value}` is rendered.
+fn print_template(
+    template: &Template,
+    index: u64,
+    fibonacci_result: &BigUint,
+    calc_duration: Duration,
+    output: &OutputOptions,
+) {
+    // Start time of the conversion duration
+    let conversion_start_time = Instant::now();
+
+    let value = template
+        .uses(Placeholder::Value)
+        .then(|| output.settings.value_format.render(fibonacci_result));
+    let sci = template
+        .uses(Placeholder::Sci)
+        .then(|| output.settings.value_format.scientific(fibonacci_result));
+
+    let conversion_duration = conversion_start_time.elapsed();
+
+    let fields = TemplateFields {
+        index,
+        digits: digit_count(fibonacci_result),
+        value: value.as_deref(),
+        sci: sci.as_deref(),
+        duration: calc_duration,
+        conversion: conversion_duration,
+        algorithm: output.settings.algorithm.name(),
+        ascii: output.ascii,
+    };
+
+    println!("{}", template.render(&fields));
 }
@@ -0,0 +1,101 @@
+#[cfg(feature = "mem-stats")]
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The number of bytes that are allocated right now.
+#[cfg(feature = "mem-stats")]
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// The largest number of bytes that were allocated at once since the last [`start_phase`].
+#[cfg(feature = "mem-stats")]
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting the live bytes and their peak.
+///
+/// Every allocation costs two atomic operations more, which is why the allocator is only
+/// used with the `mem-stats` feature.
+#[cfg(feature = "mem-stats")]
+struct CountingAllocator;
+
+#[cfg(feature = "mem-stats")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(feature = "mem-stats")]
+impl CountingAllocator {
+    fn grow(bytes: usize) {
+        let live = LIVE.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        PEAK.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn shrink(bytes: usize) {
+        LIVE.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded to the system allocator unchanged, only counting the sizes
+#[cfg(feature = "mem-stats")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            CountingAllocator::grow(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            CountingAllocator::grow(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        CountingAllocator::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            if new_size > layout.size() {
+                CountingAllocator::grow(new_size - layout.size());
+            } else {
+                CountingAllocator::shrink(layout.size() - new_size);
+            }
+        }
+        new_pointer
+    }
+}
+
+/// Starts measuring the peak memory of a new phase, like a calculation or a conversion.
+///
+/// The peak starts at the bytes that are allocated right now, so it includes everything
+/// the phase keeps alive from before, like the result a conversion starts from.
+#[cfg(feature = "mem-stats")]
+pub fn start_phase() {
+    PEAK.store(LIVE.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Does nothing, as the allocations are not counted without the `mem-stats` feature.
+#[cfg(not(feature = "mem-stats"))]
+pub fn start_phase() {}
+
+/// Returns the largest number of bytes that were allocated at once since [`start_phase`].
+///
+/// # Returns
+/// An `Option<u64>` with the peak in bytes, or `None` without the `mem-stats` feature.
+#[cfg(feature = "mem-stats")]
+pub fn phase_peak() -> Option<u64> {
+    Some(PEAK.load(Ordering::Relaxed) as u64)
+}
+
+/// Returns `None`, as the allocations are not counted without the `mem-stats` feature.
+#[cfg(not(feature = "mem-stats"))]
+pub fn phase_peak() -> Option<u64> {
+    None
+}
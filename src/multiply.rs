@@ -0,0 +1,134 @@
+use crate::{threads::join, tuning::parallel_multiply_bits};
+use num_bigint::BigUint;
+
+/// Products of operands with fewer bits are left to num-bigint alone, as splitting them
+/// costs more in additions and copies than another thread saves (about 630,000 digits).
+///
+/// This is the default of `Thresholds::parallel_multiply_bits`, which `calibrate` measures
+/// on the current machine.
+pub(crate) const PARALLEL_MULTIPLY_BITS: u64 = 1 << 21;
+
+/// Multiplies two numbers with their sub-products spread over the threads of rayon.
+///
+/// From `Thresholds::parallel_multiply_bits` on, [`PARALLEL_MULTIPLY_BITS`] unless it was
+/// calibrated, the operands are split in half like in Karatsuba's
+/// algorithm, `a = a1·2^h + a0` and `b = b1·2^h + b0`, and the three products `a1·b1`,
+/// `a0·b0` and `(a0 + a1)·(b0 + b1)` are calculated in parallel, each of them split again
+/// while it is large enough. The middle term is recovered as
+/// `(a0 + a1)·(b0 + b1) − a1·b1 − a0·b0` and the three are shifted into place.
+///
+/// A Karatsuba level does more work than the Toom-3 multiplication num-bigint uses for such
+/// operands, so the products are only split until there are about as many of them as
+/// `threads`, and for a single thread not at all. Squares are split into squares, as
+/// the sums of both operands are equal as well.
+///
+/// # Arguments
+/// * `a` - The first factor.
+/// * `b` - The second factor.
+/// * `threads` - The number of threads the product may keep busy.
+///
+/// # Returns
+/// The product `a·b`, the same as `a * b`.
+pub(crate) fn parallel_multiply(a: &BigUint, b: &BigUint, threads: usize) -> BigUint {
+    parallel_multiply_from(a, b, threads, parallel_multiply_bits())
+}
+
+/// Multiplies two numbers like [`parallel_multiply`], splitting products from `min_bits`
+/// on instead of the threshold in use, which the calibration needs to time both methods.
+pub(crate) fn parallel_multiply_from(
+    a: &BigUint,
+    b: &BigUint,
+    threads: usize,
+    min_bits: u64,
+) -> BigUint {
+    // Every level triples the number of products
+    let levels = (0..)
+        .find(|level| 3usize.pow(*level) >= threads)
+        .unwrap_or(0);
+    multiply_split(a, b, levels, min_bits)
+}
+
+/// Multiplies two numbers like [`parallel_multiply`], splitting at most `levels` times.
+fn multiply_split(a: &BigUint, b: &BigUint, levels: u32, min_bits: u64) -> BigUint {
+    if levels == 0 || a.bits().min(b.bits()) < min_bits {
+        return a * b;
+    }
+
+    // Splitting at a whole number of digits keeps the halves simple copies
+    let half_digits = (a.bits().max(b.bits()) / 2).div_ceil(32) as usize;
+    let half = half_digits as u64 * 32;
+    let (a1, a0) = split(a, half_digits);
+    let (b1, b0) = split(b, half_digits);
+
+    let (high, (low, middle)) = join(
+        || multiply_split(&a1, &b1, levels - 1, min_bits),
+        || {
+            join(
+                || multiply_split(&a0, &b0, levels - 1, min_bits),
+                || multiply_split(&(&a0 + &a1), &(&b0 + &b1), levels - 1, min_bits),
+            )
+        },
+    );
+    let middle = middle - &high - &low;
+
+    (high << (2 * half)) + (middle << half) + low
+}
+
+/// Splits a number into its upper digits and its `low_digits` lowest 32-bit digits.
+fn split(value: &BigUint, low_digits: usize) -> (BigUint, BigUint) {
+    let digits = value.to_u32_digits();
+    let (low, high) = digits.split_at(low_digits.min(digits.len()));
+    (BigUint::from_slice(high), BigUint::from_slice(low))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplitMix64;
+
+    /// A random number of exactly `bits` bits.
+    fn random_number(random: &mut SplitMix64, bits: u64) -> BigUint {
+        let digits: Vec<u32> = (0..bits.div_ceil(32))
+            .map(|_| random.next_u64() as u32)
+            .collect();
+        let mut value = BigUint::from_slice(&digits) >> (digits.len() as u64 * 32 - bits);
+        value.set_bit(bits - 1, true);
+        value
+    }
+
+    #[test]
+    fn split_products_match_num_bigint() {
+        let mut random = SplitMix64::new(51);
+        for (a_bits, b_bits) in [(1, 1), (64, 4000), (5000, 5000), (9001, 3000), (20_000, 31)] {
+            let a = random_number(&mut random, a_bits);
+            let b = random_number(&mut random, b_bits);
+            let expected = &a * &b;
+            for threads in [1, 2, 3, 4, 9, 10, 27] {
+                assert_eq!(
+                    parallel_multiply_from(&a, &b, threads, 64),
+                    expected,
+                    "{} by {} bits on {} threads",
+                    a_bits,
+                    b_bits,
+                    threads
+                );
+                assert_eq!(parallel_multiply_from(&a, &a, threads, 64), &a * &a);
+            }
+        }
+        assert_eq!(
+            parallel_multiply(&BigUint::ZERO, &BigUint::from(7u32), 8),
+            BigUint::ZERO
+        );
+    }
+
+    #[test]
+    fn numbers_split_at_whole_digits() {
+        let value = (BigUint::from(0xabcu32) << 64) + 0x1234u32;
+        assert_eq!(
+            split(&value, 2),
+            (BigUint::from(0xabcu32), BigUint::from(0x1234u32))
+        );
+        assert_eq!(split(&value, 5), (BigUint::ZERO, value.clone()));
+        assert_eq!(split(&value, 0), (value, BigUint::ZERO));
+    }
+}
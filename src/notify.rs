@@ -0,0 +1,69 @@
+use fibonacci_sequence::{digit_count, Notify, Settings};
+use num_bigint::BigUint;
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+/// Announces a finished calculation if it took at least as long as configured.
+///
+/// Depending on the settings the terminal bell is rung on stderr, so it never ends up in
+/// piped results, or a desktop notification with the index, digit count and duration is
+/// sent. Desktop notifications need the `desktop-notify` feature and fall back to the bell
+/// without it. A notification that cannot be delivered is only logged at debug level and
+/// never fails the calculation.
+///
+/// # Arguments
+/// * `index` - The index of the calculated Fibonacci number.
+/// * `value` - The calculated Fibonacci number.
+/// * `elapsed` - The total time of the calculation, including printing the result.
+/// * `settings` - The settings with the kind of notification and its threshold.
+pub fn notify_finished(index: u64, value: &BigUint, elapsed: Duration, settings: &Settings) {
+    if elapsed < settings.notify_after {
+        return;
+    }
+
+    match settings.notify {
+        Notify::None => {}
+        Notify::Bell => ring_bell(),
+        Notify::Desktop => {
+            let value_format = &settings.value_format;
+            let digits = digit_count(value);
+            let body = format!(
+                "F({}) with {} {} took {}",
+                value_format.count(index),
+                value_format.count(digits),
+                if digits == 1 { "digit" } else { "digits" },
+                value_format.duration(elapsed)
+            );
+            send_desktop(&body);
+        }
+    }
+}
+
+/// Rings the terminal bell.
+fn ring_bell() {
+    let mut stderr = io::stderr();
+    if let Err(error) = stderr.write_all(b"\x07").and_then(|()| stderr.flush()) {
+        log::debug!("could not ring the terminal bell: {}", error);
+    }
+}
+
+/// Sends a desktop notification with the summary of a finished calculation.
+#[cfg(feature = "desktop-notify")]
+fn send_desktop(body: &str) {
+    let sent = notify_rust::Notification::new()
+        .summary("Fibonacci calculation finished")
+        .body(body)
+        .show();
+    if let Err(error) = sent {
+        log::debug!("could not send a desktop notification: {}", error);
+    }
+}
+
+/// Rings the terminal bell instead, as desktop notifications were not compiled in.
+#[cfg(not(feature = "desktop-notify"))]
+fn send_desktop(_body: &str) {
+    log::debug!("desktop notifications need the desktop-notify feature, ringing the bell");
+    ring_bell();
+}
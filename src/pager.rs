@@ -0,0 +1,108 @@
+use std::{
+    env,
+    io::{self, BufWriter, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+use terminal_size::{terminal_size, Height, Width};
+
+/// The pager used when `PAGER` is not set.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Prints a result, through a pager if it does not fit on the screen.
+///
+/// The pager is only used when `enabled` is set, stdout is a terminal and the text needs
+/// more lines than the terminal has. It is taken from `PAGER` and defaults to `less -R`.
+/// If the pager cannot be started the text is printed directly, and a pager that is quit
+/// before it read all of the text is not an error.
+///
+/// # Arguments
+/// * `text` - The result to print, without a trailing newline.
+/// * `enabled` - Whether a pager may be used at all.
+pub fn print_paged(text: &str, enabled: bool) {
+    let lines = |width: usize| {
+        text.lines()
+            .map(|line| line.chars().count().div_ceil(width).max(1))
+            .sum()
+    };
+    write_paged(lines, enabled, |writer| {
+        writer.write_all(text.as_bytes())?;
+        writer.write_all(b"\n")
+    });
+}
+
+/// Writes a result, through a pager if it does not fit on the screen.
+///
+/// This is [`print_paged`] for results that are written piece by piece instead of being
+/// rendered into a single string first.
+///
+/// # Arguments
+/// * `lines` - Returns the number of lines the result takes on a terminal of the given width.
+/// * `enabled` - Whether a pager may be used at all.
+/// * `write` - Writes the result, including its trailing newline.
+pub fn write_paged(
+    lines: impl FnOnce(usize) -> usize,
+    enabled: bool,
+    mut write: impl FnMut(&mut dyn Write) -> io::Result<()>,
+) {
+    let paged = enabled && exceeds_screen(lines);
+
+    // Errors of stdout are ignored like those of println, a closed pager is not an error
+    if !paged || !page(&mut write) {
+        let _ = write(&mut io::stdout().lock());
+    }
+}
+
+/// Returns the width of the terminal on stdout in columns, or `None` if stdout is not one.
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    terminal_size().map(|(Width(width), _)| usize::from(width.max(1)))
+}
+
+/// Checks whether the result needs more lines than the terminal on stdout has.
+fn exceeds_screen(lines: impl FnOnce(usize) -> usize) -> bool {
+    let stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return false;
+    }
+    let Some((Width(width), Height(height))) = terminal_size() else {
+        return false;
+    };
+
+    lines(usize::from(width.max(1))) >= usize::from(height)
+}
+
+/// Streams the result into the stdin of the pager and waits for the pager to quit.
+///
+/// # Returns
+/// `false` if the pager could not be started, in which case nothing has been written.
+fn page(write: &mut dyn FnMut(&mut dyn Write) -> io::Result<()>) -> bool {
+    let command = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return false;
+    };
+
+    // Everything printed so far has to appear before the pager takes over the terminal
+    let _ = io::stdout().flush();
+
+    let Ok(mut pager) = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+
+    if let Some(stdin) = pager.stdin.take() {
+        // The pager closes its stdin when it is quit early, which is not an error
+        let mut stdin = BufWriter::new(stdin);
+        let _ = write(&mut stdin).and_then(|()| stdin.flush());
+    }
+    let _ = pager.wait();
+    true
+}
@@ -0,0 +1,400 @@
+use crate::FibError;
+use std::str::FromStr;
+
+/// Parses the index of a Fibonacci number.
+///
+/// Besides plain integers this accepts underscores as digit separators (`1_000_000`) and
+/// scientific shorthand with an integer mantissa (`1e6`, `25E4`). Scientific inputs with a
+/// fractional mantissa such as `1.5e3` are rejected, as are negative exponents. Simple
+/// arithmetic like `2^32`, `10^9+7` or `3*10^8` is evaluated to the index it describes.
+///
+/// # Arguments
+/// * `input` - The index as entered by the user.
+///
+/// # Returns
+/// A `Result<u64, FibError>` with the parsed index, or a `FibError::InvalidIndex` if the
+/// input is not a valid index or does not fit into a `u64`.
+pub fn parse_index(input: &str) -> Result<u64, FibError> {
+    let trimmed = input.trim();
+    let invalid = |reason: &str| FibError::InvalidIndex(format!("'{}' {}", trimmed, reason));
+
+    if trimmed.contains(['+', '-', '*', '^', '(', ')']) {
+        return evaluate_index_expression(trimmed).map_err(|reason| invalid(&reason));
+    }
+
+    parse_literal(trimmed).map_err(invalid)
+}
+
+/// Parses several whitespace-separated indices, like `10 20 30`.
+///
+/// Every value is parsed on its own with [`parse_index`], so invalid values do not keep
+/// the valid ones from being used.
+///
+/// # Arguments
+/// * `input` - The indices as entered by the user.
+///
+/// # Returns
+/// A `Vec` with the position of each value, starting at 1, and the result of parsing it.
+pub fn parse_indices(input: &str) -> Vec<(usize, Result<u64, FibError>)> {
+    input
+        .split_whitespace()
+        .enumerate()
+        .map(|(position, value)| (position + 1, parse_index(value)))
+        .collect()
+}
+
+/// Parses a list of indices, like the contents of an input file.
+///
+/// The indices are separated by whitespace and newlines, and everything after a `#` on a
+/// line is a comment. Like with [`parse_indices`], every value is parsed on its own, and
+/// each result comes with where the value was found so errors can be reported precisely.
+///
+/// # Arguments
+/// * `input` - The text with the indices.
+///
+/// # Returns
+/// A `Vec` with the line of each value and its position on the line, both starting at 1,
+/// and the result of parsing it.
+pub fn parse_index_list(input: &str) -> Vec<(usize, usize, Result<u64, FibError>)> {
+    input
+        .lines()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            let values = text.split('#').next().unwrap_or_default();
+            parse_indices(values)
+                .into_iter()
+                .map(move |(position, result)| (line + 1, position, result))
+        })
+        .collect()
+}
+
+/// A single request for a number of the Fibonacci family, as entered at the prompt or in a
+/// batch of inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FibRequest {
+    /// The Fibonacci number `F(n)`.
+    Fib(u64),
+    /// The Lucas number `L(n)`.
+    Lucas(u64),
+    /// The Fibonacci number at a possibly negative index, `F(−n) = (−1)^(n+1)·F(n)`.
+    Signed(i64),
+    /// The Fibonacci number `F(n)` modulo `m`.
+    Mod { n: u64, m: u64 },
+    /// Quit the prompt.
+    Quit,
+}
+
+impl FromStr for FibRequest {
+    type Err = FibError;
+
+    /// Parses a request like `10`, `l10`, `-10`, `z-10`, `10 mod 7` or `q`.
+    ///
+    /// Indices are accepted in every form of [`parse_index`]. `l` asks for the Lucas number,
+    /// `z` for the Fibonacci number at a signed index and a leading `-` for the one at a
+    /// negative index. `N mod M` asks for `F(N)` modulo a nonzero `M`, and `q`, `quit` or
+    /// `:quit` (in any case) quit. Surrounding whitespace is ignored.
+    ///
+    /// # Returns
+    /// A `Result<FibRequest, FibError>` with the request, or a `FibError::InvalidIndex` if
+    /// the input is not a valid request.
+    fn from_str(input: &str) -> Result<FibRequest, FibError> {
+        let input = input.trim();
+
+        if ["q", "quit", ":quit"]
+            .iter()
+            .any(|quit| input.eq_ignore_ascii_case(quit))
+        {
+            return Ok(FibRequest::Quit);
+        }
+
+        if let Some((n, m)) = input.split_once(" mod ") {
+            let n = parse_index(n)?;
+            let m = parse_index(m)?;
+            if m == 0 {
+                return Err(FibError::InvalidIndex(
+                    "the modulus must not be zero".to_string(),
+                ));
+            }
+            return Ok(FibRequest::Mod { n, m });
+        }
+
+        if let Some(index) = input.strip_prefix(['l', 'L']) {
+            return parse_index(index).map(FibRequest::Lucas);
+        }
+
+        let (signed, rest) = match input.strip_prefix(['z', 'Z']) {
+            Some(rest) => (true, rest),
+            None => (false, input),
+        };
+        let (negative, magnitude) = match rest.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude),
+            None => (false, rest),
+        };
+        if !signed && !negative {
+            return parse_index(input).map(FibRequest::Fib);
+        }
+
+        let index = parse_index(magnitude)?;
+        let signed = if negative {
+            0i64.checked_sub_unsigned(index)
+        } else {
+            i64::try_from(index).ok()
+        };
+        signed.map(FibRequest::Signed).ok_or_else(|| {
+            FibError::InvalidIndex(format!("'{}' does not fit into a signed index", input))
+        })
+    }
+}
+
+/// Parses a single integer literal like `1_000_000` or `1e6`.
+///
+/// # Returns
+/// A `Result<u64, &str>` with the parsed number, or the reason it is invalid.
+fn parse_literal(literal: &str) -> Result<u64, &'static str> {
+    let digits: String = literal.chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err("is not a number");
+    }
+
+    let (mantissa, exponent) = match digits.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (digits.as_str(), None),
+    };
+
+    if mantissa.contains('.') {
+        return Err("is not an integer");
+    }
+    if mantissa.is_empty() || !mantissa.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("is not a number");
+    }
+
+    let mantissa = mantissa.parse::<u64>().map_err(|_| "is too large")?;
+
+    let Some(exponent) = exponent else {
+        return Ok(mantissa);
+    };
+
+    if exponent.is_empty() || !exponent.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("has an invalid exponent");
+    }
+
+    exponent
+        .parse::<u32>()
+        .ok()
+        .and_then(|exponent| 10u64.checked_pow(exponent))
+        .and_then(|power| mantissa.checked_mul(power))
+        .ok_or("is too large")
+}
+
+/// Evaluates an arithmetic expression for an index like `2^32`, `10^9+7` or `3*10^8`.
+///
+/// The grammar is deliberately small: literals as accepted by `parse_literal`, `+`, `-`,
+/// `*`, `^` (right-associative) and parentheses. Intermediate results are calculated in
+/// `i128` with overflow detection, so `2^64-1` works while the result must fit into a `u64`.
+///
+/// # Returns
+/// A `Result<u64, String>` with the index, or the reason the expression is invalid.
+fn evaluate_index_expression(expression: &str) -> Result<u64, String> {
+    let mut parser = IndexParser {
+        input: expression.as_bytes(),
+        position: 0,
+    };
+
+    let value = parser.expression()?;
+    parser.skip_whitespace();
+    if let Some(&byte) = parser.input.get(parser.position) {
+        return Err(format!(
+            "has an unexpected '{}' at position {}",
+            byte as char,
+            parser.position + 1
+        ));
+    }
+
+    if value < 0 {
+        return Err("is negative".to_string());
+    }
+    u64::try_from(value).map_err(|_| "is too large".to_string())
+}
+
+/// A recursive descent parser that evaluates index expressions while parsing them.
+struct IndexParser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl IndexParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .input
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Consumes the next non-whitespace byte if it is one of `operators`.
+    fn operator(&mut self, operators: &[u8]) -> Option<u8> {
+        self.skip_whitespace();
+        let byte = *self.input.get(self.position)?;
+        operators.contains(&byte).then(|| {
+            self.position += 1;
+            byte
+        })
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<i128, String> {
+        let mut value = self.term()?;
+        while let Some(operator) = self.operator(b"+-") {
+            let right = self.term()?;
+            value = if operator == b'+' {
+                value.checked_add(right)
+            } else {
+                value.checked_sub(right)
+            }
+            .ok_or("is too large")?;
+        }
+        Ok(value)
+    }
+
+    /// term := power ('*' power)*
+    fn term(&mut self) -> Result<i128, String> {
+        let mut value = self.power()?;
+        while self.operator(b"*").is_some() {
+            let right = self.power()?;
+            value = value.checked_mul(right).ok_or("is too large")?;
+        }
+        Ok(value)
+    }
+
+    /// power := atom ('^' power)?
+    fn power(&mut self) -> Result<i128, String> {
+        let base = self.atom()?;
+        if self.operator(b"^").is_none() {
+            return Ok(base);
+        }
+
+        let exponent = self.power()?;
+        u32::try_from(exponent)
+            .ok()
+            .and_then(|exponent| base.checked_pow(exponent))
+            .ok_or_else(|| "is too large".to_string())
+    }
+
+    /// atom := literal | '(' expression ')'
+    fn atom(&mut self) -> Result<i128, String> {
+        if self.operator(b"(").is_some() {
+            let value = self.expression()?;
+            return match self.operator(b")") {
+                Some(_) => Ok(value),
+                None => Err(format!(
+                    "is missing a ')' at position {}",
+                    self.position + 1
+                )),
+            };
+        }
+
+        let start = self.position;
+        while self
+            .input
+            .get(self.position)
+            .is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'_' || *byte == b'.')
+        {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(format!("is missing a number at position {}", start + 1));
+        }
+
+        let literal = std::str::from_utf8(&self.input[start..self.position])
+            .expect("the literal only holds ASCII characters");
+        parse_literal(literal)
+            .map(i128::from)
+            .map_err(|reason| format!("contains '{}', which {}", literal, reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn underscores_and_scientific_shorthand_are_accepted() {
+        let cases = [
+            ("0", 0),
+            ("42", 42),
+            ("  7 ", 7),
+            ("1_000_000", 1_000_000),
+            ("1__0", 10),
+            ("1e6", 1_000_000),
+            ("25E4", 250_000),
+            ("3e0", 3),
+            ("1_5e1_0", 150_000_000_000),
+            ("18446744073709551615", u64::MAX),
+            ("1e19", 10_000_000_000_000_000_000),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_index(input).unwrap(), expected, "'{}'", input);
+        }
+    }
+
+    #[test]
+    fn malformed_literals_are_rejected() {
+        for input in [
+            "",
+            "_",
+            "abc",
+            "1.5e3",
+            "1.0",
+            "e6",
+            "1e",
+            "1e-3",
+            "1e+3",
+            "0x10",
+            "1e20",
+            "2e19",
+            "18446744073709551616",
+        ] {
+            assert!(
+                matches!(parse_index(input), Err(FibError::InvalidIndex(_))),
+                "'{}' was accepted",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn index_expressions_are_evaluated() {
+        let cases = [
+            ("2^32", 1 << 32),
+            ("10^9+7", 1_000_000_007),
+            ("3*10^8", 300_000_000),
+            ("2^3^2", 512),
+            ("(1 + 2) * 3", 9),
+            ("2^64-1", u64::MAX),
+            ("1e3 - 1_000", 0),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_index(input).unwrap(), expected, "'{}'", input);
+        }
+        for input in ["2^64", "1-2", "(1+2", "2*", "2 3+1"] {
+            assert!(
+                matches!(parse_index(input), Err(FibError::InvalidIndex(_))),
+                "'{}' was accepted",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_values_do_not_hide_the_valid_indices() {
+        let values = parse_indices(" 5 eight\t13 ");
+        assert_eq!(values.len(), 3);
+        assert_eq!((values[0].0, values[0].1.as_ref().ok()), (1, Some(&5)));
+        assert_eq!(values[1].0, 2);
+        assert!(matches!(values[1].1, Err(FibError::InvalidIndex(_))));
+        assert_eq!((values[2].0, values[2].1.as_ref().ok()), (3, Some(&13)));
+        assert!(parse_indices("  ").is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+/// The default number of bytes a [`BufferPool`] keeps between conversions (256 MiB).
+pub const DEFAULT_BUFFER_POOL_BUDGET: u64 = 256 << 20;
+
+/// Byte buffers that are kept between conversions, so the large allocations of one
+/// conversion are reused by the next one of a similar size.
+///
+/// Converting a number with millions of digits allocates a copy of its bytes and a buffer
+/// for its digits, and freeing them returns the memory to the system, so the next
+/// conversion has to fault it in again. The pool keeps the buffers instead, as long as
+/// they fit into its budget together. The products of a calculation are allocated by
+/// num-bigint, which cannot be handed a buffer, so they are not pooled.
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+    max_bytes: u64,
+}
+
+impl BufferPool {
+    /// Creates an empty pool that keeps at most `max_bytes` bytes of buffers.
+    ///
+    /// A pool with a budget of 0 keeps nothing, so every buffer is allocated anew.
+    pub fn new(max_bytes: u64) -> BufferPool {
+        BufferPool {
+            buffers: Vec::new(),
+            max_bytes,
+        }
+    }
+
+    /// Returns an empty buffer with room for at least `capacity` bytes.
+    ///
+    /// The smallest kept buffer that is large enough is reused, otherwise a new one is
+    /// allocated, so a small request never takes the buffer of a large one.
+    pub fn take(&mut self, capacity: usize) -> Vec<u8> {
+        let best = self
+            .buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.capacity() >= capacity)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(position, _)| position);
+        match best {
+            Some(position) => self.buffers.swap_remove(position),
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Hands a buffer back to the pool to be reused by [`BufferPool::take`].
+    ///
+    /// The buffer is cleared and kept if it fits into the budget, where the smallest kept
+    /// buffers are dropped to make room for a larger one. Buffers larger than the whole
+    /// budget are dropped right away.
+    pub fn recycle(&mut self, mut buffer: Vec<u8>) {
+        let size = buffer.capacity() as u64;
+        if size == 0 || size > self.max_bytes {
+            return;
+        }
+
+        buffer.clear();
+        self.buffers.push(buffer);
+        self.buffers
+            .sort_unstable_by_key(|buffer| std::cmp::Reverse(buffer.capacity()));
+        while self.bytes() > self.max_bytes {
+            self.buffers.pop();
+        }
+    }
+
+    /// Returns the number of bytes the kept buffers take.
+    pub fn bytes(&self) -> u64 {
+        self.buffers
+            .iter()
+            .map(|buffer| buffer.capacity() as u64)
+            .sum()
+    }
+
+    /// Returns the number of kept buffers.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Checks whether no buffer is kept.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Drops every kept buffer, returning their memory to the system.
+    ///
+    /// # Returns
+    /// The number of bytes that were freed.
+    pub fn clear(&mut self) -> u64 {
+        let bytes = self.bytes();
+        self.buffers.clear();
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_smallest_fitting_buffer_is_reused() {
+        let mut pool = BufferPool::new(1 << 20);
+        for capacity in [1000, 100, 10_000] {
+            pool.recycle(vec![7; capacity]);
+        }
+        assert_eq!((pool.len(), pool.bytes()), (3, 11_100));
+
+        let buffer = pool.take(500);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), 1000);
+        assert_eq!(pool.take(20_000).capacity(), 20_000);
+        assert_eq!(pool.take(50).capacity(), 100);
+        assert_eq!((pool.len(), pool.bytes()), (1, 10_000));
+    }
+
+    #[test]
+    fn the_budget_drops_the_smallest_buffers() {
+        let mut pool = BufferPool::new(10_000);
+        pool.recycle(Vec::with_capacity(4000));
+        pool.recycle(Vec::with_capacity(3000));
+        pool.recycle(Vec::with_capacity(5000));
+        assert_eq!((pool.len(), pool.bytes()), (2, 9000));
+
+        // Larger than the whole budget, or nothing to keep
+        pool.recycle(Vec::with_capacity(10_001));
+        pool.recycle(Vec::new());
+        assert_eq!(pool.len(), 2);
+
+        assert_eq!(pool.clear(), 9000);
+        assert!(pool.is_empty());
+
+        let mut none = BufferPool::new(0);
+        none.recycle(Vec::with_capacity(1));
+        assert!(none.is_empty());
+    }
+}
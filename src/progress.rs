@@ -0,0 +1,177 @@
+use crate::style::{ColorChoice, Style};
+use fibonacci_sequence::{format_bytes, CostModel, DoublingState, ValueFormat};
+use num_bigint::BigUint;
+use std::{
+    io::{self, IsTerminal, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often the progress line is updated.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Calculates a Fibonacci number with fast doubling while printing its progress to stderr.
+///
+/// A separate thread prints the estimated percentage of the work that is done together with
+/// the elapsed time. On a terminal the line is updated in place and cleared once the
+/// calculation has finished, otherwise a line is printed whenever the percentage changes.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to calculate.
+/// * `value_format` - The format of the index and the elapsed time in the progress line.
+/// * `color` - When to color the progress line.
+///
+/// # Returns
+/// A tuple with the Fibonacci number and the duration of the calculation.
+pub fn calculate_with_progress(
+    index: u64,
+    value_format: &ValueFormat,
+    color: ColorChoice,
+) -> (BigUint, Duration) {
+    let mut state = DoublingState::new(index);
+    let remaining_bits = AtomicU32::new(state.remaining_bits());
+    let start_time = Instant::now();
+
+    let duration = thread::scope(|scope| {
+        let (finished, done) = mpsc::channel::<()>();
+        scope.spawn(|| {
+            report(
+                index,
+                &remaining_bits,
+                start_time,
+                done,
+                value_format,
+                color,
+            )
+        });
+
+        while !state.is_finished() {
+            state.step();
+            remaining_bits.store(state.remaining_bits(), Ordering::SeqCst);
+        }
+        let duration = start_time.elapsed();
+        drop(finished);
+        duration
+    });
+    (state.into_value(), duration)
+}
+
+/// Prints the progress line until the calculation disconnects from `done`.
+fn report(
+    index: u64,
+    remaining_bits: &AtomicU32,
+    start_time: Instant,
+    done: mpsc::Receiver<()>,
+    value_format: &ValueFormat,
+    color: ColorChoice,
+) {
+    let style = Style::stderr(color);
+    let terminal = io::stderr().is_terminal();
+    let model = CostModel::default();
+    let mut last_percent = None;
+
+    while let Err(RecvTimeoutError::Timeout) = done.recv_timeout(TICK) {
+        let fraction = model.fraction_done(index, remaining_bits.load(Ordering::SeqCst));
+        let percent = (fraction * 100.0).floor() as u32;
+        if !terminal && last_percent == Some(percent) {
+            continue;
+        }
+        last_percent = Some(percent);
+
+        let line = format!(
+            "Calculating F({}): {:>3}% done, {} elapsed",
+            style.number(value_format.count(index)),
+            percent,
+            style.duration(value_format.duration(start_time.elapsed()))
+        );
+        let mut stderr = io::stderr().lock();
+        // The progress line is only informative, so failing to print it is ignored
+        let _ = if terminal {
+            write!(stderr, "\r\x1b[2K{}", line)
+        } else {
+            writeln!(stderr, "{}", line)
+        };
+        let _ = stderr.flush();
+    }
+
+    if terminal && last_percent.is_some() {
+        let _ = write!(io::stderr().lock(), "\r\x1b[2K");
+    }
+}
+
+/// Reports the progress of writing a large file on stderr.
+///
+/// Nothing is printed for files that are written within the first tick, so only writes
+/// that take noticeably long report their progress. Like [`calculate_with_progress`] the
+/// line is updated in place on a terminal, otherwise a line is printed whenever the
+/// percentage changes.
+pub struct WriteProgress<'a> {
+    path: &'a Path,
+    /// The expected size of the file, which may be an estimate.
+    total: u64,
+    start_time: Instant,
+    last_update: Option<Instant>,
+    last_percent: Option<u64>,
+    terminal: bool,
+    style: Style,
+}
+
+impl<'a> WriteProgress<'a> {
+    /// Starts reporting the progress of writing about `total` bytes to `path`.
+    pub fn new(path: &'a Path, total: u64, color: ColorChoice) -> WriteProgress<'a> {
+        WriteProgress {
+            path,
+            total: total.max(1),
+            start_time: Instant::now(),
+            last_update: None,
+            last_percent: None,
+            terminal: io::stderr().is_terminal(),
+            style: Style::stderr(color),
+        }
+    }
+
+    /// Prints the progress line if a tick has passed since the last one.
+    pub fn update(&mut self, written: u64) {
+        let now = Instant::now();
+        let since = self.last_update.unwrap_or(self.start_time);
+        if now.duration_since(since) < TICK {
+            return;
+        }
+        self.last_update = Some(now);
+
+        // The total may be an estimate, so the line never claims to be done early
+        let percent = (written * 100 / self.total).min(99);
+        if !self.terminal && self.last_percent == Some(percent) {
+            return;
+        }
+        self.last_percent = Some(percent);
+
+        let line = format!(
+            "Writing {}: {:>2}% done, {} of about {}",
+            self.path.display(),
+            percent,
+            self.style.number(format_bytes(written)),
+            self.style.number(format_bytes(self.total))
+        );
+        let mut stderr = io::stderr().lock();
+        // The progress line is only informative, so failing to print it is ignored
+        let _ = if self.terminal {
+            write!(stderr, "\r\x1b[2K{}", line)
+        } else {
+            writeln!(stderr, "{}", line)
+        };
+        let _ = stderr.flush();
+    }
+
+    /// Clears the progress line from the terminal once the file is written.
+    pub fn finish(&self) {
+        if self.terminal && self.last_percent.is_some() {
+            let _ = write!(io::stderr().lock(), "\r\x1b[2K");
+        }
+    }
+}
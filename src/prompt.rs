@@ -0,0 +1,193 @@
+use fibonacci_sequence::{complete_input, parse_input, Input};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, CompletionType, Config, Context, EditMode,
+    Editor, Helper,
+};
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+/// The number of recently used indices that are offered for completion.
+const RECENT_INDICES: usize = 20;
+
+/// Reads lines from the user for the interactive prompt.
+///
+/// When stdin is a terminal that rustyline supports, lines are read with Emacs-style line
+/// editing, Tab completion and a history that is persisted across sessions. Otherwise, for example when
+/// input is piped in, lines are read with a plain `read_line` from stdin.
+pub enum LineReader {
+    Editor {
+        editor: Box<Editor<ReplHelper, DefaultHistory>>,
+        history_path: Option<PathBuf>,
+    },
+    Plain,
+}
+
+impl LineReader {
+    /// Creates a line reader, falling back to plain reads if line editing is unavailable.
+    pub fn new() -> LineReader {
+        if !io::stdin().is_terminal() {
+            return LineReader::Plain;
+        }
+
+        let config = Config::builder()
+            .edit_mode(EditMode::Emacs)
+            .history_ignore_dups(true)
+            .map(|builder| builder.completion_type(CompletionType::Circular).build());
+        let Ok(mut editor) = config.and_then(Editor::with_config) else {
+            return LineReader::Plain;
+        };
+        editor.set_helper(Some(ReplHelper::default()));
+
+        // A missing history file is expected on the first run
+        let history_path = history_path();
+        if let Some(path) = &history_path {
+            let _ = editor.load_history(path);
+        }
+
+        LineReader::Editor {
+            editor: Box::new(editor),
+            history_path,
+        }
+    }
+
+    /// Prompts the user and reads a single line.
+    ///
+    /// # Arguments
+    /// * `prompt` - The prompt printed before reading.
+    ///
+    /// Ctrl+C discards the current line and prompts again, consecutive duplicate lines are
+    /// only added to the history once.
+    ///
+    /// # Returns
+    /// An `Option<String>` with the line read, or `None` if the user ended the input
+    /// with Ctrl+D, the piped input ended or it could not be read.
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        match self {
+            LineReader::Editor { editor, .. } => loop {
+                match editor.readline(prompt) {
+                    Ok(line) => {
+                        if !line.trim().is_empty() {
+                            let _ = editor.add_history_entry(line.as_str());
+                        }
+                        if let Some(helper) = editor.helper_mut() {
+                            helper.remember(&line);
+                        }
+                        return Some(line);
+                    }
+                    Err(ReadlineError::Interrupted) => continue,
+                    Err(ReadlineError::Eof) => return None,
+                    Err(error) => {
+                        println!("Error: {}", error);
+                        return None;
+                    }
+                }
+            },
+            LineReader::Plain => {
+                print!("{}", prompt);
+                let _ = io::stdout().flush();
+
+                // Zero bytes means the end of the input, like Ctrl+D or the end of a pipe
+                let mut input = String::new();
+                match io::stdin().read_line(&mut input) {
+                    Ok(0) => {
+                        println!();
+                        None
+                    }
+                    Ok(_) => Some(input),
+                    Err(error) => {
+                        println!("Error: could not read the input ({})", error);
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for LineReader {
+    fn drop(&mut self) {
+        if let LineReader::Editor {
+            editor,
+            history_path: Some(path),
+        } = self
+        {
+            // Failing to persist the history should not affect the result of the session
+            if let Some(directory) = path.parent() {
+                let _ = fs::create_dir_all(directory);
+            }
+            let _ = editor.save_history(path);
+        }
+    }
+}
+
+/// Completes colon-commands, their values and recently used indices at the prompt.
+#[derive(Default)]
+pub struct ReplHelper {
+    /// The indices of the previous lines, the most recent first
+    recent: Vec<u64>,
+}
+
+impl ReplHelper {
+    /// Remembers the indices of an entered line to offer them for completion.
+    fn remember(&mut self, line: &str) {
+        let indices = match parse_input(line) {
+            Input::Index(index) => vec![index],
+            Input::Range(start, end) | Input::Compare(start, end) => vec![end, start],
+            Input::Indices(values) => values
+                .into_iter()
+                .filter_map(|(_, value)| value.ok())
+                .collect(),
+            _ => return,
+        };
+
+        for index in indices {
+            self.recent.retain(|&recent| recent != index);
+            self.recent.insert(0, index);
+        }
+        self.recent.truncate(RECENT_INDICES);
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_input(&line[..pos], &self.recent))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Returns the path of the file the prompt history is persisted to.
+///
+/// This is `$XDG_DATA_HOME/fibonacci_sequence/history`, falling back to
+/// `~/.local/share/fibonacci_sequence/history` when `XDG_DATA_HOME` is not set.
+fn history_path() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+
+    Some(data_home.join("fibonacci_sequence").join("history"))
+}
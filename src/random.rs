@@ -0,0 +1,57 @@
+/// A small, fast and deterministic pseudo-random number generator (SplitMix64).
+///
+/// The same seed always produces the same sequence on every platform, which makes
+/// benchmarks and stress runs reproducible across machines. It is not suitable for
+/// anything security related.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    /// Returns the next pseudo-random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns the next pseudo-random `f64` in the range `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_sequence_is_the_same_on_every_platform() {
+        // The first outputs of SplitMix64 for the seed 0
+        let mut random = SplitMix64::new(0);
+        assert_eq!(random.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(random.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(random.next_u64(), 0x06c4_5d18_8009_454f);
+
+        let mut again = SplitMix64::new(0);
+        again.next_u64();
+        assert_eq!(again.clone().next_u64(), again.next_u64());
+    }
+
+    #[test]
+    fn floats_stay_below_one() {
+        let mut random = SplitMix64::new(7);
+        let samples: Vec<f64> = (0..10_000).map(|_| random.next_f64()).collect();
+        assert!(samples.iter().all(|sample| (0.0..1.0).contains(sample)));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 0.5).abs() < 0.02, "mean {}", mean);
+    }
+}
@@ -0,0 +1,474 @@
+use crate::{
+    checkpoint::{checkpoint_path, RangeCheckpoint},
+    write_decimal, FibError, FibIter,
+};
+use num_bigint::BigUint;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, LineWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Formats a single line of range output in the `index value` format of OEIS b-files.
+pub fn range_line(index: u64, value: &BigUint) -> String {
+    format!("{} {}\n", index, value)
+}
+
+/// The smallest number of Fibonacci numbers [`fib_range_par`] hands to a single thread, as
+/// smaller chunks spend more time on their first pair than on the additions.
+#[cfg(feature = "parallel")]
+const MIN_CHUNK_LEN: u64 = 256;
+
+/// Calculates the Fibonacci numbers `F(start)` to `F(end)`.
+///
+/// Only `F(start)` is calculated with fast doubling, every following number is a single
+/// addition of the previous two.
+///
+/// # Arguments
+/// * `start` - The index of the first Fibonacci number.
+/// * `end` - The index of the last Fibonacci number (inclusive).
+///
+/// # Returns
+/// A `Vec<BigUint>` with the Fibonacci numbers in the order of their indices, which is
+/// empty if `start` is larger than `end`.
+pub fn fib_range(start: u64, end: u64) -> Vec<BigUint> {
+    if start > end {
+        return Vec::new();
+    }
+    FibIter::new(start)
+        .take_while(|(index, _)| *index <= end)
+        .map(|(_, value)| value)
+        .collect()
+}
+
+/// Calculates the Fibonacci numbers `F(start)` to `F(end)` on every thread of rayon.
+///
+/// The range is split into contiguous chunks of at least [`MIN_CHUNK_LEN`] numbers, a few
+/// for every thread so uneven chunks even out. Every chunk starts from its own pair
+/// calculated with fast doubling and is filled forward by additions like [`fib_range`],
+/// and the chunks are joined in order, so the result is exactly the one of `fib_range`.
+///
+/// # Arguments
+/// * `start` - The index of the first Fibonacci number.
+/// * `end` - The index of the last Fibonacci number (inclusive).
+///
+/// # Returns
+/// A `Vec<BigUint>` with the Fibonacci numbers in the order of their indices, which is
+/// empty if `start` is larger than `end`.
+#[cfg(feature = "parallel")]
+pub fn fib_range_par(start: u64, end: u64) -> Vec<BigUint> {
+    if start > end {
+        return Vec::new();
+    }
+    let len = end - start;
+    let chunks = (rayon::current_num_threads() as u64 * 4).clamp(1, len / MIN_CHUNK_LEN + 1);
+    let chunk_len = len / chunks + 1;
+
+    (0..chunks)
+        .into_par_iter()
+        .flat_map_iter(|chunk| {
+            // The last chunk may be shorter, and the chunks past the end are empty
+            let chunk_start = start.saturating_add(chunk * chunk_len);
+            let chunk_end = chunk_start.saturating_add(chunk_len - 1).min(end);
+            fib_range(chunk_start, chunk_end)
+        })
+        .collect()
+}
+
+/// Calculates the Fibonacci numbers `F(start)` to `F(end)` like [`fib_range`], as without the
+/// `parallel` feature there are no threads to split the range over.
+///
+/// # Returns
+/// A `Vec<BigUint>` with the Fibonacci numbers in the order of their indices, which is
+/// empty if `start` is larger than `end`.
+#[cfg(not(feature = "parallel"))]
+pub fn fib_range_par(start: u64, end: u64) -> Vec<BigUint> {
+    fib_range(start, end)
+}
+
+/// Writes the Fibonacci numbers `F(start)` to `F(end)` as `index value` lines.
+///
+/// # Arguments
+/// * `start` - The index of the first Fibonacci number to write.
+/// * `end` - The index of the last Fibonacci number to write (inclusive).
+/// * `writer` - The writer the lines are written to.
+///
+/// # Returns
+/// A `Result<(), FibError>` which is an error if writing failed.
+pub fn write_range<W: Write>(start: u64, end: u64, writer: &mut W) -> Result<(), FibError> {
+    for (index, value) in FibIter::new(start).take_while(|(index, _)| *index <= end) {
+        writer.write_all(range_line(index, &value).as_bytes())?;
+        if index == end {
+            break;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the Fibonacci numbers `F(start)` to `F(end)`, one value per line, as they are
+/// calculated.
+///
+/// Unlike [`fib_range`] nothing is collected: only the two running numbers of the
+/// recurrence are held in memory and every value is written right away with
+/// [`write_decimal`], so ranges far larger than the memory can be dumped to a file.
+///
+/// # Arguments
+/// * `start` - The index of the first Fibonacci number to write.
+/// * `end` - The index of the last Fibonacci number to write (inclusive).
+/// * `writer` - The writer the lines are written to, which is best buffered.
+///
+/// # Returns
+/// A `Result<(), FibError>` which is an error if writing failed.
+pub fn stream_range<W: Write>(start: u64, end: u64, writer: &mut W) -> Result<(), FibError> {
+    for (index, value) in FibIter::new(start).take_while(|(index, _)| *index <= end) {
+        write_decimal(&value, writer)?;
+        writer.write_all(b"\n")?;
+        if index == end {
+            break;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the Fibonacci numbers `F(start)` to `F(end)` to a file with periodic checkpoints.
+///
+/// Every `checkpoint_every` indices the output is flushed to disk and a checkpoint is
+/// written next to the output file (with an added `.ckpt` extension). When `resume` is set,
+/// the checkpoint is loaded, the tail of the output file is verified against it, anything
+/// written after the checkpoint is discarded and the computation continues from there.
+/// The checkpoint file is removed once the range has been written completely.
+///
+/// # Arguments
+/// * `start` - The index of the first Fibonacci number to write.
+/// * `end` - The index of the last Fibonacci number to write (inclusive).
+/// * `path` - The path of the output file.
+/// * `checkpoint_every` - The number of indices between two checkpoints.
+/// * `resume` - Whether to continue from an existing checkpoint.
+///
+/// # Returns
+/// A `Result<(), FibError>` which is an error if writing failed or if the checkpoint is
+/// corrupt or does not match the output file.
+pub fn write_range_to_file(
+    start: u64,
+    end: u64,
+    path: &Path,
+    checkpoint_every: u64,
+    resume: bool,
+) -> Result<(), FibError> {
+    let checkpoint_every = checkpoint_every.max(1);
+    let checkpoint_path = checkpoint_path(path);
+
+    let (mut iter, file, mut output_len) = if resume {
+        let checkpoint = match RangeCheckpoint::read(&checkpoint_path) {
+            Err(FibError::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(FibError::CheckpointMismatch(format!(
+                    "no checkpoint found at '{}'",
+                    checkpoint_path.display()
+                )));
+            }
+            result => result?,
+        };
+
+        if (checkpoint.start, checkpoint.end) != (start, end) {
+            return Err(FibError::CheckpointMismatch(format!(
+                "checkpoint belongs to the range {} to {}, not {} to {}",
+                checkpoint.start, checkpoint.end, start, end
+            )));
+        }
+
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        verify_output_tail(&mut file, &checkpoint)?;
+
+        // Discard output that was written after the checkpoint
+        file.set_len(checkpoint.output_len)?;
+        file.seek(SeekFrom::End(0))?;
+
+        let following = &checkpoint.current + &checkpoint.next;
+        let iter = FibIter::from_pair(checkpoint.last_index + 1, checkpoint.next, following);
+        if checkpoint.last_index == end {
+            fs::remove_file(&checkpoint_path)?;
+            return Ok(());
+        }
+        (iter, file, checkpoint.output_len)
+    } else {
+        (FibIter::new(start), File::create(path)?, 0)
+    };
+
+    let mut writer = BufWriter::new(file);
+
+    while let Some((index, value)) = iter.next() {
+        let line = range_line(index, &value);
+        writer.write_all(line.as_bytes())?;
+        output_len += line.len() as u64;
+
+        if (index - start + 1).is_multiple_of(checkpoint_every) || index == end {
+            // The output must be on disk before the checkpoint refers to it
+            writer.flush()?;
+            writer.get_ref().sync_data()?;
+
+            RangeCheckpoint {
+                start,
+                end,
+                last_index: index,
+                output_len,
+                current: value,
+                next: iter.pair().0.clone(),
+            }
+            .write(&checkpoint_path)?;
+        }
+
+        if index == end {
+            break;
+        }
+    }
+
+    fs::remove_file(&checkpoint_path)?;
+    Ok(())
+}
+
+/// Appends a sequence of Fibonacci numbers to a file as `index value` lines as they arrive.
+///
+/// Every line reaches the file as soon as it is complete, so a long generation can be
+/// followed while it runs and an interrupted one keeps every finished line, without the
+/// checkpoints of [`write_range_to_file`]. The file is created if it does not exist.
+///
+/// # Arguments
+/// * `sequence` - The `(index, value)` pairs to append.
+/// * `path` - The path of the output file.
+///
+/// # Returns
+/// A `Result<u64, FibError>` with the number of appended lines, or the error of the file.
+pub fn append_range(
+    sequence: impl IntoIterator<Item = (u64, BigUint)>,
+    path: &Path,
+) -> Result<u64, FibError> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut writer = LineWriter::new(file);
+    let mut lines = 0;
+    for (index, value) in sequence {
+        writer.write_all(range_line(index, &value).as_bytes())?;
+        lines += 1;
+    }
+    writer.flush()?;
+    Ok(lines)
+}
+
+/// Verifies that the output file ends with the line recorded in the checkpoint.
+///
+/// The line for the checkpoint's last index must end exactly at the recorded output length
+/// and must be preceded by a line break, or be the first line of the range.
+fn verify_output_tail(file: &mut File, checkpoint: &RangeCheckpoint) -> Result<(), FibError> {
+    let expected = range_line(checkpoint.last_index, &checkpoint.current);
+    let expected_len = expected.len() as u64;
+    let file_len = file.metadata()?.len();
+
+    if file_len < checkpoint.output_len || checkpoint.output_len < expected_len {
+        return Err(FibError::CheckpointMismatch(format!(
+            "output file is shorter than the {} bytes recorded in the checkpoint",
+            checkpoint.output_len
+        )));
+    }
+
+    let line_start = checkpoint.output_len - expected_len;
+    let is_first_line = line_start == 0 && checkpoint.last_index == checkpoint.start;
+
+    // Read the expected line together with the line break before it
+    let read_start = line_start.saturating_sub(1);
+    let mut tail = vec![0; (checkpoint.output_len - read_start) as usize];
+    file.seek(SeekFrom::Start(read_start))?;
+    file.read_exact(&mut tail)?;
+
+    let (separator, line) = tail.split_at(tail.len() - expected.len());
+    if line != expected.as_bytes() || !(is_first_line || separator == b"\n") {
+        return Err(FibError::CheckpointMismatch(format!(
+            "output file does not end with F({}) at the checkpoint position",
+            checkpoint.last_index
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+    use std::path::PathBuf;
+
+    const START: u64 = 90;
+    const END: u64 = 400;
+
+    /// Returns an empty directory of its own for every test, as they run in parallel.
+    fn scratch_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("fibonacci-range-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// Returns the output of an uninterrupted run over the range.
+    fn complete_output() -> Vec<u8> {
+        let mut output = Vec::new();
+        write_range(START, END, &mut output).unwrap();
+        output
+    }
+
+    /// Leaves the files an interrupted run would leave after its checkpoint at `last_index`,
+    /// followed by `after` in the output.
+    fn interrupt(path: &Path, last_index: u64, after: &[u8]) {
+        let complete = complete_output();
+        let output_len: usize = (START..=last_index)
+            .map(|index| range_line(index, &calculate_fibonacci(index).unwrap()).len())
+            .sum();
+        let mut output = complete[..output_len].to_vec();
+        output.extend_from_slice(after);
+        fs::write(path, output).unwrap();
+
+        RangeCheckpoint {
+            start: START,
+            end: END,
+            last_index,
+            output_len: output_len as u64,
+            current: calculate_fibonacci(last_index).unwrap(),
+            next: calculate_fibonacci(last_index + 1).unwrap(),
+        }
+        .write(&checkpoint_path(path))
+        .unwrap();
+    }
+
+    #[test]
+    fn uninterrupted_run_writes_the_range_and_removes_its_checkpoint() {
+        let path = scratch_directory("complete").join("range.txt");
+        write_range_to_file(START, END, &path, 25, false).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete_output());
+        assert!(!checkpoint_path(&path).exists());
+    }
+
+    #[test]
+    fn parallel_chunks_join_to_the_sequential_range() {
+        let range = fib_range(0, 500);
+        assert_eq!(range.len(), 501);
+        assert_eq!(range[500], calculate_fibonacci(500).unwrap());
+        for (start, end) in [(0, 500), (0, 0), (37, 500), (300, 301), (501, 500)] {
+            assert_eq!(
+                fib_range_par(start, end),
+                fib_range(start, end),
+                "F({}) to F({})",
+                start,
+                end
+            );
+        }
+    }
+
+    #[test]
+    fn streamed_ranges_match_the_collected_ones() {
+        for (start, end) in [(0, 500), (7, 7), (START, END)] {
+            let mut streamed = Vec::new();
+            stream_range(start, end, &mut streamed).unwrap();
+            let expected: String = fib_range(start, end)
+                .iter()
+                .map(|value| format!("{}\n", value))
+                .collect();
+            assert!(
+                streamed == expected.as_bytes(),
+                "F({}) to F({})",
+                start,
+                end
+            );
+        }
+    }
+
+    #[test]
+    fn appending_continues_an_existing_file() {
+        let path = scratch_directory("append").join("range.txt");
+        let numbers = |indices: std::ops::RangeInclusive<u64>| {
+            indices.map(|index| (index, calculate_fibonacci(index).unwrap()))
+        };
+        assert_eq!(append_range(numbers(START..=200), &path).unwrap(), 111);
+        assert_eq!(append_range(numbers(201..=END), &path).unwrap(), END - 200);
+        assert_eq!(fs::read(&path).unwrap(), complete_output());
+    }
+
+    #[test]
+    fn resuming_after_a_partial_line_writes_an_identical_file() {
+        let path = scratch_directory("partial-line").join("range.txt");
+        interrupt(&path, 200, b"201 4539736941");
+        write_range_to_file(START, END, &path, 25, true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete_output());
+        assert!(!checkpoint_path(&path).exists());
+    }
+
+    #[test]
+    fn resuming_discards_lines_written_after_the_checkpoint() {
+        let path = scratch_directory("later-lines").join("range.txt");
+        let later: String = (91..=120)
+            .map(|index| range_line(index, &calculate_fibonacci(index).unwrap()))
+            .collect();
+        interrupt(&path, START, later.as_bytes());
+        write_range_to_file(START, END, &path, 7, true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete_output());
+    }
+
+    #[test]
+    fn resuming_ignores_a_partially_written_checkpoint() {
+        let path = scratch_directory("partial-checkpoint").join("range.txt");
+        interrupt(&path, 300, b"");
+
+        // A crash while the next checkpoint was written leaves only its temporary file
+        let next = RangeCheckpoint::read(&checkpoint_path(&path))
+            .unwrap()
+            .to_bytes();
+        let mut temp_path = checkpoint_path(&path).into_os_string();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, &next[..next.len() / 2]).unwrap();
+
+        write_range_to_file(START, END, &path, 25, true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete_output());
+    }
+
+    #[test]
+    fn resuming_the_last_checkpoint_only_removes_it() {
+        let path = scratch_directory("finished").join("range.txt");
+        interrupt(&path, END, b"");
+        write_range_to_file(START, END, &path, 25, true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), complete_output());
+        assert!(!checkpoint_path(&path).exists());
+    }
+
+    #[test]
+    fn truncated_checkpoint_is_rejected_without_touching_the_output() {
+        let path = scratch_directory("truncated").join("range.txt");
+        interrupt(&path, 200, b"201 4");
+        let checkpoint = fs::read(checkpoint_path(&path)).unwrap();
+        fs::write(checkpoint_path(&path), &checkpoint[..checkpoint.len() - 3]).unwrap();
+        let output = fs::read(&path).unwrap();
+
+        let result = write_range_to_file(START, END, &path, 25, true);
+        assert!(matches!(result, Err(FibError::CorruptCheckpoint(_))));
+        assert_eq!(fs::read(&path).unwrap(), output);
+    }
+
+    #[test]
+    fn truncated_output_is_rejected() {
+        let path = scratch_directory("short-output").join("range.txt");
+        interrupt(&path, 200, b"");
+        let output = fs::read(&path).unwrap();
+        fs::write(&path, &output[..output.len() - 10]).unwrap();
+
+        let result = write_range_to_file(START, END, &path, 25, true);
+        assert!(matches!(result, Err(FibError::CheckpointMismatch(_))));
+    }
+
+    #[test]
+    fn checkpoint_of_another_range_is_rejected() {
+        let path = scratch_directory("other-range").join("range.txt");
+        interrupt(&path, 200, b"");
+        let result = write_range_to_file(START, END + 1, &path, 25, true);
+        assert!(matches!(result, Err(FibError::CheckpointMismatch(_))));
+    }
+}
@@ -0,0 +1,735 @@
+use crate::{
+    group_digits_with, parse_index, parse_indices, to_decimal_string_in, Algorithm, BufferPool,
+    FibError, FibRequest, Grouping, Notation, Notify, OutputFormat, Setting, ValueFormat,
+};
+use num_bigint::BigUint;
+use std::path::PathBuf;
+
+/// The help text printed for `:help` and unknown commands in the interactive prompt.
+pub const REPL_HELP: &str = "\
+Available commands:
+  cmp <m> <n>          compare F(m) and F(n)
+  l<n>                 calculate the Lucas number L(n)
+  z<n>                 calculate F(n) at a signed index, like z-10
+  <n> mod <m>          calculate F(n) modulo m
+  :base <2-36>         show the last result in another base
+  :sci                 show the last result in scientific notation
+  :full                show all digits of the last result
+  :group               show all digits of the last result with separators
+  :copy [full]         copy the last result to the clipboard, or all of its digits
+  :history             list the results of the session
+  :show <N>            print result #N of the history again
+  :export <N> <path>   write all digits of result #N to a file
+  :precision <1-50>    set the significant digits of scientific notation
+  :format <text|json|latex>
+                       set the output format of results
+  :threads <N>         set the number of worker threads
+  :threshold <1eN|none|always>
+                       show results from 10^N on in scientific notation
+  :algorithm <name>    use auto, fast-doubling, matrix or iterative
+  :max-print-digits <N|none>
+                       shorten printed results with more than N digits
+  :pager <on|off>      show results longer than a screen in a pager
+  :grouping <style>    group digits with comma, period, space, thin-space,
+                       underscore, indian or none
+  :notify <kind>       announce long calculations with bell, desktop or none
+  :notation <name>     write large results in scientific or engineering notation
+  :superscript <on|off>
+                       write scientific notation like 4.3466 × 10²⁰⁸
+  :set [name value]    show all settings, or change one
+  :transcript on <path>
+                       log every input and result summary to a file
+  :transcript off      stop logging to the transcript
+  :clear-buffers       free the conversion buffers kept for the next result
+  :help                show this help
+  :quit                quit the prompt";
+
+/// The colon-commands of the interactive prompt besides the settings in [`Setting::NAMES`].
+///
+/// Only these names and the settings are accepted as commands, and they are the names that
+/// are completed after `:`.
+pub const REPL_COMMANDS: [&str; 13] = [
+    "base",
+    "sci",
+    "full",
+    "group",
+    "copy",
+    "history",
+    "show",
+    "export",
+    "set",
+    "transcript",
+    "clear-buffers",
+    "help",
+    "quit",
+];
+
+/// A format the most recently calculated Fibonacci number can be re-rendered in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    /// Show the last result in the given base (2 to 36).
+    Base(u32),
+    /// Show the last result in scientific notation.
+    Sci,
+    /// Show all digits of the last result, regardless of the digit limit.
+    Full,
+    /// Show all digits of the last result with separators in the configured style.
+    Group,
+}
+
+impl View {
+    /// Renders a Fibonacci number in the format requested by the view.
+    ///
+    /// # Arguments
+    /// * `value` - The Fibonacci number to render.
+    /// * `value_format` - The current rendering options, which provide the precision of
+    ///   scientific notation and the digit limit of other bases.
+    ///
+    /// # Returns
+    /// A `String` with the rendered number.
+    pub fn render(self, value: &BigUint, value_format: &ValueFormat) -> String {
+        self.render_in(value, value_format, &mut BufferPool::new(0))
+    }
+
+    /// Renders a Fibonacci number like [`View::render`], taking the buffers of the decimal
+    /// conversion from `pool`.
+    pub fn render_in(
+        self,
+        value: &BigUint,
+        value_format: &ValueFormat,
+        pool: &mut BufferPool,
+    ) -> String {
+        match self {
+            View::Base(base) => ValueFormat {
+                base,
+                group: false,
+                ..*value_format
+            }
+            .render_limited_in(value, pool),
+            View::Sci => value_format.scientific(value),
+            View::Full => to_decimal_string_in(value, pool),
+            View::Group => {
+                let digits = to_decimal_string_in(value, pool);
+                let grouped = group_digits_with(&digits, value_format.grouping);
+                pool.recycle(digits.into_bytes());
+                grouped
+            }
+        }
+    }
+
+    /// Returns a short description of the format for output headings.
+    pub fn description(self) -> String {
+        match self {
+            View::Base(base) => format!("base {}", base),
+            View::Sci => "scientific notation".to_string(),
+            View::Full => "full".to_string(),
+            View::Group => "grouped".to_string(),
+        }
+    }
+}
+
+/// A colon-command entered in the interactive prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplCommand {
+    /// Re-render the most recently calculated Fibonacci number without calculating it again.
+    Show(View),
+    /// Change a setting for every following calculation.
+    Set(Setting),
+    /// Copy the most recently calculated Fibonacci number to the clipboard, with every digit
+    /// when `full` is set.
+    Copy { full: bool },
+    /// List the numbered results of the session.
+    History,
+    /// Print the result with the sequence number again.
+    ShowEntry(usize),
+    /// Write every digit of the result with the sequence number to the file at the path.
+    Export(usize, PathBuf),
+    /// Show the current settings.
+    Settings,
+    /// Start logging to the transcript file at the path, or stop logging when `None`.
+    Transcript(Option<PathBuf>),
+    /// Free the conversion buffers that are kept for the next result.
+    ClearBuffers,
+    /// Show the available commands.
+    Help,
+    /// Quit the interactive prompt.
+    Quit,
+}
+
+/// Parses a colon-command entered in the interactive prompt.
+///
+/// Settings can be changed with `:<name> <value>` or `:set <name> <value>`, while `:set`
+/// without arguments shows the current settings.
+///
+/// # Arguments
+/// * `input` - The trimmed line entered by the user.
+///
+/// # Returns
+/// `None` when the input is not a colon-command, otherwise a `Result<ReplCommand, String>`
+/// with the parsed command or a `String` error message for unknown or malformed commands.
+pub fn parse_repl_command(input: &str) -> Option<Result<ReplCommand, String>> {
+    let command = input.strip_prefix(':')?;
+
+    // The path of ':transcript on <path>' may contain spaces
+    if let Some(arguments) = command.strip_prefix("transcript") {
+        if arguments.is_empty() || arguments.starts_with(char::is_whitespace) {
+            return Some(parse_transcript_command(arguments.trim()));
+        }
+    }
+
+    // The path of ':export <N> <path>' may contain spaces as well
+    if let Some(arguments) = command.strip_prefix("export") {
+        if arguments.is_empty() || arguments.starts_with(char::is_whitespace) {
+            return Some(parse_export_command(arguments.trim()));
+        }
+    }
+
+    let mut words: Vec<&str> = command.split_whitespace().collect();
+
+    // ':set <name> <value>' is the same as ':<name> <value>'
+    if words.first() == Some(&"set") && !words[1..].is_empty() {
+        words.remove(0);
+        if words.len() == 1 {
+            return Some(Err(format!(
+                "Missing value, usage: :set {} <value>",
+                words[0]
+            )));
+        }
+    }
+
+    let name = words.first().copied().unwrap_or_default();
+    let argument = words.get(1).copied();
+
+    if !is_repl_command(name) {
+        return Some(Err(format!("Unknown command ':{}'. {}", name, REPL_HELP)));
+    }
+
+    if words.len() > 2 {
+        return Some(Err(format!("Too many arguments for ':{}'", name)));
+    }
+
+    let parsed = match (name, argument) {
+        ("base", Some(base)) => match base.parse::<u32>() {
+            Ok(base) if (2..=36).contains(&base) => Ok(ReplCommand::Show(View::Base(base))),
+            _ => Err(format!(
+                "Invalid base '{}', expected a number from 2 to 36",
+                base
+            )),
+        },
+        ("sci", None) => Ok(ReplCommand::Show(View::Sci)),
+        ("full", None) => Ok(ReplCommand::Show(View::Full)),
+        ("group", None) => Ok(ReplCommand::Show(View::Group)),
+        ("copy", None) => Ok(ReplCommand::Copy { full: false }),
+        ("copy", Some("full")) => Ok(ReplCommand::Copy { full: true }),
+        ("copy", Some(argument)) => Err(format!(
+            "Invalid argument '{}' for ':copy', expected nothing or 'full'",
+            argument
+        )),
+        ("history", None) => Ok(ReplCommand::History),
+        ("show", Some(number)) => parse_entry_number(number).map(ReplCommand::ShowEntry),
+        ("set", None) => Ok(ReplCommand::Settings),
+        ("clear-buffers", None) => Ok(ReplCommand::ClearBuffers),
+        ("help", None) => Ok(ReplCommand::Help),
+        ("quit", None) => Ok(ReplCommand::Quit),
+        ("sci" | "full" | "group" | "history" | "clear-buffers" | "help" | "quit", Some(_)) => {
+            Err(format!("':{}' does not take an argument", name))
+        }
+        (name, Some(value)) => match Setting::parse(name, value) {
+            Some(setting) => setting.map(ReplCommand::Set),
+            None => Err(format!("':{}' does not take an argument", name)),
+        },
+        (name, None) => Err(format!("Missing value, usage: :{} <value>", name)),
+    };
+
+    Some(parsed)
+}
+
+/// Parses the arguments of `:export`, which are `<N> <path>`.
+fn parse_export_command(arguments: &str) -> Result<ReplCommand, String> {
+    match arguments.split_once(char::is_whitespace) {
+        Some((number, path)) => Ok(ReplCommand::Export(
+            parse_entry_number(number)?,
+            PathBuf::from(path.trim()),
+        )),
+        None => Err("Missing arguments, usage: :export <N> <path>".to_string()),
+    }
+}
+
+/// Parses the sequence number of a result in the history, which starts at 1.
+fn parse_entry_number(number: &str) -> Result<usize, String> {
+    let number = number.strip_prefix('#').unwrap_or(number);
+    match number.parse::<usize>() {
+        Ok(number) if number >= 1 => Ok(number),
+        _ => Err(format!(
+            "Invalid result number '{}', expected a number from :history",
+            number
+        )),
+    }
+}
+
+/// Checks whether `name` is a colon-command, either one of [`REPL_COMMANDS`] or a setting.
+fn is_repl_command(name: &str) -> bool {
+    REPL_COMMANDS.contains(&name) || Setting::NAMES.contains(&name)
+}
+
+/// Returns the values the argument of a colon-command can be completed with.
+fn argument_candidates(name: &str) -> Vec<&'static str> {
+    match name {
+        "set" => Setting::NAMES.to_vec(),
+        "format" => OutputFormat::ALL.map(OutputFormat::name).to_vec(),
+        "algorithm" => Algorithm::ALL.map(Algorithm::name).to_vec(),
+        "grouping" => Grouping::ALL.map(Grouping::name).to_vec(),
+        "notify" => Notify::ALL.map(Notify::name).to_vec(),
+        "notation" => Notation::ALL.map(Notation::name).to_vec(),
+        "pager" | "superscript" | "transcript" => vec!["on", "off"],
+        "threshold" => vec!["none", "always"],
+        "max-print-digits" => vec!["none"],
+        "copy" => vec!["full"],
+        _ => Vec::new(),
+    }
+}
+
+/// Completes the word before the cursor in the interactive prompt.
+///
+/// After `:` the names of the colon-commands are completed, and after a command like
+/// `:format ` or `:set algorithm ` its possible values. Anywhere else a number, like the
+/// end of `10..1`, is completed with the recently used indices that start with it.
+///
+/// # Arguments
+/// * `line` - The line up to the cursor.
+/// * `recent` - The recently used indices, the most recent first.
+///
+/// # Returns
+/// A tuple with the byte position the completed word starts at and the candidates that
+/// replace it, in the order they are offered.
+pub fn complete_input(line: &str, recent: &[u64]) -> (usize, Vec<String>) {
+    let word_start = line.len()
+        - line
+            .split(char::is_whitespace)
+            .next_back()
+            .unwrap_or("")
+            .len();
+    let word = &line[word_start..];
+    let previous: Vec<&str> = line[..word_start].split_whitespace().collect();
+
+    if previous.first().is_some_and(|first| first.starts_with(':')) || word.starts_with(':') {
+        let candidates: Vec<String> = match previous[..] {
+            [] => REPL_COMMANDS
+                .iter()
+                .chain(&Setting::NAMES)
+                .map(|name| format!(":{}", name))
+                .collect(),
+            [":set", name] | [name] => argument_candidates(name.trim_start_matches(':'))
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+        let candidates = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .collect();
+        return (word_start, candidates);
+    }
+
+    // Indices are also completed at the end of ranges and inside expressions like 'fib(1'
+    let number_start = line
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |position| position + 1);
+    let number = &line[number_start..];
+    let follows_separator = line[..number_start]
+        .chars()
+        .next_back()
+        .is_none_or(|c| c.is_whitespace() || matches!(c, '.' | '=' | '+' | '-' | '*' | '(' | ','));
+    if !follows_separator {
+        return (number_start, Vec::new());
+    }
+
+    let candidates = recent
+        .iter()
+        .map(u64::to_string)
+        .filter(|index| index.starts_with(number))
+        .collect();
+    (number_start, candidates)
+}
+
+/// Parses the arguments of `:transcript`, which are `on <path>` or `off`.
+fn parse_transcript_command(arguments: &str) -> Result<ReplCommand, String> {
+    const USAGE: &str = "usage: :transcript on <path> or :transcript off";
+
+    match arguments.split_once(char::is_whitespace) {
+        Some(("on", path)) => Ok(ReplCommand::Transcript(Some(PathBuf::from(path.trim())))),
+        None if arguments == "on" => Err(format!("Missing path, {}", USAGE)),
+        None if arguments == "off" => Ok(ReplCommand::Transcript(None)),
+        _ => Err(format!("Invalid arguments for ':transcript', {}", USAGE)),
+    }
+}
+
+/// An index entered relative to the most recently calculated index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelativeIndex {
+    /// `+N` adds `N` to the last index.
+    Add(u64),
+    /// `-N` subtracts `N` from the last index.
+    Subtract(u64),
+    /// `*N` multiplies the last index by `N`.
+    Multiply(u64),
+    /// `=` or `!` repeats the last index.
+    Repeat,
+}
+
+impl RelativeIndex {
+    /// Applies the relative input to the last index.
+    ///
+    /// # Arguments
+    /// * `last` - The most recently calculated index, or `None` if there is none yet.
+    ///
+    /// # Returns
+    /// A `Result<u64, FibError>` with the adjusted index, or a `FibError::InvalidIndex` if
+    /// there is no last index or the adjusted index does not fit into a `u64`.
+    pub fn resolve(self, last: Option<u64>) -> Result<u64, FibError> {
+        let last = last.ok_or_else(|| {
+            FibError::InvalidIndex(
+                "there is no previous index yet, enter an absolute index first".to_string(),
+            )
+        })?;
+
+        let adjusted = match self {
+            RelativeIndex::Add(amount) => last.checked_add(amount),
+            RelativeIndex::Subtract(amount) => last.checked_sub(amount),
+            RelativeIndex::Multiply(factor) => last.checked_mul(factor),
+            RelativeIndex::Repeat => Some(last),
+        };
+
+        adjusted.ok_or_else(|| {
+            FibError::InvalidIndex(format!(
+                "adjusting the previous index {} is out of range",
+                last
+            ))
+        })
+    }
+}
+
+/// A classified line of input from the interactive prompt.
+#[derive(Debug)]
+pub enum Input {
+    /// Quit the interactive prompt.
+    Quit,
+    /// A colon-command, or the error message if it could not be parsed.
+    Command(Result<ReplCommand, String>),
+    /// An index of a Fibonacci number to calculate.
+    Index(u64),
+    /// Several whitespace-separated indices, each with its position and whether it is valid.
+    Indices(Vec<(usize, Result<u64, FibError>)>),
+    /// An index relative to the most recently calculated one.
+    Relative(RelativeIndex),
+    /// Show the most recent result again without calculating it.
+    Last,
+    /// An expression over Fibonacci values like `fib(20) + fib(21)`.
+    Expression(String),
+    /// The Fibonacci numbers from the first to the second index (inclusive).
+    Range(u64, u64),
+    /// Compare the Fibonacci numbers at two indices.
+    Compare(u64, u64),
+    /// A Lucas number, a Fibonacci number at a signed index or one modulo a number.
+    Request(FibRequest),
+    /// A blank line or a comment starting with `#`, which is ignored.
+    Empty,
+    /// Input that could not be interpreted.
+    Invalid(FibError),
+}
+
+/// Classifies a line entered in the interactive prompt.
+///
+/// Surrounding whitespace is ignored, as are blank lines and comments starting with `#`.
+/// `q` (in any case) and `:quit` quit, lines starting with `:` are colon-commands and
+/// `last` shows the previous result again. `l10`, `z-10` and `10 mod 7` are requests for
+/// [`FibRequest::Lucas`], [`FibRequest::Signed`] and [`FibRequest::Mod`], while a plain
+/// `-10` stays relative to the previous index. `START..END` is the range of indices up to but
+/// excluding `END`, `START..=END` includes `END`. Other lines with
+/// letters besides the exponent of `1e6` are expressions like `fib(20) + fib(21)`, while
+/// `+N`, `-N`, `*N`, `=` and `!` are relative to the previous index. Several values
+/// separated by whitespace, like `10 20 30`, are indices that are each parsed on their own.
+/// Everything else must be a valid index as accepted by `parse_index`.
+///
+/// # Arguments
+/// * `line` - The raw line entered by the user.
+///
+/// # Returns
+/// The `Input` the line represents.
+pub fn parse_input(line: &str) -> Input {
+    let input = line.trim();
+
+    if input.is_empty() || input.starts_with('#') {
+        return Input::Empty;
+    }
+
+    if input.eq_ignore_ascii_case("q") {
+        return Input::Quit;
+    }
+
+    if let Some(command) = parse_repl_command(input) {
+        return match command {
+            Ok(ReplCommand::Quit) => Input::Quit,
+            command => Input::Command(command),
+        };
+    }
+
+    if input.eq_ignore_ascii_case("last") {
+        return Input::Last;
+    }
+    if let Some(indices) = input.strip_prefix("cmp ") {
+        return parse_compare(indices);
+    }
+    if input.contains(" mod ") {
+        return match input.parse() {
+            Ok(request) => Input::Request(request),
+            Err(error) => Input::Invalid(error),
+        };
+    }
+    // Other lines starting with a letter that are no request are expressions
+    if input.starts_with(['l', 'L', 'z', 'Z']) {
+        if let Ok(request) = input.parse() {
+            return Input::Request(request);
+        }
+    }
+    if is_index_list(input) {
+        return Input::Indices(parse_indices(input));
+    }
+    if input.contains(|c: char| c.is_alphabetic() && !matches!(c, 'e' | 'E')) {
+        return Input::Expression(input.to_string());
+    }
+
+    if let Some((start, end)) = input.split_once("..") {
+        return parse_range(start, end);
+    }
+
+    if input == "=" || input == "!" {
+        return Input::Relative(RelativeIndex::Repeat);
+    }
+
+    if let Some(operator @ ('+' | '-' | '*')) = input.chars().next() {
+        return match parse_index(&input[1..]) {
+            Ok(amount) => Input::Relative(match operator {
+                '+' => RelativeIndex::Add(amount),
+                '-' => RelativeIndex::Subtract(amount),
+                _ => RelativeIndex::Multiply(amount),
+            }),
+            Err(error) => Input::Invalid(error),
+        };
+    }
+
+    match parse_index(input) {
+        Ok(index) => Input::Index(index),
+        Err(error) => Input::Invalid(error),
+    }
+}
+
+/// Checks whether a line holds several whitespace-separated values instead of a single one.
+///
+/// Expressions and arithmetic written with spaces, like `fib(20) + fib(21)` or `2 ^ 10`,
+/// have parentheses or operators standing on their own, and ranges contain `..`, so they
+/// are left alone.
+fn is_index_list(input: &str) -> bool {
+    const OPERATORS: [char; 6] = ['+', '-', '*', '/', '%', '^'];
+
+    input.split_whitespace().nth(1).is_some()
+        && !input.contains(['(', ')'])
+        && !input.contains("..")
+        && input
+            .split_whitespace()
+            .all(|value| !value.starts_with(OPERATORS) && !value.ends_with(OPERATORS))
+}
+
+/// Parses the two indices of `cmp M N`.
+fn parse_compare(indices: &str) -> Input {
+    let indices: Vec<&str> = indices.split_whitespace().collect();
+    let [first, second] = indices[..] else {
+        return Input::Command(Err("Usage: cmp <index> <index>".to_string()));
+    };
+
+    match (parse_index(first), parse_index(second)) {
+        (Ok(first), Ok(second)) => Input::Compare(first, second),
+        (Err(error), _) | (_, Err(error)) => Input::Invalid(error),
+    }
+}
+
+/// Parses the two sides of a `START..END` or `START..=END` range.
+fn parse_range(start: &str, end: &str) -> Input {
+    let (end, inclusive) = match end.strip_prefix('=') {
+        Some(end) => (end, true),
+        None => (end, false),
+    };
+
+    let (start, end) = match (parse_index(start), parse_index(end)) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(error), _) | (_, Err(error)) => return Input::Invalid(error),
+    };
+
+    let last = if inclusive {
+        Some(end)
+    } else {
+        end.checked_sub(1)
+    };
+    match last {
+        Some(last) if start <= last => Input::Range(start, last),
+        _ => Input::Invalid(FibError::InvalidIndex(format!(
+            "the range {}..{}{} is empty",
+            start,
+            if inclusive { "=" } else { "" },
+            end
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(input: &str) -> Result<ReplCommand, String> {
+        parse_repl_command(input).expect("a colon-command")
+    }
+
+    #[test]
+    fn colon_commands_and_their_errors() {
+        assert_eq!(parse_repl_command("10"), None);
+        assert_eq!(command(":base 16"), Ok(ReplCommand::Show(View::Base(16))));
+        assert_eq!(command(":sci"), Ok(ReplCommand::Show(View::Sci)));
+        assert_eq!(command(":copy full"), Ok(ReplCommand::Copy { full: true }));
+        assert_eq!(command(":show #3"), Ok(ReplCommand::ShowEntry(3)));
+        assert_eq!(command(":set"), Ok(ReplCommand::Settings));
+        assert_eq!(command(":set precision 8"), command(":precision 8"));
+        assert_eq!(
+            command(":precision 8"),
+            Ok(ReplCommand::Set(Setting::Precision(8)))
+        );
+        assert_eq!(
+            command(":export 2 my digits.txt"),
+            Ok(ReplCommand::Export(2, PathBuf::from("my digits.txt")))
+        );
+        assert_eq!(
+            command(":transcript on a b.log"),
+            Ok(ReplCommand::Transcript(Some(PathBuf::from("a b.log"))))
+        );
+        assert_eq!(
+            command(":transcript off"),
+            Ok(ReplCommand::Transcript(None))
+        );
+
+        for (input, error) in [
+            (
+                ":base 37",
+                "Invalid base '37', expected a number from 2 to 36",
+            ),
+            (":sci now", "':sci' does not take an argument"),
+            (
+                ":show 0",
+                "Invalid result number '0', expected a number from :history",
+            ),
+            (":base 2 3", "Too many arguments for ':base'"),
+            (":precision", "Missing value, usage: :precision <value>"),
+            (
+                ":set precision",
+                "Missing value, usage: :set precision <value>",
+            ),
+            (":export 2", "Missing arguments, usage: :export <N> <path>"),
+            (
+                ":transcript on",
+                "Missing path, usage: :transcript on <path> or :transcript off",
+            ),
+        ] {
+            assert_eq!(command(input), Err(error.to_string()), "{}", input);
+        }
+        assert!(command(":nope")
+            .unwrap_err()
+            .starts_with("Unknown command ':nope'. "));
+    }
+
+    #[test]
+    fn lines_are_classified_by_their_shape() {
+        assert!(matches!(parse_input("Q"), Input::Quit));
+        assert!(matches!(parse_input(" 1000 "), Input::Index(1000)));
+        assert!(matches!(parse_input(" 1_000 "), Input::Index(1000)));
+        assert!(matches!(parse_input("1e6"), Input::Index(1_000_000)));
+        assert!(matches!(
+            parse_input(":full"),
+            Input::Command(Ok(ReplCommand::Show(View::Full)))
+        ));
+        assert!(matches!(parse_input("fib(20) + 1"), Input::Expression(_)));
+        assert!(!matches!(parse_input("2 ^ 10"), Input::Indices(_)));
+        assert!(matches!(parse_input("abc"), Input::Expression(_)));
+        assert!(matches!(parse_input("-1.5"), Input::Invalid(_)));
+        assert!(matches!(parse_input(":quit"), Input::Quit));
+        assert!(matches!(parse_input("last"), Input::Last));
+        assert!(matches!(parse_input("  "), Input::Empty));
+        assert!(matches!(parse_input("# a comment"), Input::Empty));
+        assert!(matches!(parse_input("10..20"), Input::Range(10, 19)));
+        assert!(matches!(parse_input("10..=20"), Input::Range(10, 20)));
+        assert!(matches!(parse_input("20..20"), Input::Invalid(_)));
+        assert!(matches!(parse_input("cmp 5 1e2"), Input::Compare(5, 100)));
+        assert!(matches!(parse_input("cmp 5"), Input::Command(Err(_))));
+        assert!(matches!(
+            parse_input("l10"),
+            Input::Request(FibRequest::Lucas(10))
+        ));
+        assert!(matches!(
+            parse_input("z-10"),
+            Input::Request(FibRequest::Signed(-10))
+        ));
+        assert!(matches!(
+            parse_input("10 mod 7"),
+            Input::Request(FibRequest::Mod { n: 10, m: 7 })
+        ));
+        assert!(matches!(parse_input("12x"), Input::Expression(_)));
+
+        let Input::Indices(indices) = parse_input("10 x 30") else {
+            panic!("a list of indices");
+        };
+        assert_eq!(indices.len(), 3);
+        assert!(indices[0].1.is_ok() && indices[1].1.is_err() && indices[2].1.is_ok());
+    }
+
+    #[test]
+    fn relative_indices_adjust_the_last_one() {
+        let relative = |line| match parse_input(line) {
+            Input::Relative(relative) => relative,
+            other => panic!("{:?} is not relative", other),
+        };
+        assert_eq!(relative("+5").resolve(Some(10)).unwrap(), 15);
+        assert_eq!(relative("-5").resolve(Some(10)).unwrap(), 5);
+        assert_eq!(relative("*3").resolve(Some(10)).unwrap(), 30);
+        assert_eq!(relative("!").resolve(Some(10)).unwrap(), 10);
+        assert_eq!(relative("="), RelativeIndex::Repeat);
+        assert!(relative("-11").resolve(Some(10)).is_err());
+        assert!(relative("*2").resolve(Some(u64::MAX)).is_err());
+        assert!(relative("+1").resolve(None).is_err());
+    }
+
+    #[test]
+    fn completion_offers_commands_values_and_recent_indices() {
+        assert_eq!(
+            complete_input(":gr", &[]),
+            (0, vec![":group".to_string(), ":grouping".to_string()])
+        );
+        assert_eq!(
+            complete_input(":pager o", &[]),
+            (7, vec!["on".into(), "off".into()])
+        );
+        assert_eq!(
+            complete_input(":set algorithm m", &[]),
+            (15, vec!["matrix".to_string()])
+        );
+        assert_eq!(complete_input(":set threads ", &[]), (13, Vec::new()));
+
+        let recent = [1200, 15, 1_000_000];
+        assert_eq!(
+            complete_input("10..1", &recent),
+            (4, vec!["1200".into(), "15".into(), "1000000".into()])
+        );
+        assert_eq!(
+            complete_input("fib(12", &recent),
+            (4, vec!["1200".to_string()])
+        );
+        assert_eq!(complete_input("x1", &recent), (1, Vec::new()));
+    }
+}
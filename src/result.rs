@@ -0,0 +1,196 @@
+use crate::{
+    checkpoint::{Decoder, Encoder},
+    FibError,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Read, Write},
+    time::Duration,
+};
+
+/// The magic bytes at the start of a binary result.
+const RESULT_MAGIC: &[u8; 8] = b"FIBVALUE";
+
+/// The version of the binary result format written by this program.
+const RESULT_VERSION: u32 = 1;
+
+/// A calculated Fibonacci number together with the duration of its calculation.
+///
+/// With serde the value is serialized as a decimal string, which every JSON parser reads
+/// without losing precision, and the duration as whole nanoseconds named `duration_ns`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FibResult {
+    /// The index of the Fibonacci number.
+    pub index: u64,
+    /// The Fibonacci number `F(index)`.
+    #[serde(with = "decimal")]
+    pub value: BigUint,
+    /// The duration of the calculation.
+    #[serde(rename = "duration_ns", with = "nanoseconds")]
+    pub duration: Duration,
+}
+
+impl FibResult {
+    /// Creates a result from a calculated Fibonacci number.
+    pub fn new(index: u64, value: BigUint, duration: Duration) -> FibResult {
+        FibResult {
+            index,
+            value,
+            duration,
+        }
+    }
+}
+
+/// Writes a result in a compact binary format.
+///
+/// The format starts with a magic header and a format version, followed by the index, the
+/// duration in nanoseconds and the little-endian bytes of the value, and ends with an
+/// integrity hash. It is about 2.4 times smaller than the decimal digits of the value and
+/// is read back without any base conversion.
+///
+/// # Arguments
+/// * `w` - The writer the result is written to.
+/// * `result` - The result to write.
+///
+/// # Returns
+/// A `Result<(), FibError>` with a `FibError::Io` if writing failed.
+pub fn write_result(mut w: impl Write, result: &FibResult) -> Result<(), FibError> {
+    let mut encoder = Encoder::new(RESULT_MAGIC, RESULT_VERSION);
+    encoder.u64(result.index);
+    encoder.u64(u64::try_from(result.duration.as_nanos()).unwrap_or(u64::MAX));
+    encoder.biguint(&result.value);
+    w.write_all(&encoder.finish())?;
+    Ok(())
+}
+
+/// Reads a result written by [`write_result`].
+///
+/// # Arguments
+/// * `r` - The reader the result is read from, which must contain nothing else.
+///
+/// # Returns
+/// A `Result<FibResult, FibError>` with the result, or a `FibError::CorruptResult` if the
+/// header, version or integrity hash is wrong.
+pub fn read_result(mut r: impl Read) -> Result<FibResult, FibError> {
+    let mut bytes = Vec::new();
+    r.read_to_end(&mut bytes)?;
+
+    let decode = || {
+        let mut decoder = Decoder::new(&bytes, RESULT_MAGIC, RESULT_VERSION)?;
+        let index = decoder.u64()?;
+        let duration = Duration::from_nanos(decoder.u64()?);
+        let value = decoder.biguint()?;
+        decoder.finish()?;
+        Ok(FibResult::new(index, value, duration))
+    };
+
+    // The decoder is shared with checkpoints, so its errors name them
+    decode().map_err(|error| match error {
+        FibError::CorruptCheckpoint(reason) => FibError::CorruptResult(reason),
+        error => error,
+    })
+}
+
+/// Serializes a `BigUint` as a decimal string.
+mod decimal {
+    use num_bigint::BigUint;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let digits = String::deserialize(deserializer)?;
+        digits
+            .parse()
+            .map_err(|_| D::Error::custom(format!("'{}' is not a decimal number", digits)))
+    }
+}
+
+/// Serializes a `Duration` as a whole number of nanoseconds.
+mod nanoseconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+
+    fn encoded(result: &FibResult) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_result(&mut bytes, result).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn binary_results_read_back_unchanged() {
+        for n in [0, 1, 94, 10_000] {
+            let result = FibResult::new(n, calculate_fibonacci(n).unwrap(), Duration::new(n, 123));
+            let bytes = encoded(&result);
+            assert!(bytes.starts_with(RESULT_MAGIC));
+            assert_eq!(read_result(bytes.as_slice()).unwrap(), result);
+        }
+
+        // Durations beyond u64 nanoseconds are saturated
+        let long = FibResult::new(3, BigUint::from(2u32), Duration::MAX);
+        let read = read_result(encoded(&long).as_slice()).unwrap();
+        assert_eq!(read.duration, Duration::from_nanos(u64::MAX));
+    }
+
+    #[test]
+    fn damaged_results_are_rejected() {
+        let result = FibResult::new(500, calculate_fibonacci(500).unwrap(), Duration::ZERO);
+        let bytes = encoded(&result);
+        for damaged in [
+            bytes[..bytes.len() - 1].to_vec(),
+            [bytes.as_slice(), b"x"].concat(),
+            {
+                let mut flipped = bytes.clone();
+                flipped[30] ^= 1;
+                flipped
+            },
+            b"FIBCHECK".to_vec(),
+            Vec::new(),
+        ] {
+            assert!(
+                matches!(
+                    read_result(damaged.as_slice()),
+                    Err(FibError::CorruptResult(_))
+                ),
+                "{} bytes",
+                damaged.len()
+            );
+        }
+    }
+
+    #[test]
+    fn json_holds_the_value_as_a_decimal_string() {
+        let result = FibResult::new(
+            100,
+            calculate_fibonacci(100).unwrap(),
+            Duration::from_micros(1500),
+        );
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            json,
+            r#"{"index":100,"value":"354224848179261915075","duration_ns":1500000}"#
+        );
+        assert_eq!(serde_json::from_str::<FibResult>(&json).unwrap(), result);
+        assert!(
+            serde_json::from_str::<FibResult>(r#"{"index":1,"value":"1x","duration_ns":0}"#)
+                .is_err()
+        );
+    }
+}
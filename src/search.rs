@@ -0,0 +1,87 @@
+use core::cmp::Ordering;
+
+/// Searches a sorted slice for a value using Fibonacci search.
+///
+/// Fibonacci search splits the remaining range at Fibonacci numbers instead of halving it,
+/// so it only needs additions and subtractions to find the next position to compare. Like
+/// binary search it takes `O(log n)` comparisons, and the compared positions stay closer
+/// together, which suits data where accessing distant elements is expensive.
+///
+/// # Arguments
+/// * `slice` - The slice to search, which must be sorted in ascending order.
+/// * `target` - The value to search for.
+///
+/// # Returns
+/// An `Option<usize>` with the index of an element equal to `target`, or `None` if the
+/// slice holds no such element.
+pub fn fibonacci_search<T: Ord>(slice: &[T], target: &T) -> Option<usize> {
+    let len = slice.len();
+
+    // Find the smallest Fibonacci number F(m) >= len, keeping F(m-1) and F(m-2)
+    let (mut fib_m2, mut fib_m1, mut fib_m) = (0usize, 1usize, 1usize);
+    while fib_m < len {
+        fib_m2 = fib_m1;
+        fib_m1 = fib_m;
+        fib_m = fib_m1 + fib_m2;
+    }
+
+    // The number of elements at the front of the slice that are known to be smaller
+    let mut eliminated = 0;
+
+    while fib_m > 1 {
+        let index = (eliminated + fib_m2 - 1).min(len - 1);
+
+        match slice[index].cmp(target) {
+            Ordering::Less => {
+                // Continue in the upper part of size F(m-1)
+                fib_m = fib_m1;
+                fib_m1 = fib_m2;
+                fib_m2 = fib_m - fib_m1;
+                eliminated = index + 1;
+            }
+            Ordering::Greater => {
+                // Continue in the lower part of size F(m-2)
+                fib_m = fib_m2;
+                fib_m1 -= fib_m2;
+                fib_m2 = fib_m - fib_m1;
+            }
+            Ordering::Equal => return Some(index),
+        }
+    }
+
+    // A single element may remain after the loop
+    (fib_m1 == 1 && eliminated < len && slice[eliminated] == *target).then_some(eliminated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_element_and_gap_is_found_in_every_length() {
+        let odd: [u32; 40] = core::array::from_fn(|i| 2 * i as u32 + 1);
+        for len in 0..=odd.len() {
+            let slice = &odd[..len];
+            for target in 0..=2 * len as u32 + 1 {
+                let expected = slice.binary_search(&target).ok();
+                assert_eq!(
+                    fibonacci_search(slice, &target),
+                    expected,
+                    "{} in {}",
+                    target,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn duplicates_return_an_equal_element() {
+        let slice = [1, 2, 2, 2, 2, 3, 5, 5, 8];
+        for target in [1, 2, 3, 5, 8] {
+            let index = fibonacci_search(&slice, &target).unwrap();
+            assert_eq!(slice[index], target);
+        }
+        assert_eq!(fibonacci_search(&slice, &4), None);
+    }
+}
@@ -0,0 +1,778 @@
+use crate::{
+    build_thread_pool,
+    clipboard::copy_value,
+    notify::notify_finished,
+    pager::print_paged,
+    print_comparison, print_result, print_result_of, print_sequence,
+    style::print_error,
+    transcript::{format_timestamp, Transcript},
+    write_value, OutputOptions,
+};
+use fibonacci_sequence::{
+    calculate_fibonacci_signed, calculate_fibonacci_with, calculate_lucas, cancel_requested,
+    clear_cancel, evaluate, fib_mod, format_bytes, parse_expr, parse_input, repl::REPL_HELP,
+    request_cancel, BufferPool, Estimator, FibError, FibIter, FibRequest, Input, OutputFormat,
+    ReplCommand, ResultHistory, SessionCache, Setting, Speculation, ThreadPool,
+    DEFAULT_SPECULATION_BUDGET,
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use std::{
+    io::{self, Write},
+    path::Path,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Whether a line of a session is being run, so Ctrl+C cancels it instead of quitting.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl+C cancel the calculation of the running line instead of quitting.
+///
+/// The calculation stops at its next fast doubling level and the line fails, so the prompt
+/// or script continues with the next one. Ctrl+C while no line is running, or a second
+/// Ctrl+C before the calculation stopped, quits right away as before.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if the handler could not be installed.
+pub fn cancel_on_interrupt() -> Result<(), String> {
+    ctrlc::set_handler(|| {
+        if !RUNNING.load(Ordering::SeqCst) || cancel_requested() {
+            process::exit(130);
+        }
+        request_cancel();
+    })
+    .map_err(|error| format!("Error: could not handle Ctrl+C: {}", error))
+}
+
+/// What happens to indices whose estimated cost exceeds the limits of the settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Confirm {
+    /// Show the estimate and ask whether to continue.
+    Ask,
+    /// Fail with the estimate, as nobody can be asked.
+    Refuse,
+    /// Calculate them without asking.
+    Skip,
+}
+
+/// Whether a session continues after a line was run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// The state of an interactive or scripted session.
+///
+/// Every line is run the same way whether it was typed at the prompt or read from a script,
+/// so a script behaves exactly like the same lines entered by hand.
+pub struct Session {
+    output: OutputOptions,
+    pool: Option<ThreadPool>,
+    /// The results of the session, to step from the previous index and to show it again
+    cache: SessionCache,
+    /// The numbered results of the session, to list and show them again
+    history: ResultHistory,
+    last_duration: Duration,
+    /// The transcript every line and result summary is logged to, if any
+    transcript: Option<Transcript>,
+    /// Whether transcripts log every digit of a result besides its summary
+    transcript_values: bool,
+    /// What happens to indices that are estimated to exceed the limits
+    confirm: Confirm,
+    /// Predicts the cost of an index before it is calculated
+    estimator: Box<dyn Estimator>,
+    /// Whether the likely next indices are calculated while the session waits for a line
+    speculate: bool,
+    /// The calculation of the likely next indices that runs until the next line, if any
+    speculation: Option<Speculation>,
+    /// The buffers of the last conversions, reused by the next ones until `:clear-buffers`
+    buffers: BufferPool,
+}
+
+impl Session {
+    /// Creates a session that prints with `output` and calculates on `pool`.
+    ///
+    /// `transcript_values` decides whether transcripts started in the session log every
+    /// digit of a result besides its summary. `confirm` decides what happens to indices
+    /// that `estimator` predicts to exceed the limits of the settings, and to results of
+    /// the history that were evicted to stay within `history_budget` bytes. The buffers of
+    /// the decimal conversions are kept for the next result up to `buffer_budget` bytes.
+    pub fn new(
+        output: OutputOptions,
+        pool: Option<ThreadPool>,
+        transcript_values: bool,
+        confirm: Confirm,
+        estimator: Box<dyn Estimator>,
+        history_budget: u64,
+        buffer_budget: u64,
+    ) -> Session {
+        Session {
+            output,
+            pool,
+            cache: SessionCache::new(),
+            history: ResultHistory::new(history_budget),
+            last_duration: Duration::ZERO,
+            transcript: None,
+            transcript_values,
+            confirm,
+            estimator,
+            speculate: false,
+            speculation: None,
+            buffers: BufferPool::new(buffer_budget),
+        }
+    }
+
+    /// Calculates `F(n+1)`, `F(n−1)` and `F(2n)` in the background after every line that
+    /// calculated `F(n)` with fast doubling, until the next line is run.
+    ///
+    /// The pairs take at most [`DEFAULT_SPECULATION_BUDGET`] bytes, and whatever was not
+    /// finished when the next line arrives is cancelled, so a line never waits for it.
+    pub fn enable_speculation(&mut self) {
+        self.speculate = true;
+    }
+
+    /// Starts logging every following line and result summary to the transcript at `path`.
+    ///
+    /// The file is appended to, so a transcript can be continued across sessions.
+    ///
+    /// # Returns
+    /// A `Result<(), String>` with the error message if the file could not be opened.
+    pub fn start_transcript(&mut self, path: &Path) -> Result<(), String> {
+        let transcript = Transcript::open(path, self.transcript_values).map_err(|error| {
+            format!(
+                "Error: could not open the transcript {}: {}",
+                path.display(),
+                error
+            )
+        })?;
+        self.transcript = Some(transcript);
+        Ok(())
+    }
+
+    /// Runs a single line as if it was typed at the prompt.
+    ///
+    /// # Arguments
+    /// * `line` - The line to run.
+    ///
+    /// # Returns
+    /// A `Result<Flow, String>` telling whether the session continues, or the message to
+    /// show if the line could not be run.
+    pub fn run_line(&mut self, line: &str) -> Result<Flow, String> {
+        let line = line.trim();
+        if !line.is_empty() {
+            self.log(|transcript| transcript.input(line))?;
+        }
+
+        if let Some(speculation) = self.speculation.take() {
+            self.cache.add_precomputed(speculation.finish());
+        }
+
+        clear_cancel();
+        RUNNING.store(true, Ordering::SeqCst);
+        let result = self.dispatch(line);
+        RUNNING.store(false, Ordering::SeqCst);
+
+        if self.speculate {
+            self.speculation = self.cache.speculate(DEFAULT_SPECULATION_BUDGET);
+        }
+        if let Err(message) = &result {
+            self.log(|transcript| transcript.note(message))?;
+        }
+        result
+    }
+
+    /// Runs a single line and logs the summaries of its results to the transcript.
+    fn dispatch(&mut self, line: &str) -> Result<Flow, String> {
+        let index = match parse_input(line) {
+            Input::Quit => return Ok(Flow::Quit),
+            Input::Empty => return Ok(Flow::Continue),
+            Input::Index(index) => index,
+            Input::Relative(relative) => relative
+                .resolve(self.cache.last_index())
+                .map_err(|error| format!("Please enter a valid number ({})", error))?,
+            Input::Last => {
+                let (index, value) = self.cache.last().ok_or(NO_RESULT)?;
+                print_result(index, value, self.last_duration, &self.output);
+                self.print_separator();
+                self.log_last_result(self.last_duration)?;
+                return Ok(Flow::Continue);
+            }
+            Input::Range(start, end) => {
+                self.output
+                    .settings
+                    .check_index(end)
+                    .map_err(|error| format!("Error: {}", error))?;
+                let sequence = FibIter::new(start).take((end - start).saturating_add(1) as usize);
+                print_sequence(sequence, &self.output, &mut io::stdout().lock())
+                    .map_err(|error| format!("Error: {}", error))?;
+                let summary = format!(
+                    "F({}) to F({})",
+                    self.output.settings.value_format.count(start),
+                    self.output.settings.value_format.count(end)
+                );
+                self.log(|transcript| transcript.note(&summary))?;
+                return Ok(Flow::Continue);
+            }
+            Input::Compare(first, second) => {
+                let digits = self.output.settings.value_format.precision as u16;
+                let comparison = || print_comparison(first, second, digits, false, &self.output);
+                match &self.pool {
+                    Some(pool) => pool.install(comparison),
+                    None => comparison(),
+                }?;
+                println!();
+                return Ok(Flow::Continue);
+            }
+            Input::Request(request) => return self.run_request(request),
+            Input::Expression(expression) => {
+                self.print_expression(&expression)?;
+                return Ok(Flow::Continue);
+            }
+            Input::Indices(values) => {
+                self.calculate_indices(values)?;
+                return Ok(Flow::Continue);
+            }
+            Input::Invalid(error) => {
+                return Err(format!("Please enter a valid number ({})", error));
+            }
+            Input::Command(command) => {
+                self.run_command(command?)?;
+                return Ok(Flow::Continue);
+            }
+        };
+
+        self.calculate(index)?;
+        Ok(Flow::Continue)
+    }
+
+    /// Calculates every valid index of a line with several values and warns about the rest.
+    ///
+    /// # Returns
+    /// A `Result<(), String>` with the message to show if none of the values is a valid
+    /// index or a calculation failed.
+    fn calculate_indices(
+        &mut self,
+        values: Vec<(usize, Result<u64, FibError>)>,
+    ) -> Result<(), String> {
+        let count = values.len();
+        let mut indices = Vec::with_capacity(count);
+        for (position, value) in values {
+            match value {
+                Ok(index) => indices.push(index),
+                Err(error) => {
+                    let warning = format!(
+                        "Warning: skipping value {} of {} ({})",
+                        position, count, error
+                    );
+                    print_error(self.output.color, &warning);
+                    self.log(|transcript| transcript.note(&warning))?;
+                }
+            }
+        }
+
+        if indices.is_empty() {
+            return Err(
+                "Please enter a valid number (none of the values is a valid index)".to_string(),
+            );
+        }
+        for index in indices {
+            self.calculate(index)?;
+        }
+        Ok(())
+    }
+
+    /// Calculates and prints the Fibonacci number at `index` and logs it to the transcript.
+    ///
+    /// # Returns
+    /// A `Result<(), String>` with the message to show if the index was not calculated.
+    fn calculate(&mut self, index: u64) -> Result<(), String> {
+        self.check_index(index)?;
+
+        // Calculate the Fibonacci number, stepping from the previous result when possible
+        let start_time = Instant::now();
+        let algorithm = self.output.settings.algorithm;
+        let precomputed = self.cache.is_precomputed(index, algorithm);
+        let cache = &mut self.cache;
+        let mut calculation = || {
+            cache
+                .calculate(index, algorithm)
+                .map(|(_, duration)| duration)
+        };
+        let calc_duration = match &self.pool {
+            Some(pool) => pool.install(calculation),
+            None => calculation(),
+        }
+        .map_err(|error| format!("Error: {}", error))?;
+
+        let (index, fibonacci_result) = self.cache.last().ok_or(NO_RESULT)?;
+        print_result_of(
+            index,
+            fibonacci_result,
+            calc_duration,
+            precomputed,
+            &mut self.buffers,
+            &self.output,
+        );
+        notify_finished(
+            index,
+            fibonacci_result,
+            start_time.elapsed(),
+            &self.output.settings,
+        );
+        self.history.record(index, fibonacci_result, calc_duration);
+        self.last_duration = calc_duration;
+        self.print_separator();
+        self.log_last_result(calc_duration)
+    }
+
+    /// Makes sure an index is within the limits of the settings and, if its estimated cost
+    /// exceeds the limits, that it is meant to be calculated.
+    fn check_index(&self, index: u64) -> Result<(), String> {
+        self.output
+            .settings
+            .check_index(index)
+            .map_err(|error| format!("Error: {}", error))?;
+        self.confirm_cost(index)
+    }
+
+    /// Makes sure an index whose estimated cost exceeds the limits is meant to be calculated.
+    ///
+    /// Depending on `confirm` the estimate is shown with a `[y/N]` question, it is returned
+    /// as an error, or nothing happens.
+    ///
+    /// # Returns
+    /// A `Result<(), String>` with the message to show if the index is not calculated.
+    fn confirm_cost(&self, index: u64) -> Result<(), String> {
+        let limits = &self.output.settings.limits;
+        let estimate = self.estimator.estimate(index);
+        if self.confirm == Confirm::Skip || !limits.exceeded_by(&estimate) {
+            return Ok(());
+        }
+
+        let value_format = &self.output.settings.value_format;
+        let description = format!(
+            "F({}) is estimated to take about {} and {} of memory",
+            value_format.count(index),
+            value_format.duration(estimate.duration),
+            format_bytes(estimate.memory)
+        );
+        if self.confirm == Confirm::Refuse {
+            return Err(format!(
+                "{}, which is above the limits. Pass --yes to calculate it anyway",
+                description
+            ));
+        }
+
+        if self.ask(&format!("{}. Continue?", description))? {
+            Ok(())
+        } else {
+            Err("Cancelled".to_string())
+        }
+    }
+
+    /// Asks a yes or no question on the terminal, where anything but yes means no.
+    ///
+    /// # Returns
+    /// A `Result<bool, String>` telling whether the answer was yes, or the error message if
+    /// the terminal could not be used.
+    fn ask(&self, question: &str) -> Result<bool, String> {
+        print!("{} [y/N] ", question);
+        io::stdout()
+            .flush()
+            .map_err(|error| format!("Error: {}", error))?;
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .map_err(|error| format!("Error: {}", error))?;
+
+        Ok(matches!(
+            answer.trim().to_ascii_lowercase().as_str(),
+            "y" | "yes"
+        ))
+    }
+
+    /// Makes sure a result of the history can be shown, calculating it again if its value
+    /// was evicted.
+    ///
+    /// Like an index above the cost limits, the calculation is confirmed first, refused
+    /// when nobody can be asked, or done right away with `--yes`.
+    ///
+    /// # Arguments
+    /// * `number` - The sequence number of the result.
+    ///
+    /// # Returns
+    /// A `Result<Option<BigUint>, String>` with the recalculated value, `None` if the value
+    /// is still stored, or the message to show if there is no such result or it was not
+    /// calculated again.
+    fn recall(&mut self, number: usize) -> Result<Option<BigUint>, String> {
+        let entry = self.history.get(number).ok_or_else(|| {
+            format!(
+                "There is no result #{}, enter :history to list the results",
+                number
+            )
+        })?;
+        if entry.value().is_some() {
+            return Ok(None);
+        }
+
+        let index = entry.index;
+        let description = format!(
+            "Result #{} F({}) is no longer stored to stay within the history budget",
+            number,
+            self.output.settings.value_format.count(index)
+        );
+        match self.confirm {
+            Confirm::Refuse => {
+                return Err(format!(
+                    "{}. Enter {} to calculate it again",
+                    description, index
+                ))
+            }
+            Confirm::Ask if !self.ask(&format!("{}. Calculate it again?", description))? => {
+                return Err("Cancelled".to_string());
+            }
+            _ => {}
+        }
+
+        let algorithm = self.output.settings.algorithm;
+        let calculation = || calculate_fibonacci_with(index, algorithm);
+        let value = match &self.pool {
+            Some(pool) => pool.install(calculation),
+            None => calculation(),
+        }
+        .map_err(|error| format!("Error: {}", error))?;
+
+        self.history.restore(number, &value);
+        Ok(Some(value))
+    }
+
+    /// Lists the numbered results of the session with their digit counts and durations.
+    fn print_history(&self) {
+        let value_format = &self.output.settings.value_format;
+        if self.history.entries().is_empty() {
+            println!("No results yet\n");
+            return;
+        }
+
+        for entry in self.history.entries() {
+            println!(
+                "#{}  F({})  {} digits  {}  {}{}",
+                entry.number,
+                value_format.count(entry.index),
+                value_format.count(entry.digits),
+                value_format.duration(entry.duration),
+                format_timestamp(entry.calculated_at),
+                if entry.value().is_some() {
+                    ""
+                } else {
+                    "  (not stored)"
+                }
+            );
+        }
+        println!();
+    }
+
+    /// Logs the summary of the most recent result to the transcript.
+    fn log_last_result(&mut self, calc_duration: Duration) -> Result<(), String> {
+        let value_format = &self.output.settings.value_format;
+        let logged = match (&mut self.transcript, self.cache.last()) {
+            (Some(transcript), Some((index, value))) => {
+                transcript.result(index, value, calc_duration, value_format)
+            }
+            _ => return Ok(()),
+        };
+        logged.map_err(|error| self.stop_transcript_after(error))
+    }
+
+    /// Writes an entry to the transcript, if one is being logged.
+    fn log(&mut self, write: impl FnOnce(&mut Transcript) -> io::Result<()>) -> Result<(), String> {
+        let logged = match &mut self.transcript {
+            Some(transcript) => write(transcript),
+            None => return Ok(()),
+        };
+        logged.map_err(|error| self.stop_transcript_after(error))
+    }
+
+    /// Stops logging after writing to the transcript failed and returns the error message.
+    fn stop_transcript_after(&mut self, error: io::Error) -> String {
+        let path = self
+            .transcript
+            .take()
+            .map(|transcript| transcript.path().display().to_string())
+            .unwrap_or_default();
+        format!(
+            "Error: could not write to the transcript {}: {}, stopped logging",
+            path, error
+        )
+    }
+
+    /// Runs a colon-command.
+    ///
+    /// View commands like `:base 16` re-render the last result, while settings commands like
+    /// `:threads 2` change how every following result is calculated and printed.
+    fn run_command(&mut self, command: ReplCommand) -> Result<(), String> {
+        match command {
+            ReplCommand::Show(view) => {
+                let (index, value) = self.cache.last().ok_or(NO_RESULT)?;
+                let settings = &self.output.settings;
+                println!(
+                    "F({}) ({}):",
+                    settings.value_format.count(index),
+                    view.description()
+                );
+                let rendered = view.render_in(value, &settings.value_format, &mut self.buffers);
+                print_paged(&rendered, settings.pager);
+                self.buffers.recycle(rendered.into_bytes());
+                println!();
+            }
+            ReplCommand::Set(setting) => {
+                if setting == Setting::Superscript(true) && self.output.ascii {
+                    return Err(
+                        "Superscript exponents are disabled by --ascii or a locale without UTF-8"
+                            .to_string(),
+                    );
+                }
+                if let Setting::Threads(threads) = setting {
+                    self.pool = build_thread_pool(Some(threads))
+                        .map_err(|error| format!("Error: {}", error))?;
+                }
+                self.output.settings.apply(setting);
+                println!("{}\n", self.output.settings);
+            }
+            ReplCommand::Copy { full } => {
+                let (_, value) = self.cache.last().ok_or(NO_RESULT)?;
+                let full = full || self.output.full || self.output.pretty.is_some();
+                copy_value(value, full, &self.output)?;
+                println!();
+            }
+            ReplCommand::History => self.print_history(),
+            ReplCommand::ShowEntry(number) => {
+                let recalculated = self.recall(number)?;
+                let entry = self.history.get(number).ok_or(NO_RESULT)?;
+                let value = entry.value().or(recalculated.as_ref()).ok_or(NO_RESULT)?;
+                print_result(entry.index, value, entry.duration, &self.output);
+                self.print_separator();
+            }
+            ReplCommand::Export(number, path) => {
+                let recalculated = self.recall(number)?;
+                let entry = self.history.get(number).ok_or(NO_RESULT)?;
+                let value = entry.value().or(recalculated.as_ref()).ok_or(NO_RESULT)?;
+                write_value(value, &path, &self.output).map_err(|error| {
+                    format!("Error: could not write {}: {}", path.display(), error)
+                })?;
+                println!();
+            }
+            ReplCommand::Settings => println!("{}\n", self.output.settings),
+            ReplCommand::Transcript(Some(path)) => {
+                self.start_transcript(&path)?;
+                println!("Logging to the transcript {}\n", path.display());
+            }
+            ReplCommand::Transcript(None) => {
+                let transcript = self
+                    .transcript
+                    .take()
+                    .ok_or("No transcript is being logged")?;
+                println!(
+                    "Stopped logging to the transcript {}\n",
+                    transcript.path().display()
+                );
+            }
+            ReplCommand::ClearBuffers => {
+                let freed = self.buffers.clear();
+                println!("Freed {} of conversion buffers\n", format_bytes(freed));
+            }
+            ReplCommand::Help => println!("{}\n", REPL_HELP),
+            // ':quit' is turned into Input::Quit by parse_input
+            ReplCommand::Quit => {}
+        }
+        Ok(())
+    }
+
+    /// Calculates and prints a Lucas number, a Fibonacci number at a signed index or one
+    /// modulo a number.
+    ///
+    /// Like every other index, the indices of Lucas numbers and signed indices are checked
+    /// with [`Session::check_index`].
+    ///
+    /// # Returns
+    /// A `Result<Flow, String>` telling whether the session continues, or the message to
+    /// show if the request could not be calculated.
+    fn run_request(&mut self, request: FibRequest) -> Result<Flow, String> {
+        let count = |index: u64| self.output.settings.value_format.count(index);
+
+        let (label, calculation): (String, Box<dyn FnOnce() -> Result<BigInt, FibError> + Send>) =
+            match request {
+                FibRequest::Quit => return Ok(Flow::Quit),
+                FibRequest::Fib(index) => {
+                    self.calculate(index)?;
+                    return Ok(Flow::Continue);
+                }
+                FibRequest::Lucas(n) => {
+                    self.check_index(n)?;
+                    let label = format!("L({})", count(n));
+                    (
+                        label,
+                        Box::new(move || calculate_lucas(n).map(BigInt::from)),
+                    )
+                }
+                FibRequest::Signed(n) => {
+                    self.check_index(n.unsigned_abs())?;
+                    let sign = if n < 0 { "-" } else { "" };
+                    let label = format!("F({}{})", sign, count(n.unsigned_abs()));
+                    (label, Box::new(move || calculate_fibonacci_signed(n)))
+                }
+                FibRequest::Mod { n, m } => {
+                    let label = format!("F({}) mod {}", count(n), count(m));
+                    (label, Box::new(move || Ok(BigInt::from(fib_mod(n, m)))))
+                }
+            };
+
+        let value = match &self.pool {
+            Some(pool) => pool.install(calculation),
+            None => calculation(),
+        }
+        .map_err(|error| format!("Error: {}", error))?;
+
+        let result = self.render_integer(&value);
+        if self.output.quiet {
+            println!("{}", result);
+        } else {
+            println!("{} =\n{}\n", label, result);
+        }
+        self.log(|transcript| transcript.note(&result))?;
+        Ok(Flow::Continue)
+    }
+
+    /// Evaluates an expression over Fibonacci values and prints its exact value.
+    ///
+    /// Every `fib(n)` call of the expression is calculated through the session cache, so
+    /// neighboring and repeated indices are cheap.
+    fn print_expression(&mut self, expression: &str) -> Result<(), String> {
+        let algorithm = self.output.settings.algorithm;
+        let cache = &mut self.cache;
+        let mut evaluation = || {
+            let expr = parse_expr(expression)?;
+            evaluate(&expr, &mut |index| {
+                cache
+                    .calculate(index, algorithm)
+                    .map(|(value, _)| value.clone())
+            })
+        };
+        let evaluation = match &self.pool {
+            Some(pool) => pool.install(evaluation),
+            None => evaluation(),
+        }
+        .map_err(|error| format!("{}\n{}\nError: {}", expression, error.caret(), error))?;
+
+        let result = self.render_integer(&evaluation.value);
+        if self.output.quiet {
+            println!("{}", result);
+        } else {
+            println!("{} =\n{}", expression, result);
+            if evaluation.truncated {
+                println!("Note: a division was truncated toward zero");
+            }
+            println!();
+        }
+
+        self.log(|transcript| transcript.note(&result))
+    }
+
+    /// Renders a signed number like any other result, with the sign in front of its magnitude.
+    fn render_integer(&self, value: &BigInt) -> String {
+        let value_format = &self.output.settings.value_format;
+        let magnitude = value.magnitude();
+        let sign = if value.sign() == Sign::Minus { "-" } else { "" };
+        let shown = if self.output.full {
+            value_format.render(magnitude)
+        } else if value_format.use_scientific(magnitude) {
+            value_format.scientific(magnitude)
+        } else {
+            value_format.render_limited(magnitude)
+        };
+        format!("{}{}", sign, shown)
+    }
+
+    /// Prints the blank lines that separate results.
+    ///
+    /// Nothing is printed for templates, quiet output and JSON, which print one line per result.
+    fn print_separator(&self) {
+        let output = &self.output;
+        if output.template.is_none()
+            && !output.quiet
+            && output.settings.format == OutputFormat::Text
+        {
+            println!("\n");
+        }
+    }
+}
+
+/// The message shown when a line needs a previous result but there is none.
+const NO_RESULT: &str = "No result to display yet, enter an index first";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::ColorChoice;
+    use fibonacci_sequence::{Estimate, Settings, DEFAULT_HISTORY_BUDGET};
+
+    /// An estimator that predicts the same cost for every index.
+    struct FixedCost(Estimate);
+
+    impl Estimator for FixedCost {
+        fn estimate(&self, _n: u64) -> Estimate {
+            self.0
+        }
+    }
+
+    fn session(confirm: Confirm, duration: Duration) -> Session {
+        let output = OutputOptions {
+            template: None,
+            settings: Settings::default(),
+            quiet: true,
+            full: false,
+            pretty: None,
+            summary: false,
+            color: ColorChoice::Never,
+            ascii: false,
+            progress: false,
+            stats: false,
+        };
+        let estimate = Estimate {
+            duration,
+            memory: 0,
+        };
+        Session::new(
+            output,
+            None,
+            false,
+            confirm,
+            Box::new(FixedCost(estimate)),
+            DEFAULT_HISTORY_BUDGET,
+            0,
+        )
+    }
+
+    #[test]
+    fn costly_indices_are_refused_unless_skipped() {
+        let hour = Duration::from_secs(3600);
+        let refused = session(Confirm::Refuse, hour).run_line("10").unwrap_err();
+        assert!(refused.starts_with("F(10) is estimated to take about "));
+        assert!(refused.ends_with(
+            " and 0 B of memory, which is above the limits. Pass --yes to calculate it anyway"
+        ));
+        assert_eq!(
+            session(Confirm::Skip, hour).run_line("10"),
+            Ok(Flow::Continue)
+        );
+
+        // Estimates within the limits are never in the way
+        let second = Duration::from_secs(1);
+        assert_eq!(
+            session(Confirm::Refuse, second).run_line("10"),
+            Ok(Flow::Continue)
+        );
+    }
+}
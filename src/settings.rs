@@ -0,0 +1,493 @@
+use crate::{
+    format_bytes, Algorithm, CostLimits, FibError, Grouping, Notation, SciThreshold, ValueFormat,
+};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// The largest number of significant digits accepted for scientific notation.
+pub const MAX_PRECISION: usize = 50;
+
+/// The largest number of worker threads accepted for calculations.
+pub const MAX_THREADS: usize = 1024;
+
+/// The environment variable with the default exponent above which numbers are shown in
+/// scientific notation.
+pub const SCI_THRESHOLD_VAR: &str = "FIB_SCI_THRESHOLD";
+
+/// The default duration after which a finished calculation is announced.
+pub const DEFAULT_NOTIFY_AFTER: Duration = Duration::from_secs(10);
+
+/// The format single results are printed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The human-readable summary with durations and the result.
+    #[default]
+    Text,
+    /// A single JSON object per result.
+    Json,
+    /// A LaTeX equation per result, and an `align*` environment for ranges.
+    Latex,
+}
+
+impl OutputFormat {
+    /// Every output format.
+    pub const ALL: [OutputFormat; 3] =
+        [OutputFormat::Text, OutputFormat::Json, OutputFormat::Latex];
+
+    /// Returns the name of the format as accepted by `from_str`.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Latex => "latex",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        OutputFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+            .ok_or_else(|| format!("unknown format '{}', expected text, json or latex", name))
+    }
+}
+
+/// How the end of a long calculation is announced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notify {
+    /// Do not announce finished calculations.
+    #[default]
+    None,
+    /// Ring the terminal bell.
+    Bell,
+    /// Send a desktop notification with the index, digit count and duration.
+    Desktop,
+}
+
+impl Notify {
+    /// Every way of announcing a finished calculation.
+    pub const ALL: [Notify; 3] = [Notify::None, Notify::Bell, Notify::Desktop];
+
+    /// Returns the name of the notification as accepted by `from_str`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Notify::None => "none",
+            Notify::Bell => "bell",
+            Notify::Desktop => "desktop",
+        }
+    }
+}
+
+impl fmt::Display for Notify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Notify {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Notify::ALL
+            .into_iter()
+            .find(|notify| notify.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown notification '{}', expected bell, desktop or none",
+                    name
+                )
+            })
+    }
+}
+
+/// A single setting changed from the interactive prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    /// The number of significant digits shown in scientific notation.
+    Precision(usize),
+    /// The format single results are printed in.
+    Format(OutputFormat),
+    /// The number of worker threads used for calculations.
+    Threads(usize),
+    /// The numbers that are shown in scientific notation.
+    Threshold(SciThreshold),
+    /// The algorithm used for calculations.
+    Algorithm(Algorithm),
+    /// The number of digits above which printed numbers are shortened, `None` for no limit.
+    MaxPrintDigits(Option<usize>),
+    /// Whether results longer than a screen are shown in a pager.
+    Pager(bool),
+    /// The style of the separators in grouped numbers.
+    Grouping(Grouping),
+    /// How the end of a long calculation is announced.
+    Notify(Notify),
+    /// Whether scientific notation is written with superscript exponents.
+    Superscript(bool),
+    /// Whether the exponent of large numbers is any power of ten or a multiple of 3.
+    Notation(Notation),
+}
+
+impl Setting {
+    /// The names of the settings, as used by the `:<name> <value>` commands.
+    pub const NAMES: [&'static str; 11] = [
+        "precision",
+        "format",
+        "threads",
+        "threshold",
+        "algorithm",
+        "max-print-digits",
+        "pager",
+        "grouping",
+        "notify",
+        "superscript",
+        "notation",
+    ];
+
+    /// Parses a setting from its name and value.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the setting, one of [`Setting::NAMES`].
+    /// * `value` - The new value of the setting.
+    ///
+    /// # Returns
+    /// `None` when `name` is not a setting, otherwise a `Result<Setting, String>` with the
+    /// parsed setting or a `String` error message for an invalid value.
+    pub fn parse(name: &str, value: &str) -> Option<Result<Setting, String>> {
+        let parsed = match name {
+            "precision" => match value.parse::<usize>() {
+                Ok(precision) if (1..=MAX_PRECISION).contains(&precision) => {
+                    Ok(Setting::Precision(precision))
+                }
+                _ => Err(format!(
+                    "Invalid precision '{}', expected a number from 1 to {}",
+                    value, MAX_PRECISION
+                )),
+            },
+            "format" => value.parse().map(Setting::Format),
+            "threads" => match value.parse::<usize>() {
+                Ok(threads) if (1..=MAX_THREADS).contains(&threads) => {
+                    Ok(Setting::Threads(threads))
+                }
+                _ => Err(format!(
+                    "Invalid thread count '{}', expected a number from 1 to {}",
+                    value, MAX_THREADS
+                )),
+            },
+            "threshold" => parse_threshold(value).map(Setting::Threshold),
+            "algorithm" => value.parse().map(Setting::Algorithm),
+            "max-print-digits" => parse_max_print_digits(value).map(Setting::MaxPrintDigits),
+            "pager" => match value {
+                "on" => Ok(Setting::Pager(true)),
+                "off" => Ok(Setting::Pager(false)),
+                _ => Err(format!("Invalid pager '{}', expected on or off", value)),
+            },
+            "grouping" => value.parse().map(Setting::Grouping),
+            "notation" => value.parse().map(Setting::Notation),
+            "notify" => value.parse().map(Setting::Notify),
+            "superscript" => match value {
+                "on" => Ok(Setting::Superscript(true)),
+                "off" => Ok(Setting::Superscript(false)),
+                _ => Err(format!(
+                    "Invalid superscript '{}', expected on or off",
+                    value
+                )),
+            },
+            _ => return None,
+        };
+
+        Some(parsed)
+    }
+}
+
+/// Parses a scientific notation threshold written as a power of ten.
+///
+/// The threshold is accepted as `1e50`, `10^50` or just the exponent `50`, or as `none` to
+/// never use scientific notation and `always` to use it for every number.
+///
+/// # Arguments
+/// * `input` - The threshold to parse.
+///
+/// # Returns
+/// A `Result<SciThreshold, String>` with the threshold, or a `String` error message.
+pub fn parse_threshold(input: &str) -> Result<SciThreshold, String> {
+    input.parse()
+}
+
+/// Parses the number of digits above which printed numbers are shortened.
+///
+/// The limit is accepted as a number of at least 2, or `none` to always print every digit.
+///
+/// # Arguments
+/// * `input` - The limit to parse.
+///
+/// # Returns
+/// A `Result<Option<usize>, String>` with the limit or `None` for no limit, or a `String`
+/// error message.
+pub fn parse_max_print_digits(input: &str) -> Result<Option<usize>, String> {
+    if input == "none" {
+        return Ok(None);
+    }
+
+    match input.parse::<usize>() {
+        Ok(max_digits) if max_digits >= 2 => Ok(Some(max_digits)),
+        _ => Err(format!(
+            "Invalid digit limit '{}', expected a number of at least 2 or none",
+            input
+        )),
+    }
+}
+
+/// The settings that control how Fibonacci numbers are calculated and shown.
+///
+/// The settings can be changed from the interactive prompt and apply to every following
+/// calculation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// The algorithm used for calculations.
+    pub algorithm: Algorithm,
+    /// The number of worker threads, or `None` for one per CPU core.
+    pub threads: Option<usize>,
+    /// The format single results are printed in.
+    pub format: OutputFormat,
+    /// The options for rendering results.
+    pub value_format: ValueFormat,
+    /// Whether results longer than a screen are shown in a pager.
+    pub pager: bool,
+    /// How the end of a long calculation is announced.
+    pub notify: Notify,
+    /// Calculations that take at least this long are announced.
+    pub notify_after: Duration,
+    /// The estimated cost above which calculations at the prompt have to be confirmed.
+    pub limits: CostLimits,
+    /// The largest index that is calculated, or `None` for no limit.
+    pub max_index: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            algorithm: Algorithm::default(),
+            threads: None,
+            format: OutputFormat::default(),
+            value_format: ValueFormat::default(),
+            pager: true,
+            notify: Notify::default(),
+            notify_after: DEFAULT_NOTIFY_AFTER,
+            limits: CostLimits::default(),
+            max_index: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Changes a single setting.
+    pub fn apply(&mut self, setting: Setting) {
+        match setting {
+            Setting::Precision(precision) => self.value_format.precision = precision,
+            Setting::Format(format) => self.format = format,
+            Setting::Threads(threads) => self.threads = Some(threads),
+            Setting::Threshold(threshold) => self.value_format.sci_threshold = threshold,
+            Setting::Algorithm(algorithm) => self.algorithm = algorithm,
+            Setting::MaxPrintDigits(max_digits) => self.value_format.max_print_digits = max_digits,
+            Setting::Pager(pager) => self.pager = pager,
+            Setting::Grouping(grouping) => self.value_format.grouping = grouping,
+            Setting::Notify(notify) => self.notify = notify,
+            Setting::Superscript(superscript) => self.value_format.superscript = superscript,
+            Setting::Notation(notation) => self.value_format.notation = notation,
+        }
+    }
+
+    /// Checks that an index is not above the largest index that is calculated.
+    ///
+    /// # Returns
+    /// A `Result<(), FibError>` with a `FibError::InvalidIndex` if `index` is above
+    /// `max_index`.
+    pub fn check_index(&self, index: u64) -> Result<(), FibError> {
+        match self.max_index {
+            Some(max_index) if index > max_index => Err(FibError::InvalidIndex(format!(
+                "{} is above the maximum index {}",
+                self.value_format.count(index),
+                self.value_format.count(max_index)
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "algorithm        = {}", self.algorithm)?;
+        match self.threads {
+            Some(threads) => writeln!(f, "threads          = {}", threads)?,
+            None => writeln!(f, "threads          = default")?,
+        }
+        writeln!(f, "format           = {}", self.format)?;
+        writeln!(f, "precision        = {}", self.value_format.precision)?;
+        writeln!(f, "threshold        = {}", self.value_format.sci_threshold)?;
+        match self.value_format.max_print_digits {
+            Some(max_digits) => writeln!(f, "max-print-digits = {}", max_digits)?,
+            None => writeln!(f, "max-print-digits = none")?,
+        }
+        writeln!(f, "base             = {}", self.value_format.base)?;
+        writeln!(f, "group            = {}", self.value_format.group)?;
+        writeln!(f, "grouping         = {}", self.value_format.grouping)?;
+        writeln!(f, "notation         = {}", self.value_format.notation)?;
+        writeln!(
+            f,
+            "superscript      = {}",
+            if self.value_format.superscript {
+                "on"
+            } else {
+                "off"
+            }
+        )?;
+        writeln!(
+            f,
+            "pager            = {}",
+            if self.pager { "on" } else { "off" }
+        )?;
+        writeln!(
+            f,
+            "notify           = {} (after {}s)",
+            self.notify,
+            self.notify_after.as_secs()
+        )?;
+        match self.max_index {
+            Some(max_index) => writeln!(f, "max-index        = {}", max_index)?,
+            None => writeln!(f, "max-index        = none")?,
+        }
+        writeln!(f, "confirm-after    = {}s", self.limits.duration.as_secs())?;
+        write!(f, "confirm-memory   = {}", format_bytes(self.limits.memory))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(name: &str, value: &str) -> Result<Setting, String> {
+        Setting::parse(name, value).expect("a setting")
+    }
+
+    #[test]
+    fn every_setting_is_applied_and_shown() {
+        let mut settings = Settings::default();
+        for (name, value) in [
+            ("precision", "12"),
+            ("format", "json"),
+            ("threads", "3"),
+            ("threshold", "1e50"),
+            ("algorithm", "matrix"),
+            ("max-print-digits", "none"),
+            ("pager", "off"),
+            ("grouping", "indian"),
+            ("notify", "bell"),
+            ("superscript", "on"),
+            ("notation", "engineering"),
+        ] {
+            settings.apply(setting(name, value).unwrap());
+            let shown = settings.to_string();
+            let line = shown
+                .lines()
+                .find(|line| line.starts_with(&format!("{} ", name)))
+                .expect("every setting is shown");
+            assert!(line.contains(&format!("= {}", value)), "{}", line);
+        }
+        assert_eq!(settings.threads, Some(3));
+        assert_eq!(settings.value_format.grouping, Grouping::Indian);
+        assert_eq!(
+            settings.value_format.sci_threshold,
+            SciThreshold::Digits(50)
+        );
+        assert_eq!(Setting::NAMES.len(), 11);
+        assert_eq!(Setting::parse("base", "16"), None);
+    }
+
+    #[test]
+    fn values_out_of_range_are_rejected() {
+        for (name, value, error) in [
+            (
+                "precision",
+                "0",
+                "Invalid precision '0', expected a number from 1 to 50",
+            ),
+            (
+                "precision",
+                "51",
+                "Invalid precision '51', expected a number from 1 to 50",
+            ),
+            (
+                "threads",
+                "1025",
+                "Invalid thread count '1025', expected a number from 1 to 1024",
+            ),
+            ("pager", "yes", "Invalid pager 'yes', expected on or off"),
+            (
+                "superscript",
+                "1",
+                "Invalid superscript '1', expected on or off",
+            ),
+            (
+                "format",
+                "xml",
+                "unknown format 'xml', expected text, json or latex",
+            ),
+            (
+                "max-print-digits",
+                "1",
+                "Invalid digit limit '1', expected a number of at least 2 or none",
+            ),
+        ] {
+            assert_eq!(
+                setting(name, value),
+                Err(error.to_string()),
+                "{} {}",
+                name,
+                value
+            );
+        }
+        assert!(setting("threshold", "1e").is_err());
+        assert!(setting("algorithm", "guess").is_err());
+        assert!(setting("notify", "email").is_err());
+    }
+
+    #[test]
+    fn thresholds_and_digit_limits_in_every_spelling() {
+        for input in ["1e50", "10^50", "50"] {
+            assert_eq!(
+                parse_threshold(input),
+                Ok(SciThreshold::Digits(50)),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(parse_threshold("none"), Ok(SciThreshold::Never));
+        assert_eq!(parse_threshold("always"), Ok(SciThreshold::Always));
+        assert!(parse_threshold("-5").is_err());
+        assert_eq!(parse_max_print_digits("2"), Ok(Some(2)));
+        assert_eq!(parse_max_print_digits("none"), Ok(None));
+        assert!(parse_max_print_digits("-5").is_err());
+    }
+
+    #[test]
+    fn indices_above_the_maximum_are_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.check_index(u64::MAX).is_ok());
+        settings.max_index = Some(1_000_000);
+        assert!(settings.check_index(1_000_000).is_ok());
+        let error = settings.check_index(1_000_001).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            FibError::InvalidIndex("1,000,001 is above the maximum index 1,000,000".into())
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::doubling::combine_squares;
+use num_bigint::BigUint;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+/// The default number of bytes the pairs of a [`Speculation`] may take together (256 MiB).
+pub const DEFAULT_SPECULATION_BUDGET: u64 = 256 << 20;
+
+/// A Fibonacci pair `(index, F(index), F(index+1))` calculated ahead of time.
+pub type PrecomputedPair = (u64, BigUint, BigUint);
+
+/// Calculates the likely next queries of an interactive session in the background.
+///
+/// After `F(k)` people tend to ask for `F(k+1)`, `F(k−1)` or `F(2k)`, so while the session
+/// waits for its next line a background thread derives their pairs from the pair of `k`:
+/// the neighbors with a single addition or subtraction, and `F(2k)` with a single fast
+/// doubling step of two squarings, which costs about as much as the last step of the
+/// calculation of `F(k)`. The squarings run one after the other on that thread only, so
+/// the pool of the session stays free.
+///
+/// [`Speculation::finish`] cancels the thread without waiting for it and returns the
+/// pairs it finished so far, so a new line never waits for the speculation. A squaring
+/// that is running when it is cancelled still ends, but its result is thrown away.
+pub struct Speculation {
+    cancel: Arc<AtomicBool>,
+    receiver: Receiver<PrecomputedPair>,
+}
+
+impl Speculation {
+    /// Returns the indices that are likely asked for after `k`: `k + 1`, `k − 1` and `2k`,
+    /// as far as they exist.
+    pub fn targets(k: u64) -> Vec<u64> {
+        let mut targets = Vec::new();
+        for target in [k.checked_add(1), k.checked_sub(1), k.checked_mul(2)]
+            .into_iter()
+            .flatten()
+        {
+            if target != k && !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    /// Starts calculating the pairs of the [`Speculation::targets`] of `k` from its pair.
+    ///
+    /// Pairs that would not fit into `budget` bytes together with the ones before them are
+    /// skipped, and so are the indices in `known`.
+    ///
+    /// # Arguments
+    /// * `k` - The index of the known pair.
+    /// * `current` - The Fibonacci number `F(k)`.
+    /// * `next` - The Fibonacci number `F(k+1)`.
+    /// * `budget` - The most bytes the calculated pairs may take together.
+    /// * `known` - The indices whose pairs are known already.
+    ///
+    /// # Returns
+    /// The running `Speculation`.
+    pub fn start(
+        k: u64,
+        current: &BigUint,
+        next: &BigUint,
+        budget: u64,
+        known: &[u64],
+    ) -> Speculation {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let (current, next) = (current.clone(), next.clone());
+        let targets: Vec<u64> = Speculation::targets(k)
+            .into_iter()
+            .filter(|target| !known.contains(target))
+            .collect();
+        let cancelled = Arc::clone(&cancel);
+
+        // Without a thread nothing is precomputed, which only makes the next line slower
+        let _ = thread::Builder::new()
+            .name("speculate".to_string())
+            .spawn(move || speculate(k, current, next, &targets, budget, &cancelled, &sender));
+
+        Speculation { cancel, receiver }
+    }
+
+    /// Cancels the speculation and returns the pairs that were finished until now.
+    ///
+    /// This never waits for the background thread.
+    pub fn finish(self) -> Vec<PrecomputedPair> {
+        self.cancel.store(true, Ordering::SeqCst);
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for Speculation {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Calculates the pairs of a [`Speculation`] and sends each one as soon as it is finished.
+fn speculate(
+    k: u64,
+    current: BigUint,
+    next: BigUint,
+    targets: &[u64],
+    budget: u64,
+    cancel: &AtomicBool,
+    sender: &Sender<PrecomputedPair>,
+) {
+    let cancelled = || cancel.load(Ordering::SeqCst);
+    let mut used = 0;
+
+    // The neighbors are cheap, so they go first
+    let mut targets = targets.to_vec();
+    targets.sort_by_key(|&target| target.abs_diff(k));
+    for target in targets {
+        let neighbor = target.abs_diff(k) == 1;
+        // A pair takes about twice the bytes of its larger number
+        let bits = if neighbor {
+            next.bits() + 1
+        } else {
+            next.bits() * 2
+        };
+        used += bits.div_ceil(8) * 2;
+        if cancelled() || used > budget {
+            return;
+        }
+
+        let pair = if neighbor && target > k {
+            (next.clone(), &current + &next)
+        } else if neighbor {
+            (&next - &current, current.clone())
+        } else {
+            // A squaring cannot be interrupted, but its result is thrown away
+            let current_squared = &current * &current;
+            if cancelled() {
+                return;
+            }
+            let next_squared = &next * &next;
+            if cancelled() {
+                return;
+            }
+            combine_squares(current_squared, next_squared, target)
+        };
+        if sender.send((target, pair.0, pair.1)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+
+    /// Runs the speculation for `k` on the calling thread and collects its pairs.
+    fn speculated(k: u64, budget: u64, cancelled: bool) -> Vec<PrecomputedPair> {
+        let (current, next) = (
+            calculate_fibonacci(k).unwrap(),
+            calculate_fibonacci(k + 1).unwrap(),
+        );
+        let (sender, receiver) = mpsc::channel();
+        let targets = Speculation::targets(k);
+        let cancel = AtomicBool::new(cancelled);
+        speculate(k, current, next, &targets, budget, &cancel, &sender);
+        receiver.try_iter().collect()
+    }
+
+    #[test]
+    fn targets_are_the_neighbors_and_the_double() {
+        assert_eq!(Speculation::targets(10), [11, 9, 20]);
+        assert_eq!(Speculation::targets(1), [2, 0]);
+        assert_eq!(Speculation::targets(0), [1]);
+        assert_eq!(Speculation::targets(u64::MAX), [u64::MAX - 1]);
+    }
+
+    #[test]
+    fn pairs_are_derived_from_the_known_pair() {
+        for k in [1, 2, 3, 1000, 12_345] {
+            let pairs = speculated(k, u64::MAX, false);
+            assert_eq!(pairs.len(), Speculation::targets(k).len());
+            for (index, value, next) in pairs {
+                assert_eq!(value, calculate_fibonacci(index).unwrap(), "F({})", index);
+                assert_eq!(
+                    next,
+                    calculate_fibonacci(index + 1).unwrap(),
+                    "F({})",
+                    index + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_budget_and_cancelling_stop_the_speculation() {
+        // The neighbors of F(1000) take 174 bytes each, its double 348
+        let indices = |pairs: Vec<PrecomputedPair>| -> Vec<u64> {
+            pairs.into_iter().map(|(index, _, _)| index).collect()
+        };
+        assert_eq!(indices(speculated(1000, 400, false)), [1001, 999]);
+        assert_eq!(indices(speculated(1000, 347, false)), [1001]);
+        assert!(speculated(1000, u64::MAX, true).is_empty());
+
+        // Starting and finishing right away never waits for the thread
+        let (current, next) = (BigUint::from(5u32), BigUint::from(8u32));
+        let pairs = Speculation::start(5, &current, &next, u64::MAX, &[6, 4]).finish();
+        assert!(pairs.iter().all(|(index, _, _)| *index == 10));
+    }
+}
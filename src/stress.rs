@@ -0,0 +1,169 @@
+use crate::{
+    calculate_fibonacci, digit_count, fib_approx_log, fib_mod, to_decimal_string, FibError,
+    SplitMix64,
+};
+
+/// A check of a stress run that failed for a single index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StressFailure {
+    /// The index of the Fibonacci number that failed the check.
+    pub index: u64,
+    /// What the check found, like the two digit counts that disagree.
+    pub reason: String,
+}
+
+/// The outcome of a stress run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StressReport {
+    /// The number of indices that were calculated and checked.
+    pub checked: usize,
+    /// The failed checks, in the order of the indices.
+    pub failures: Vec<StressFailure>,
+}
+
+/// Picks reproducible pseudo-random indices for a stress run.
+///
+/// The bit length of every index is picked uniformly first and the index below it second,
+/// so small indices and their edge cases are picked as often as huge ones. Only integer
+/// arithmetic is used, so the same seed produces the same indices on every platform.
+///
+/// # Arguments
+/// * `seed` - The seed of the pseudo-random number generator.
+/// * `count` - The number of indices to pick.
+/// * `max_index` - The largest index that may be picked.
+///
+/// # Returns
+/// A `Vec<u64>` with `count` indices up to `max_index`, in the order they were picked.
+pub fn stress_indices(seed: u64, count: usize, max_index: u64) -> Vec<u64> {
+    let mut rng = SplitMix64::new(seed);
+    let bit_lengths = u64::from(u64::BITS - max_index.leading_zeros()) + 1;
+
+    (0..count)
+        .map(|_| {
+            let bits = rng.next_u64() % bit_lengths;
+            let index = match bits {
+                0 => 0,
+                bits => rng.next_u64() >> (64 - bits),
+            };
+            index.min(max_index)
+        })
+        .collect()
+}
+
+/// Calculates a Fibonacci number and checks it against independent results.
+///
+/// The exact digit count of [`digit_count`] has to agree with the length of the decimal
+/// string and with the digit count of Binet's formula from [`fib_approx_log`], and the
+/// number modulo a pseudo-random modulus has to agree with [`fib_mod`], which never builds
+/// the number.
+///
+/// # Arguments
+/// * `index` - The index of the Fibonacci number to check.
+/// * `modulus` - The modulus of the check against `fib_mod`, which has to be positive.
+///
+/// # Returns
+/// A `Result<Option<StressFailure>, FibError>` with the first failed check, `None` if all
+/// checks passed, or the error of the calculation.
+pub fn stress_check(index: u64, modulus: u64) -> Result<Option<StressFailure>, FibError> {
+    let value = calculate_fibonacci(index)?;
+    let failure = |reason: String| Ok(Some(StressFailure { index, reason }));
+
+    let counted = digit_count(&value);
+    let converted = to_decimal_string(&value).len() as u64;
+    if counted != converted {
+        return failure(format!(
+            "digit_count gives {} digits, but the decimal string has {}",
+            counted, converted
+        ));
+    }
+
+    let estimated = fib_approx_log(index).exponent + 1;
+    if counted != estimated {
+        return failure(format!(
+            "the result has {} digits, but Binet's formula gives {}",
+            counted, estimated
+        ));
+    }
+
+    let remainder = (&value % modulus).iter_u64_digits().next().unwrap_or(0);
+    let expected = fib_mod(index, modulus);
+    if remainder != expected {
+        return failure(format!(
+            "the result is {} modulo {}, but fib_mod gives {}",
+            remainder, modulus, expected
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Checks `count` pseudo-random Fibonacci numbers with [`stress_check`].
+///
+/// The indices come from [`stress_indices`] and the moduli from the same generator, seeded
+/// with the bitwise complement of `seed`, so a failing run can be repeated exactly from its
+/// seed.
+///
+/// # Arguments
+/// * `seed` - The seed of the pseudo-random number generator.
+/// * `count` - The number of indices to check.
+/// * `max_index` - The largest index that may be checked.
+///
+/// # Returns
+/// A `Result<StressReport, FibError>` with the number of checked indices and the failures,
+/// or the error of a calculation.
+pub fn run_stress(seed: u64, count: usize, max_index: u64) -> Result<StressReport, FibError> {
+    let mut moduli = SplitMix64::new(!seed);
+    let mut report = StressReport::default();
+
+    for index in stress_indices(seed, count, max_index) {
+        let modulus = moduli.next_u64().max(2);
+        if let Some(failure) = stress_check(index, modulus)? {
+            report.failures.push(failure);
+        }
+        report.checked += 1;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_are_reproducible_and_cover_every_bit_length() {
+        let indices = stress_indices(53, 2000, 1 << 20);
+        assert_eq!(indices, stress_indices(53, 2000, 1 << 20));
+        assert_ne!(indices, stress_indices(54, 2000, 1 << 20));
+        assert!(indices.iter().all(|&index| index <= 1 << 20));
+        for bits in 0..=20 {
+            assert!(
+                indices
+                    .iter()
+                    .any(|index| u64::BITS - index.leading_zeros() == bits),
+                "no index with {} bits",
+                bits
+            );
+        }
+        assert!(stress_indices(1, 100, 0).iter().all(|&index| index == 0));
+        assert!(stress_indices(1, 100, u64::MAX)
+            .iter()
+            .any(|&index| index > 1 << 62));
+    }
+
+    #[test]
+    fn correct_numbers_pass_every_check() {
+        for index in [0, 1, 2, 93, 94, 10_000] {
+            for modulus in [2, 1_000_000_007, u64::MAX] {
+                assert_eq!(stress_check(index, modulus).unwrap(), None, "F({})", index);
+            }
+        }
+        let report = run_stress(5, 30, 50_000).unwrap();
+        assert_eq!(
+            report,
+            StressReport {
+                checked: 30,
+                failures: Vec::new()
+            }
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use clap::ValueEnum;
+use std::{
+    env,
+    fmt::Display,
+    io::{self, IsTerminal},
+};
+
+/// When the output is colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color the output when it is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always color the output.
+    Always,
+    /// Never color the output.
+    Never,
+}
+
+const BOLD_CYAN: &str = "1;36";
+const YELLOW: &str = "33";
+const MAGENTA: &str = "35";
+const RED: &str = "31";
+
+/// Colors parts of the output with ANSI escape codes.
+///
+/// Every colored part of the output goes through a `Style`. When colors are disabled the
+/// text is returned unchanged, so the output is exactly the same as without colors.
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    enabled: bool,
+}
+
+impl Style {
+    /// Creates the style for text printed to stdout.
+    pub fn stdout(choice: ColorChoice) -> Style {
+        Style::new(choice, io::stdout().is_terminal())
+    }
+
+    /// Creates the style for text printed to stderr.
+    pub fn stderr(choice: ColorChoice) -> Style {
+        Style::new(choice, io::stderr().is_terminal())
+    }
+
+    fn new(choice: ColorChoice, terminal: bool) -> Style {
+        // Any non-empty NO_COLOR disables colors, see https://no-color.org
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let enabled = match choice {
+            ColorChoice::Auto => terminal && !no_color,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        Style { enabled }
+    }
+
+    /// Colors an index or a digit count.
+    pub fn number(self, text: impl Display) -> String {
+        self.paint(BOLD_CYAN, text)
+    }
+
+    /// Colors a duration.
+    pub fn duration(self, text: impl Display) -> String {
+        self.paint(YELLOW, text)
+    }
+
+    /// Colors an error message.
+    pub fn error(self, text: impl Display) -> String {
+        self.paint(RED, text)
+    }
+
+    /// Highlights the exponent of a number in scientific notation like `1.2345e+67` or
+    /// `1.2345 × 10⁶⁷`.
+    ///
+    /// Text without an exponent is returned unchanged.
+    pub fn scientific(self, text: &str) -> String {
+        match text.find('e').or_else(|| text.find(" × ")) {
+            Some(position) => {
+                let (significand, exponent) = text.split_at(position);
+                format!("{}{}", significand, self.paint(MAGENTA, exponent))
+            }
+            None => text.to_string(),
+        }
+    }
+
+    fn paint(self, code: &str, text: impl Display) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Checks whether the terminal can show Unicode characters like superscript digits.
+///
+/// The first of `LC_ALL`, `LC_CTYPE` and `LANG` that is set decides, like it does for
+/// other programs, and has to name a UTF-8 locale. Windows terminals always support Unicode.
+pub fn unicode_supported() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+        .is_some_and(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+/// Prints an error message to stderr, in red if colors are enabled.
+pub fn print_error(choice: ColorChoice, message: impl Display) {
+    eprintln!("{}", Style::stderr(choice).error(message));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Styles the summary of a calculation like it is printed for `F(1000)`.
+    fn summary(style: Style) -> String {
+        format!(
+            "Calculated the {}th Fibonacci number\n\
+             Fibonacci calculation duration: {}\n\
+             Digits: {}\n\
+             Result:\n{}\n\
+             {}",
+            style.number("1,000"),
+            style.duration("12.3\u{b5}s"),
+            style.number(209),
+            style.scientific("4.3466e+208"),
+            style.error("Error: interrupted"),
+        )
+    }
+
+    #[test]
+    fn output_without_colors_is_plain_text() {
+        let snapshot = "Calculated the 1,000th Fibonacci number\n\
+                        Fibonacci calculation duration: 12.3\u{b5}s\n\
+                        Digits: 209\n\
+                        Result:\n4.3466e+208\n\
+                        Error: interrupted";
+        assert_eq!(summary(Style::new(ColorChoice::Never, true)), snapshot);
+        assert_eq!(summary(Style::new(ColorChoice::Auto, false)), snapshot);
+    }
+
+    #[test]
+    fn colored_output_wraps_every_part_in_escape_codes() {
+        assert_eq!(
+            summary(Style::new(ColorChoice::Always, false)),
+            "Calculated the \x1b[1;36m1,000\x1b[0mth Fibonacci number\n\
+             Fibonacci calculation duration: \x1b[33m12.3\u{b5}s\x1b[0m\n\
+             Digits: \x1b[1;36m209\x1b[0m\n\
+             Result:\n4.3466\x1b[35me+208\x1b[0m\n\
+             \x1b[31mError: interrupted\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn superscript_exponents_are_highlighted_from_the_multiplication_sign() {
+        let style = Style::new(ColorChoice::Always, true);
+        assert_eq!(
+            style.scientific("4.3466 × 10²⁰⁸"),
+            "4.3466\x1b[35m × 10²⁰⁸\x1b[0m"
+        );
+        assert_eq!(style.scientific("12345"), "12345");
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{digit_count, ValueFormat};
+use num_bigint::BigUint;
+
+/// The layout of a table of Fibonacci numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableStyle {
+    /// A Markdown table with a header separator row, for pasting into docs or issues.
+    Markdown,
+    /// A plain text table with fixed-width, right-aligned columns.
+    Plain,
+}
+
+/// The headings of the table columns.
+const HEADINGS: [&str; 3] = ["index", "value", "digits"];
+
+/// Renders a sequence of Fibonacci numbers as a table.
+///
+/// The table has the columns index, value and digits. Each value that would be shown in
+/// scientific notation is shown that way, so rows of huge numbers stay readable.
+///
+/// # Arguments
+/// * `sequence` - The `(index, value)` pairs to put in the table.
+/// * `style` - The layout of the table.
+/// * `value_format` - The format of values that are shown with all digits.
+///
+/// # Returns
+/// A `String` with the rendered table, ending in a line break.
+pub fn render_table(
+    sequence: impl Iterator<Item = (u64, BigUint)>,
+    style: TableStyle,
+    value_format: &ValueFormat,
+) -> String {
+    let rows: Vec<[String; 3]> = sequence
+        .map(|(index, value)| {
+            let shown = if value_format.use_scientific(&value) {
+                value_format.scientific(&value)
+            } else {
+                value_format.render(&value)
+            };
+            [index.to_string(), shown, digit_count(&value).to_string()]
+        })
+        .collect();
+
+    match style {
+        TableStyle::Markdown => render_markdown(&rows),
+        TableStyle::Plain => render_plain(&rows),
+    }
+}
+
+fn render_markdown(rows: &[[String; 3]]) -> String {
+    let mut table = format!("| {} |\n", HEADINGS.join(" | "));
+    table.push_str("| ---: | ---: | ---: |\n");
+    for row in rows {
+        table.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    table
+}
+
+fn render_plain(rows: &[[String; 3]]) -> String {
+    let mut widths = HEADINGS.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: [&str; 3]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+            .collect();
+        format!("{}\n", padded.join("  "))
+    };
+
+    let mut table = format_row(HEADINGS);
+    let separators = widths.map(|width| "-".repeat(width));
+    table.push_str(&format_row(separators.each_ref().map(String::as_str)));
+    for row in rows {
+        table.push_str(&format_row(row.each_ref().map(String::as_str)));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FibIter;
+
+    fn table(style: TableStyle) -> String {
+        let sequence = FibIter::new(0).step_by(15).take(3);
+        render_table(sequence, style, &ValueFormat::default())
+    }
+
+    #[test]
+    fn markdown_tables_align_every_column_right() {
+        assert_eq!(
+            table(TableStyle::Markdown),
+            "| index | value | digits |\n\
+             | ---: | ---: | ---: |\n\
+             | 0 | 0 | 1 |\n\
+             | 15 | 610 | 3 |\n\
+             | 30 | 832040 | 6 |\n"
+        );
+    }
+
+    #[test]
+    fn plain_tables_pad_to_the_widest_cell() {
+        let plain = table(TableStyle::Plain);
+        assert_eq!(
+            plain,
+            "index   value  digits\n\
+             -----  ------  ------\n\
+             \x20   0       0       1\n\
+             \x20  15     610       3\n\
+             \x20  30  832040       6\n"
+        );
+        assert!(plain.lines().all(|line| line.len() == 21));
+    }
+
+    #[test]
+    fn large_values_are_shown_in_scientific_notation() {
+        let sequence = FibIter::new(169).take(2);
+        let markdown = render_table(sequence, TableStyle::Markdown, &ValueFormat::default());
+        assert!(markdown.ends_with(
+            "| 169 | 93202207781383214849429075266681969 | 35 |\n| 170 | 1.5080e+35 | 36 |\n"
+        ));
+    }
+}
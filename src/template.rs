@@ -0,0 +1,295 @@
+use std::{fmt, time::Duration};
+
+/// A placeholder that can be used inside an output template.
+///
+/// Placeholders are written as `{name}` in the template string, where `name` is the
+/// value returned by [`Placeholder::name`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    Index,
+    Digits,
+    Value,
+    Sci,
+    Duration,
+    DurationNs,
+    DurationUs,
+    DurationMs,
+    DurationS,
+    Conversion,
+    ConversionNs,
+    ConversionUs,
+    ConversionMs,
+    ConversionS,
+    Algorithm,
+}
+
+impl Placeholder {
+    /// Every placeholder that is recognized in a template, in documentation order.
+    pub const ALL: [Placeholder; 15] = [
+        Placeholder::Index,
+        Placeholder::Digits,
+        Placeholder::Value,
+        Placeholder::Sci,
+        Placeholder::Duration,
+        Placeholder::DurationNs,
+        Placeholder::DurationUs,
+        Placeholder::DurationMs,
+        Placeholder::DurationS,
+        Placeholder::Conversion,
+        Placeholder::ConversionNs,
+        Placeholder::ConversionUs,
+        Placeholder::ConversionMs,
+        Placeholder::ConversionS,
+        Placeholder::Algorithm,
+    ];
+
+    /// Returns the name of the placeholder as written between braces.
+    pub fn name(self) -> &'static str {
+        match self {
+            Placeholder::Index => "index",
+            Placeholder::Digits => "digits",
+            Placeholder::Value => "value",
+            Placeholder::Sci => "sci",
+            Placeholder::Duration => "duration",
+            Placeholder::DurationNs => "duration_ns",
+            Placeholder::DurationUs => "duration_us",
+            Placeholder::DurationMs => "duration_ms",
+            Placeholder::DurationS => "duration_s",
+            Placeholder::Conversion => "conversion",
+            Placeholder::ConversionNs => "conversion_ns",
+            Placeholder::ConversionUs => "conversion_us",
+            Placeholder::ConversionMs => "conversion_ms",
+            Placeholder::ConversionS => "conversion_s",
+            Placeholder::Algorithm => "algorithm",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Placeholder> {
+        Placeholder::ALL.into_iter().find(|p| p.name() == name)
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}}", self.name())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// The values a template can refer to for a single Fibonacci result.
+///
+/// `value` and `sci` are optional because converting a huge result is expensive;
+/// callers only need to fill them in when [`Template::uses`] reports that the
+/// template refers to them.
+#[derive(Debug)]
+pub struct TemplateFields<'a> {
+    pub index: u64,
+    pub digits: u64,
+    pub value: Option<&'a str>,
+    pub sci: Option<&'a str>,
+    pub duration: Duration,
+    pub conversion: Duration,
+    pub algorithm: &'a str,
+    /// Whether `{duration}` and `{conversion}` are written with ASCII units only.
+    pub ascii: bool,
+}
+
+/// A parsed output template such as `"{index}\t{digits}\t{duration_ms}"`.
+///
+/// Templates consist of literal text and `{placeholder}` references. The escapes
+/// `\t`, `\n`, `\\`, `\{` and `\}` are recognized in the literal text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parses a template string.
+    ///
+    /// # Arguments
+    /// * `template` - The template string to parse.
+    ///
+    /// # Returns
+    /// A `Result<Template, String>` with the parsed template, or a `String` error message
+    /// naming the offending placeholder and listing the valid ones.
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some(escaped @ ('\\' | '{' | '}')) => literal.push(escaped),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unterminated placeholder '{{{}'", name)),
+                        }
+                    }
+
+                    let placeholder = Placeholder::from_name(&name).ok_or_else(|| {
+                        format!(
+                            "unknown placeholder '{{{}}}', valid placeholders are: {}",
+                            name,
+                            valid_placeholders()
+                        )
+                    })?;
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => {
+                    return Err("unmatched '}' in template, use '\\}' for a literal brace".into())
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Template { segments })
+    }
+
+    /// Checks whether the template refers to the given placeholder.
+    pub fn uses(&self, placeholder: Placeholder) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| segment == &Segment::Placeholder(placeholder))
+    }
+
+    /// Renders the template for a single result.
+    ///
+    /// # Arguments
+    /// * `fields` - The values of the result to substitute into the template.
+    ///
+    /// # Returns
+    /// A `String` with every placeholder replaced by its value. Placeholders for values
+    /// that were not provided in `fields` render as an empty string.
+    pub fn render(&self, fields: &TemplateFields) -> String {
+        let mut output = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => output.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    output.push_str(&render_placeholder(*placeholder, fields))
+                }
+            }
+        }
+
+        output
+    }
+}
+
+fn render_placeholder(placeholder: Placeholder, fields: &TemplateFields) -> String {
+    match placeholder {
+        Placeholder::Index => fields.index.to_string(),
+        Placeholder::Digits => fields.digits.to_string(),
+        Placeholder::Value => fields.value.unwrap_or_default().to_string(),
+        Placeholder::Sci => fields.sci.unwrap_or_default().to_string(),
+        Placeholder::Duration => crate::format_duration_with(fields.duration, fields.ascii),
+        Placeholder::DurationNs => fields.duration.as_nanos().to_string(),
+        Placeholder::DurationUs => format!("{:.3}", fields.duration.as_secs_f64() * 1e6),
+        Placeholder::DurationMs => format!("{:.3}", fields.duration.as_secs_f64() * 1e3),
+        Placeholder::DurationS => format!("{:.6}", fields.duration.as_secs_f64()),
+        Placeholder::Conversion => crate::format_duration_with(fields.conversion, fields.ascii),
+        Placeholder::ConversionNs => fields.conversion.as_nanos().to_string(),
+        Placeholder::ConversionUs => format!("{:.3}", fields.conversion.as_secs_f64() * 1e6),
+        Placeholder::ConversionMs => format!("{:.3}", fields.conversion.as_secs_f64() * 1e3),
+        Placeholder::ConversionS => format!("{:.6}", fields.conversion.as_secs_f64()),
+        Placeholder::Algorithm => fields.algorithm.to_string(),
+    }
+}
+
+fn valid_placeholders() -> String {
+    Placeholder::ALL
+        .iter()
+        .map(|placeholder| placeholder.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(value: Option<&str>) -> TemplateFields<'_> {
+        TemplateFields {
+            index: 20,
+            digits: 4,
+            value,
+            sci: Some("6.7650e+3"),
+            duration: Duration::from_nanos(1_234_567),
+            conversion: Duration::from_micros(8),
+            algorithm: "fast doubling",
+            ascii: true,
+        }
+    }
+
+    #[test]
+    fn placeholders_are_replaced_by_their_values() {
+        let template = Template::parse("{index}\\t{value} ({sci}, {digits} digits)\\n").unwrap();
+        assert_eq!(
+            template.render(&fields(Some("6765"))),
+            "20\t6765 (6.7650e+3, 4 digits)\n"
+        );
+        assert_eq!(
+            template.render(&fields(None)),
+            "20\t (6.7650e+3, 4 digits)\n"
+        );
+
+        let times = Template::parse(
+            "{duration}|{duration_ns}|{duration_us}|{duration_ms}|{duration_s}|\
+             {conversion}|{conversion_ns}|{conversion_ms}|{algorithm}",
+        )
+        .unwrap();
+        assert_eq!(
+            times.render(&fields(None)),
+            "1.2ms|1234567|1234.567|1.235|0.001235|8.0us|8000|0.008|fast doubling"
+        );
+    }
+
+    #[test]
+    fn escapes_and_errors() {
+        let template = Template::parse("\\{index\\} \\\\ \\x {index}\\").unwrap();
+        assert_eq!(template.render(&fields(None)), "{index} \\ \\x 20\\");
+        assert!(template.uses(Placeholder::Index));
+        assert!(!template.uses(Placeholder::Value));
+
+        assert_eq!(
+            Template::parse("{index").unwrap_err(),
+            "unterminated placeholder '{index'"
+        );
+        assert!(Template::parse("{nope}")
+            .unwrap_err()
+            .starts_with("unknown placeholder '{nope}', valid placeholders are: {index}, "));
+        assert!(Template::parse("index}")
+            .unwrap_err()
+            .starts_with("unmatched '}'"));
+        for placeholder in Placeholder::ALL {
+            let parsed = Template::parse(&placeholder.to_string()).unwrap();
+            assert!(parsed.uses(placeholder), "{}", placeholder);
+        }
+    }
+}
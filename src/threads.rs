@@ -0,0 +1,100 @@
+//! The parts of rayon the calculations and the command-line program use, which run
+//! everything on the calling thread without the `parallel` feature.
+
+#[cfg(feature = "parallel")]
+pub use rayon::{current_num_threads, ThreadPool};
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::join;
+
+/// Creates a thread pool whose calculations run on `threads` worker threads.
+///
+/// # Arguments
+/// * `threads` - The number of worker threads.
+///
+/// # Returns
+/// A `Result<ThreadPool, String>` with the thread pool, or a `String` error message if the
+/// threads could not be started.
+#[cfg(feature = "parallel")]
+pub fn thread_pool(threads: usize) -> Result<ThreadPool, String> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|error| error.to_string())
+}
+
+/// Runs `a` and then `b` on the calling thread, as without the `parallel` feature there are
+/// no other threads to hand one of them to.
+///
+/// # Returns
+/// The results of both closures, like `rayon::join`.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA,
+    B: FnOnce() -> RB,
+{
+    (a(), b())
+}
+
+/// Returns 1, as without the `parallel` feature every calculation runs on the calling thread.
+#[cfg(not(feature = "parallel"))]
+pub fn current_num_threads() -> usize {
+    1
+}
+
+/// The thread pool of builds without the `parallel` feature, which runs everything it is
+/// given on the calling thread.
+#[cfg(not(feature = "parallel"))]
+#[derive(Debug)]
+pub struct ThreadPool(());
+
+#[cfg(not(feature = "parallel"))]
+impl ThreadPool {
+    /// Runs `operation` on the calling thread, like `rayon::ThreadPool::install` runs it in
+    /// the pool.
+    pub fn install<R>(&self, operation: impl FnOnce() -> R) -> R {
+        operation()
+    }
+
+    /// Returns 1, the only thread the pool runs on.
+    pub fn current_num_threads(&self) -> usize {
+        1
+    }
+}
+
+/// Creates the thread pool of builds without the `parallel` feature, which runs on the
+/// calling thread however many `threads` are asked for.
+///
+/// # Returns
+/// A `Result<ThreadPool, String>`, which is always the single-threaded pool.
+#[cfg(not(feature = "parallel"))]
+pub fn thread_pool(_threads: usize) -> Result<ThreadPool, String> {
+    Ok(ThreadPool(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculate_fibonacci, checkpoint::fnv1a_hash, to_decimal_string};
+
+    #[test]
+    fn every_pool_calculates_the_same_numbers() {
+        for threads in [1, 2, 4] {
+            let pool = thread_pool(threads).unwrap();
+            let (value, digits, sum) = pool.install(|| {
+                let value = calculate_fibonacci(100_000).unwrap();
+                let digits = to_decimal_string(&value);
+                let (three, five) = join(|| &value * 3u32, || &value * 5u32);
+                (value, digits, three + five)
+            });
+
+            // The digits of F(100000), which every build has to calculate alike
+            assert_eq!(digits.len(), 20_899);
+            assert!(digits.starts_with("259740693472217241661550340212"));
+            assert!(digits.ends_with("3428746875"));
+            assert_eq!(fnv1a_hash(digits.as_bytes()), 0x650c_65e0_f0ff_eaef);
+            assert_eq!(sum, value * 8u32);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use fibonacci_sequence::{digit_count, ValueFormat};
+use num_bigint::BigUint;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A log of the inputs of a session and summaries of their results.
+///
+/// The file is opened in append mode and every entry is written in one piece and synced
+/// to disk right away, so the transcript survives a crash of the program or the system.
+pub struct Transcript {
+    file: File,
+    path: PathBuf,
+    /// Whether every digit of a result is logged besides its summary
+    values: bool,
+}
+
+impl Transcript {
+    /// Opens the transcript file at `path`, creating it if it does not exist.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the transcript file.
+    /// * `values` - Whether every digit of a result is logged besides its summary.
+    ///
+    /// # Returns
+    /// An `io::Result<Transcript>` with the opened transcript.
+    pub fn open(path: &Path, values: bool) -> io::Result<Transcript> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Transcript {
+            file,
+            path: path.to_path_buf(),
+            values,
+        })
+    }
+
+    /// Returns the path of the transcript file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Logs a line entered at the prompt or read from a script, with a timestamp.
+    pub fn input(&mut self, line: &str) -> io::Result<()> {
+        self.write_entry(&format!(
+            "[{}] > {}\n",
+            format_timestamp(SystemTime::now()),
+            line
+        ))
+    }
+
+    /// Logs the summary of a calculated Fibonacci number.
+    ///
+    /// The summary holds the index, the digit count, the calculation duration and the
+    /// result in scientific notation. Every digit of the result is only logged if the
+    /// transcript was opened with `values`.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the calculated Fibonacci number.
+    /// * `value` - The calculated Fibonacci number.
+    /// * `calc_duration` - The duration of the calculation.
+    /// * `value_format` - The precision of the scientific notation and the grouping style.
+    pub fn result(
+        &mut self,
+        index: u64,
+        value: &BigUint,
+        calc_duration: Duration,
+        value_format: &ValueFormat,
+    ) -> io::Result<()> {
+        let digits = digit_count(value);
+        let mut entry = format!(
+            "    F({}): {} {}, calculated in {}\n    {}\n",
+            value_format.count(index),
+            value_format.count(digits),
+            if digits == 1 { "digit" } else { "digits" },
+            value_format.duration(calc_duration),
+            value_format.scientific(value)
+        );
+        if self.values {
+            entry.push_str(&format!("    {}\n", value));
+        }
+        self.write_entry(&entry)
+    }
+
+    /// Logs a message that belongs to the previous input, like an error or a short result.
+    pub fn note(&mut self, message: &str) -> io::Result<()> {
+        let mut entry = String::new();
+        for line in message.lines() {
+            entry.push_str(&format!("    {}\n", line));
+        }
+        self.write_entry(&entry)
+    }
+
+    /// Appends an entry and makes sure it reached the disk.
+    fn write_entry(&mut self, entry: &str) -> io::Result<()> {
+        self.file.write_all(entry.as_bytes())?;
+        self.file.flush()?;
+        self.file.sync_data()
+    }
+}
+
+/// Formats a point in time as a UTC timestamp like `2024-05-17 09:41:07 UTC`.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+
+    // Convert the days since 1970-01-01 to a date of the proleptic Gregorian calendar
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_are_gregorian_dates_in_utc() {
+        for (seconds, expected) in [
+            (0, "1970-01-01 00:00:00 UTC"),
+            (951_782_400, "2000-02-29 00:00:00 UTC"),
+            (1_715_938_867, "2024-05-17 09:41:07 UTC"),
+            (4_107_542_399, "2100-02-28 23:59:59 UTC"),
+            (4_107_542_400, "2100-03-01 00:00:00 UTC"),
+        ] {
+            let time = UNIX_EPOCH + Duration::from_secs(seconds);
+            assert_eq!(format_timestamp(time), expected, "{}", seconds);
+        }
+        let before = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(format_timestamp(before), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn entries_are_appended_in_order() {
+        let path =
+            std::env::temp_dir().join(format!("fibonacci-transcript-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        for values in [false, true] {
+            let mut transcript = Transcript::open(&path, values).unwrap();
+            transcript.input("10").unwrap();
+            let value = BigUint::from(55u32);
+            let format = ValueFormat::default();
+            transcript
+                .result(10, &value, Duration::from_micros(2), &format)
+                .unwrap();
+            transcript.note("first\nsecond").unwrap();
+        }
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert!(lines[0].starts_with('[') && lines[0].ends_with("UTC] > 10"));
+        assert_eq!(
+            lines[1..5],
+            [
+                "    F(10): 2 digits, calculated in 2.0µs",
+                "    5.5000e+1",
+                "    first",
+                "    second",
+            ]
+        );
+        assert_eq!(lines[8], "    55");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,396 @@
+use crate::OutputOptions;
+#[cfg(feature = "tui")]
+use fibonacci_sequence::{parse_index, to_decimal_string, CostModel, DoublingState};
+#[cfg(feature = "tui")]
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Gauge, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+#[cfg(feature = "tui")]
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often the interface is redrawn while no key is pressed.
+#[cfg(feature = "tui")]
+const TICK: Duration = Duration::from_millis(50);
+
+/// Runs the terminal interface until the user quits.
+///
+/// The interface has an input box for the index, a panel with the progress of the running
+/// calculation, the list of completed calculations and a scrollable view of the digits of
+/// the selected one. Calculations run on a worker thread, so the interface stays responsive
+/// and Esc cancels them. The terminal is restored on exit, and on a panic as well.
+///
+/// # Arguments
+/// * `output` - The options whose settings, like the maximum index, apply to calculations.
+///
+/// # Returns
+/// A `Result<(), String>` with the error message if the terminal could not be used.
+#[cfg(feature = "tui")]
+pub fn run_tui(output: &OutputOptions) -> Result<(), String> {
+    let mut terminal = ratatui::try_init()
+        .map_err(|error| format!("Error: could not open the terminal interface ({})", error))?;
+    let result = App::new(output).run(&mut terminal);
+    ratatui::restore();
+    result.map_err(|error| format!("Error: {}", error))
+}
+
+/// Fails, as the terminal interface was not compiled in.
+#[cfg(not(feature = "tui"))]
+pub fn run_tui(_output: &OutputOptions) -> Result<(), String> {
+    Err("Error: the terminal interface needs the tui feature".to_string())
+}
+
+/// The progress of a calculation, shared between the worker thread and the interface.
+#[cfg(feature = "tui")]
+struct Progress {
+    /// The number of fast doubling steps that are done
+    steps: AtomicU32,
+    /// The number of fast doubling steps of the whole calculation
+    total_steps: u32,
+    /// The number of bits of the larger number of the current pair
+    bits: AtomicU64,
+    /// Set by the interface to stop the calculation after the current step
+    cancelled: AtomicBool,
+}
+
+/// A calculation running on the worker thread.
+#[cfg(feature = "tui")]
+struct Job {
+    index: u64,
+    started: Instant,
+    progress: Arc<Progress>,
+    /// Receives the result, or `None` if the calculation was cancelled
+    receiver: Receiver<Option<Completed>>,
+}
+
+/// A completed calculation with the digits of its result.
+#[cfg(feature = "tui")]
+struct Completed {
+    index: u64,
+    digits: String,
+    duration: Duration,
+}
+
+/// The state of the terminal interface.
+#[cfg(feature = "tui")]
+struct App<'a> {
+    output: &'a OutputOptions,
+    input: String,
+    /// The outcome of the last action, like an invalid index or a cancelled calculation
+    status: String,
+    job: Option<Job>,
+    completed: Vec<Completed>,
+    selected: ListState,
+    /// The first line of the digits that is shown
+    scroll: usize,
+    /// The number of lines of digits that fit into the viewer
+    page: usize,
+    quit: bool,
+}
+
+#[cfg(feature = "tui")]
+impl App<'_> {
+    fn new(output: &OutputOptions) -> App<'_> {
+        App {
+            output,
+            input: String::new(),
+            status: "Enter an index and press Enter".to_string(),
+            job: None,
+            completed: Vec::new(),
+            selected: ListState::default(),
+            scroll: 0,
+            page: 1,
+            quit: false,
+        }
+    }
+
+    /// Draws the interface and handles keys and finished calculations until the user quits.
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        self.handle_key(key);
+                    }
+                }
+            }
+            self.receive();
+        }
+
+        // The worker stops after its current step, the process ends before it matters
+        if let Some(job) = &self.job {
+            job.progress.cancelled.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c' | 'd') if control => self.quit = true,
+            KeyCode::Char(c) if !control => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => self.submit(),
+            KeyCode::Esc => match &self.job {
+                Some(job) => job.progress.cancelled.store(true, Ordering::SeqCst),
+                None => self.input.clear(),
+            },
+            KeyCode::Up => self.select(self.selected.selected().map(|i| i.saturating_sub(1))),
+            KeyCode::Down => self.select(self.selected.selected().map(|i| i + 1)),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(self.page),
+            KeyCode::PageDown => self.scroll += self.page,
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = usize::MAX,
+            _ => {}
+        }
+    }
+
+    /// Selects a completed calculation and shows its digits from the start.
+    fn select(&mut self, selected: Option<usize>) {
+        if let Some(selected) = selected.filter(|&i| i < self.completed.len()) {
+            self.selected.select(Some(selected));
+            self.scroll = 0;
+        }
+    }
+
+    /// Starts calculating the index in the input box.
+    fn submit(&mut self) {
+        let input = self.input.trim();
+        if input.eq_ignore_ascii_case("q") {
+            self.quit = true;
+            return;
+        }
+        if self.job.is_some() {
+            self.status = "A calculation is running, press Esc to cancel it".to_string();
+            return;
+        }
+
+        let index = match parse_index(input).and_then(|index| {
+            self.output.settings.check_index(index)?;
+            Ok(index)
+        }) {
+            Ok(index) => index,
+            Err(error) => {
+                self.status = format!("Please enter a valid number ({})", error);
+                return;
+            }
+        };
+
+        let progress = Arc::new(Progress {
+            steps: AtomicU32::new(0),
+            total_steps: u64::BITS - index.leading_zeros(),
+            bits: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        });
+        let (sender, receiver) = mpsc::channel();
+        let worker_progress = Arc::clone(&progress);
+        thread::spawn(move || {
+            let _ = sender.send(calculate(index, &worker_progress));
+        });
+
+        self.input.clear();
+        self.status = format!("Calculating F({})", self.count(index));
+        self.job = Some(Job {
+            index,
+            started: Instant::now(),
+            progress,
+            receiver,
+        });
+    }
+
+    /// Collects the result of the running calculation once it finished.
+    fn receive(&mut self) {
+        let Some(job) = &self.job else {
+            return;
+        };
+        let index = job.index;
+        match job.receiver.try_recv() {
+            Err(TryRecvError::Empty) => return,
+            Ok(Some(completed)) => {
+                self.status = format!(
+                    "Calculated F({}) in {}",
+                    self.count(index),
+                    self.output
+                        .settings
+                        .value_format
+                        .duration(completed.duration)
+                );
+                self.completed.push(completed);
+                self.select(Some(self.completed.len() - 1));
+            }
+            Ok(None) | Err(TryRecvError::Disconnected) => {
+                self.status = format!("Cancelled F({})", self.count(index));
+            }
+        }
+        self.job = None;
+    }
+
+    fn count(&self, value: u64) -> String {
+        self.output.settings.value_format.count(value)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input_area, status_area, middle_area, digits_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(8),
+            Constraint::Min(3),
+        ])
+        .areas(frame.area());
+        let [progress_area, history_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(middle_area);
+
+        frame.render_widget(
+            Paragraph::new(format!("{}▏", self.input)).block(
+                Block::bordered()
+                    .title(" Index (Enter to calculate, Esc to cancel, Ctrl+C to quit) "),
+            ),
+            input_area,
+        );
+        frame.render_widget(Paragraph::new(self.status.as_str()), status_area);
+        self.draw_progress(frame, progress_area);
+
+        let items: Vec<ListItem> = self
+            .completed
+            .iter()
+            .map(|completed| {
+                ListItem::new(format!(
+                    "F({})  {} digits  {}",
+                    self.count(completed.index),
+                    self.count(completed.digits.len() as u64),
+                    self.output
+                        .settings
+                        .value_format
+                        .duration(completed.duration)
+                ))
+            })
+            .collect();
+        let history = List::new(items)
+            .block(Block::bordered().title(" History (Up/Down to select) "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(history, history_area, &mut self.selected);
+
+        self.draw_digits(frame, digits_area);
+    }
+
+    /// Draws the doubling level, operand size, elapsed time and remaining time.
+    fn draw_progress(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Progress ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let [gauge_area, text_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+
+        let Some(job) = &self.job else {
+            frame.render_widget(Paragraph::new("Idle"), text_area);
+            return;
+        };
+
+        let steps = job.progress.steps.load(Ordering::SeqCst);
+        let total_steps = job.progress.total_steps;
+        let fraction = CostModel::default().fraction_done(job.index, total_steps - steps);
+        let elapsed = job.started.elapsed();
+        let remaining = Duration::try_from_secs_f64(elapsed.as_secs_f64() * (1.0 / fraction - 1.0))
+            .map_or_else(
+                |_| "unknown".to_string(),
+                |remaining| self.output.settings.value_format.duration(remaining),
+            );
+
+        frame.render_widget(Gauge::default().ratio(fraction.clamp(0.0, 1.0)), gauge_area);
+        let lines = vec![
+            Line::from(format!("Calculating F({})", self.count(job.index))),
+            Line::from(format!("Doubling level: {} of {}", steps, total_steps)),
+            Line::from(format!(
+                "Operand size: {} bits",
+                self.count(job.progress.bits.load(Ordering::SeqCst))
+            )),
+            Line::from(format!(
+                "Elapsed: {}",
+                self.output.settings.value_format.duration(elapsed)
+            )),
+            Line::from(format!("Remaining: about {}", remaining)),
+        ];
+        frame.render_widget(Paragraph::new(lines), text_area);
+    }
+
+    /// Draws the lines of digits of the selected result that fit into the area.
+    fn draw_digits(&mut self, frame: &mut Frame, area: Rect) {
+        let selected = self
+            .selected
+            .selected()
+            .and_then(|selected| self.completed.get(selected));
+        let title = match selected {
+            Some(completed) => format!(
+                " Digits of F({}) (PgUp/PgDn/Home/End to scroll) ",
+                self.count(completed.index)
+            ),
+            None => " Digits ".to_string(),
+        };
+        let block = Block::bordered().title(title);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some(completed) = selected else {
+            return;
+        };
+        let width = usize::from(inner.width).max(1);
+        let height = usize::from(inner.height).max(1);
+        let line_count = completed.digits.len().div_ceil(width);
+        self.page = height;
+        self.scroll = self.scroll.min(line_count.saturating_sub(height));
+
+        // Only the visible lines are cut out, as a result can have millions of digits
+        let lines: Vec<Line> = (self.scroll..line_count.min(self.scroll + height))
+            .map(|line| {
+                let start = line * width;
+                let end = (start + width).min(completed.digits.len());
+                Line::from(&completed.digits[start..end])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+}
+
+/// Calculates `F(n)` with fast doubling and converts it to decimal, publishing the progress
+/// after every step.
+///
+/// # Returns
+/// An `Option<Completed>` with the result, or `None` if the calculation was cancelled.
+#[cfg(feature = "tui")]
+fn calculate(index: u64, progress: &Progress) -> Option<Completed> {
+    let start_time = Instant::now();
+    let mut state = DoublingState::new(index);
+    while !state.is_finished() {
+        if progress.cancelled.load(Ordering::SeqCst) {
+            return None;
+        }
+        state.step();
+        progress.steps.fetch_add(1, Ordering::SeqCst);
+        progress.bits.store(state.pair().1.bits(), Ordering::SeqCst);
+    }
+    let duration = start_time.elapsed();
+
+    let digits = to_decimal_string(&state.into_value());
+    (!progress.cancelled.load(Ordering::SeqCst)).then_some(Completed {
+        index,
+        digits,
+        duration,
+    })
+}
@@ -0,0 +1,345 @@
+use crate::{
+    config::config_dir, decimal::DIVIDE_AND_CONQUER_BITS, fib_core::MAX_U64_INDEX,
+    multiply::PARALLEL_MULTIPLY_BITS, FibError,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The name of the file the `calibrate` command writes the measured thresholds to.
+pub const CALIBRATION_FILE: &str = "fib-calibration.toml";
+
+/// The version of the calibration file format, written as its `version` field.
+///
+/// Files of another version are rejected instead of being guessed at, so a calibration of
+/// an older release has to be run again.
+pub const CALIBRATION_VERSION: u32 = 1;
+
+/// The threshold of [`Thresholds::parallel_multiply_bits`] in use.
+static PARALLEL_MULTIPLY: AtomicU64 = AtomicU64::new(PARALLEL_MULTIPLY_BITS);
+
+/// The threshold of [`Thresholds::word_index`] in use.
+static WORD_INDEX: AtomicU64 = AtomicU64::new(MAX_U64_INDEX);
+
+/// The threshold of [`Thresholds::conversion_bits`] in use.
+static CONVERSION: AtomicU64 = AtomicU64::new(DIVIDE_AND_CONQUER_BITS);
+
+/// The machine-dependent thresholds at which the calculation and conversion switch methods.
+///
+/// The defaults were measured on a single machine. The `calibrate` command measures them on the
+/// current one and saves them with [`Thresholds::save`], and [`Thresholds::apply`] makes
+/// every following calculation and conversion of the process use them. A file looks like
+/// this:
+///
+/// ```toml
+/// version = 1
+/// parallel-multiply-bits = 2097152
+/// word-index = 93
+/// conversion-bits = 8388608
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Products of operands with fewer bits are not split over the threads of rayon.
+    pub parallel_multiply_bits: u64,
+    /// Up to this index `Algorithm::Auto` calculates in machine words, at most
+    /// [`MAX_U64_INDEX`] as `F(94)` does not fit into 64 bits.
+    pub word_index: u64,
+    /// Numbers with fewer bits are converted to decimal with `BigUint::to_string` instead of
+    /// divide and conquer.
+    pub conversion_bits: u64,
+}
+
+/// Thresholds given explicitly, like with command-line flags, which take precedence over
+/// the calibrated ones in [`Thresholds::merge`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThresholdOverrides {
+    pub parallel_multiply_bits: Option<u64>,
+    pub word_index: Option<u64>,
+    pub conversion_bits: Option<u64>,
+}
+
+/// The calibration file as it is written, with its version.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct CalibrationFile {
+    version: u32,
+    parallel_multiply_bits: u64,
+    word_index: u64,
+    conversion_bits: u64,
+}
+
+/// Only the version of a calibration file, read before the rest so that files of other
+/// versions are reported as such instead of with their unknown fields.
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            parallel_multiply_bits: PARALLEL_MULTIPLY_BITS,
+            word_index: MAX_U64_INDEX,
+            conversion_bits: DIVIDE_AND_CONQUER_BITS,
+        }
+    }
+}
+
+impl Thresholds {
+    /// Returns the thresholds the calculations and conversions use right now.
+    pub fn current() -> Thresholds {
+        Thresholds {
+            parallel_multiply_bits: PARALLEL_MULTIPLY.load(Ordering::Relaxed),
+            word_index: WORD_INDEX.load(Ordering::Relaxed),
+            conversion_bits: CONVERSION.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Makes every following calculation and conversion of the process use the thresholds.
+    ///
+    /// The thresholds only decide which method is used, so every method returns the same
+    /// values with any of them, only slower or faster.
+    pub fn apply(self) {
+        PARALLEL_MULTIPLY.store(self.parallel_multiply_bits, Ordering::Relaxed);
+        WORD_INDEX.store(self.word_index.min(MAX_U64_INDEX), Ordering::Relaxed);
+        CONVERSION.store(self.conversion_bits, Ordering::Relaxed);
+    }
+
+    /// Replaces the thresholds that are given in `overrides`.
+    ///
+    /// # Returns
+    /// The `Thresholds` with every threshold of `overrides` that is set and the others of
+    /// `self`.
+    pub fn merge(self, overrides: ThresholdOverrides) -> Thresholds {
+        Thresholds {
+            parallel_multiply_bits: overrides
+                .parallel_multiply_bits
+                .unwrap_or(self.parallel_multiply_bits),
+            word_index: overrides.word_index.unwrap_or(self.word_index),
+            conversion_bits: overrides.conversion_bits.unwrap_or(self.conversion_bits),
+        }
+    }
+
+    /// Writes the thresholds as the TOML of a calibration file.
+    pub fn to_toml(&self) -> String {
+        let file = CalibrationFile {
+            version: CALIBRATION_VERSION,
+            parallel_multiply_bits: self.parallel_multiply_bits,
+            word_index: self.word_index,
+            conversion_bits: self.conversion_bits,
+        };
+        toml::to_string(&file).expect("the calibration file consists of integers only")
+    }
+
+    /// Parses the contents of a calibration file.
+    ///
+    /// # Arguments
+    /// * `text` - The TOML text of the calibration file.
+    ///
+    /// # Returns
+    /// A `Result<Thresholds, FibError>` with the thresholds, or a `FibError::InvalidConfig`
+    /// for malformed TOML, another version than [`CALIBRATION_VERSION`], missing or unknown
+    /// fields and a `word-index` above [`MAX_U64_INDEX`].
+    pub fn parse(text: &str) -> Result<Thresholds, FibError> {
+        let invalid = |error: toml::de::Error| FibError::InvalidConfig(error.to_string());
+        let Version { version } = toml::from_str(text).map_err(invalid)?;
+        if version != CALIBRATION_VERSION {
+            return Err(FibError::InvalidConfig(format!(
+                "calibration version {} is not supported, expected {}, run `fibonacci_sequence calibrate` again",
+                version, CALIBRATION_VERSION
+            )));
+        }
+
+        let file: CalibrationFile = toml::from_str(text).map_err(invalid)?;
+        if file.word_index > MAX_U64_INDEX {
+            return Err(FibError::InvalidConfig(format!(
+                "word-index {} is above {}, whose Fibonacci number is the last that fits into 64 bits",
+                file.word_index, MAX_U64_INDEX
+            )));
+        }
+        Ok(Thresholds {
+            parallel_multiply_bits: file.parallel_multiply_bits,
+            word_index: file.word_index,
+            conversion_bits: file.conversion_bits,
+        })
+    }
+
+    /// Reads the calibration file at `path`.
+    ///
+    /// # Returns
+    /// A `Result<Thresholds, FibError>` with the thresholds, a `FibError::Io` if the file
+    /// could not be read, or a `FibError::InvalidConfig` naming the file if it is invalid.
+    pub fn load(path: &Path) -> Result<Thresholds, FibError> {
+        let text = fs::read_to_string(path)?;
+        Thresholds::parse(&text).map_err(|error| match error {
+            FibError::InvalidConfig(reason) => {
+                FibError::InvalidConfig(format!("{}: {}", path.display(), reason))
+            }
+            error => error,
+        })
+    }
+
+    /// Writes the thresholds to a calibration file at `path`, creating its directory.
+    ///
+    /// # Returns
+    /// A `Result<(), FibError>` with a `FibError::Io` if the file could not be written.
+    pub fn save(&self, path: &Path) -> Result<(), FibError> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, self.to_toml())?;
+        Ok(())
+    }
+
+    /// Returns the path of the calibration file in the configuration directory, see
+    /// [`config_dir`].
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|directory| directory.join(CALIBRATION_FILE))
+    }
+
+    /// Reads the calibration file of the configuration directory, if there is one.
+    ///
+    /// Unlike `fib.toml`, the file is not looked up in the current directory, as the
+    /// thresholds belong to the machine and not to a project.
+    ///
+    /// # Returns
+    /// A `Result<Option<Thresholds>, FibError>` with the thresholds, `None` when there is no
+    /// calibration file, or the error of a file that could not be loaded.
+    pub fn discover() -> Result<Option<Thresholds>, FibError> {
+        let Some(path) = Thresholds::path() else {
+            return Ok(None);
+        };
+        match Thresholds::load(&path) {
+            Err(FibError::Io(error)) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            loaded => loaded.map(Some),
+        }
+    }
+}
+
+impl fmt::Display for Thresholds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Parallel products from:      {} bits",
+            self.parallel_multiply_bits
+        )?;
+        writeln!(f, "Machine words up to index:   {}", self.word_index)?;
+        write!(
+            f,
+            "Divide and conquer from:     {} bits",
+            self.conversion_bits
+        )
+    }
+}
+
+/// Returns the threshold of [`Thresholds::parallel_multiply_bits`] in use.
+pub(crate) fn parallel_multiply_bits() -> u64 {
+    PARALLEL_MULTIPLY.load(Ordering::Relaxed)
+}
+
+/// Returns the threshold of [`Thresholds::word_index`] in use.
+pub(crate) fn word_index() -> u64 {
+    WORD_INDEX.load(Ordering::Relaxed)
+}
+
+/// Returns the threshold of [`Thresholds::conversion_bits`] in use.
+pub(crate) fn conversion_bits() -> u64 {
+    CONVERSION.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEASURED: Thresholds = Thresholds {
+        parallel_multiply_bits: 1 << 19,
+        word_index: 80,
+        conversion_bits: 1 << 16,
+    };
+
+    #[test]
+    fn calibration_files_round_trip() {
+        let text = MEASURED.to_toml();
+        assert_eq!(
+            text,
+            "version = 1\n\
+             parallel-multiply-bits = 524288\n\
+             word-index = 80\n\
+             conversion-bits = 65536\n"
+        );
+        assert_eq!(Thresholds::parse(&text).unwrap(), MEASURED);
+        assert_eq!(
+            Thresholds::parse(&Thresholds::default().to_toml()).unwrap(),
+            Thresholds::default()
+        );
+    }
+
+    #[test]
+    fn saved_files_load_again() {
+        let path = std::env::temp_dir()
+            .join(format!("fibonacci-calibration-{}", std::process::id()))
+            .join(CALIBRATION_FILE);
+        MEASURED.save(&path).unwrap();
+        assert_eq!(Thresholds::load(&path).unwrap(), MEASURED);
+
+        fs::write(&path, "version = 1\n").unwrap();
+        match Thresholds::load(&path) {
+            Err(FibError::InvalidConfig(reason)) => {
+                assert!(
+                    reason.starts_with(&path.display().to_string()),
+                    "{}",
+                    reason
+                )
+            }
+            result => panic!("loaded an incomplete file: {:?}", result),
+        }
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(Thresholds::load(&path), Err(FibError::Io(_))));
+    }
+
+    #[test]
+    fn invalid_calibration_files_are_rejected() {
+        let valid = MEASURED.to_toml();
+        for text in [
+            String::new(),
+            "not toml".to_string(),
+            valid.replace("version = 1", "version = 2"),
+            valid.replace("version = 1\n", ""),
+            valid.replace("word-index = 80\n", ""),
+            valid.replace("word-index = 80", "word-index = 94"),
+            valid.replace("word-index = 80", "word-index = -1"),
+            format!("{}threads = 4\n", valid),
+        ] {
+            assert!(
+                matches!(Thresholds::parse(&text), Err(FibError::InvalidConfig(_))),
+                "accepted {:?}",
+                text
+            );
+        }
+
+        match Thresholds::parse("version = 2\nanything = true\n") {
+            Err(FibError::InvalidConfig(reason)) => assert!(reason.contains("version 2")),
+            result => panic!("accepted another version: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn overrides_replace_only_the_given_thresholds() {
+        assert_eq!(MEASURED.merge(ThresholdOverrides::default()), MEASURED);
+        assert_eq!(
+            MEASURED.merge(ThresholdOverrides {
+                word_index: Some(10),
+                conversion_bits: Some(1),
+                ..ThresholdOverrides::default()
+            }),
+            Thresholds {
+                parallel_multiply_bits: 1 << 19,
+                word_index: 10,
+                conversion_bits: 1,
+            }
+        );
+    }
+}
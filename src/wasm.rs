@@ -0,0 +1,93 @@
+//! The entry point for WebAssembly, which only needs num-bigint and an allocator.
+
+use crate::doubling::fib_pair_sequential;
+#[cfg(feature = "std")]
+use crate::scientific_notation;
+use alloc::string::{String, ToString};
+#[cfg(not(feature = "std"))]
+use num_bigint::BigUint;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Calculates a Fibonacci number and returns it as text, as an entry point for WebAssembly.
+///
+/// This is a pure function without any IO or threads: the number is calculated with fast
+/// doubling on the calling thread and converted with num-bigint instead of the parallel
+/// conversion of `to_decimal_string`, so it runs in a browser where no threads can be
+/// spawned. It is available without the `std` feature, which the WebAssembly build leaves
+/// out. On `wasm32` it is exported with `wasm-bindgen`, where `n` is a `BigInt` in
+/// JavaScript.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci number to calculate.
+/// * `scientific` - Whether the number is returned in scientific notation with 5
+///   significant digits, like `4.3466e+208`, instead of every decimal digit.
+///
+/// # Returns
+/// A `String` with the nth Fibonacci number.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn fib_string(n: u64, scientific: bool) -> String {
+    let (value, _) = fib_pair_sequential(n);
+    if scientific {
+        scientific_notation(&value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes a number in scientific notation like `crate::scientific_notation`, from its
+/// decimal digits as the leading digits of `format` need `std`.
+#[cfg(not(feature = "std"))]
+fn scientific_notation(value: &BigUint) -> String {
+    let digits = value.to_string();
+    let mantissa = alloc::format!("{:0<5.5}", digits);
+
+    // The exponent is grouped with commas, like `e+20,898`
+    let exponent = (digits.len() - 1).to_string();
+    let mut grouped = String::new();
+    for (position, digit) in exponent.chars().enumerate() {
+        if position > 0 && (exponent.len() - position).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    alloc::format!("{}.{}e+{}", &mantissa[..1], &mantissa[1..], grouped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn digits_match_the_numbers_calculated_with_fast_doubling() {
+        let (mut current, mut next) = (BigUint::ZERO, BigUint::from(1u32));
+        for n in 0..500 {
+            assert_eq!(fib_string(n, false), current.to_string());
+            let sum = &current + &next;
+            current = core::mem::replace(&mut next, sum);
+        }
+    }
+
+    #[test]
+    fn scientific_notation_has_five_significant_digits() {
+        assert_eq!(fib_string(0, true), "0.0000e+0");
+        assert_eq!(fib_string(7, true), "1.3000e+1");
+        assert_eq!(fib_string(1000, true), "4.3466e+208");
+        assert_eq!(fib_string(100_000, true), "2.5974e+20,898");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn strings_match_calculate_fibonacci() {
+        use crate::{calculate_fibonacci, SplitMix64};
+
+        let mut random = SplitMix64::new(99);
+        for _ in 0..50 {
+            let n = random.next_u64() % 200_000;
+            let value = calculate_fibonacci(n).unwrap();
+            assert_eq!(fib_string(n, false), value.to_string(), "F({})", n);
+            assert_eq!(fib_string(n, true), scientific_notation(&value), "F({})", n);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{style::Style, OutputOptions};
+use fibonacci_sequence::{to_decimal_string, DoublingState, ValueFormat};
+use num_bigint::BigUint;
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The measurements of a single row of the watch table.
+struct Row {
+    index: u64,
+    compute: Duration,
+    conversion: Duration,
+}
+
+/// Calculates Fibonacci numbers at geometrically growing indices and prints their timings.
+///
+/// Starting at `start`, the index is multiplied by `factor` after every row until it is
+/// larger than `max` or no longer fits into a `u64`. Every row shows the index, the digit
+/// count, the calculation and conversion durations and their ratios to the previous row.
+/// Ctrl+C cancels the running calculation and a second Ctrl+C quits immediately. In both
+/// cases, and when the last index is reached, a summary with the scaling exponents fitted
+/// to the log-log data is printed.
+///
+/// # Arguments
+/// * `start` - The first index, at least 1.
+/// * `factor` - The factor between consecutive indices, at least 2.
+/// * `max` - The largest index to calculate, or `None` to continue until interrupted.
+/// * `output` - The options that control how the table is printed.
+///
+/// # Returns
+/// A `Result<(), String>` with a `String` error message if Ctrl+C could not be handled.
+pub fn run_watch(
+    start: u64,
+    factor: u64,
+    max: Option<u64>,
+    output: &OutputOptions,
+) -> Result<(), String> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = Arc::clone(&interrupted);
+    ctrlc::set_handler(move || {
+        // The first Ctrl+C stops the table, a second one quits right away
+        if handler_flag.swap(true, Ordering::SeqCst) {
+            process::exit(130);
+        }
+    })
+    .map_err(|error| format!("Error: could not handle Ctrl+C: {}", error))?;
+
+    let value_format = &output.settings.value_format;
+    let style = Style::stdout(output.color);
+    println!(
+        "{:>20}  {:>15}  {:>10}  {:>7}  {:>10}  {:>7}",
+        "index", "digits", "compute", "ratio", "convert", "ratio"
+    );
+
+    let mut rows: Vec<Row> = Vec::new();
+    let mut index = Some(start);
+    while let Some(current) = index.filter(|&index| max.is_none_or(|max| index <= max)) {
+        let compute_start = Instant::now();
+        let Some(value) = calculate_cancellable(current, &interrupted) else {
+            break;
+        };
+        let compute = compute_start.elapsed();
+
+        let Some((digits, conversion)) = convert_cancellable(value, &interrupted) else {
+            break;
+        };
+
+        let previous = rows.last();
+        let ratio = |duration: Duration, previous: Option<Duration>| match previous {
+            Some(previous) if !previous.is_zero() => {
+                format!("{:.2}×", duration.as_secs_f64() / previous.as_secs_f64())
+            }
+            _ => "-".to_string(),
+        };
+        // Colors are applied after padding so the escape codes do not break the alignment
+        println!(
+            "{}  {}  {}  {:>7}  {}  {:>7}",
+            style.number(format!("{:>20}", value_format.count(current))),
+            style.number(format!("{:>15}", value_format.count(digits))),
+            style.duration(format!("{:>10}", value_format.duration(compute))),
+            ratio(compute, previous.map(|row| row.compute)),
+            style.duration(format!("{:>10}", value_format.duration(conversion))),
+            ratio(conversion, previous.map(|row| row.conversion)),
+        );
+
+        rows.push(Row {
+            index: current,
+            compute,
+            conversion,
+        });
+        if interrupted.load(Ordering::SeqCst) {
+            break;
+        }
+        index = current.checked_mul(factor);
+    }
+
+    print_summary(&rows, value_format);
+    Ok(())
+}
+
+/// Calculates `F(n)` with fast doubling, checking for an interrupt after every step.
+///
+/// # Returns
+/// An `Option<BigUint>` with the Fibonacci number, or `None` if the calculation was
+/// interrupted.
+fn calculate_cancellable(n: u64, interrupted: &AtomicBool) -> Option<BigUint> {
+    let mut state = DoublingState::new(n);
+    while !state.is_finished() {
+        if interrupted.load(Ordering::SeqCst) {
+            return None;
+        }
+        state.step();
+    }
+    Some(state.into_value())
+}
+
+/// Converts a Fibonacci number to decimal on another thread, so an interrupt does not have
+/// to wait for the conversion to finish.
+///
+/// # Returns
+/// An `Option<(u64, Duration)>` with the digit count and the conversion duration, or `None`
+/// if the conversion was interrupted.
+fn convert_cancellable(value: BigUint, interrupted: &AtomicBool) -> Option<(u64, Duration)> {
+    let conversion = thread::spawn(move || {
+        let conversion_start = Instant::now();
+        let digits = to_decimal_string(&value).len() as u64;
+        (digits, conversion_start.elapsed())
+    });
+
+    // An interrupted conversion is left running, the program ends right after the summary
+    while !conversion.is_finished() {
+        if interrupted.load(Ordering::SeqCst) {
+            return None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    conversion.join().ok()
+}
+
+/// Prints the number of rows and the scaling exponents of both durations.
+fn print_summary(rows: &[Row], value_format: &ValueFormat) {
+    println!();
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        println!("No index was calculated");
+        return;
+    };
+    println!(
+        "Calculated {} {} from F({}) to F({})",
+        rows.len(),
+        if rows.len() == 1 { "index" } else { "indices" },
+        value_format.count(first.index),
+        value_format.count(last.index)
+    );
+
+    let exponent = |duration: fn(&Row) -> Duration| {
+        fit_slope(rows.iter().map(|row| (row.index, duration(row))))
+            .map_or("-".to_string(), |slope| format!("n^{:.2}", slope))
+    };
+    println!("Compute time grows like    {}", exponent(|row| row.compute));
+    println!(
+        "Conversion time grows like {}",
+        exponent(|row| row.conversion)
+    );
+}
+
+/// Fits a line to the points `(ln index, ln duration)` with least squares.
+///
+/// # Returns
+/// An `Option<f64>` with the slope of the line, which is the exponent `k` of the growth
+/// `duration ~ index^k`, or `None` with fewer than two distinct indices.
+fn fit_slope(points: impl Iterator<Item = (u64, Duration)>) -> Option<f64> {
+    let points: Vec<(f64, f64)> = points
+        .map(|(index, duration)| {
+            // A zero duration has no logarithm, one nanosecond is below any measurement
+            let seconds = duration.max(Duration::from_nanos(1)).as_secs_f64();
+            ((index as f64).ln(), seconds.ln())
+        })
+        .collect();
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slopes_are_the_exponent_of_the_growth() {
+        // duration = index^1.5 nanoseconds
+        let points = [1_000u64, 10_000, 100_000, 1_000_000]
+            .map(|index| (index, Duration::from_nanos((index as f64).powf(1.5) as u64)));
+        let slope = fit_slope(points.into_iter()).unwrap();
+        assert!((slope - 1.5).abs() < 1e-4, "{}", slope);
+
+        let flat = [(10, Duration::ZERO), (1000, Duration::ZERO)];
+        assert_eq!(fit_slope(flat.into_iter()), Some(0.0));
+        assert_eq!(
+            fit_slope([(5, Duration::from_secs(1)); 3].into_iter()),
+            None
+        );
+        assert_eq!(fit_slope(std::iter::empty()), None);
+    }
+}
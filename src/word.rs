@@ -0,0 +1,85 @@
+use crate::{fib_u64, FibError};
+
+/// The most characters a Fibonacci word built by [`fibonacci_word`] may have (1 GiB).
+pub const MAX_WORD_LENGTH: u64 = 1 << 30;
+
+/// Builds the nth Fibonacci word.
+///
+/// The words start with `S(0) = "b"` and `S(1) = "a"`, and every later word is the
+/// previous one followed by the one before it, `S(n) = S(n-1) + S(n-2)`:
+/// `S(2) = "ab"`, `S(3) = "aba"`, `S(4) = "abaab"`, `S(5) = "abaababa"`. With this
+/// convention `S(n)` has exactly `F(n+1)` characters, of which `F(n)` are `a` and `F(n-1)`
+/// are `b`.
+///
+/// From `S(1)` on every word is a prefix of the next one, so the word is built in a single
+/// buffer by appending the prefix of the length of the word before.
+///
+/// # Arguments
+/// * `n` - The index of the Fibonacci word.
+///
+/// # Returns
+/// A `Result<String, FibError>` with the nth Fibonacci word, or `FibError::InvalidIndex`
+/// if it would have more than [`MAX_WORD_LENGTH`] characters.
+pub fn fibonacci_word(n: u64) -> Result<String, FibError> {
+    let length = fib_u64(n.saturating_add(1))
+        .filter(|length| *length <= MAX_WORD_LENGTH)
+        .ok_or_else(|| {
+            FibError::InvalidIndex(format!(
+                "the Fibonacci word S({}) has more than {} characters",
+                n, MAX_WORD_LENGTH
+            ))
+        })?;
+
+    match n {
+        0 => return Ok("b".to_string()),
+        1 => return Ok("a".to_string()),
+        _ => {}
+    }
+
+    let mut word = Vec::with_capacity(length as usize);
+    word.extend_from_slice(b"ab");
+    // The length of the word before the current one, which starts as S(1) before S(2)
+    let mut previous = 1;
+    while word.len() < length as usize {
+        let current = word.len();
+        word.extend_from_within(..previous);
+        previous = current;
+    }
+
+    Ok(String::from_utf8(word).expect("the word only holds the letters a and b"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_concatenate_the_two_before() {
+        let mut words = vec!["b".to_string(), "a".to_string()];
+        for n in 2..30 {
+            let word = format!("{}{}", words[n - 1], words[n - 2]);
+            words.push(word);
+        }
+        for (n, expected) in words.iter().enumerate() {
+            let word = fibonacci_word(n as u64).unwrap();
+            assert_eq!(&word, expected, "S({})", n);
+
+            // F(n+1) letters, F(n) of them `a` and F(n-1) of them `b`
+            let a = word.bytes().filter(|&letter| letter == b'a').count() as u64;
+            assert_eq!(word.len() as u64, fib_u64(n as u64 + 1).unwrap());
+            if n > 0 {
+                assert_eq!(a, fib_u64(n as u64).unwrap());
+            }
+        }
+        assert_eq!(fibonacci_word(5).unwrap(), "abaababa");
+    }
+
+    #[test]
+    fn words_beyond_the_limit_are_rejected() {
+        assert!(matches!(fibonacci_word(44), Err(FibError::InvalidIndex(_))));
+        assert!(matches!(
+            fibonacci_word(u64::MAX),
+            Err(FibError::InvalidIndex(_))
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::FibError;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// The number of bytes [`write_file_with_progress`] hands to the file at once (1 MiB).
+pub const CHUNK_SIZE: usize = 1 << 20;
+
+/// A writer that counts the bytes the inner writer accepted and reports every write.
+///
+/// Bytes that are still buffered in front of it are not counted, so after an error the
+/// count is exactly the number of bytes that reached the inner writer.
+pub struct ProgressWriter<W, F> {
+    inner: W,
+    written: u64,
+    on_progress: F,
+}
+
+impl<W: Write, F: FnMut(u64)> ProgressWriter<W, F> {
+    /// Creates a writer that calls `on_progress` with the total number of written bytes
+    /// after every write to `inner`.
+    pub fn new(inner: W, on_progress: F) -> ProgressWriter<W, F> {
+        ProgressWriter {
+            inner,
+            written: 0,
+            on_progress,
+        }
+    }
+
+    /// Returns the number of bytes the inner writer accepted so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Returns the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write, F: FnMut(u64)> Write for ProgressWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        (self.on_progress)(self.written);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes a file in chunks of [`CHUNK_SIZE`] bytes while reporting the progress.
+///
+/// The file is replaced if it exists and synced to disk once everything was written. If
+/// writing fails, for example because the disk is full, the partial file is removed, so a
+/// file at `path` is always complete. Paths that are not regular files, like `/dev/null`,
+/// are never removed.
+///
+/// # Arguments
+/// * `path` - The path of the file.
+/// * `on_progress` - Called with the number of bytes written so far after every chunk.
+/// * `write` - Writes the contents of the file to the writer it is given.
+///
+/// # Returns
+/// A `Result<u64, FibError>` with the size of the file, or `FibError::WriteFailed` with
+/// the number of bytes that were written before the error.
+pub fn write_file_with_progress(
+    path: &Path,
+    on_progress: impl FnMut(u64),
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<u64, FibError> {
+    let file = File::create(path)?;
+    let regular = file.metadata()?.is_file();
+    let mut writer = BufWriter::with_capacity(CHUNK_SIZE, ProgressWriter::new(file, on_progress));
+
+    let result = write(&mut writer)
+        .and_then(|()| writer.flush())
+        .and_then(|()| writer.get_ref().inner.sync_all());
+    let written = writer.get_ref().written();
+    match result {
+        Ok(()) => Ok(written),
+        Err(error) => {
+            // The buffered rest must not be written while the file is dropped
+            let (progress_writer, _) = writer.into_parts();
+            drop(progress_writer.into_inner());
+            // The write error is the one worth reporting, not a failed cleanup
+            if regular {
+                let _ = fs::remove_file(path);
+            }
+            Err(FibError::WriteFailed { written, error })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("fibonacci-writer-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn progress_is_reported_per_chunk() {
+        let path = scratch_file("complete");
+        let mut reports = Vec::new();
+        let size = write_file_with_progress(
+            &path,
+            |written| reports.push(written),
+            |writer| writer.write_all(&vec![b'7'; 2 * CHUNK_SIZE + 5]),
+        )
+        .unwrap();
+        assert_eq!(size, 2 * CHUNK_SIZE as u64 + 5);
+        assert_eq!(fs::read(&path).unwrap().len() as u64, size);
+        assert_eq!(reports.last(), Some(&size));
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_writes_remove_the_partial_file() {
+        let path = scratch_file("failed");
+        let result = write_file_with_progress(
+            &path,
+            |_| {},
+            |writer| {
+                writer.write_all(&vec![b'1'; CHUNK_SIZE + 10])?;
+                writer.write_all(b"still buffered")?;
+                Err(io::Error::other("disk full"))
+            },
+        );
+        let Err(FibError::WriteFailed { written, error }) = result else {
+            panic!("the write should fail");
+        };
+        // The large write went straight to the file, the small one was still buffered
+        assert_eq!(written, CHUNK_SIZE as u64 + 10);
+        assert_eq!(error.to_string(), "disk full");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn only_accepted_bytes_are_counted() {
+        let mut buffer = [0u8; 4];
+        let mut writer = ProgressWriter::new(&mut buffer[..], |_| {});
+        assert_eq!(writer.write(b"abcdef").unwrap(), 4);
+        assert!(writer.write_all(b"g").is_err());
+        assert_eq!(writer.written(), 4);
+        assert_eq!(writer.into_inner().len(), 0);
+        assert_eq!(&buffer, b"abcd");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::FibError;
+use num_bigint::BigUint;
+
+/// Decomposes a number into a sum of non-consecutive Fibonacci numbers.
+///
+/// By Zeckendorf's theorem every positive integer is the sum of distinct Fibonacci numbers
+/// `F(k)` with `k >= 2`, no two of them consecutive, in exactly one way. The decomposition
+/// is found greedily, always taking the largest Fibonacci number that still fits, like
+/// `100 = F(11) + F(6) + F(4) = 89 + 8 + 3`.
+///
+/// The Fibonacci numbers are walked down with the pair `(F(k), F(k+1))` instead of being
+/// stored, so only a few numbers of the size of `n` are alive at once.
+///
+/// # Arguments
+/// * `n` - The number to decompose.
+///
+/// # Returns
+/// A `Vec<u64>` with the indices of the Fibonacci numbers in descending order, empty for 0.
+pub fn zeckendorf(n: &BigUint) -> Vec<u64> {
+    let mut indices = Vec::new();
+    if n == &BigUint::ZERO {
+        return indices;
+    }
+
+    // Find the largest F(k) <= n, starting at the pair (F(2), F(3))
+    let (mut k, mut a, mut b) = (2, BigUint::from(1u32), BigUint::from(2u32));
+    while &b <= n {
+        let next = &a + &b;
+        (a, b) = (b, next);
+        k += 1;
+    }
+
+    let mut remainder = n.clone();
+    loop {
+        if a <= remainder {
+            remainder -= &a;
+            indices.push(k);
+            if remainder == BigUint::ZERO {
+                return indices;
+            }
+        }
+        // Step down from (F(k), F(k+1)) to (F(k-1), F(k))
+        let previous = &b - &a;
+        (a, b) = (previous, a);
+        k -= 1;
+    }
+}
+
+/// Encodes a positive integer with the Fibonacci code.
+///
+/// The code has one bit per Fibonacci number from `F(2) = 1` up to the largest one of the
+/// [`zeckendorf`] decomposition, set if that number is part of it, followed by an extra
+/// `1`. As the decomposition never holds two consecutive Fibonacci numbers, `11` only
+/// appears at the end of a code, so a stream of codes can be split without lengths and
+/// resynchronizes after a damaged bit. The first codes are `11` for 1, `011` for 2, `0011`
+/// for 3 and `1011` for 4.
+///
+/// The code is only defined for `n ≥ 1`: 0 is the empty sum, which would be the empty
+/// code, and that cannot be told apart from no code at all in a stream.
+///
+/// # Arguments
+/// * `n` - The number to encode, which has to be positive.
+///
+/// # Returns
+/// A `Result<String, FibError>` with the code of `n` as `0` and `1` characters, or
+/// `FibError::InvalidCode` for 0, which has no Fibonacci code.
+pub fn fib_encode(n: &BigUint) -> Result<String, FibError> {
+    let indices = zeckendorf(n);
+    let Some(&largest) = indices.first() else {
+        return Err(FibError::InvalidCode(
+            "only positive integers have a code, not 0".to_string(),
+        ));
+    };
+
+    let mut code = vec![b'0'; largest as usize - 1];
+    for index in indices {
+        code[index as usize - 2] = b'1';
+    }
+    code.push(b'1');
+    Ok(String::from_utf8(code).expect("the code only holds the digits 0 and 1"))
+}
+
+/// Decodes a single Fibonacci code written by [`fib_encode`].
+///
+/// # Arguments
+/// * `code` - The bits of the code as `0` and `1` characters.
+///
+/// # Returns
+/// A `Result<BigUint, FibError>` with the encoded number, or `FibError::InvalidCode` if
+/// the code holds other characters, does not end with the terminating `11` or has a `11`
+/// before its end.
+pub fn fib_decode(code: &str) -> Result<BigUint, FibError> {
+    if let Some(invalid) = code.chars().find(|c| *c != '0' && *c != '1') {
+        return Err(FibError::InvalidCode(format!("'{}' is not a bit", invalid)));
+    }
+    let Some(bits) = code.strip_suffix('1').filter(|bits| bits.ends_with('1')) else {
+        return Err(FibError::InvalidCode(
+            "the code does not end with 11".to_string(),
+        ));
+    };
+    if bits.contains("11") {
+        return Err(FibError::InvalidCode(
+            "11 appears before the end of the code".to_string(),
+        ));
+    }
+
+    // The bits stand for F(2), F(3), … in this order
+    let mut value = BigUint::ZERO;
+    let (mut a, mut b) = (BigUint::from(1u32), BigUint::from(2u32));
+    for bit in bits.bytes() {
+        if bit == b'1' {
+            value += &a;
+        }
+        let next = &a + &b;
+        (a, b) = (b, next);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_fibonacci;
+
+    fn fib(k: u64) -> BigUint {
+        calculate_fibonacci(k).unwrap()
+    }
+
+    #[test]
+    fn zeckendorf_sums_to_the_number_without_consecutive_indices() {
+        for n in 1u32..2000 {
+            let indices = zeckendorf(&BigUint::from(n));
+            let sum: BigUint = indices.iter().map(|&k| fib(k)).sum();
+            assert_eq!(sum, BigUint::from(n), "decomposition of {}", n);
+            assert!(indices.windows(2).all(|pair| pair[0] >= pair[1] + 2));
+            assert!(indices.iter().all(|&k| k >= 2));
+        }
+        assert!(zeckendorf(&BigUint::ZERO).is_empty());
+    }
+
+    #[test]
+    fn first_codes_match_the_table() {
+        let codes = ["11", "011", "0011", "1011", "00011", "10011", "01011"];
+        for (n, code) in (1u32..).zip(codes) {
+            assert_eq!(fib_encode(&BigUint::from(n)).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for n in 1u32..5000 {
+            let n = BigUint::from(n);
+            let code = fib_encode(&n).unwrap();
+            assert!(code.ends_with("11") && !code[..code.len() - 1].contains("11"));
+            assert_eq!(fib_decode(&code).unwrap(), n);
+        }
+        let large = fib(300) + fib(120) + 7u32;
+        assert_eq!(fib_decode(&fib_encode(&large).unwrap()).unwrap(), large);
+    }
+
+    #[test]
+    fn zero_has_no_code() {
+        assert!(matches!(
+            fib_encode(&BigUint::ZERO),
+            Err(FibError::InvalidCode(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        for code in ["", "1", "10", "0110", "1101", "01a11"] {
+            assert!(
+                matches!(fib_decode(code), Err(FibError::InvalidCode(_))),
+                "{}",
+                code
+            );
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Cancelling is a process-wide request, so it is checked in a test binary of its own
+//! where no other calculation runs at the same time.
+
+#![cfg(feature = "std")]
+
+use fibonacci_sequence::{
+    calculate_fibonacci, calculate_fibonacci_with, cancel_requested, clear_cancel, request_cancel,
+    Algorithm, FibError,
+};
+
+#[test]
+fn requested_cancels_stop_calculations_until_cleared() {
+    request_cancel();
+    assert!(cancel_requested());
+    assert!(matches!(
+        calculate_fibonacci(100_000),
+        Err(FibError::Cancelled)
+    ));
+    assert!(matches!(
+        calculate_fibonacci_with(100_000, Algorithm::FastDoubling),
+        Err(FibError::Cancelled)
+    ));
+
+    clear_cancel();
+    assert!(!cancel_requested());
+    assert_eq!(calculate_fibonacci(10).unwrap(), 55u32.into());
+}
@@ -0,0 +1,224 @@
+//! The binary needs the default `std` feature, so these tests are only built with it.
+
+#![cfg(feature = "std")]
+
+use fibonacci_sequence::calculate_fibonacci;
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs the command-line program with the given arguments.
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fibonacci_sequence"))
+        .args(args)
+        .output()
+        .expect("the program runs")
+}
+
+/// Runs the command-line program and returns its standard output, which must succeed.
+fn stdout(args: &[&str]) -> String {
+    let output = run(args);
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("the output is UTF-8")
+}
+
+#[test]
+fn count_prints_one_line_per_number() {
+    let output = stdout(&["--count", "10"]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0], "0 0");
+    assert_eq!(lines[9], "9 34");
+}
+
+#[test]
+fn completions_and_manpage_are_printed() {
+    for shell in ["bash", "elvish", "fish", "powershell", "zsh"] {
+        let script = stdout(&["completions", shell]);
+        assert!(script.contains("fibonacci_sequence"), "{}", shell);
+    }
+    let manpage = stdout(&["manpage"]);
+    assert!(manpage.starts_with(".ie"));
+    assert!(manpage.contains(".TH fibonacci_sequence 1"));
+}
+
+#[test]
+fn scripts_stop_at_the_first_error_unless_told_to_keep_going() {
+    let path = std::env::temp_dir().join(format!("fibonacci-cli-{}.fib", std::process::id()));
+    std::fs::write(&path, "# a demo\n10\n\n:sci\n+1\nxyz\n20\n").unwrap();
+    let script = path.to_str().unwrap();
+
+    let stopped = run(&["--script", script]);
+    let output = String::from_utf8_lossy(&stopped.stdout);
+    assert!(!stopped.status.success());
+    assert!(output.contains("> 10\n"));
+    assert!(output.contains("\n55\n"));
+    assert!(output.contains("> :sci\n"));
+    assert!(output.contains("5.5000e+1"));
+    assert!(output.contains("\n89\n"));
+    assert!(!output.contains("# a demo"));
+    assert!(!output.contains("6765"));
+
+    let kept_going = run(&["--script", script, "--keep-going"]);
+    assert!(!kept_going.status.success());
+    assert!(String::from_utf8_lossy(&kept_going.stdout).contains("\n6765\n"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn raw_output_is_the_little_endian_bytes_of_the_number() {
+    for n in [0, 1, 1000, 100_000] {
+        let output = run(&[&n.to_string(), "--raw"]);
+        assert!(output.status.success());
+        let bytes = calculate_fibonacci(n).unwrap().to_bytes_le();
+        assert_eq!(output.stdout.len(), bytes.len(), "F({})", n);
+        assert_eq!(output.stdout, bytes, "F({})", n);
+    }
+}
+
+#[test]
+fn mod_table_shows_the_pisano_period() {
+    let output = stdout(&["mod-table", "3", "--terms", "16"]);
+    assert!(output.contains("\n0 1 1 2 0 2 2 1 0 1 1 2 0 2 2 1\n"));
+
+    let marked = stdout(&["mod-table", "3", "--terms", "16", "--mark-period"]);
+    assert!(marked.contains("\n0 1 1 2 0 2 2 1 | 0 1 1 2 0 2 2 1\n"));
+    assert!(marked.ends_with("Pisano period: 8\n"));
+}
+
+#[test]
+fn the_threshold_is_read_from_the_environment_unless_given() {
+    let run_with = |threshold: &str, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_fibonacci_sequence"))
+            .args(args)
+            .env("FIB_SCI_THRESHOLD", threshold)
+            .output()
+            .expect("the program runs")
+    };
+
+    let from_env = run_with("1e5", &["30", "--quiet"]);
+    assert_eq!(String::from_utf8_lossy(&from_env.stdout), "8.3204e+5\n");
+    let overridden = run_with("1e5", &["30", "--quiet", "--sci-threshold", "1e10"]);
+    assert_eq!(String::from_utf8_lossy(&overridden.stdout), "832040\n");
+
+    // An invalid value is reported and the default threshold is used
+    let invalid = run_with("abc", &["30", "--quiet"]);
+    assert!(invalid.status.success());
+    assert_eq!(String::from_utf8_lossy(&invalid.stdout), "832040\n");
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("ignoring FIB_SCI_THRESHOLD=abc"));
+}
+
+#[test]
+fn flags_override_the_configuration_file() {
+    let directory = std::env::temp_dir().join(format!("fibonacci-config-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(
+        directory.join("fib.toml"),
+        "threshold = 5\nmax-index = 100\n",
+    )
+    .unwrap();
+    let run_in = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_fibonacci_sequence"))
+            .args(args)
+            .current_dir(&directory)
+            .env_remove("FIB_SCI_THRESHOLD")
+            .output()
+            .expect("the program runs")
+    };
+
+    let configured = run_in(&["30", "--quiet"]);
+    assert_eq!(String::from_utf8_lossy(&configured.stdout), "8.3204e+5\n");
+    let overridden = run_in(&["30", "--quiet", "--sci-threshold", "1e10"]);
+    assert_eq!(String::from_utf8_lossy(&overridden.stdout), "832040\n");
+
+    assert!(!run_in(&["200", "--quiet"]).status.success());
+    assert!(run_in(&["200", "--quiet", "--max-index", "300"])
+        .status
+        .success());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn lines_with_several_indices_skip_the_invalid_ones() {
+    let path = std::env::temp_dir().join(format!("fibonacci-indices-{}.fib", std::process::id()));
+    std::fs::write(&path, "5 eight 13\n").unwrap();
+    let output = run(&["--script", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("5th Fibonacci number") && stdout.contains("\n5\n"));
+    assert!(stdout.contains("13th Fibonacci number") && stdout.contains("\n233\n"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("Warning:").count(), 1, "{}", stderr);
+    assert!(stderr.contains("skipping value 2 of 3"));
+}
+
+#[test]
+fn the_prompt_quits_at_the_end_of_the_input() {
+    for input in ["", "10\n", "10"] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_fibonacci_sequence"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("the program runs");
+        // Dropping stdin closes the pipe, which ends the input
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert!(output.status.success(), "{:?}", input);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).is_empty(),
+            "{:?}",
+            input
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.contains("\n55\n"), !input.is_empty(), "{:?}", input);
+        assert!(stdout.ends_with("(or 'q' to quit): \n"), "{:?}", input);
+    }
+}
+
+#[test]
+fn every_index_of_an_input_file_gets_a_result() {
+    let directory = std::env::temp_dir().join(format!("fibonacci-input-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let input = directory.join("indices.txt");
+    let results = directory.join("results.txt");
+    std::fs::write(&input, "10 20\n# a comment\n30 # thirty\n\n93\n").unwrap();
+    let args = [
+        "--input",
+        input.to_str().unwrap(),
+        "--output",
+        results.to_str().unwrap(),
+    ];
+
+    stdout(&args);
+    let written = std::fs::read_to_string(&results).unwrap();
+    assert_eq!(
+        written,
+        "10 55\n20 6765\n30 832040\n93 12200160415121876738\n"
+    );
+
+    // Invalid indices fail the run, but the others are still calculated
+    std::fs::write(&input, "5\n8 abc 13\n").unwrap();
+    let output = run(&args);
+    let written = std::fs::read_to_string(&results).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(written, "5 5\n8 21\n13 233\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("indices.txt:2, value 2"), "{}", stderr);
+}